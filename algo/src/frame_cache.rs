@@ -1106,6 +1106,37 @@ impl<K: Hash + Eq, V> FrameCache<K, V> {
   pub fn end_frame<'a>(&'a mut self, label: &'a str) -> FrameDrain<'a, K, V> {
     FrameDrain::new(self, label)
   }
+
+  /// End the frame like [`FrameCache::end_frame`], but the items not hit by
+  /// this frame are only removed from the least recently used one while
+  /// `evict` returns true, the rest of them are retained to the next frame.
+  pub fn end_frame_evict_while<'a>(
+    &'a mut self, label: &'a str, mut evict: impl FnMut(&V) -> bool,
+  ) -> FrameDrain<'a, K, V> {
+    unsafe {
+      let tail = self.tail;
+      let mut last = tail;
+      while !(*last).next.is_null() {
+        last = (*last).next;
+      }
+
+      let mut cursor = ptr::null_mut();
+      while last != tail && evict(&*(*last).val.as_ptr()) {
+        cursor = last;
+        last = (*last).prev;
+      }
+
+      // The retained items keep after the items hit by this frame, so they are
+      // still the least recently used ones.
+      self.detach(tail);
+      if !cursor.is_null() {
+        self.break_at(cursor);
+      }
+      self.attach(tail);
+      let size = self.len();
+      FrameDrain { size, label, cursor, cache: self }
+    }
+  }
 }
 
 impl<K: Hash + Eq, V> Default for FrameCache<K, V> {
@@ -1764,6 +1795,36 @@ mod tests {
     assert_eq!(cache.pop_lru(), None);
   }
 
+  #[test]
+  fn end_frame_evict_while() {
+    let mut cache = FrameCache::new();
+    cache.put("apple", 1);
+    cache.put("banana", 2);
+    cache.put("pear", 3);
+    cache.end_frame("");
+
+    cache.get(&"apple");
+    let mut budget = 2;
+    let removed: Vec<_> = cache
+      .end_frame_evict_while("", |_| {
+        budget -= 1;
+        budget > 0
+      })
+      .collect();
+    // "banana" is the least recently used one.
+    assert_eq!(removed, vec![2]);
+    assert_opt_eq(cache.get(&"pear"), 3);
+
+    cache.end_frame_evict_while("", |_| false);
+    assert_eq!(cache.len(), 2);
+    cache.get(&"pear");
+    cache.end_frame("");
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(&"pear"));
+    assert_eq!(cache.end_frame_evict_while("", |_| true).count(), 1);
+    assert!(cache.is_empty());
+  }
+
   #[test]
   fn end_frame_remove_none() {
     let mut cache = FrameCache::new();
@@ -141,6 +141,17 @@ impl Lerp for Color {
   }
 }
 
+impl Lerp for Shadow {
+  fn lerp(&self, to: &Self, factor: f32) -> Self {
+    Self {
+      offset: Lerp::lerp(&self.offset, &to.offset, factor),
+      blur_radius: self.blur_radius.lerp(&to.blur_radius, factor),
+      spread: self.spread.lerp(&to.spread, factor),
+      color: self.color.lerp(&to.color, factor),
+    }
+  }
+}
+
 impl Lerp for Brush {
   fn lerp(&self, to: &Self, factor: f32) -> Self {
     match (self, to) {
@@ -173,6 +184,24 @@ impl Lerp for Transform {
   }
 }
 
+impl Lerp for SvgAnimValue {
+  fn lerp(&self, to: &Self, factor: f32) -> Self {
+    match (self, to) {
+      (SvgAnimValue::Number(from), SvgAnimValue::Number(to)) => {
+        SvgAnimValue::Number(from.lerp(to, factor))
+      }
+      (SvgAnimValue::Color(from), SvgAnimValue::Color(to)) => {
+        SvgAnimValue::Color(from.lerp(to, factor))
+      }
+      (SvgAnimValue::Transform(from), SvgAnimValue::Transform(to)) => {
+        SvgAnimValue::Transform([0, 1, 2].map(|i| from[i].lerp(&to[i], factor)))
+      }
+      _ if factor < 0.5 => self.clone(),
+      _ => to.clone(),
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
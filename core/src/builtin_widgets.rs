@@ -12,6 +12,7 @@ use std::ops::DerefMut;
 pub use key::{Key, KeyWidget};
 pub use painting_style::*;
 pub mod image_widget;
+pub use image_widget::*;
 pub mod keep_alive;
 pub use keep_alive::*;
 mod theme;
@@ -42,6 +43,10 @@ mod unconstrained_box;
 pub use unconstrained_box::*;
 mod opacity;
 pub use opacity::*;
+mod blur;
+pub use blur::*;
+mod blend_mode;
+pub use blend_mode::*;
 mod anchor;
 pub use anchor::*;
 mod layout_box;
@@ -130,6 +135,8 @@ pub struct FatObj<T> {
   margin: Option<State<Margin>>,
   transform: Option<State<TransformWidget>>,
   opacity: Option<State<Opacity>>,
+  blur: Option<State<Blur>>,
+  blend_mode: Option<State<BlendModeWidget>>,
   visibility: Option<State<Visibility>>,
   h_align: Option<State<HAlignWidget>>,
   v_align: Option<State<VAlignWidget>>,
@@ -173,6 +180,8 @@ impl<T> FatObj<T> {
       text_style: self.text_style,
       visibility: self.visibility,
       opacity: self.opacity,
+      blur: self.blur,
+      blend_mode: self.blend_mode,
       tooltips: self.tooltips,
       keep_alive: self.keep_alive,
       keep_alive_unsubscribe_handle: self.keep_alive_unsubscribe_handle,
@@ -203,6 +212,8 @@ impl<T> FatObj<T> {
       && self.text_style.is_none()
       && self.visibility.is_none()
       && self.opacity.is_none()
+      && self.blur.is_none()
+      && self.blend_mode.is_none()
       && self.keep_alive.is_none()
       && self.tooltips.is_none()
   }
@@ -418,6 +429,22 @@ impl<T> FatObj<T> {
       .get_or_insert_with(|| State::value(<_>::default()))
   }
 
+  /// Returns the `State<Blur>` widget from the FatObj. If it doesn't exist, a
+  /// new one will be created.
+  pub fn get_blur_widget(&mut self) -> &State<Blur> {
+    self
+      .blur
+      .get_or_insert_with(|| State::value(<_>::default()))
+  }
+
+  /// Returns the `State<BlendModeWidget>` widget from the FatObj. If it
+  /// doesn't exist, a new one will be created.
+  pub fn get_blend_mode_widget(&mut self) -> &State<BlendModeWidget> {
+    self
+      .blend_mode
+      .get_or_insert_with(|| State::value(<_>::default()))
+  }
+
   /// Returns the `State<KeepAlive>` widget from the FatObj. If it doesn't
   /// exist, a new one will be created.
   pub fn get_keep_alive_widget(&mut self) -> &State<KeepAlive> {
@@ -790,6 +817,23 @@ impl<T> FatObj<T> {
     self.declare_builtin_init(v, Self::get_text_style_widget, |m, v| m.text_style.overflow = v)
   }
 
+  /// Initializes the text decoration of this widget.
+  pub fn text_decoration<const M: usize>(
+    self, v: impl DeclareInto<TextDecorationStyle, M>,
+  ) -> Self {
+    self.declare_builtin_init(v, Self::get_text_style_widget, |m, v| m.text_style.decoration = v)
+  }
+
+  /// Initializes the max lines of the text of this widget.
+  pub fn text_max_lines<const M: usize>(self, v: impl DeclareInto<Option<usize>, M>) -> Self {
+    self.declare_builtin_init(v, Self::get_text_style_widget, |m, v| m.text_style.max_lines = v)
+  }
+
+  /// Initializes how to hyphenate the words of the text of this widget.
+  pub fn text_hyphens<const M: usize>(self, v: impl DeclareInto<Hyphens, M>) -> Self {
+    self.declare_builtin_init(v, Self::get_text_style_widget, |m, v| m.text_style.hyphens = v)
+  }
+
   /// Initializes the background of the widget.
   pub fn background<const M: usize>(self, v: impl DeclareInto<Option<Brush>, M>) -> Self {
     self.declare_builtin_init(v, Self::get_box_decoration_widget, |m, v| m.background = v)
@@ -810,6 +854,11 @@ impl<T> FatObj<T> {
     self.declare_builtin_init(v, Self::get_box_decoration_widget, |m, v| m.border_radius = v)
   }
 
+  /// Initializes the shadow of the widget box.
+  pub fn box_shadow<const M: usize>(self, v: impl DeclareInto<Option<Shadow>, M>) -> Self {
+    self.declare_builtin_init(v, Self::get_box_decoration_widget, |m, v| m.box_shadow = v)
+  }
+
   /// Initializes the extra space within the widget.
   pub fn padding<const M: usize>(self, v: impl DeclareInto<EdgeInsets, M>) -> Self {
     self.declare_builtin_init(v, Self::get_padding_widget, |m, v| m.padding = v)
@@ -875,6 +924,21 @@ impl<T> FatObj<T> {
     self.declare_builtin_init(v, Self::get_opacity_widget, |m, v| m.opacity = v)
   }
 
+  /// Initializes the gaussian blur radius of the widget content.
+  pub fn blur<const M: usize>(self, v: impl DeclareInto<f32, M>) -> Self {
+    self.declare_builtin_init(v, Self::get_blur_widget, |m, v| m.blur = v)
+  }
+
+  /// Initializes the gaussian blur radius of the backdrop behind the widget.
+  pub fn backdrop_blur<const M: usize>(self, v: impl DeclareInto<f32, M>) -> Self {
+    self.declare_builtin_init(v, Self::get_blur_widget, |m, v| m.backdrop_blur = v)
+  }
+
+  /// Initializes how the widget is blended with the backdrop.
+  pub fn blend_mode<const M: usize>(self, v: impl DeclareInto<BlendMode, M>) -> Self {
+    self.declare_builtin_init(v, Self::get_blend_mode_widget, |m, v| m.blend_mode = v)
+  }
+
   /// Initializes the tooltips of the widget.
   pub fn tooltips<const M: usize>(self, v: impl DeclareInto<CowArc<str>, M>) -> Self {
     self.declare_builtin_init(v, Self::get_tooltips_widget, |m, v| m.tooltips = v)
@@ -957,6 +1021,7 @@ impl<'a> FatObj<Widget<'a>> {
           padding,
           fitted_box,
           box_decoration,
+          blur,
           foreground,
           painting_style,
           text_style,
@@ -971,6 +1036,7 @@ impl<'a> FatObj<Widget<'a>> {
           request_focus,
           transform,
           opacity,
+          blend_mode,
           visibility,
           h_align,
           v_align,
@@ -0,0 +1,66 @@
+use crate::{prelude::*, wrap_render::*};
+
+/// A wrapper that sets how the widget and its descendants are blended with
+/// the backdrop. Like the opacity, the blend mode applies to every path
+/// painted by them.
+#[derive(Clone, Default)]
+pub struct BlendModeWidget {
+  pub blend_mode: BlendMode,
+}
+
+impl Declare for BlendModeWidget {
+  type Builder = FatObj<()>;
+  #[inline]
+  fn declarer() -> Self::Builder { FatObj::new(()) }
+}
+
+impl_compose_child_for_wrap_render!(BlendModeWidget);
+
+impl WrapRender for BlendModeWidget {
+  fn perform_layout(&self, clamp: BoxClamp, host: &dyn Render, ctx: &mut LayoutCtx) -> Size {
+    host.perform_layout(clamp, ctx)
+  }
+
+  fn paint(&self, host: &dyn Render, ctx: &mut PaintingCtx) {
+    ctx.painter().set_blend_mode(self.blend_mode);
+    host.paint(ctx)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_dev_helper::*;
+
+  use super::*;
+  use crate::{reset_test_env, test_helper::*};
+
+  #[test]
+  #[cfg(not(target_arch = "wasm32"))]
+  fn multiply_children() {
+    reset_test_env!();
+
+    assert_widget_eq_image!(
+      WidgetTester::new(fn_widget! {
+        @MockStack {
+          @MockBox {
+            size: Size::new(100., 60.),
+            background: Color::from_rgb(255, 200, 64),
+          }
+          @MockBox {
+            size: Size::new(60., 40.),
+            margin: EdgeInsets::all(10.),
+            blend_mode: BlendMode::Multiply,
+            @MockBox {
+              size: Size::new(40., 40.),
+              margin: EdgeInsets::only_left(20.),
+              background: Color::from_rgb(64, 160, 224),
+            }
+          }
+        }
+      })
+      .with_wnd_size(Size::new(100., 60.))
+      .with_comparison(0.002),
+      "multiply_children"
+    );
+  }
+}
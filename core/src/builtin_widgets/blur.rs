@@ -0,0 +1,88 @@
+use crate::{prelude::*, wrap_render::*};
+
+/// A wrapper that applies a gaussian blur to its content or to the backdrop
+/// behind it, the blur radius is in logical pixels and `0.` means no blur.
+///
+/// The backdrop is blurred within the box of the widget, so a panel with a
+/// translucent background over it looks like a frosted glass.
+#[derive(Clone, Default)]
+pub struct Blur {
+  /// The blur radius of the widget content.
+  pub blur: f32,
+  /// The blur radius of the backdrop behind the widget.
+  pub backdrop_blur: f32,
+}
+
+impl Declare for Blur {
+  type Builder = FatObj<()>;
+  #[inline]
+  fn declarer() -> Self::Builder { FatObj::new(()) }
+}
+
+impl_compose_child_for_wrap_render!(Blur);
+
+impl WrapRender for Blur {
+  fn perform_layout(&self, clamp: BoxClamp, host: &dyn Render, ctx: &mut LayoutCtx) -> Size {
+    host.perform_layout(clamp, ctx)
+  }
+
+  fn paint(&self, host: &dyn Render, ctx: &mut PaintingCtx) {
+    let size = ctx.box_size().unwrap();
+    let rect = Rect::from_size(size);
+    let painter = ctx.painter();
+    if self.backdrop_blur > 0. && !size.is_empty() {
+      let path = PaintPath::Share(Resource::new(Path::rect(&rect)));
+      painter.blur_layer(path, self.backdrop_blur, BlurTarget::Backdrop);
+    }
+    if self.blur > 0. {
+      let path = PaintPath::Share(Resource::new(Path::rect(&rect)));
+      painter.blur_layer(path, self.blur, BlurTarget::Content);
+    }
+    host.paint(ctx)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_dev_helper::*;
+
+  use super::*;
+  use crate::{reset_test_env, test_helper::*};
+
+  #[test]
+  #[cfg(not(target_arch = "wasm32"))]
+  fn blur_and_backdrop_blur() {
+    reset_test_env!();
+
+    assert_widget_eq_image!(
+      WidgetTester::new(fn_widget! {
+        let stripe = |color: Color| mock_box! {
+          size: Size::new(10., 100.),
+          background: color,
+        };
+        @MockStack {
+          @MockMulti {
+            @ { (0..8).map(move |i| {
+              stripe(if i % 2 == 0 { Color::RED } else { Color::BLUE })
+            })}
+          }
+          @MockBox {
+            size: Size::new(60., 60.),
+            margin: EdgeInsets::all(10.),
+            backdrop_blur: 6.,
+            background: Color::WHITE.with_alpha(0.3),
+          }
+          @MockBox {
+            size: Size::new(20., 20.),
+            margin: EdgeInsets { left: 110., top: 40., ..EdgeInsets::ZERO },
+            blur: 4.,
+            background: Color::BLACK,
+          }
+        }
+      })
+      .with_wnd_size(Size::new(160., 100.))
+      .with_comparison(0.002),
+      "blur_and_backdrop_blur"
+    );
+  }
+}
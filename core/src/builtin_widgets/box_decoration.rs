@@ -1,8 +1,8 @@
 use std::ops::Range;
 
 use crate::{prelude::*, wrap_render::*};
-/// The BoxDecoration provides configuration options to draw the background and
-/// border of a box.
+/// The BoxDecoration provides configuration options to draw the shadow,
+/// background and border of a box.
 ///
 /// If a background color is specified, a derived foreground calculation from
 /// the background will be applied to its children.
@@ -15,6 +15,9 @@ pub struct BoxDecoration {
   /// The corners of this box are rounded by this `BorderRadius`. The round
   /// corner only work if the two borders beside it are same style.
   pub border_radius: Option<Radius>,
+  /// The shadow cast by the box, painted below the background. It follows the
+  /// `border_radius` of the box and may paint outside the box.
+  pub box_shadow: Option<Shadow>,
 }
 
 impl Declare for BoxDecoration {
@@ -31,15 +34,62 @@ pub struct Border {
   pub bottom: BorderSide,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Lerp)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct BorderSide {
   pub color: Brush,
   pub width: f32,
+  /// The lengths of the alternating dashes and gaps of the side, an empty
+  /// array draws a solid side. See [`StrokeOptions::dash_array`].
+  pub dash_array: Vec<f32>,
+  /// The distance into the dash pattern at which the dashing starts.
+  pub dash_offset: f32,
 }
 
 impl BorderSide {
   #[inline]
-  pub fn new(width: f32, color: Brush) -> Self { Self { width, color } }
+  pub fn new(width: f32, color: Brush) -> Self { Self { width, color, ..Default::default() } }
+
+  /// Returns the side drawn with the dash pattern. The side is stroked with
+  /// butt caps, so a dotted side needs short dashes, like `[2., 2.]` for a side
+  /// of width `2.`, rather than the zero length ones.
+  pub fn with_dash(mut self, dash_array: impl Into<Vec<f32>>, dash_offset: f32) -> Self {
+    self.dash_array = dash_array.into();
+    self.dash_offset = dash_offset;
+    self
+  }
+
+  fn is_dashed(&self) -> bool { self.width > 0. && !self.dash_array.is_empty() }
+
+  fn stroke_options(&self) -> StrokeOptions {
+    StrokeOptions {
+      width: self.width,
+      dash_array: self.dash_array.clone(),
+      dash_offset: self.dash_offset,
+      ..Default::default()
+    }
+  }
+}
+
+impl Lerp for BorderSide {
+  fn lerp(&self, to: &Self, factor: f32) -> Self {
+    // Only the patterns with the same number of lengths can be interpolated.
+    let dash_array = if self.dash_array.len() == to.dash_array.len() {
+      self
+        .dash_array
+        .iter()
+        .zip(&to.dash_array)
+        .map(|(from, to)| from.lerp(to, factor))
+        .collect()
+    } else {
+      to.dash_array.clone()
+    };
+    Self {
+      color: self.color.lerp(&to.color, factor),
+      width: self.width.lerp(&to.width, factor),
+      dash_array,
+      dash_offset: self.dash_offset.lerp(&to.dash_offset, factor),
+    }
+  }
 }
 
 impl_compose_child_for_wrap_render!(BoxDecoration);
@@ -59,6 +109,9 @@ impl WrapRender for BoxDecoration {
     if !size.is_empty() {
       let rect = Rect::from_size(size);
       let painter = ctx.painter();
+      if let Some(shadow) = &self.box_shadow {
+        painter.draw_box_shadow(&rect, &self.limited_radius(size), shadow);
+      }
       if let Some(ref background) = self.background {
         painter.set_fill_brush(background.clone());
         if let Some(radius) = &self.border_radius {
@@ -84,32 +137,17 @@ impl WrapRender for BoxDecoration {
 
 impl BoxDecoration {
   fn paint_border(&self, painter: &mut Painter, size: Size) {
-    // Connecting adjacent borders implies that the styles of the neighboring
-    // borders should match. If one of the adjacent borders is absent, the corner
-    // radius will align with the existing border.
-
     let Some(border) = self.border.as_ref() else {
       return;
     };
-    let first = border
-      .find_visible(SidePos::Top..SidePos::Top)
-      .map(|side| border.expand_continuous(side));
-
-    if let Some(rg) = first {
-      let old_brush = painter.fill_brush().clone();
-      let radius = self.limited_radius(size);
-      border.paint_continuous_borders(size, &rg, &radius, painter);
-
-      // if the first continuous border only has one side, there maybe existing
-      // another border on its opposite side
-      if rg.start.next() == rg.end {
-        let opposite = rg.end.next();
-        if let Some(side) = border.find_visible(opposite..opposite.next()) {
-          border.paint_continuous_borders(size, &(side..side.next()), &radius, painter);
-        }
-      }
-
-      painter.set_fill_brush(old_brush);
+    let radius = self.limited_radius(size);
+    if [&border.top, &border.right, &border.bottom, &border.left]
+      .iter()
+      .any(|side| side.is_dashed())
+    {
+      border.paint_dashed(size, &radius, painter);
+    } else {
+      border.paint_solid(size, &radius, painter);
     }
   }
 
@@ -152,6 +190,91 @@ impl Border {
 }
 
 impl Border {
+  fn paint_solid(&self, size: Size, radius: &Radius, painter: &mut Painter) {
+    // Connecting adjacent borders implies that the styles of the neighboring
+    // borders should match. If one of the adjacent borders is absent, the corner
+    // radius will align with the existing border.
+    let first = self
+      .find_visible(SidePos::Top..SidePos::Top)
+      .map(|side| self.expand_continuous(side));
+
+    if let Some(rg) = first {
+      let old_brush = painter.fill_brush().clone();
+      self.paint_continuous_borders(size, &rg, radius, painter);
+
+      // if the first continuous border only has one side, there maybe existing
+      // another border on its opposite side
+      if rg.start.next() == rg.end {
+        let opposite = rg.end.next();
+        if let Some(side) = self.find_visible(opposite..opposite.next()) {
+          self.paint_continuous_borders(size, &(side..side.next()), radius, painter);
+        }
+      }
+
+      painter.set_fill_brush(old_brush);
+    }
+  }
+
+  /// The dashed sides are stroked along their center line. If all the sides
+  /// are the same, the border is stroked as a whole, so the dashes run around
+  /// the rounded corners.
+  fn paint_dashed(&self, size: Size, radius: &Radius, painter: &mut Painter) {
+    painter.save();
+    let Self { left, right, top, bottom } = self;
+    if left == right && left == top && left == bottom {
+      let half = top.width / 2.;
+      let rect = Rect::new(
+        Point::splat(half),
+        Size::new(size.width - top.width, size.height - top.width).max(Size::zero()),
+      );
+      let Radius { top_left, top_right, bottom_left, bottom_right } = *radius;
+      let radius = Radius::new(
+        (top_left - half).max(0.),
+        (top_right - half).max(0.),
+        (bottom_left - half).max(0.),
+        (bottom_right - half).max(0.),
+      );
+      painter
+        .set_stroke_brush(top.color.clone())
+        .set_strokes(top.stroke_options())
+        .rect_round(&rect, &radius)
+        .stroke();
+    } else {
+      let solid = |side: &BorderSide| if side.is_dashed() { <_>::default() } else { side.clone() };
+      let solid_border =
+        Border { left: solid(left), right: solid(right), top: solid(top), bottom: solid(bottom) };
+      solid_border.paint_solid(size, radius, painter);
+
+      for pos in [SidePos::Top, SidePos::Right, SidePos::Bottom, SidePos::Left] {
+        let side = self.get_side(pos);
+        if !side.is_dashed() {
+          continue;
+        }
+        let half = side.width / 2.;
+        let (from, to) = match pos {
+          SidePos::Top => (Point::new(0., half), Point::new(size.width, half)),
+          SidePos::Right => {
+            let x = size.width - half;
+            (Point::new(x, 0.), Point::new(x, size.height))
+          }
+          SidePos::Bottom => {
+            let y = size.height - half;
+            (Point::new(size.width, y), Point::new(0., y))
+          }
+          SidePos::Left => (Point::new(half, size.height), Point::new(half, 0.)),
+        };
+        painter
+          .set_stroke_brush(side.color.clone())
+          .set_strokes(side.stroke_options())
+          .begin_path(from)
+          .line_to(to)
+          .end_path(false)
+          .stroke();
+      }
+    }
+    painter.restore();
+  }
+
   fn paint_continuous_borders(
     &self, size: Size, rg: &Range<SidePos>, radius: &Radius, painter: &mut Painter,
   ) {
@@ -537,6 +660,7 @@ mod tests {
     assert_eq!(w.read().border, None);
     assert_eq!(w.read().border_radius, None);
     assert_eq!(w.read().background, None);
+    assert_eq!(w.read().box_shadow, None);
 
     std::mem::forget(ctx);
   }
@@ -798,4 +922,108 @@ mod tests {
       "all_borders"
     );
   }
+
+  #[test]
+  #[cfg(not(target_arch = "wasm32"))]
+  fn dashed_borders() {
+    reset_test_env!();
+
+    fn dashed() -> BorderSide { BorderSide::new(4., Color::RED.into()).with_dash([8., 4.], 0.) }
+    assert_widget_eq_image!(
+      WidgetTester::new(mock_multi! {
+        // all dashed with radius, like a focus ring
+        @MockBox {
+          size: Size::new(100., 50.),
+          margin: EdgeInsets::all(10.),
+          border: Border::all(dashed()),
+          border_radius: Radius::all(10.),
+        }
+        // only the bottom is dashed
+        @MockBox {
+          size: Size::new(100., 50.),
+          margin: EdgeInsets::all(10.),
+          border: Border {
+            bottom: dashed(),
+            ..Border::all(BorderSide::new(4., Color::BLUE.into()))
+          },
+        }
+        // dotted top
+        @MockBox {
+          size: Size::new(100., 50.),
+          margin: EdgeInsets::all(10.),
+          border: Border::only_top(dashed().with_dash([4.], 0.)),
+        }
+      })
+      .with_wnd_size(Size::new(400., 80.))
+      .with_comparison(0.0005),
+      "dashed_borders"
+    );
+  }
+
+  #[test]
+  #[cfg(not(target_arch = "wasm32"))]
+  fn box_shadow() {
+    reset_test_env!();
+
+    let shadow = Shadow::new(Vector::new(0., 4.), 8., 0., Color::BLACK.with_alpha(0.5));
+    assert_widget_eq_image!(
+      WidgetTester::new(mock_multi! {
+        @MockBox {
+          size: Size::new(100., 50.),
+          margin: EdgeInsets::all(20.),
+          background: Color::WHITE,
+          box_shadow: shadow,
+        }
+        @MockBox {
+          size: Size::new(100., 50.),
+          margin: EdgeInsets::all(20.),
+          background: Color::WHITE,
+          border_radius: Radius::all(10.),
+          box_shadow: Shadow { spread: 4., ..shadow },
+        }
+      })
+      .with_wnd_size(Size::new(280., 100.))
+      .with_comparison(0.0005),
+      "box_shadow"
+    );
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn bubble() -> NinePatch {
+    // A 5x5 bubble, its 2 pixels wide frame is dark and the center is light.
+    let mut data = vec![];
+    for y in 0..5 {
+      for x in 0..5 {
+        let frame = x != 2 || y != 2;
+        data.extend(if frame { [64, 96, 160, 255] } else { [224, 236, 255, 255] });
+      }
+    }
+    let img = Resource::new(PixelImage::new(data.into(), 5, 5, image::ColorFormat::Rgba8));
+    NinePatch::new(img, 2, 2, 2, 2)
+  }
+
+  #[test]
+  #[cfg(not(target_arch = "wasm32"))]
+  fn nine_patch_background() {
+    reset_test_env!();
+
+    assert_widget_eq_image!(
+      WidgetTester::new(mock_multi! {
+        @MockBox {
+          size: Size::new(100., 50.),
+          margin: EdgeInsets::all(10.),
+          background: bubble(),
+        }
+        @MockBox {
+          size: Size::new(60., 80.),
+          margin: EdgeInsets::all(10.),
+          background: bubble(),
+          border_radius: Radius::all(10.),
+        }
+      })
+      .with_wnd_size(Size::new(200., 100.))
+      .with_comparison(0.0005),
+      "nine_patch_background"
+    );
+  }
 }
@@ -16,3 +16,279 @@ impl Render for Resource<PixelImage> {
     }
   }
 }
+
+/// How the image is resized to fit the box of the [`Image`] widget.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ObjectFit {
+  /// The image is stretched to fill the box, its aspect ratio is not kept.
+  Fill,
+  /// The image is scaled to maintain its aspect ratio while fitting within the
+  /// box, so the entire image is visible.
+  #[default]
+  Contain,
+  /// The image is scaled to maintain its aspect ratio while filling the box,
+  /// the parts out of the box are clipped.
+  Cover,
+  /// The same as [`ObjectFit::Contain`], but the image is never scaled up.
+  ScaleDown,
+  /// The image is not resized, it keeps its natural size.
+  None,
+}
+
+/// How the image is repeated to cover the box of the [`Image`] widget.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ImageRepeat {
+  /// The image is painted once.
+  #[default]
+  NoRepeat,
+  /// The image is repeated in both directions.
+  Repeat,
+  /// The image is repeated in the horizontal direction.
+  RepeatX,
+  /// The image is repeated in the vertical direction.
+  RepeatY,
+}
+
+/// A widget that displays an image, the image is resized by the `fit` and
+/// placed in its box by the `x_align` and `y_align`.
+///
+/// The image is scaled down to fit the max size its parent allowed, and
+/// keeps its natural size otherwise.
+///
+/// # Example
+///
+/// ```
+/// use ribir_core::prelude::*;
+///
+/// fn pixel_art(src: Resource<PixelImage>) -> Widget<'static> {
+///   fn_widget! {
+///     @Image {
+///       src: src.clone(),
+///       fit: ObjectFit::Cover,
+///       filter_quality: FilterQuality::Nearest,
+///       clamp: BoxClamp::fixed_size(Size::new(128., 128.)),
+///     }
+///   }
+///   .into_widget()
+/// }
+/// ```
+#[derive(Declare, Clone)]
+pub struct Image {
+  pub src: Resource<PixelImage>,
+  #[declare(default)]
+  pub fit: ObjectFit,
+  #[declare(default = Align::Center)]
+  pub x_align: Align,
+  #[declare(default = Align::Center)]
+  pub y_align: Align,
+  #[declare(default)]
+  pub repeat: ImageRepeat,
+  #[declare(default)]
+  pub filter_quality: FilterQuality,
+}
+
+impl Image {
+  fn natural_size(&self) -> Size { Size::new(self.src.width() as f32, self.src.height() as f32) }
+
+  /// The rect the image is painted in a box of `box_size`, without repeating.
+  pub fn image_rect(&self, box_size: Size) -> Rect {
+    let natural = self.natural_size();
+    if natural.is_empty() {
+      return Rect::zero();
+    }
+    let (sx, sy) = (box_size.width / natural.width, box_size.height / natural.height);
+    let size = match self.fit {
+      ObjectFit::Fill => box_size,
+      ObjectFit::Contain => natural * sx.min(sy),
+      ObjectFit::Cover => natural * sx.max(sy),
+      ObjectFit::ScaleDown => natural * sx.min(sy).min(1.),
+      ObjectFit::None => natural,
+    };
+    let x = self
+      .x_align
+      .align_value(size.width, box_size.width);
+    let y = self
+      .y_align
+      .align_value(size.height, box_size.height);
+    Rect::new(Point::new(x, y), size)
+  }
+}
+
+impl Render for Image {
+  fn perform_layout(&self, clamp: BoxClamp, _: &mut LayoutCtx) -> Size {
+    let natural = self.natural_size();
+    if natural.is_empty() {
+      return clamp.min;
+    }
+    let scale = (clamp.max.width / natural.width)
+      .min(clamp.max.height / natural.height)
+      .min(1.);
+    clamp.clamp(natural * scale)
+  }
+
+  fn paint(&self, ctx: &mut PaintingCtx) {
+    let box_rect = Rect::from_size(ctx.box_size().unwrap());
+    let img_rect = self.image_rect(box_rect.size);
+    if img_rect.is_empty() {
+      return;
+    }
+
+    let (repeat_x, repeat_y) = match self.repeat {
+      ImageRepeat::NoRepeat => (false, false),
+      ImageRepeat::Repeat => (true, true),
+      ImageRepeat::RepeatX => (true, false),
+      ImageRepeat::RepeatY => (false, true),
+    };
+    let mut area = img_rect;
+    if repeat_x {
+      area.origin.x = box_rect.min_x();
+      area.size.width = box_rect.width();
+    }
+    if repeat_y {
+      area.origin.y = box_rect.min_y();
+      area.size.height = box_rect.height();
+    }
+    let Some(area) = area.intersection(&box_rect) else { return };
+
+    // Start the tiles from a tile that covers the start of the area, so the
+    // image is sampled with the positive positions.
+    let tile_start = |img: f32, area: f32, size: f32| img - ((img - area) / size).ceil() * size;
+    let x = tile_start(img_rect.min_x(), area.min_x(), img_rect.width());
+    let y = tile_start(img_rect.min_y(), area.min_y(), img_rect.height());
+    let natural = self.natural_size();
+    let sx = img_rect.width() / natural.width;
+    let sy = img_rect.height() / natural.height;
+    let fill_rect = Rect::new(
+      Point::new((area.min_x() - x) / sx, (area.min_y() - y) / sy),
+      Size::new(area.width() / sx, area.height() / sy),
+    );
+
+    ctx
+      .painter()
+      .set_filter_quality(self.filter_quality)
+      .translate(x, y)
+      .scale(sx, sy)
+      .rect(&fill_rect)
+      .set_fill_brush(self.src.clone())
+      .fill();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_dev_helper::*;
+
+  use super::*;
+  use crate::{reset_test_env, test_helper::*};
+
+  fn image(width: u32, height: u32, fit: ObjectFit) -> Image {
+    let data = vec![255; (width * height * 4) as usize];
+    Image {
+      src: Resource::new(PixelImage::new(data.into(), width, height, image::ColorFormat::Rgba8)),
+      fit,
+      x_align: Align::Center,
+      y_align: Align::Center,
+      repeat: ImageRepeat::NoRepeat,
+      filter_quality: FilterQuality::Linear,
+    }
+  }
+
+  #[test]
+  fn fit_rects() {
+    let box_size = Size::new(100., 50.);
+    let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+
+    assert_eq!(image(20, 20, ObjectFit::Fill).image_rect(box_size), rect(0., 0., 100., 50.));
+    assert_eq!(image(20, 20, ObjectFit::Contain).image_rect(box_size), rect(25., 0., 50., 50.));
+    assert_eq!(image(20, 20, ObjectFit::Cover).image_rect(box_size), rect(0., -25., 100., 100.));
+    assert_eq!(image(20, 20, ObjectFit::ScaleDown).image_rect(box_size), rect(40., 15., 20., 20.));
+    assert_eq!(image(200, 200, ObjectFit::ScaleDown).image_rect(box_size), rect(25., 0., 50., 50.));
+    assert_eq!(image(200, 20, ObjectFit::None).image_rect(box_size), rect(-50., 15., 200., 20.));
+
+    let mut img = image(20, 20, ObjectFit::None);
+    img.x_align = Align::Start;
+    img.y_align = Align::End;
+    assert_eq!(img.image_rect(box_size), rect(0., 30., 20., 20.));
+  }
+
+  #[test]
+  fn keep_aspect_ratio_in_layout() {
+    reset_test_env!();
+
+    let mut wnd = TestWindow::new_with_size(
+      fn_widget! {
+        @MockMulti {
+          @Image { src: image(200, 100, ObjectFit::Contain).src }
+          @Image { src: image(20, 10, ObjectFit::Contain).src }
+        }
+      },
+      Size::new(100., 100.),
+    );
+    wnd.draw_frame();
+    wnd.assert_root_size(Size::new(120., 50.));
+    assert_eq!(wnd.layout_info_by_path(&[0, 0]).unwrap().size, Some(Size::new(100., 50.)));
+    assert_eq!(wnd.layout_info_by_path(&[0, 1]).unwrap().size, Some(Size::new(20., 10.)));
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn rows_image() -> Resource<PixelImage> {
+    // A 2x3 image, the first row is red and blue, the others are green and white.
+    let mut data = vec![];
+    for row in 0..3 {
+      let pixels: [[u8; 4]; 2] = if row == 0 {
+        [[255, 0, 0, 255], [0, 0, 255, 255]]
+      } else {
+        [[0, 255, 0, 255], [255, 255, 255, 255]]
+      };
+      data.extend(pixels.iter().flatten());
+    }
+    Resource::new(PixelImage::new(data.into(), 2, 3, image::ColorFormat::Rgba8))
+  }
+
+  #[test]
+  #[cfg(not(target_arch = "wasm32"))]
+  fn fit_and_repeat() {
+    reset_test_env!();
+
+    assert_widget_eq_image!(
+      WidgetTester::new(fn_widget! {
+        let src = rows_image();
+        let size = BoxClamp::fixed_size(Size::new(40., 30.));
+        @MockMulti {
+          @Image {
+            src: src.clone(),
+            clamp: size,
+            filter_quality: FilterQuality::Nearest,
+          }
+          @Image {
+            src: src.clone(),
+            clamp: size,
+            fit: ObjectFit::Cover,
+            filter_quality: FilterQuality::Nearest,
+          }
+          @Image {
+            src: src.clone(),
+            clamp: size,
+            fit: ObjectFit::Fill,
+          }
+          @Image {
+            src: src.clone(),
+            clamp: size,
+            fit: ObjectFit::None,
+            repeat: ImageRepeat::Repeat,
+            filter_quality: FilterQuality::Nearest,
+          }
+          @Image {
+            src: src.clone(),
+            clamp: size,
+            repeat: ImageRepeat::RepeatX,
+            filter_quality: FilterQuality::Nearest,
+          }
+        }
+      })
+      .with_wnd_size(Size::new(200., 30.))
+      .with_comparison(0.002),
+      "image_fit_and_repeat"
+    );
+  }
+}
@@ -1,12 +1,152 @@
 use crate::prelude::*;
 
-impl Render for Svg {
+/// The widget of a SVG, it plays the SMIL animations of the SVG by the
+/// animation system, the SVG without animation is painted statically.
+struct SvgPlayer {
+  svg: Resource<Svg>,
+  /// The progress of every animation in its simple duration, `None` if the
+  /// animation isn't applied now.
+  progress: Box<[Option<f32>]>,
+}
+
+impl IntoWidgetStrict<'static, RENDER> for Svg {
+  fn into_widget_strict(self) -> Widget<'static> { Resource::new(self).into_widget_strict() }
+}
+
+impl IntoWidgetStrict<'static, RENDER> for Resource<Svg> {
+  fn into_widget_strict(self) -> Widget<'static> {
+    if self.animations().is_empty() {
+      return SvgPlayer { svg: self, progress: Box::new([]) }.into_widget();
+    }
+
+    fn_widget! {
+      let anims = self.animations();
+      let progress = anims.iter().map(end_progress).collect();
+      let player = Stateful::new(SvgPlayer { svg: self.clone(), progress });
+      let animates: Vec<_> = anims
+        .iter()
+        .enumerate()
+        .map(|(i, anim)| {
+          let state = player.map_writer(move |p| PartData::from_ref_mut(&mut p.progress[i]));
+          let animate = @Animate {
+            transition: anim_transition(anim),
+            state: LerpFnState::new(state, |_, _, rate| Some(rate)),
+            from: None,
+          };
+          animate.run();
+          animate
+        })
+        .collect();
+
+      let player = FatObj::new(player);
+      @ $player {
+        on_disposed: move |_| animates.iter().for_each(|a| a.stop()),
+      }
+    }
+    .into_widget()
+  }
+}
+
+/// The progress of the animation after it ends, `None` if it goes back to the
+/// base value.
+fn end_progress(anim: &SvgAnimation) -> Option<f32> {
+  let repeat = anim.repeat.unwrap_or(f32::INFINITY);
+  if !anim.freeze {
+    None
+  } else if repeat.fract() > 0. {
+    Some(repeat.fract())
+  } else {
+    Some(1.)
+  }
+}
+
+fn anim_transition(anim: &SvgAnimation) -> Box<dyn Transition> {
+  let repeat = anim.repeat.unwrap_or(f32::INFINITY);
+  let simple = EasingTransition { duration: anim.dur, easing: easing::LINEAR }.repeat(repeat);
+  if anim.elapsed.is_zero() {
+    simple.delay(anim.begin).box_it()
+  } else {
+    SeekTransition { elapsed: anim.elapsed, transition: simple }.box_it()
+  }
+}
+
+/// The transition starts from the middle, for the animations have begun before
+/// the SVG is played.
+struct SeekTransition<T> {
+  elapsed: Duration,
+  transition: T,
+}
+
+impl<T: Transition> Transition for SeekTransition<T> {
+  fn rate_of_change(&self, dur: Duration) -> AnimateProgress {
+    self.transition.rate_of_change(dur + self.elapsed)
+  }
+
+  fn duration(&self) -> Duration { self.transition.duration() - self.elapsed }
+}
+
+/// The value of the animation at the `progress` of its simple duration.
+fn anim_value(anim: &SvgAnimation, progress: f32) -> SvgAnimValue {
+  let values = &anim.values;
+  let last = values.len() - 1;
+  if last == 0 {
+    return values[0].clone();
+  }
+
+  let key_times = &anim.key_times;
+  if anim.calc_mode == SvgCalcMode::Discrete {
+    let idx = if key_times.is_empty() {
+      (progress * values.len() as f32) as usize
+    } else {
+      key_times
+        .iter()
+        .rposition(|t| *t <= progress)
+        .unwrap_or(0)
+    };
+    return values[idx.min(last)].clone();
+  }
+
+  let (idx, rate) = if key_times.is_empty() {
+    let pos = progress * last as f32;
+    let idx = (pos as usize).min(last - 1);
+    (idx, pos - idx as f32)
+  } else {
+    let idx = key_times[..last]
+      .iter()
+      .rposition(|t| *t <= progress)
+      .unwrap_or(0);
+    let (start, end) = (key_times[idx], key_times[idx + 1]);
+    (idx, if end > start { (progress - start) / (end - start) } else { 1. })
+  };
+  let rate = rate.clamp(0., 1.);
+  let rate = match anim.key_splines.get(idx) {
+    Some(&[x1, y1, x2, y2]) if anim.calc_mode == SvgCalcMode::Spline => {
+      easing::CubicBezierEasing::new(x1, y1, x2, y2).easing(rate)
+    }
+    _ => rate,
+  };
+  values[idx].lerp(&values[idx + 1], rate)
+}
+
+impl Render for SvgPlayer {
   #[inline]
-  fn perform_layout(&self, clamp: BoxClamp, _: &mut LayoutCtx) -> Size { clamp.clamp(self.size()) }
+  fn perform_layout(&self, clamp: BoxClamp, _: &mut LayoutCtx) -> Size {
+    clamp.clamp(self.svg.size())
+  }
 
   fn paint(&self, ctx: &mut PaintingCtx) {
-    let painter = ctx.painter();
-    painter.draw_svg(self);
+    let svg = &*self.svg;
+    let colors =
+      if svg.color_slots().is_empty() { vec![] } else { IconTheme::slot_colors_of(svg, &&*ctx) };
+    let values: Vec<_> = svg
+      .animations()
+      .iter()
+      .zip(self.progress.iter())
+      .map(|(anim, progress)| progress.map(|p| anim_value(anim, p)))
+      .collect();
+    ctx
+      .painter()
+      .draw_svg_frame(svg, &colors, &values);
   }
 }
 
@@ -55,6 +195,7 @@ mod tests {
   use ribir_dev_helper::*;
 
   use super::*;
+  use crate::{reset_test_env, test_helper::*, window::WindowFlags};
 
   fn svgs_smoke() -> Painter {
     named_svgs::register(
@@ -76,4 +217,101 @@ mod tests {
   }
 
   painter_backend_eq_image_test!(svgs_smoke, comparison = 0.001);
+
+  fn smil_animations(animations: &str) -> Svg {
+    let svg = format!(
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="8">
+        <rect width="8" height="8">{animations}</rect>
+      </svg>"#
+    );
+    Svg::parse_from_bytes(svg.as_bytes(), false, false).unwrap()
+  }
+
+  #[test]
+  fn smil_anim_values() {
+    let svg = smil_animations(
+      r#"<animate attributeName="opacity" values="0;1;0.5" keyTimes="0;0.8;1" dur="1s" />
+      <animate attributeName="opacity" values="0;1;0.5" calcMode="discrete" dur="1s" />
+      <animate attributeName="opacity" values="0;1" calcMode="spline" keyTimes="0;1"
+        keySplines="0.5 0 0.5 1" dur="1s" />
+      <animateTransform attributeName="transform" type="rotate" from="0 4 4" to="90 4 4"
+        dur="1s" />"#,
+    );
+    let [key_times, discrete, spline, rotate] = svg.animations() else {
+      panic!("expect 4 animations");
+    };
+    let number = |anim, progress| match anim_value(anim, progress) {
+      SvgAnimValue::Number(v) => v,
+      v => panic!("expect a number, but {v:?}"),
+    };
+    let eq = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+    assert!(eq(number(key_times, 0.4), 0.5));
+    assert!(eq(number(key_times, 0.9), 0.75));
+    assert!(eq(number(key_times, 1.), 0.5));
+
+    assert!(eq(number(discrete, 0.3), 0.));
+    assert!(eq(number(discrete, 0.5), 1.));
+    assert!(eq(number(discrete, 1.), 0.5));
+
+    assert!(number(spline, 0.25) < 0.25);
+    assert!(eq(number(spline, 0.5), 0.5));
+    assert!(number(spline, 0.75) > 0.75);
+
+    assert_eq!(anim_value(rotate, 0.5), SvgAnimValue::Transform([45., 4., 4.]));
+  }
+
+  #[test]
+  fn smil_play_until_disposed() {
+    reset_test_env!();
+
+    let svg = smil_animations(
+      r#"<animate attributeName="opacity" from="1" to="0" dur="1s" repeatCount="indefinite" />"#,
+    );
+    let show = Stateful::new(true);
+    let c_show = show.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! {
+      let svg = svg.clone();
+      @MockMulti {
+        @ { pipe!(*$show).map(move |show| show.then(|| svg.clone())) }
+      }
+    });
+    wnd.set_flags(WindowFlags::ANIMATIONS);
+    wnd.draw_frame();
+    assert_eq!(wnd.running_animates.get(), 1);
+
+    *c_show.write() = false;
+    wnd.draw_frame();
+    assert_eq!(wnd.running_animates.get(), 0);
+  }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod color_slots_tests {
+  use ribir::{core::test_helper::*, material as ribir_material, prelude::*};
+  use ribir_dev_helper::*;
+
+  const TWO_TONE_ICON: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="48">
+    <circle cx="24" cy="24" r="20" fill="var(--primary-container)" />
+    <path d="M14 24l7 7 13-14" fill="none" stroke-width="4" stroke="var(--accent, #FF5722)" />
+  </svg>"#;
+
+  widget_image_tests!(
+    svg_color_slots,
+    WidgetTester::new(fn_widget! {
+      let icon = Svg::parse_from_bytes(TWO_TONE_ICON.as_bytes(), false, false).unwrap();
+      let mut icon_theme = IconTheme::new(IconSize::of(BuildCtx::get()).clone());
+      icon_theme.set_color("accent", Color::from_rgb(0, 150, 136));
+      let themed = icon.clone();
+
+      @MockMulti {
+        @ { icon }
+        @ {
+          Provider::new(Box::new(Queryable(icon_theme))).with_child(fn_widget! { themed })
+        }
+      }
+    })
+    .with_wnd_size(Size::new(96., 48.))
+    .with_comparison(0.001)
+  );
 }
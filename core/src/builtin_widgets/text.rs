@@ -39,22 +39,7 @@ impl Render for Text {
   #[inline]
   fn only_sized_by_parent(&self) -> bool { false }
 
-  fn paint(&self, ctx: &mut PaintingCtx) {
-    let box_rect = Rect::from_size(ctx.box_size().unwrap());
-    if ctx
-      .painter()
-      .intersection_paint_bounds(&box_rect)
-      .is_none()
-    {
-      return;
-    };
-
-    let visual_glyphs = self.glyphs().unwrap();
-    let font_db = AppCtx::font_db().clone();
-    ctx
-      .painter()
-      .draw_glyphs_in_rect(&visual_glyphs, box_rect, &font_db.borrow());
-  }
+  fn paint(&self, ctx: &mut PaintingCtx) { paint_glyphs(&self.glyphs().unwrap(), ctx) }
 }
 
 impl Text {
@@ -66,6 +51,88 @@ impl Text {
   }
 }
 
+/// The text widget display the styled spans as one paragraph, the spans are
+/// wrapped together and inherit the text style of the widget.
+///
+/// # Example
+///
+/// ```
+/// use ribir_core::prelude::*;
+///
+/// let _ = fn_widget! {
+///   @RichText {
+///     spans: [
+///       TextSpan::new("Tap "),
+///       TextSpan::new("here").with_brush(Color::BLUE).with_font_size(18.),
+///       TextSpan::new(" to continue."),
+///     ],
+///   }
+/// };
+/// ```
+#[derive(Declare)]
+pub struct RichText {
+  pub spans: Box<[TextSpan]>,
+  #[declare(default = TextAlign::Start)]
+  pub text_align: TextAlign,
+  #[declare(default)]
+  glyphs: RefCell<Option<VisualGlyphs>>,
+}
+
+impl Render for RichText {
+  fn perform_layout(&self, clamp: BoxClamp, ctx: &mut LayoutCtx) -> Size {
+    let style = ctx.text_style();
+    let info = AppCtx::typography_store()
+      .borrow_mut()
+      .typography_spans(
+        &self.spans,
+        style,
+        clamp.max,
+        self.text_align,
+        GlyphBaseline::Middle,
+        PlaceLineDirection::TopToBottom,
+      );
+
+    let size = info.visual_rect().size;
+    *self.glyphs.borrow_mut() = Some(info);
+
+    clamp.clamp(size)
+  }
+
+  #[inline]
+  fn only_sized_by_parent(&self) -> bool { false }
+
+  fn paint(&self, ctx: &mut PaintingCtx) { paint_glyphs(&self.glyphs().unwrap(), ctx) }
+}
+
+impl RichText {
+  pub fn new(spans: impl Into<Box<[TextSpan]>>) -> Self {
+    Self { spans: spans.into(), text_align: TextAlign::Start, glyphs: Default::default() }
+  }
+
+  /// The text of all the spans.
+  pub fn text(&self) -> String { self.spans.iter().map(|s| &*s.text).collect() }
+
+  pub fn glyphs(&self) -> Option<Ref<'_, VisualGlyphs>> {
+    Ref::filter_map(self.glyphs.borrow(), |v| v.as_ref()).ok()
+  }
+}
+
+fn paint_glyphs(visual_glyphs: &VisualGlyphs, ctx: &mut PaintingCtx) {
+  let box_rect = Rect::from_size(ctx.box_size().unwrap());
+  if ctx
+    .painter()
+    .intersection_paint_bounds(&box_rect)
+    .is_none()
+  {
+    return;
+  };
+
+  let font_db = AppCtx::font_db().clone();
+  ctx
+    .painter()
+    .draw_glyphs_in_rect(visual_glyphs, box_rect, &font_db.borrow());
+}
+
 macro_rules! define_text_with_theme_style {
   ($name:ident, $style:ident) => {
     #[derive(Declare)]
@@ -134,6 +201,99 @@ mod tests {
     .with_comparison(0.000025)
   );
 
+  widget_image_tests!(
+    rich_text,
+    WidgetTester::new(fn_widget! {
+      @RichText {
+        spans: [
+          TextSpan::new("Hello "),
+          TextSpan::new("Ribir").with_font_size(24.).with_brush(Color::RED),
+          TextSpan::new(", nice to ").with_letter_space(2.),
+          TextSpan::new("meet").with_brush(Color::BLUE),
+          TextSpan::new(" you!"),
+        ],
+        text_overflow: TextOverflow::AutoWrap,
+      }
+    })
+    .with_wnd_size(WND_SIZE)
+    .with_comparison(0.000025)
+  );
+
+  widget_image_tests!(
+    text_decorations,
+    WidgetTester::new(self::column! {
+      item_gap: 8.,
+      @Text {
+        text: "Underline and overline",
+        text_decoration: TextDecorationStyle::new(
+          TextDecoration::UNDERLINE | TextDecoration::OVERLINE
+        ),
+      }
+      @Text {
+        text: "Line through",
+        font_size: 20.,
+        text_decoration: TextDecorationStyle::new(TextDecoration::THROUGHLINE)
+          .with_line_style(DecorationLineStyle::Dashed)
+          .with_color(Color::RED),
+      }
+      @RichText {
+        spans: [
+          TextSpan::new("A "),
+          TextSpan::new("wavy")
+            .with_brush(Color::BLUE)
+            .with_decoration(
+              TextDecorationStyle::new(TextDecoration::UNDERLINE)
+                .with_line_style(DecorationLineStyle::Wavy)
+                .with_thickness(1.5),
+            ),
+          TextSpan::new(" span"),
+        ],
+        font_size: 18.,
+      }
+    })
+    .with_wnd_size(Size::new(150., 80.))
+    .with_comparison(0.000025)
+  );
+
+  widget_image_tests!(
+    text_ellipsis,
+    WidgetTester::new(self::column! {
+      item_gap: 8.,
+      @Text {
+        text: "A single line is elided at the end.",
+        text_overflow: TextOverflow::Ellipsis,
+        text_max_lines: 1,
+      }
+      @Text {
+        text: "The text wraps into two lines and the last line is elided.",
+        text_overflow: TextOverflow::Ellipsis,
+        text_max_lines: 2,
+      }
+    })
+    .with_wnd_size(Size::new(120., 80.))
+    .with_comparison(0.000025)
+  );
+
+  widget_image_tests!(
+    text_justify,
+    WidgetTester::new(self::column! {
+      item_gap: 8.,
+      @Text {
+        text: "The wrapped lines are justified, but not the last line.",
+        text_overflow: TextOverflow::AutoWrap,
+        text_align: TextAlign::Justify,
+      }
+      @Text {
+        text: "Words like in\u{AD}com\u{AD}pre\u{AD}hen\u{AD}si\u{AD}bil\u{AD}i\u{AD}ty break.",
+        text_overflow: TextOverflow::AutoWrap,
+        text_align: TextAlign::Justify,
+        text_hyphens: Hyphens::Manual,
+      }
+    })
+    .with_wnd_size(Size::new(120., 130.))
+    .with_comparison(0.000025)
+  );
+
   widget_image_tests!(
     middle_baseline,
     WidgetTester::new(self::column! {
@@ -267,10 +267,13 @@ fn typography_theme() -> TypographyTheme {
     };
     let overflow = TextOverflow::Clip;
     TextTheme {
-      text: TextStyle { line_height, font_size, letter_space, font_face, overflow },
-      decoration: TextDecorationStyle {
-        decoration: TextDecoration::NONE,
-        decoration_color: Color::BLACK.with_alpha(0.87).into(),
+      text: TextStyle {
+        line_height,
+        font_size,
+        letter_space,
+        font_face,
+        overflow,
+        ..<_>::default()
       },
     }
   }
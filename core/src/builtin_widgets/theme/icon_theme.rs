@@ -2,16 +2,22 @@
 // functionality.
 use std::collections::HashMap;
 
-use crate::{prelude::*, render_helper::RenderProxy};
+use crate::prelude::*;
 
 /// The theme of icon, which specify the icon size standard and provide a store
 /// of svg icons to use.
+///
+/// It also supplies the colors of the named color slots of the svg icons, like
+/// `var(--accent)`. The slots without a color in any `IconTheme` use the color
+/// of the same name in the `Palette`, see [`Palette::named_color`].
 #[derive(Clone)]
 pub struct IconTheme {
   /// icon size standard
   pub icon_size: IconSize,
   /// a collection of icons.
   svgs: HashMap<NamedSvg, Resource<Svg>, ahash::RandomState>,
+  /// the colors of the named color slots of the svg icons.
+  colors: HashMap<String, Color, ahash::RandomState>,
 }
 
 /// A five level standard of the size of icon in application.
@@ -75,7 +81,7 @@ impl IconTheme {
     let mut icons = HashMap::<_, _, ahash::RandomState>::default();
     icons.insert(MISS_ICON, miss_icon);
 
-    Self { icon_size, svgs: icons }
+    Self { icon_size, svgs: icons, colors: <_>::default() }
   }
 
   /// Retrieve the nearest `IconTheme` from the context among its ancestors
@@ -100,6 +106,28 @@ impl IconTheme {
 
   #[inline]
   pub fn has_svg(&mut self, name: &NamedSvg) -> bool { self.svgs.contains_key(name) }
+
+  /// Set the color of the color slot `var(--name)` of the svg icons.
+  #[inline]
+  pub fn set_color(&mut self, name: impl Into<String>, color: Color) -> Option<Color> {
+    self.colors.insert(name.into(), color)
+  }
+
+  /// The color of the color slot `var(--name)` of the svg icons.
+  #[inline]
+  pub fn color(&self, name: &str) -> Option<Color> { self.colors.get(name).copied() }
+
+  /// The colors of the color slots of the `svg` supplied by the nearest
+  /// `IconTheme` that has it, or by the `Palette`.
+  pub fn slot_colors_of(svg: &Svg, ctx: &impl ProviderCtx) -> Vec<Color> {
+    let palette = Palette::of(ctx);
+    svg.slot_colors(|name| {
+      ctx
+        .all_of::<IconTheme>()
+        .find_map(|t| t.color(name))
+        .or_else(|| palette.named_color(name))
+    })
+  }
 }
 
 impl IconSize {
@@ -160,7 +188,3 @@ pub mod svgs {
     THEME_EXTEND
   );
 }
-
-impl RenderProxy for Resource<Svg> {
-  fn proxy(&self) -> impl Deref<Target = impl Render + ?Sized> { &**self }
-}
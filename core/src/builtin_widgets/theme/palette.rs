@@ -326,6 +326,59 @@ impl Palette {
   #[inline]
   pub fn scrim(&self) -> Color { self.shadow() }
 
+  /// The color of the role named in kebab case, like `primary`,
+  /// `on-primary-container` or `surface-variant`. It's used to supply the
+  /// color slots of the SVG, like `var(--primary)`.
+  pub fn named_color(&self, name: &str) -> Option<Color> {
+    let color = match name {
+      "primary" => self.primary(),
+      "on-primary" => self.on_primary(),
+      "primary-container" => self.primary_container(),
+      "on-primary-container" => self.on_primary_container(),
+      "secondary" => self.secondary(),
+      "on-secondary" => self.on_secondary(),
+      "secondary-container" => self.secondary_container(),
+      "on-secondary-container" => self.on_secondary_container(),
+      "tertiary" => self.tertiary(),
+      "on-tertiary" => self.on_tertiary(),
+      "tertiary-container" => self.tertiary_container(),
+      "on-tertiary-container" => self.on_tertiary_container(),
+      "success" => self.success(),
+      "on-success" => self.on_success(),
+      "success-container" => self.success_container(),
+      "on-success-container" => self.on_success_container(),
+      "warning" => self.warning(),
+      "on-warning" => self.on_warning(),
+      "warning-container" => self.warning_container(),
+      "on-warning-container" => self.on_warning_container(),
+      "error" => self.error(),
+      "on-error" => self.on_error(),
+      "error-container" => self.error_container(),
+      "on-error-container" => self.on_error_container(),
+      "background" => self.background(),
+      "on-background" => self.on_background(),
+      "surface" => self.surface(),
+      "surface-dim" => self.surface_dim(),
+      "surface-bright" => self.surface_bright(),
+      "surface-container-lowest" => self.surface_container_lowest(),
+      "surface-container-low" => self.surface_container_low(),
+      "surface-container" => self.surface_container(),
+      "surface-container-high" => self.surface_container_high(),
+      "surface-container-highest" => self.surface_container_highest(),
+      "on-surface" => self.on_surface(),
+      "surface-variant" => self.surface_variant(),
+      "on-surface-variant" => self.on_surface_variant(),
+      "outline" => self.outline(),
+      "outline-variant" => self.outline_variant(),
+      "inverse-surface" => self.inverse_surface(),
+      "inverse-on-surface" => self.inverse_on_surface(),
+      "shadow" => self.shadow(),
+      "scrim" => self.scrim(),
+      _ => return None,
+    };
+    Some(color)
+  }
+
   /// change color to the `base` light tone of the palette.
   #[inline]
   pub fn base_of(&self, color: &Color) -> Color {
@@ -38,7 +38,17 @@ impl_compose_child_for_wrap_render!(VisibilityRender);
 impl WrapRender for VisibilityRender {
   #[inline]
   fn perform_layout(&self, clamp: BoxClamp, host: &dyn Render, ctx: &mut LayoutCtx) -> Size {
-    if self.display { host.perform_layout(clamp, ctx) } else { clamp.min }
+    if self.display {
+      host.perform_layout(clamp, ctx)
+    } else {
+      // The children are not laid out, clear their layout of the last time to
+      // not paint them.
+      let (ctx, children) = ctx.split_children();
+      for c in children {
+        ctx.force_child_relayout(c);
+      }
+      clamp.min
+    }
   }
 
   fn paint(&self, host: &dyn Render, ctx: &mut PaintingCtx) {
@@ -63,3 +73,33 @@ impl Visibility {
   #[inline]
   fn get_visible(&self) -> bool { self.visible }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{reset_test_env, test_helper::*};
+
+  #[test]
+  fn hidden_children_not_laid_out() {
+    reset_test_env!();
+
+    let (visible, w_visible) = split_value(true);
+    let mut wnd = TestWindow::new(fn_widget! {
+      @MockBox {
+        size: Size::new(10., 10.),
+        visible: pipe!(*$visible),
+        @MockBox { size: Size::new(5., 5.) }
+      }
+    });
+    wnd.draw_frame();
+    assert!(wnd.layout_info_by_path(&[0, 0]).is_some());
+
+    *w_visible.write() = false;
+    wnd.draw_frame();
+    assert!(wnd.layout_info_by_path(&[0, 0]).is_none());
+
+    *w_visible.write() = true;
+    wnd.draw_frame();
+    assert!(wnd.layout_info_by_path(&[0, 0]).is_some());
+  }
+}
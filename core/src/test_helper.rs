@@ -17,6 +17,8 @@ use crate::{
 pub struct Frame {
   pub commands: Vec<PaintCommand>,
   pub viewport: Rect,
+  /// The areas changed since the last frame.
+  pub damages: Vec<Rect>,
   pub surface: Color,
 }
 
@@ -171,9 +173,13 @@ impl ShellWindow for TestShellWindow {
 
   fn begin_frame(&mut self, surface: Color) { self.surface_color = surface; }
 
-  fn draw_commands(&mut self, viewport: Rect, commands: &[PaintCommand]) {
-    self.last_frame =
-      Some(Frame { commands: commands.to_owned(), viewport, surface: self.surface_color });
+  fn draw_commands(&mut self, viewport: Rect, damages: &[Rect], commands: &[PaintCommand]) {
+    self.last_frame = Some(Frame {
+      commands: commands.to_owned(),
+      viewport,
+      damages: damages.to_owned(),
+      surface: self.surface_color,
+    });
   }
 
   fn end_frame(&mut self) {}
@@ -6,6 +6,8 @@ use widget_id::RenderQueryable;
 pub use widget_id::{TrackId, WidgetId};
 mod layout_info;
 pub use layout_info::*;
+mod damage;
+pub(crate) use damage::{Damages, PaintedBounds, PaintingSubtree};
 
 use self::widget::widget_id::new_node;
 use crate::{overlay::ShowingOverlays, prelude::*, render_helper::PureRender, window::WindowId};
@@ -19,6 +21,7 @@ pub(crate) struct WidgetTree {
   pub(crate) store: LayoutStore,
   pub(crate) dirty_set: DirtySet,
   pub(crate) dummy_id: WidgetId,
+  pub(crate) damages: Damages,
 }
 
 /// A tool that help you to mark a widget as dirty
@@ -63,13 +66,16 @@ impl WidgetTree {
 
   pub(crate) fn dirty_marker(&self) -> DirtyMarker { DirtyMarker(self.dirty_set.clone()) }
 
-  /// Draw current tree by painter.
-  pub(crate) fn draw(&self) {
+  /// Draw current tree by painter, return the bounds of the subtrees painted
+  /// out of their boxes.
+  pub(crate) fn draw(&self) -> PaintedBounds {
     let wnd = self.window();
     let mut painter = wnd.painter.borrow_mut();
     let tree = wnd.tree();
     let mut ctx = PaintingCtx::new(self.root(), tree, &mut painter);
-    self.root().paint_subtree(&mut ctx);
+    let mut painted = PaintedBounds::default();
+    self.root().paint_subtree(&mut ctx, &mut painted);
+    painted
   }
 
   /// Do the work of computing the layout for all node which need, Return if any
@@ -94,6 +100,7 @@ impl WidgetTree {
         ctx.perform_child_layout(wid, clamp);
       }
     }
+    self.collect_layout_damages();
   }
 
   pub(crate) fn layout_info(&self, id: WidgetId) -> Option<&LayoutInfo> {
@@ -151,11 +158,7 @@ impl WidgetTree {
         continue;
       }
 
-      if let Some(info) = self.store.get_mut(id) {
-        info.size.take();
-      }
-
-      let mut relayout_root = *id;
+      let mut relayout_path = vec![*id];
       // All ancestors of this render widget should relayout until the one which only
       // sized by parent.
       for p in id.0.ancestors(&self.arena).skip(1).map(WidgetId) {
@@ -166,15 +169,19 @@ impl WidgetTree {
           break;
         }
 
-        relayout_root = p;
-        if let Some(info) = self.store.get_mut(&p) {
-          info.size.take();
-        }
-
+        relayout_path.push(p);
         if p.assert_get(self).only_sized_by_parent() {
           break;
         }
       }
+
+      let relayout_root = *relayout_path.last().unwrap();
+      self.record_relayout(*id, relayout_root);
+      for w in relayout_path {
+        if let Some(info) = self.store.get_mut(&w) {
+          info.size.take();
+        }
+      }
       needs_layout.push(relayout_root);
     }
 
@@ -194,6 +201,7 @@ impl WidgetTree {
       self.root = new_root;
     }
 
+    self.add_subtree_damage(id);
     id.0.detach(&mut self.arena);
   }
 
@@ -232,7 +240,15 @@ impl WidgetTree {
     let dummy_id = new_node(&mut arena, Box::new(PureRender(Void)));
     dummy_id.0.remove(&mut arena);
 
-    Self { root, dummy_id, wnd_id, arena, store: <_>::default(), dirty_set: <_>::default() }
+    Self {
+      root,
+      dummy_id,
+      wnd_id,
+      arena,
+      store: <_>::default(),
+      dirty_set: <_>::default(),
+      damages: <_>::default(),
+    }
   }
 }
 
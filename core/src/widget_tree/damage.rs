@@ -0,0 +1,229 @@
+use std::{collections::HashMap, mem::take};
+
+use super::{WidgetId, WidgetTree};
+use crate::prelude::{Painter, Rect, Size, Transform};
+
+const MAX_DAMAGES: usize = 32;
+
+/// The global bounds of the pixels painted by the subtrees, only the subtrees
+/// painted out of the layout box of their root are recorded.
+pub(crate) type PaintedBounds = HashMap<WidgetId, Rect, ahash::RandomState>;
+
+/// The areas of the window changed since the last frame.
+///
+/// The damages are tracked by the layout boxes of the widgets and the bounds
+/// of the subtrees painted out of their boxes, such as the shadows and the
+/// blurs.
+#[derive(Default)]
+pub(crate) struct Damages {
+  rects: Vec<Rect>,
+  /// The painted bounds of the last frame.
+  painted: PaintedBounds,
+  /// The widgets whose painted bounds of the new frame are damaged.
+  repainted: Vec<WidgetId>,
+  /// The global layout boxes of the widgets before they relayout.
+  prev_boxes: HashMap<WidgetId, Rect, ahash::RandomState>,
+  /// The roots of the subtrees that will relayout.
+  relayout_roots: Vec<WidgetId>,
+  /// The widgets marked as dirty, their whole subtree is repainted.
+  dirty: Vec<WidgetId>,
+}
+
+impl WidgetTree {
+  /// Record the subtree of `root` will relayout because the `dirty` widget is
+  /// changed, must be called before the layout information is cleared.
+  pub(crate) fn record_relayout(&mut self, dirty: WidgetId, root: WidgetId) {
+    let bounds = self.subtree_damage(dirty);
+    let mut damages = take(&mut self.damages);
+    damages.rects.extend(bounds);
+    damages.dirty.push(dirty);
+    if !damages.relayout_roots.contains(&root) {
+      damages.relayout_roots.push(root);
+      self.visit_global_boxes(root, &mut |id, rect| {
+        damages.prev_boxes.entry(id).or_insert(rect);
+      });
+    }
+    self.damages = damages;
+  }
+
+  /// Compare the layout boxes with the recorded ones after layout, the widgets
+  /// that moved or resized are damaged.
+  pub(crate) fn collect_layout_damages(&mut self) {
+    let Damages { mut rects, painted, mut repainted, prev_boxes, relayout_roots, dirty } =
+      take(&mut self.damages);
+    for root in relayout_roots {
+      if root.is_dropped(self) {
+        continue;
+      }
+      self.visit_global_boxes(root, &mut |id, rect| {
+        match prev_boxes.get(&id) {
+          Some(prev) if *prev == rect => return,
+          Some(prev) => rects.extend([*prev, rect]),
+          None => rects.push(rect),
+        }
+        rects.extend(painted.get(&id));
+        repainted.push(id);
+      });
+    }
+    for id in dirty {
+      if !id.is_dropped(self) {
+        rects.extend(self.global_subtree_bounds(id));
+        rects.extend(painted.get(&id));
+        repainted.push(id);
+      }
+    }
+
+    self.damages.rects = rects;
+    self.damages.painted = painted;
+    self.damages.repainted = repainted;
+  }
+
+  /// Replace the painted bounds with the ones of the new frame, the repainted
+  /// widgets are damaged by their new painted bounds.
+  pub(crate) fn collect_paint_damages(&mut self, painted: PaintedBounds) {
+    let damages = &mut self.damages;
+    for id in damages.repainted.drain(..) {
+      damages.rects.extend(painted.get(&id));
+    }
+    damages.painted = painted;
+  }
+
+  /// Mark the subtree of `id` as damaged, both the area it painted in the last
+  /// frame and the one it will paint in the new frame.
+  pub(crate) fn add_subtree_damage(&mut self, id: WidgetId) {
+    let bounds = self.subtree_damage(id);
+    self.damages.rects.extend(bounds);
+    self.damages.repainted.push(id);
+  }
+
+  /// Take the damages since the last frame, the damages are clipped by the
+  /// `viewport` and the ones covered by others are dropped. Too many damages
+  /// are merged to one.
+  pub(crate) fn take_damages(&mut self, viewport: &Rect) -> Vec<Rect> {
+    let rects = self
+      .damages
+      .rects
+      .drain(..)
+      .filter_map(|rect| rect.intersection(viewport))
+      .filter(|rect| !rect.is_empty())
+      .collect::<Vec<_>>();
+    if rects.len() > MAX_DAMAGES {
+      let union = rects[1..]
+        .iter()
+        .fold(rects[0], |acc, r| acc.union(r));
+      return vec![union];
+    }
+
+    let mut res: Vec<Rect> = vec![];
+    for rect in rects {
+      if !res.iter().any(|r| r.contains_rect(&rect)) {
+        res.retain(|r| !rect.contains_rect(r));
+        res.push(rect);
+      }
+    }
+    res
+  }
+
+  /// The global bounds of the `id` and all its descendants that have been laid
+  /// out.
+  pub(crate) fn global_subtree_bounds(&self, id: WidgetId) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+    self.visit_global_boxes(id, &mut |_, rect| {
+      bounds = Some(bounds.map_or(rect, |b| b.union(&rect)));
+    });
+    bounds
+  }
+
+  /// The area the subtree of `id` painted in the last frame.
+  fn subtree_damage(&self, id: WidgetId) -> Option<Rect> {
+    let bounds = self.global_subtree_bounds(id);
+    match (bounds, self.damages.painted.get(&id)) {
+      (Some(bounds), Some(painted)) => Some(bounds.union(painted)),
+      (bounds, painted) => bounds.or(painted.copied()),
+    }
+  }
+
+  /// Visit the global layout boxes of the `id` and its descendants.
+  fn visit_global_boxes(&self, id: WidgetId, f: &mut impl FnMut(WidgetId, Rect)) {
+    self.visit_boxes(id, &self.global_transform(id), f);
+  }
+
+  fn visit_boxes(&self, id: WidgetId, matrix: &Transform, f: &mut impl FnMut(WidgetId, Rect)) {
+    if let Some(size) = self.store.layout_box_size(id) {
+      f(id, matrix.outer_transformed_rect(&Rect::from_size(size)));
+    }
+    for c in id.children(self) {
+      let matrix = self.local_transform(c).then(matrix);
+      self.visit_boxes(c, &matrix, f);
+    }
+  }
+}
+
+/// Track the bounds of the commands painted by a subtree during painting.
+pub(crate) struct PaintingSubtree {
+  id: WidgetId,
+  /// The global layout box of the root of the subtree.
+  global_box: Option<Rect>,
+  /// The index of the first command painted by the subtree.
+  start: usize,
+  /// The commands before this index are merged to the `bounds`.
+  scanned: usize,
+  bounds: Option<Rect>,
+}
+
+impl PaintingSubtree {
+  pub(crate) fn new(id: WidgetId, painter: &Painter) -> Self {
+    let start = painter.commands_count();
+    Self { id, global_box: None, start, scanned: start, bounds: None }
+  }
+
+  pub(crate) fn start(&self) -> usize { self.start }
+
+  /// Set the layout box size of the root, the `painter` is at its position.
+  pub(crate) fn set_box(&mut self, size: Size, painter: &Painter) {
+    let rect = Rect::from_size(size);
+    self.global_box = Some(painter.transform().outer_transformed_rect(&rect));
+  }
+
+  /// Merge the commands painted by the root itself before its next child
+  /// starts painting.
+  pub(crate) fn scan(&mut self, painter: &Painter) {
+    self.merge_bounds(painter.commands_bounds_from(self.scanned));
+    self.scanned = painter.commands_count();
+  }
+
+  /// Merge the painted bounds of a child subtree that just finished painting.
+  pub(crate) fn merge(&mut self, child: Option<Rect>, painter: &Painter) {
+    self.merge_bounds(child);
+    self.scanned = painter.commands_count();
+  }
+
+  /// Finish the subtree after the painter restored its state and return its
+  /// painted bounds, the bounds are recorded to `painted` if out of the box.
+  ///
+  /// If a layer closed by the restore, the commands of the subtree are
+  /// replaced by the layer, so `rescan` them.
+  pub(crate) fn finish(
+    mut self, painter: &Painter, rescan: bool, painted: &mut PaintedBounds,
+  ) -> Option<Rect> {
+    if rescan {
+      self.bounds = painter.commands_bounds_from(self.start);
+    } else {
+      self.scan(painter);
+    }
+    let bounds = self.bounds?;
+    if !self
+      .global_box
+      .is_some_and(|b| b.contains_rect(&bounds))
+    {
+      painted.insert(self.id, bounds);
+    }
+    Some(bounds)
+  }
+
+  fn merge_bounds(&mut self, bounds: Option<Rect>) {
+    if let Some(bounds) = bounds {
+      self.bounds = Some(self.bounds.map_or(bounds, |b| b.union(&bounds)));
+    }
+  }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use ribir_geom::ZERO_SIZE;
 
 use super::{Lerp, WidgetId, WidgetTree};
-use crate::prelude::{INFINITY_SIZE, Point, Size};
+use crate::prelude::{INFINITY_SIZE, Point, Size, Transform};
 
 /// boundary limit of the render object's layout
 #[derive(Debug, Clone, PartialEq, Copy, Lerp)]
@@ -151,6 +151,25 @@ impl WidgetTree {
       .rev()
       .fold(pos, |pos, p| self.map_from_parent(*p, pos))
   }
+
+  /// The transform that maps the coordinate of the `id` to its parent.
+  pub(crate) fn local_transform(&self, id: WidgetId) -> Transform {
+    self
+      .store
+      .layout_box_pos(id)
+      .map_or_else(Transform::identity, |offset| {
+        id.assert_get(self)
+          .get_transform()
+          .unwrap_or_else(Transform::identity)
+          .then_translate(offset.to_vector())
+      })
+  }
+
+  /// The transform that maps the coordinate of the `id` to the global.
+  pub(crate) fn global_transform(&self, id: WidgetId) -> Transform {
+    id.ancestors(self)
+      .fold(Transform::identity(), |t, p| t.then(&self.local_transform(p)))
+  }
 }
 
 impl BoxClamp {
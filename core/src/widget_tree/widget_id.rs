@@ -287,10 +287,17 @@ impl WidgetId {
     self.wrap_node(tree, |render| Box::new(AnonymousAttacher::new(render, Box::new(data))));
   }
 
-  pub(crate) fn paint_subtree(self, ctx: &mut PaintingCtx) {
+  /// Paint the subtree of the widget, and record the bounds of the subtrees
+  /// painted out of their boxes to `painted`.
+  pub(crate) fn paint_subtree(self, ctx: &mut PaintingCtx, painted: &mut PaintedBounds) {
+    let mut subtrees: Vec<PaintingSubtree> = vec![];
     let mut w = Some(self);
     while let Some(id) = w {
       ctx.id = id;
+      if let Some(parent) = subtrees.last_mut() {
+        parent.scan(ctx.painter);
+      }
+      let mut subtree = PaintingSubtree::new(id, ctx.painter);
       ctx.painter.save();
       let wnd = ctx.window();
       let tree = wnd.tree();
@@ -302,10 +309,12 @@ impl WidgetId {
           ctx
             .painter
             .translate(layout_box.min_x(), layout_box.min_y());
+          subtree.set_box(layout_box.size, ctx.painter);
           render.paint(ctx);
           need_paint = true;
         }
       }
+      subtrees.push(subtree);
 
       w = id
         .first_child(tree)
@@ -314,7 +323,13 @@ impl WidgetId {
           let mut node = w;
           while let Some(p) = node {
             // self node sub-tree paint finished, goto sibling
+            let subtree = subtrees.pop().unwrap();
+            let rescan = ctx.painter.has_open_layer_from(subtree.start());
             ctx.painter.restore();
+            let bounds = subtree.finish(ctx.painter, rescan, painted);
+            if let Some(parent) = subtrees.last_mut() {
+              parent.merge(bounds, ctx.painter);
+            }
             node = p.next_sibling(tree);
             if node.is_some() {
               break;
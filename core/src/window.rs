@@ -102,7 +102,9 @@ pub trait ShellWindow {
   /// device.
   fn device_pixel_ratio(&self) -> f32;
   fn begin_frame(&mut self, surface_color: Color);
-  fn draw_commands(&mut self, viewport: Rect, commands: &[PaintCommand]);
+  /// Draw the `commands` to the window, only the `damages` areas changed since
+  /// the last frame.
+  fn draw_commands(&mut self, viewport: Rect, damages: &[Rect], commands: &[PaintCommand]);
   fn end_frame(&mut self);
 }
 
@@ -231,13 +233,19 @@ impl Window {
       ticker.next(FrameMsg::BeforeLayout(Instant::now()));
       self.layout();
 
-      self.tree().draw();
-      self.draw_delay_drop_widgets();
+      let mut painted = self.tree().draw();
+      self.draw_delay_drop_widgets(&mut painted);
+      self.tree_mut().collect_paint_damages(painted);
 
       let mut shell = self.shell_wnd.borrow_mut();
-      let inner_size = shell.inner_size();
+      let viewport = Rect::from_size(shell.inner_size());
+      let mut damages = self.tree_mut().take_damages(&viewport);
+      if damages.is_empty() {
+        // Something changed but not tracked by the tree, redraw the whole window.
+        damages.push(viewport);
+      }
       let mut painter = self.painter.borrow_mut();
-      shell.draw_commands(Rect::from_size(inner_size), &painter.finish());
+      shell.draw_commands(viewport, &damages, &painter.finish());
 
       shell.end_frame();
     }
@@ -353,7 +361,26 @@ impl Window {
     self.delay_emitter.borrow_mut().push_back(e);
   }
 
-  fn draw_delay_drop_widgets(&self) {
+  fn draw_delay_drop_widgets(&self, painted: &mut PaintedBounds) {
+    // The delay drop widgets are painted every frame until they are removed, so
+    // they are always damaged.
+    let tree = self.tree_mut();
+    let damaged = self
+      .delay_drop_widgets
+      .borrow()
+      .iter()
+      .filter_map(|(parent, wid)| {
+        let wid = wid.get()?;
+        let parent_dropped = parent
+          .as_ref()
+          .is_some_and(|p| p.ancestors(tree).any(|w| w.is_dropped(tree)));
+        (!parent_dropped).then_some(wid)
+      })
+      .collect::<Vec<_>>();
+    damaged
+      .into_iter()
+      .for_each(|wid| tree.add_subtree_damage(wid));
+
     let mut painter = self.painter.borrow_mut();
 
     self
@@ -387,7 +414,7 @@ impl Window {
             painter.translate(offset.x, offset.y);
           }
           let mut ctx = PaintingCtx::new(wid, tree, &mut painter);
-          wid.paint_subtree(&mut ctx);
+          wid.paint_subtree(&mut ctx, painted);
         }
       });
   }
@@ -852,6 +879,102 @@ mod tests {
     wnd.assert_root_size(new_size);
   }
 
+  #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+  #[test]
+  fn damage_changed_widgets() {
+    reset_test_env!();
+
+    let (height, w_height) = split_value(10.);
+    let (color, w_color) = split_value(Color::RED);
+    let mut wnd = TestWindow::new_with_size(
+      fn_widget! {
+        @MockMulti {
+          @MockBox { size: Size::new(20., 20.) }
+          @MockBox { size: pipe!(Size::new(10., *$height)) }
+          @MockBox {
+            size: Size::new(10., 10.),
+            background: pipe!(*$color),
+          }
+        }
+      },
+      Size::new(100., 100.),
+    );
+    wnd.draw_frame();
+    let frame = wnd.take_last_frame().unwrap();
+    assert_eq!(frame.damages, [Rect::from_size(Size::new(100., 100.))]);
+
+    // Only the resized widget is damaged, its siblings and parent are not moved.
+    *w_height.write() = 5.;
+    wnd.draw_frame();
+    let frame = wnd.take_last_frame().unwrap();
+    assert_eq!(frame.damages, [Rect::new(Point::new(20., 0.), Size::new(10., 10.))]);
+
+    // A widget only repaint.
+    *w_color.write() = Color::BLUE;
+    wnd.draw_frame();
+    let frame = wnd.take_last_frame().unwrap();
+    assert_eq!(frame.damages, [Rect::new(Point::new(30., 0.), Size::new(10., 10.))]);
+  }
+
+  #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+  #[test]
+  fn damage_removed_widget() {
+    reset_test_env!();
+
+    let (show, w_show) = split_value(true);
+    let mut wnd = TestWindow::new_with_size(
+      fn_widget! {
+        @MockMulti {
+          @MockBox { size: Size::new(20., 20.) }
+          @MockBox {
+            size: Size::new(30., 30.),
+            @ {
+              pipe!(*$show).map(|show| show.then(|| @MockBox {
+                size: Size::new(10., 10.),
+                anchor: Anchor::left_top(5., 5.),
+              }))
+            }
+          }
+        }
+      },
+      Size::new(100., 100.),
+    );
+    wnd.draw_frame();
+
+    *w_show.write() = false;
+    wnd.draw_frame();
+    let frame = wnd.take_last_frame().unwrap();
+    assert_eq!(frame.damages, [Rect::new(Point::new(25., 5.), Size::new(10., 10.))]);
+  }
+
+  #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+  #[test]
+  fn damage_painted_out_of_box() {
+    reset_test_env!();
+
+    let (color, w_color) = split_value(Color::BLACK);
+    let mut wnd = TestWindow::new_with_size(
+      fn_widget! {
+        @MockBox {
+          size: Size::new(10., 10.),
+          anchor: Anchor::left_top(20., 20.),
+          box_shadow: pipe!(Shadow::new(Vector::zero(), 4., 0., *$color)),
+        }
+      },
+      Size::new(100., 100.),
+    );
+    wnd.draw_frame();
+
+    // The shadow is blurred out of the box, its pixels in the last frame and the
+    // new frame are damaged.
+    *w_color.write() = Color::RED;
+    wnd.draw_frame();
+    let frame = wnd.take_last_frame().unwrap();
+    let shadow = Rect::new(Point::new(14., 14.), Size::new(22., 22.));
+    assert_eq!(frame.damages.len(), 1);
+    assert!(frame.damages[0].contains_rect(&shadow));
+  }
+
   #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
   #[test]
   fn fire_tasks_before_new_window() {
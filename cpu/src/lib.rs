@@ -0,0 +1,503 @@
+//! A pure CPU implementation of the painter backend.
+//!
+//! It rasterizes the paint commands to a pixel buffer without any GPU, so it
+//! can work in a headless environment, like a CI machine.
+use ribir_geom::{DevicePoint, DeviceRect, Transform, transform_to_device_rect};
+use ribir_painter::{
+  BlendMode, BlurTarget, Color, LayerCommand, MaskCommand, MaskMode, PaintCommand, PaintPathAction,
+  PainterBackend, PaintingStyle, PathCommand, ShadowCommand,
+};
+use tiny_skia::Mask;
+
+mod raster;
+mod shader;
+mod texture;
+use shader::{Rgba, Shader};
+pub use texture::CpuTexture;
+
+pub struct CpuBackend {
+  surface_color: Option<Color>,
+  clip_layer_stack: Vec<ClipLayer>,
+}
+
+struct ClipLayer {
+  /// The device bounds the layer can paint.
+  bounds: DeviceRect,
+  /// The coverage of the pixels in the bounds, `None` means the whole bounds
+  /// is visible.
+  mask: Option<Mask>,
+}
+
+impl ClipLayer {
+  /// The coverage of the pixel at the device position in the bounds.
+  fn coverage(&self, x: i32, y: i32) -> u8 {
+    self.mask.as_ref().map_or(255, |mask| {
+      let idx = (y - self.bounds.min_y()) * self.bounds.width() + x - self.bounds.min_x();
+      mask.data()[idx as usize]
+    })
+  }
+}
+
+impl PainterBackend for CpuBackend {
+  type Texture = CpuTexture;
+
+  fn begin_frame(&mut self, surface: Color) { self.surface_color = Some(surface); }
+
+  /// Only the `damages` areas are redrawn, the other pixels of the `output` are
+  /// kept from the last frame.
+  fn draw_commands(
+    &mut self, viewport: DeviceRect, damages: &[DeviceRect], commands: &[PaintCommand],
+    global_matrix: &Transform, output: &mut Self::Texture,
+  ) {
+    let surface = self.surface_color.take();
+    let clips = self.clip_layer_stack.len();
+    let viewport = viewport
+      .intersection(&DeviceRect::from_size(output.size()))
+      .unwrap_or_default();
+    for damage in damages {
+      let Some(bounds) = damage.intersection(&viewport) else { continue };
+      if let Some(color) = surface {
+        output.clear_rect(&bounds, color);
+      }
+      self
+        .clip_layer_stack
+        .push(ClipLayer { bounds, mask: None });
+      for cmd in commands {
+        self.draw_command(cmd, global_matrix, output);
+      }
+      self.clip_layer_stack.pop();
+    }
+
+    assert_eq!(self.clip_layer_stack.len(), clips);
+  }
+
+  fn end_frame(&mut self) { self.surface_color = None; }
+}
+
+impl Default for CpuBackend {
+  fn default() -> Self { Self::new() }
+}
+
+impl CpuBackend {
+  pub fn new() -> Self { Self { surface_color: None, clip_layer_stack: vec![] } }
+
+  fn draw_command(
+    &mut self, cmd: &PaintCommand, global_matrix: &Transform, output: &mut CpuTexture,
+  ) {
+    match cmd {
+      PaintCommand::Path(PathCommand { path, paint_bounds, transform, action }) => {
+        let matrix = transform.then(global_matrix);
+        let bounds = transform_to_device_rect(paint_bounds, global_matrix)
+          .intersection(&self.current_clip().bounds);
+        match action {
+          PaintPathAction::Clip => {
+            let layer = bounds
+              .and_then(|bounds| {
+                let mut mask = raster::path_mask(path, &PaintingStyle::Fill, &matrix, &bounds)?;
+                let clip = self.current_clip();
+                if clip.mask.is_some() {
+                  let data = mask.data_mut();
+                  for y in bounds.y_range() {
+                    for x in bounds.x_range() {
+                      let idx =
+                        ((y - bounds.min_y()) * bounds.width() + x - bounds.min_x()) as usize;
+                      data[idx] = mul_u8(data[idx], clip.coverage(x, y));
+                    }
+                  }
+                }
+                Some(ClipLayer { bounds, mask: Some(mask) })
+              })
+              // An invisible clip layer, nothing can be painted in it.
+              .unwrap_or(ClipLayer { bounds: DeviceRect::zero(), mask: None });
+            self.clip_layer_stack.push(layer);
+          }
+          PaintPathAction::Paint { brush, painting_style, blend_mode } => {
+            let Some(bounds) = bounds else { return };
+            let Some(shader) = Shader::new(brush, &matrix) else { return };
+            let Some(mask) = raster::path_mask(path, painting_style, &matrix, &bounds) else {
+              return;
+            };
+            self.fill_mask(output, &bounds, &mask, &shader, *blend_mode);
+          }
+        }
+      }
+      PaintCommand::Shadow(cmd) => self.draw_shadow(cmd, global_matrix, output),
+      PaintCommand::Layer(layer) => self.draw_layer(layer, global_matrix, output),
+      PaintCommand::Mask(mask) => self.draw_mask(mask, global_matrix, output),
+      PaintCommand::PopClip => {
+        self.clip_layer_stack.pop();
+      }
+      PaintCommand::Bundle { transform, opacity, blend_mode, bounds, cmds } => {
+        let matrix = transform.then(global_matrix);
+        let Some(bounds) =
+          transform_to_device_rect(bounds, &matrix).intersection(&self.current_clip().bounds)
+        else {
+          return;
+        };
+
+        if *opacity >= 1. && *blend_mode == BlendMode::Normal {
+          cmds
+            .iter()
+            .for_each(|cmd| self.draw_command(cmd, &matrix, output));
+        } else if *opacity > 0. {
+          // The opacity and the blend mode apply to the whole bundle, so draw its
+          // visible area to a layer first.
+          let layer = self.draw_to_texture(cmds, &matrix, &bounds);
+          self.composite_texture(output, &layer, bounds.origin, &bounds, *opacity, *blend_mode);
+        }
+      }
+    }
+  }
+
+  fn draw_shadow(&self, cmd: &ShadowCommand, global_matrix: &Transform, output: &mut CpuTexture) {
+    let clip = self.current_clip();
+    let shadow_bounds = transform_to_device_rect(&cmd.paint_bounds, global_matrix);
+    let Some(bounds) = shadow_bounds.intersection(&clip.bounds) else { return };
+
+    // The blur of the visible pixels depends on their neighbors, so the shadow
+    // around the visible area is rasterized too.
+    let matrix = cmd.transform.then(global_matrix);
+    let sigma = cmd.blur_sigma(&matrix);
+    let margin = (sigma * 3.).ceil() as i32;
+    let Some(area) = shadow_bounds.intersection(&bounds.inflate(margin, margin)) else { return };
+    let Some(mut mask) = raster::path_mask(&cmd.path, &PaintingStyle::Fill, &matrix, &area) else {
+      return;
+    };
+    if sigma > 0. {
+      let (width, height) = (mask.width() as usize, mask.height() as usize);
+      raster::gaussian_blur(mask.data_mut(), width, height, 1, sigma);
+    }
+
+    let width = output.size().width;
+    let color = shader::premultiplied(cmd.color);
+    let data = output.data_mut();
+    let coverage = mask.data();
+    for y in bounds.y_range() {
+      for x in bounds.x_range() {
+        let idx = (y * width + x) as usize;
+        let mask_idx = (y - area.min_y()) * area.width() + x - area.min_x();
+        let cover = mul_u8(coverage[mask_idx as usize], clip.coverage(x, y));
+        if cover > 0 {
+          src_over(&mut data[idx * 4..idx * 4 + 4], color, cover as f32 / 255.);
+        }
+      }
+    }
+  }
+
+  fn draw_layer(
+    &mut self, layer: &LayerCommand, global_matrix: &Transform, output: &mut CpuTexture,
+  ) {
+    let clip_bounds = self.current_clip().bounds;
+    let matrix = layer.transform.then(global_matrix);
+    let sigma = layer.blur_sigma(&matrix);
+    let margin = (sigma * 3.).ceil() as i32;
+    match layer.target {
+      BlurTarget::Content => {
+        // The pixels around the visible area affect it by the blur, so they are
+        // painted too.
+        let layer_bounds = transform_to_device_rect(&layer.paint_bounds, global_matrix);
+        let Some(bounds) = layer_bounds.intersection(&clip_bounds.inflate(margin, margin)) else {
+          return;
+        };
+        let Some(visible) = bounds.intersection(&clip_bounds) else { return };
+
+        let mut texture = self.draw_to_texture(&layer.cmds, global_matrix, &bounds);
+        if sigma > 0. {
+          let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+          raster::gaussian_blur(texture.data_mut(), width, height, 4, sigma);
+        }
+        let normal = BlendMode::Normal;
+        self.composite_texture(output, &texture, bounds.origin, &visible, 1., normal);
+      }
+      BlurTarget::Backdrop => {
+        let area = transform_to_device_rect(&layer.path.bounds(None), &matrix);
+        if let Some(area) = area
+          .intersection(&clip_bounds)
+          .filter(|_| sigma > 0.)
+        {
+          self.blur_backdrop(layer, &matrix, sigma, &area, output);
+        }
+        layer
+          .cmds
+          .iter()
+          .for_each(|cmd| self.draw_command(cmd, global_matrix, output));
+      }
+    }
+  }
+
+  fn draw_mask(&mut self, mask: &MaskCommand, global_matrix: &Transform, output: &mut CpuTexture) {
+    let bounds = transform_to_device_rect(&mask.paint_bounds, global_matrix);
+    let Some(bounds) = bounds.intersection(&self.current_clip().bounds) else { return };
+
+    let mask_texture = self.draw_to_texture(&mask.mask, global_matrix, &bounds);
+    let mut texture = self.draw_to_texture(&mask.cmds, global_matrix, &bounds);
+
+    texture
+      .data_mut()
+      .chunks_exact_mut(4)
+      .zip(mask_texture.pixel_bytes().chunks_exact(4))
+      .for_each(|(p, m)| {
+        let cover = match mask.mode {
+          MaskMode::Alpha => m[3],
+          MaskMode::Luminance => luminance(m),
+        };
+        p.iter_mut().for_each(|c| *c = mul_u8(*c, cover));
+      });
+    self.composite_texture(output, &texture, bounds.origin, &bounds, 1., BlendMode::Normal);
+  }
+
+  fn blur_backdrop(
+    &self, layer: &LayerCommand, matrix: &Transform, sigma: f32, area: &DeviceRect,
+    output: &mut CpuTexture,
+  ) {
+    let margin = (sigma * 3.).ceil() as i32;
+    let Some(src) = area
+      .inflate(margin, margin)
+      .intersection(&DeviceRect::from_size(output.size()))
+    else {
+      return;
+    };
+    let Some(mask) = raster::path_mask(&layer.path, &PaintingStyle::Fill, matrix, area) else {
+      return;
+    };
+
+    let width = output.size().width;
+    let data = output.data_mut();
+    let mut blurred = Vec::with_capacity(src.area() as usize * 4);
+    for y in src.y_range() {
+      let start = (y * width + src.min_x()) as usize * 4;
+      blurred.extend_from_slice(&data[start..start + src.width() as usize * 4]);
+    }
+    raster::gaussian_blur(&mut blurred, src.width() as usize, src.height() as usize, 4, sigma);
+
+    let clip = self.current_clip();
+    let coverage = mask.data();
+    for y in area.y_range() {
+      for x in area.x_range() {
+        let idx = (y * width + x) as usize;
+        let mask_idx = ((y - area.min_y()) * area.width() + x - area.min_x()) as usize;
+        let cover = mul_u8(coverage[mask_idx], clip.coverage(x, y));
+        if cover > 0 {
+          let blurred_idx = ((y - src.min_y()) * src.width() + x - src.min_x()) as usize * 4;
+          let cover = cover as f32 / 255.;
+          data[idx * 4..idx * 4 + 4]
+            .iter_mut()
+            .zip(&blurred[blurred_idx..blurred_idx + 4])
+            .for_each(|(d, b)| {
+              *d = (*b as f32 * cover + *d as f32 * (1. - cover)).round() as u8;
+            });
+        }
+      }
+    }
+  }
+
+  /// Draw the commands to a texture as large as the device `bounds`, the first
+  /// pixel of the texture is the origin of the `bounds`.
+  fn draw_to_texture(
+    &mut self, cmds: &[PaintCommand], global_matrix: &Transform, bounds: &DeviceRect,
+  ) -> CpuTexture {
+    let mut texture = CpuTexture::new(bounds.size);
+    let offset = bounds.origin.to_f32().to_vector().cast_unit();
+    let matrix = global_matrix.then_translate(-offset);
+    let layer_clip = ClipLayer { bounds: DeviceRect::from_size(bounds.size), mask: None };
+    self.clip_layer_stack.push(layer_clip);
+    cmds
+      .iter()
+      .for_each(|cmd| self.draw_command(cmd, &matrix, &mut texture));
+    self.clip_layer_stack.pop();
+    texture
+  }
+
+  /// Composite the `texture` placed at the `origin` over the `visible` area of
+  /// the `output` with the `opacity` in the blend `mode`, the clip of the
+  /// current layer is applied.
+  fn composite_texture(
+    &self, output: &mut CpuTexture, texture: &CpuTexture, origin: DevicePoint,
+    visible: &DeviceRect, opacity: f32, mode: BlendMode,
+  ) {
+    let width = output.size().width;
+    let texture_width = texture.size().width;
+    let clip = self.current_clip();
+    let src = texture.pixel_bytes();
+    let dst = output.data_mut();
+    for y in visible.y_range() {
+      for x in visible.x_range() {
+        let idx = (y * width + x) as usize;
+        let src_idx = ((y - origin.y) * texture_width + x - origin.x) as usize * 4;
+        let p = &src[src_idx..src_idx + 4];
+        let cover = clip.coverage(x, y);
+        if p[3] > 0 && cover > 0 {
+          let color: Rgba = [p[0], p[1], p[2], p[3]].map(|c| c as f32 / 255.);
+          let coverage = opacity * cover as f32 / 255.;
+          blend(&mut dst[idx * 4..idx * 4 + 4], color, coverage, mode);
+        }
+      }
+    }
+  }
+
+  fn current_clip(&self) -> &ClipLayer {
+    self
+      .clip_layer_stack
+      .last()
+      .expect("Must have one clip layer in draw.")
+  }
+
+  fn fill_mask(
+    &self, output: &mut CpuTexture, bounds: &DeviceRect, mask: &Mask, shader: &Shader,
+    mode: BlendMode,
+  ) {
+    let width = output.size().width;
+    let clip = self.current_clip();
+    let data = output.data_mut();
+    let coverage = mask.data();
+    for y in bounds.y_range() {
+      for x in bounds.x_range() {
+        let idx = (y * width + x) as usize;
+        let mask_idx = ((y - bounds.min_y()) * bounds.width() + x - bounds.min_x()) as usize;
+        let cover = mul_u8(coverage[mask_idx], clip.coverage(x, y));
+        if cover > 0 {
+          let src = shader.shade(x, y);
+          blend(&mut data[idx * 4..idx * 4 + 4], src, cover as f32 / 255., mode);
+        }
+      }
+    }
+  }
+}
+
+/// Composite the premultiplied `src` over the premultiplied `dst` pixel with
+/// the `coverage`.
+fn src_over(dst: &mut [u8], src: Rgba, coverage: f32) {
+  let src = src.map(|c| c * coverage);
+  let inv_alpha = 1. - src[3];
+  for (d, s) in dst.iter_mut().zip(src) {
+    let v = s * 255. + *d as f32 * inv_alpha;
+    *d = v.round().clamp(0., 255.) as u8;
+  }
+}
+
+/// Blend the premultiplied `src` with the premultiplied `dst` pixel in the
+/// `mode`, and composite the result over `dst` with the `coverage`.
+fn blend(dst: &mut [u8], src: Rgba, coverage: f32, mode: BlendMode) {
+  if mode == BlendMode::Normal {
+    return src_over(dst, src, coverage);
+  }
+
+  let [sr, sg, sb, sa] = src.map(|c| c * coverage);
+  let d: Rgba = [dst[0], dst[1], dst[2], dst[3]].map(|c| c as f32 / 255.);
+  let da = d[3];
+  let blended = if mode == BlendMode::Plus {
+    [sr + d[0], sg + d[1], sb + d[2], sa + da]
+  } else {
+    let unmul = |c: f32, a: f32| if a > 0. { c / a } else { 0. };
+    let color = |s: f32, d: f32| {
+      let b = blend_channel(unmul(s, sa), unmul(d, da), mode);
+      s * (1. - da) + d * (1. - sa) + b * sa * da
+    };
+    [color(sr, d[0]), color(sg, d[1]), color(sb, d[2]), sa + da - sa * da]
+  };
+  for (d, c) in dst.iter_mut().zip(blended) {
+    *d = (c * 255.).round().clamp(0., 255.) as u8;
+  }
+}
+
+/// The blended straight color of the separable blend modes.
+fn blend_channel(s: f32, d: f32, mode: BlendMode) -> f32 {
+  match mode {
+    BlendMode::Normal => s,
+    BlendMode::Multiply => s * d,
+    BlendMode::Screen => s + d - s * d,
+    BlendMode::Overlay if d <= 0.5 => 2. * s * d,
+    BlendMode::Overlay => 1. - 2. * (1. - s) * (1. - d),
+    BlendMode::Darken => s.min(d),
+    BlendMode::Lighten => s.max(d),
+    BlendMode::Difference => (s - d).abs(),
+    BlendMode::Plus => (s + d).min(1.),
+  }
+}
+
+/// The luminance of the premultiplied pixel, it's already multiplied by the
+/// alpha.
+fn luminance(p: &[u8]) -> u8 {
+  let l = 0.2125 * p[0] as f32 + 0.7154 * p[1] as f32 + 0.0721 * p[2] as f32;
+  l.round().clamp(0., 255.) as u8
+}
+
+fn mul_u8(a: u8, b: u8) -> u8 { ((a as u32 * b as u32 + 127) / 255) as u8 }
+
+#[cfg(test)]
+mod tests {
+  use ribir_geom::{DevicePoint, DeviceSize, Point, Rect, Size};
+  use ribir_painter::{Painter, Path};
+
+  use super::*;
+
+  fn fill_commands(color: Color) -> Vec<PaintCommand> {
+    let mut painter = Painter::new(Rect::from_size(Size::new(10., 10.)));
+    painter
+      .set_fill_brush(color)
+      .rect(&Rect::from_size(Size::new(10., 10.)))
+      .fill();
+    let cmds = painter.finish().to_vec();
+    cmds
+  }
+
+  #[test]
+  fn only_redraw_damages() {
+    let viewport = DeviceRect::from_size(DeviceSize::new(10, 10));
+    let mut texture = CpuTexture::new(viewport.size);
+    let mut backend = CpuBackend::new();
+    let identity = Transform::identity();
+
+    backend.begin_frame(Color::WHITE);
+    backend.draw_commands(
+      viewport,
+      &[viewport],
+      &fill_commands(Color::RED),
+      &identity,
+      &mut texture,
+    );
+    backend.end_frame();
+
+    let damage = DeviceRect::new(DevicePoint::new(5, 0), DeviceSize::new(5, 10));
+    backend.begin_frame(Color::WHITE);
+    backend.draw_commands(
+      viewport,
+      &[damage],
+      &fill_commands(Color::BLUE),
+      &identity,
+      &mut texture,
+    );
+    backend.end_frame();
+
+    let img = texture.copy_as_image(&viewport);
+    let pixel = |x: usize| &img.pixel_bytes()[x * 4..x * 4 + 4];
+    assert_eq!(pixel(4), Color::RED.into_components());
+    assert_eq!(pixel(5), Color::BLUE.into_components());
+  }
+
+  #[test]
+  fn clip_and_fill_in_their_bounds() {
+    let viewport = DeviceRect::from_size(DeviceSize::new(10, 10));
+    let mut texture = CpuTexture::new(viewport.size);
+    let mut backend = CpuBackend::new();
+
+    let mut painter = Painter::new(Rect::from_size(Size::new(10., 10.)));
+    painter
+      .clip(Path::rect(&Rect::new(Point::new(4., 4.), Size::new(4., 4.))).into())
+      .set_fill_brush(Color::RED)
+      .rect(&Rect::new(Point::new(2., 2.), Size::new(8., 8.)))
+      .fill();
+    let cmds = painter.finish().to_vec();
+
+    backend.begin_frame(Color::WHITE);
+    backend.draw_commands(viewport, &[viewport], &cmds, &Transform::identity(), &mut texture);
+    backend.end_frame();
+
+    let img = texture.copy_as_image(&viewport);
+    let pixel = |x: usize, y: usize| &img.pixel_bytes()[(y * 10 + x) * 4..(y * 10 + x) * 4 + 4];
+    assert_eq!(pixel(3, 3), Color::WHITE.into_components());
+    assert_eq!(pixel(4, 4), Color::RED.into_components());
+    assert_eq!(pixel(7, 7), Color::RED.into_components());
+    assert_eq!(pixel(8, 8), Color::WHITE.into_components());
+  }
+}
@@ -0,0 +1,118 @@
+use ribir_geom::{DeviceRect, Transform};
+use ribir_painter::{LineCap, LineJoin, PaintingStyle, Path, PathSegment, StrokeOptions};
+use tiny_skia::{FillRule, Mask, PathBuilder, PathStroker, Stroke};
+
+/// Rasterize the path to a coverage mask of the device `bounds`, the first
+/// pixel of the mask is the origin of the `bounds`.
+pub(crate) fn path_mask(
+  path: &Path, style: &PaintingStyle, matrix: &Transform, bounds: &DeviceRect,
+) -> Option<Mask> {
+  let offset = bounds.origin.to_f32().to_vector().cast_unit();
+  let ts = skia_transform(&matrix.then_translate(-offset));
+  let dashed;
+  let path = match style {
+    PaintingStyle::Stroke(options) if !options.dash_array.is_empty() => {
+      dashed = path.dash(&options.dash_array, options.dash_offset);
+      &dashed
+    }
+    _ => path,
+  };
+  let path = skia_path(path)?;
+  let path = match style {
+    PaintingStyle::Fill => path,
+    PaintingStyle::Stroke(options) => {
+      let res_scale = PathStroker::compute_resolution_scale(&ts);
+      path.stroke(&skia_stroke(options), res_scale)?
+    }
+  };
+
+  let mut mask = Mask::new(bounds.width() as u32, bounds.height() as u32)?;
+  mask.fill_path(&path, FillRule::Winding, true, ts);
+  Some(mask)
+}
+
+/// Blur the pixels in place by a gaussian blur with the standard deviation
+/// `sigma`. Every pixel has `channels` bytes, and the pixels outside the
+/// `width` x `height` area are treated as transparent.
+pub(crate) fn gaussian_blur(
+  data: &mut [u8], width: usize, height: usize, channels: usize, sigma: f32,
+) {
+  let kernel = gaussian_kernel(sigma);
+  let radius = kernel.len() / 2;
+  let row_len = width * channels;
+
+  let mut horizontal = vec![0.; row_len * height];
+  for y in 0..height {
+    let row = &data[y * row_len..(y + 1) * row_len];
+    for x in 0..width {
+      let start = x.saturating_sub(radius);
+      let end = (x + radius + 1).min(width);
+      for c in 0..channels {
+        horizontal[y * row_len + x * channels + c] = (start..end)
+          .map(|i| row[i * channels + c] as f32 * kernel[i + radius - x])
+          .sum();
+      }
+    }
+  }
+
+  for x in 0..width {
+    for y in 0..height {
+      let start = y.saturating_sub(radius);
+      let end = (y + radius + 1).min(height);
+      for c in 0..channels {
+        let v: f32 = (start..end)
+          .map(|i| horizontal[i * row_len + x * channels + c] * kernel[i + radius - y])
+          .sum();
+        data[y * row_len + x * channels + c] = v.round().clamp(0., 255.) as u8;
+      }
+    }
+  }
+}
+
+/// The normalized weights of the gaussian blur, the radius of the kernel is
+/// three times of the `sigma`.
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+  let radius = (sigma * 3.).ceil() as i32;
+  let weights: Vec<f32> = (-radius..=radius)
+    .map(|i| (-((i * i) as f32) / (2. * sigma * sigma)).exp())
+    .collect();
+  let total: f32 = weights.iter().sum();
+  weights.into_iter().map(|w| w / total).collect()
+}
+
+fn skia_path(path: &Path) -> Option<tiny_skia::Path> {
+  let mut builder = PathBuilder::new();
+  for seg in path.segments() {
+    match seg {
+      PathSegment::MoveTo(p) => builder.move_to(p.x, p.y),
+      PathSegment::LineTo(p) => builder.line_to(p.x, p.y),
+      PathSegment::QuadTo { ctrl, to } => builder.quad_to(ctrl.x, ctrl.y, to.x, to.y),
+      PathSegment::CubicTo { to, ctrl1, ctrl2 } => {
+        builder.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y)
+      }
+      PathSegment::Close(true) => builder.close(),
+      PathSegment::Close(false) => {}
+    }
+  }
+  builder.finish()
+}
+
+fn skia_stroke(options: &StrokeOptions) -> Stroke {
+  let StrokeOptions { width, miter_limit, line_cap, line_join, .. } = *options;
+  let line_cap = match line_cap {
+    LineCap::Butt => tiny_skia::LineCap::Butt,
+    LineCap::Round => tiny_skia::LineCap::Round,
+    LineCap::Square => tiny_skia::LineCap::Square,
+  };
+  let line_join = match line_join {
+    LineJoin::Miter => tiny_skia::LineJoin::Miter,
+    LineJoin::MiterClip => tiny_skia::LineJoin::MiterClip,
+    LineJoin::Round => tiny_skia::LineJoin::Round,
+    LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+  };
+  Stroke { width, miter_limit, line_cap, line_join, dash: None }
+}
+
+fn skia_transform(ts: &Transform) -> tiny_skia::Transform {
+  tiny_skia::Transform::from_row(ts.m11, ts.m12, ts.m21, ts.m22, ts.m31, ts.m32)
+}
@@ -0,0 +1,224 @@
+use ribir_algo::Resource;
+use ribir_geom::{Point, Transform};
+use ribir_painter::{
+  Color, CommandBrush, FilterQuality, GradientStop, PixelImage, SpreadMethod,
+  color::{LinearGradient, RadialGradient, SweepGradient},
+  image::ColorFormat,
+};
+
+/// A premultiplied RGBA color, every channel is in `[0, 1]`.
+pub(crate) type Rgba = [f32; 4];
+
+/// Compute the color of a brush at any position of the device.
+pub(crate) struct Shader<'a> {
+  kind: ShaderKind<'a>,
+  /// Transform a device position to the brush position.
+  device_to_brush: Transform,
+}
+
+enum ShaderKind<'a> {
+  Color(Rgba),
+  Image { img: &'a Resource<PixelImage>, opacity: f32, filter_quality: FilterQuality },
+  Linear(&'a LinearGradient),
+  Radial(&'a RadialGradient),
+  Sweep(&'a SweepGradient),
+}
+
+impl<'a> Shader<'a> {
+  /// Create a shader for the brush that painted with the `matrix`, return
+  /// `None` if the brush can't be painted.
+  pub(crate) fn new(brush: &'a CommandBrush, matrix: &Transform) -> Option<Self> {
+    let device_to_brush = matrix.inverse()?;
+    let kind = match brush {
+      CommandBrush::Color(color) => ShaderKind::Color(premultiplied(*color)),
+      CommandBrush::Image { img, opacity, filter_quality } => {
+        ShaderKind::Image { img, opacity: *opacity, filter_quality: *filter_quality }
+      }
+      CommandBrush::Linear(linear) if linear.start != linear.end && !linear.stops.is_empty() => {
+        ShaderKind::Linear(linear)
+      }
+      CommandBrush::Radial(radial) if !radial.stops.is_empty() => ShaderKind::Radial(radial),
+      CommandBrush::Sweep(sweep) if !sweep.stops.is_empty() => ShaderKind::Sweep(sweep),
+      _ => return None,
+    };
+    Some(Self { kind, device_to_brush })
+  }
+
+  /// Return the color at the center of the device pixel `(x, y)`.
+  pub(crate) fn shade(&self, x: i32, y: i32) -> Rgba {
+    let pos = || {
+      let center = Point::new(x as f32 + 0.5, y as f32 + 0.5);
+      self.device_to_brush.transform_point(center)
+    };
+    match self.kind {
+      ShaderKind::Color(color) => color,
+      ShaderKind::Image { img, opacity, filter_quality } => {
+        let [r, g, b, a] = sample_image(img, pos(), filter_quality);
+        [r * opacity, g * opacity, b * opacity, a * opacity]
+      }
+      ShaderKind::Linear(linear) => {
+        let LinearGradient { start, end, stops, spread_method } = linear;
+        let pos = pos();
+        let v = *end - *start;
+        let offset = (pos - *start).dot(v) / v.square_length();
+        gradient_color(stops, spread(offset, *spread_method))
+      }
+      ShaderKind::Radial(radial) => {
+        let pos = pos();
+        match radial_offset(pos, radial) {
+          Some(offset) => gradient_color(&radial.stops, spread(offset, radial.spread_method)),
+          None => [0.; 4],
+        }
+      }
+      ShaderKind::Sweep(sweep) => match sweep.offset_at(pos()) {
+        Some(offset) => gradient_color(&sweep.stops, spread(offset, sweep.spread_method)),
+        None => [0.; 4],
+      },
+    }
+  }
+}
+
+pub(crate) fn premultiplied(color: Color) -> Rgba {
+  let [r, g, b, a] = color.into_f32_components();
+  [r * a, g * a, b * a, a]
+}
+
+fn spread(offset: f32, method: SpreadMethod) -> f32 {
+  match method {
+    SpreadMethod::Pad => offset.clamp(0., 1.),
+    SpreadMethod::Reflect => 1. - ((offset / 2.).rem_euclid(1.) - 0.5).abs() * 2.,
+    SpreadMethod::Repeat => offset.rem_euclid(1.),
+  }
+}
+
+fn gradient_color(stops: &[GradientStop], offset: f32) -> Rgba {
+  let next_idx = stops
+    .iter()
+    .position(|s| s.offset >= offset)
+    .unwrap_or(stops.len() - 1);
+  let prev_idx = next_idx.saturating_sub(1);
+  let (prev, next) = (&stops[prev_idx], &stops[next_idx]);
+
+  let range = next.offset - prev.offset;
+  let weight = if range > 0. { ((offset - prev.offset) / range).clamp(0., 1.) } else { 1. };
+  let prev = prev.color.into_f32_components();
+  let next = next.color.into_f32_components();
+  let mut color = [0.; 4];
+  for i in 0..4 {
+    color[i] = prev[i] * (1. - weight) + next[i] * weight;
+  }
+  let a = color[3];
+  [color[0] * a, color[1] * a, color[2] * a, a]
+}
+
+/// Resolve the offset of the two-point conical gradient at `pos`, see the
+/// definition at https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-createradialgradient
+fn radial_offset(pos: Point, radial: &RadialGradient) -> Option<f32> {
+  let RadialGradient { start_center: c0, start_radius: r0, end_center: c1, end_radius: r1, .. } =
+    *radial;
+  let d0 = pos - c0;
+  let d10 = c1 - c0;
+  let dr10 = r1 - r0;
+
+  let a = d10.square_length() - dr10 * dr10;
+  let b = -2. * (d10.dot(d0) + dr10 * r0);
+  let c = d0.square_length() - r0 * r0;
+
+  let offset = if a.abs() < f32::EPSILON {
+    if b.abs() < f32::EPSILON {
+      return None;
+    }
+    -c / b
+  } else {
+    let delta = b * b - 4. * a * c;
+    if delta < 0. {
+      return None;
+    }
+    let sqrt_delta = delta.sqrt();
+    let w1 = (-b + sqrt_delta) / (2. * a);
+    let w2 = (-b - sqrt_delta) / (2. * a);
+    // The bigger offset is painted over the smaller one, but only the offset
+    // with a non-negative radius can be painted.
+    let radius = |w: f32| r0 + dr10 * w;
+    match (radius(w1.max(w2)) >= 0., radius(w1.min(w2)) >= 0.) {
+      (true, _) => w1.max(w2),
+      (false, true) => w1.min(w2),
+      _ => return None,
+    }
+  };
+
+  (r0 + dr10 * offset >= 0.).then_some(offset)
+}
+
+/// Sample the image with bilinear filtering, the image is repeated to fill the
+/// whole plane.
+fn sample_image(img: &PixelImage, pos: Point, filter_quality: FilterQuality) -> Rgba {
+  let (w, h) = (img.width() as i32, img.height() as i32);
+  if w == 0 || h == 0 {
+    return [0.; 4];
+  }
+  let texel = |x: i32, y: i32| texel(img, x.rem_euclid(w), y.rem_euclid(h));
+  if filter_quality == FilterQuality::Nearest {
+    return texel(pos.x.floor() as i32, pos.y.floor() as i32);
+  }
+
+  let x = pos.x - 0.5;
+  let y = pos.y - 0.5;
+  let (x0, y0) = (x.floor(), y.floor());
+  let (fx, fy) = (x - x0, y - y0);
+  let (x0, y0) = (x0 as i32, y0 as i32);
+
+  let lerp = |a: Rgba, b: Rgba, t: f32| -> Rgba {
+    [
+      a[0] + (b[0] - a[0]) * t,
+      a[1] + (b[1] - a[1]) * t,
+      a[2] + (b[2] - a[2]) * t,
+      a[3] + (b[3] - a[3]) * t,
+    ]
+  };
+  let top = lerp(texel(x0, y0), texel(x0 + 1, y0), fx);
+  let bottom = lerp(texel(x0, y0 + 1), texel(x0 + 1, y0 + 1), fx);
+  lerp(top, bottom, fy)
+}
+
+fn texel(img: &PixelImage, x: i32, y: i32) -> Rgba {
+  let bytes = img.pixel_bytes();
+  match img.color_format() {
+    ColorFormat::Rgba8 => {
+      let idx = (y as usize * img.width() as usize + x as usize) * 4;
+      let [r, g, b, a] = [bytes[idx], bytes[idx + 1], bytes[idx + 2], bytes[idx + 3]];
+      premultiplied(Color::new(r, g, b, a))
+    }
+    // An alpha image only has the alpha channel, we treat it as a black image.
+    ColorFormat::Alpha8 => {
+      let a = bytes[y as usize * img.width() as usize + x as usize] as f32 / 255.;
+      [0., 0., 0., a]
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn spread_methods() {
+    assert_eq!(spread(1.5, SpreadMethod::Pad), 1.);
+    assert_eq!(spread(1.25, SpreadMethod::Repeat), 0.25);
+    assert_eq!(spread(1.25, SpreadMethod::Reflect), 0.75);
+    assert_eq!(spread(-0.25, SpreadMethod::Reflect), 0.25);
+  }
+
+  #[test]
+  fn radial_concentric_offset() {
+    let radial = RadialGradient {
+      start_center: Point::new(0., 0.),
+      start_radius: 0.,
+      end_center: Point::new(0., 0.),
+      end_radius: 10.,
+      ..Default::default()
+    };
+    let offset = radial_offset(Point::new(5., 0.), &radial).unwrap();
+    assert!((offset - 0.5).abs() < 1e-5);
+  }
+}
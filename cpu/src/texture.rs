@@ -0,0 +1,87 @@
+use ribir_geom::{DeviceRect, DeviceSize};
+use ribir_painter::{Color, PixelImage, image::ColorFormat};
+
+/// A RGBA8 pixel buffer that the `CpuBackend` draws to.
+///
+/// The pixels are stored with premultiplied alpha, use
+/// [`CpuTexture::copy_as_image`] to get a straight alpha image.
+pub struct CpuTexture {
+  data: Vec<u8>,
+  size: DeviceSize,
+}
+
+impl CpuTexture {
+  /// Create a transparent texture with the `size`.
+  pub fn new(size: DeviceSize) -> Self {
+    let size = size.max(DeviceSize::zero());
+    let data = vec![0; size.area() as usize * 4];
+    Self { data, size }
+  }
+
+  #[inline]
+  pub fn size(&self) -> DeviceSize { self.size }
+
+  #[inline]
+  pub fn color_format(&self) -> ColorFormat { ColorFormat::Rgba8 }
+
+  /// The premultiplied RGBA pixels of the texture, row by row.
+  #[inline]
+  pub fn pixel_bytes(&self) -> &[u8] { &self.data }
+
+  /// Fill the whole texture with the `color`.
+  pub fn clear(&mut self, color: Color) {
+    let pixel = premultiply(color.into_components());
+    self
+      .data
+      .chunks_exact_mut(4)
+      .for_each(|p| p.copy_from_slice(&pixel));
+  }
+
+  /// Fill the `rect` area of the texture with the `color`.
+  pub fn clear_rect(&mut self, rect: &DeviceRect, color: Color) {
+    let Some(rect) = rect.intersection(&DeviceRect::from_size(self.size)) else { return };
+    let pixel = premultiply(color.into_components());
+    for y in rect.y_range() {
+      let start = (y * self.size.width + rect.min_x()) as usize * 4;
+      let end = start + rect.width() as usize * 4;
+      self.data[start..end]
+        .chunks_exact_mut(4)
+        .for_each(|p| p.copy_from_slice(&pixel));
+    }
+  }
+
+  /// Return the image of the texture area, the pixels of the image are not
+  /// premultiplied.
+  pub fn copy_as_image(&self, rect: &DeviceRect) -> PixelImage {
+    let rect = rect
+      .intersection(&DeviceRect::from_size(self.size))
+      .unwrap_or_default();
+    let mut data = Vec::with_capacity(rect.area() as usize * 4);
+    for y in rect.y_range() {
+      let start = (y * self.size.width + rect.min_x()) as usize * 4;
+      let end = start + rect.width() as usize * 4;
+      self.data[start..end]
+        .chunks_exact(4)
+        .for_each(|p| data.extend_from_slice(&unpremultiply(p)));
+    }
+
+    PixelImage::new(data.into(), rect.width() as u32, rect.height() as u32, ColorFormat::Rgba8)
+  }
+
+  pub(crate) fn data_mut(&mut self) -> &mut [u8] { &mut self.data }
+}
+
+pub(crate) fn premultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+  let mul = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+  [mul(r), mul(g), mul(b), a]
+}
+
+fn unpremultiply(p: &[u8]) -> [u8; 4] {
+  let a = p[3];
+  if a == 0 {
+    [0, 0, 0, 0]
+  } else {
+    let div = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+    [div(p[0]), div(p[1]), div(p[2]), a]
+  }
+}
@@ -13,7 +13,7 @@ use ribir_painter::{PixelImage, image::ColorFormat};
 /// name}\{function name}.{fmt}`:
 ///
 /// - the `{module path}` is where the generated test is placed.
-/// - the `{backend name}` is the painter-backend name like `wgpu`.
+/// - the `{backend name}` is the painter-backend name like `wgpu` or `cpu`.
 /// - the `{function  name}` is the function you pass to the macro.
 /// - the `{fmt}` is the file format the backend wants to check.
 ///
@@ -36,6 +36,18 @@ macro_rules! painter_backend_eq_image_test {
           $(.with_comparison($comparison))?
           .test();
       }
+
+      #[test]
+      fn [<cpu_ $painter_fn>]() {
+        let mut painter = $painter_fn();
+        let viewport = painter.viewport().to_i32().cast_unit();
+        let img = $crate::cpu_render_commands(&painter.finish(), viewport, Color::TRANSPARENT);
+        let name = format!("{}_cpu", std::stringify!($painter_fn));
+        let file_path = test_case_name!(name, "png");
+        ImageTest::new(img, &file_path)
+          $(.with_comparison($comparison))?
+          .test();
+      }
     }
   };
 }
@@ -87,7 +99,7 @@ impl<'a> ImageTest<'a> {
       let mut f = File::open(ref_path).unwrap();
       let mut bytes = Vec::new();
       std::io::Read::read_to_end(&mut f, &mut bytes).unwrap();
-      let ref_img = PixelImage::from_png(&bytes);
+      let ref_img = PixelImage::from_png(&bytes).unwrap();
 
       assert_eq!(test_img.pixel_bytes().len(), ref_img.pixel_bytes().len());
       assert_eq!(test_img.color_format(), ColorFormat::Rgba8);
@@ -191,7 +203,7 @@ pub fn wgpu_render_commands(
       .get_impl_mut()
       .new_texture(rect.size, ColorFormat::Rgba8);
     backend.begin_frame(surface);
-    backend.draw_commands(rect, commands, &Transform::identity(), &mut texture);
+    backend.draw_commands(rect, &[rect], commands, &Transform::identity(), &mut texture);
     let img = texture.copy_as_image(&rect, backend.get_impl_mut());
     backend.end_frame();
     block_on(img).unwrap()
@@ -224,3 +236,21 @@ pub fn wgpu_render_commands(
     draw_img(&mut backend)
   }
 }
+
+/// Render painter by the cpu backend, and return the image.
+pub fn cpu_render_commands(
+  commands: &[ribir_painter::PaintCommand], viewport: ribir_geom::DeviceRect,
+  surface: ribir_painter::Color,
+) -> PixelImage {
+  use ribir_cpu::{CpuBackend, CpuTexture};
+  use ribir_geom::{DeviceRect, DeviceSize};
+  use ribir_painter::PainterBackend;
+
+  let rect = DeviceRect::from_size(DeviceSize::new(viewport.max_x() + 2, viewport.max_y() + 2));
+  let mut texture = CpuTexture::new(rect.size);
+  let mut backend = CpuBackend::new();
+  backend.begin_frame(surface);
+  backend.draw_commands(rect, &[rect], commands, &Transform::identity(), &mut texture);
+  backend.end_frame();
+  texture.copy_as_image(&rect)
+}
@@ -35,7 +35,8 @@ macro_rules! widget_layout_test {
 /// The macro generates image tests for a widget. It requires the test name as
 /// the first parameter and an expression that returns a `WidgetTester`.
 ///
-/// It will produce tests for the widget with every theme and painter backend.
+/// It will produce tests for the widget with every theme and painter backend,
+/// the `wgpu` and the `cpu` backends.
 /// The test and image file names are formatted as `{widget name}_with_{theme
 /// name}_by_{painter backend name}`.
 ///
@@ -70,6 +71,26 @@ macro_rules! widget_image_tests {
         let name = format!("{}_with_material_by_wgpu", std::stringify!($name));
         $crate::assert_widget_eq_image!($widget_tester, name);
       }
+
+      #[test]
+      fn [<$name _with_default_by_cpu>]() {
+        let _scope = unsafe { AppCtx::new_lock_scope() };
+        svg::named_svgs::reset();
+        unsafe { AppCtx::set_app_theme(ribir_slim::purple()) };
+
+        let name = format!("{}_with_default_by_cpu", std::stringify!($name));
+        $crate::assert_widget_eq_image!($widget_tester, name, $crate::cpu_render_commands);
+      }
+
+      #[test]
+      fn [<$name _with_material_by_cpu>]() {
+        let _scope = unsafe { AppCtx::new_lock_scope() };
+        svg::named_svgs::reset();
+        unsafe { AppCtx::set_app_theme(ribir_material::purple::light()) };
+
+        let name = format!("{}_with_material_by_cpu", std::stringify!($name));
+        $crate::assert_widget_eq_image!($widget_tester, name, $crate::cpu_render_commands);
+      }
     }
   };
 }
@@ -77,13 +98,16 @@ macro_rules! widget_image_tests {
 #[macro_export]
 macro_rules! assert_widget_eq_image {
   ($widget_tester:expr, $name:expr) => {
+    $crate::assert_widget_eq_image!($widget_tester, $name, $crate::wgpu_render_commands);
+  };
+  ($widget_tester:expr, $name:expr, $render:path) => {
     let img_path = $crate::test_case_name!($name, "png");
 
     let mut wnd = $widget_tester.create_wnd();
 
-    let Frame { commands, viewport, surface } = wnd.take_last_frame().unwrap();
+    let Frame { commands, viewport, surface, .. } = wnd.take_last_frame().unwrap();
     let viewport = viewport.to_i32().cast_unit();
-    let img = $crate::wgpu_render_commands(&commands, viewport, surface);
+    let img = $render(&commands, viewport, surface);
 
     let mut img_test = $crate::ImageTest::new(img, &img_path);
     if let Some(c) = $widget_tester.comparison {
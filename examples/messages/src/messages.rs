@@ -19,22 +19,30 @@ pub fn messages() -> Widget<'static> {
       Message {
         nick_name: "James Harden".to_string(),
         content: "Coming soon!".to_string(),
-        img: Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-2.png"))),
+        img: Resource::new(
+          PixelImage::from_png(include_bytes!("../../attachments/3DDD-2.png")).unwrap(),
+        ),
       },
       Message {
         nick_name: "Allen Iverson".to_string(),
         content: "You are welcome!".to_string(),
-        img: Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-1.png"))),
+        img: Resource::new(
+          PixelImage::from_png(include_bytes!("../../attachments/3DDD-1.png")).unwrap(),
+        ),
       },
       Message {
         nick_name: "Kyrie Irving".to_string(),
         content: "See you next week!".to_string(),
-        img: Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-3.png"))),
+        img: Resource::new(
+          PixelImage::from_png(include_bytes!("../../attachments/3DDD-3.png")).unwrap(),
+        ),
       },
       Message {
         nick_name: "Jaylon Lee".to_string(),
         content: "Fighting!".to_string(),
-        img: Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-4.png"))),
+        img: Resource::new(
+          PixelImage::from_png(include_bytes!("../../attachments/3DDD-4.png")).unwrap(),
+        ),
       },
     ],
   }
@@ -35,10 +35,10 @@ fn content() -> Widget<'static> {
               padding: EdgeInsets::new(20., 40., 20., 40.),
               background: Palette::of(BuildCtx::get()).surface_container_low(),
               border_radius: Radius::all(4.),
-              border: Border::all(BorderSide {
-                color: Palette::of(BuildCtx::get()).primary().into(),
-                width: 1.,
-              }),
+              border: Border::all(BorderSide::new(
+                1.,
+                Palette::of(BuildCtx::get()).primary().into(),
+              )),
               @Row {
                 item_gap: 20.,
                 @SizedBox {
@@ -105,10 +105,10 @@ fn content() -> Widget<'static> {
               padding: EdgeInsets::new(20., 40., 20., 40.),
               background: Palette::of(BuildCtx::get()).surface_container_lowest(),
               border_radius: Radius::all(4.),
-              border: Border::all(BorderSide {
-                color: Palette::of(BuildCtx::get()).primary().into(),
-                width: 1.,
-              }),
+              border: Border::all(BorderSide::new(
+                1.,
+                Palette::of(BuildCtx::get()).primary().into(),
+              )),
               @Row {
                 item_gap: 20.,
                 @FabButton {
@@ -137,10 +137,10 @@ fn content() -> Widget<'static> {
               padding: EdgeInsets::new(20., 40., 20., 40.),
               background: Palette::of(BuildCtx::get()).surface_container_lowest(),
               border_radius: Radius::all(4.),
-              border: Border::all(BorderSide {
-                color: Palette::of(BuildCtx::get()).primary().into(),
-                width: 1.,
-              }),
+              border: Border::all(BorderSide::new(
+                1.,
+                Palette::of(BuildCtx::get()).primary().into(),
+              )),
               @Row {
                 item_gap: 20.,
                 @Button {
@@ -235,7 +235,7 @@ fn content() -> Widget<'static> {
             @Leading::new(
               EdgeWidget::Avatar(
                 @Avatar {
-                  @ { Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-1.png"))) }
+                  @ { Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-1.png")).unwrap()) }
                 }
               )
             )
@@ -253,7 +253,7 @@ fn content() -> Widget<'static> {
           @Divider { indent: 16. }
           @ListItem {
             @Leading::new(EdgeWidget::Poster(
-              Poster(Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-3.png"))))
+              Poster(Resource::new(PixelImage::from_png(include_bytes!("../../attachments/3DDD-3.png")).unwrap()))
             ))
             @ { HeadlineText(Label::new("One lines list item")) }
             @ { SupportingText(Label::new("One lines supporting text")) }
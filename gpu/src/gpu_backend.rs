@@ -5,17 +5,20 @@ use ribir_geom::{
   DeviceRect, DeviceSize, Point, Transform, rect_corners, transform_to_device_rect,
 };
 use ribir_painter::{
-  Color, CommandBrush, PaintCommand, PaintPath, PaintPathAction, PainterBackend, PaintingStyle,
-  PathCommand, PixelImage, Vertex, VertexBuffers, image::ColorFormat,
+  BlendMode, BlurTarget, Color, CommandBrush, FilterQuality, LayerCommand, MaskCommand,
+  PaintCommand, PaintPath, PaintPathAction, PainterBackend, PaintingStyle, PathCommand, PixelImage,
+  ShadowCommand, Vertex, VertexBuffers, image::ColorFormat,
 };
 
 use crate::{
   ColorAttr, GPUBackendImpl, GradientStopPrimitive, ImagePrimIndex, ImgPrimitive,
   LinearGradientPrimIndex, LinearGradientPrimitive, MaskLayer, RadialGradientPrimIndex,
-  RadialGradientPrimitive,
+  RadialGradientPrimitive, SweepGradientPrimIndex, SweepGradientPrimitive,
 };
 
 mod atlas;
+#[cfg(test)]
+mod mock_gpu;
 
 mod textures_mgr;
 use textures_mgr::*;
@@ -32,15 +35,36 @@ pub struct GPUBackend<Impl: GPUBackendImpl> {
   linear_gradient_prims: Vec<LinearGradientPrimitive>,
   linear_gradient_stops: Vec<GradientStopPrimitive>,
   linear_gradient_vertices_buffer: VertexBuffers<LinearGradientPrimIndex>,
+  sweep_gradient_prims: Vec<SweepGradientPrimitive>,
+  sweep_gradient_stops: Vec<GradientStopPrimitive>,
+  sweep_gradient_vertices_buffer: VertexBuffers<SweepGradientPrimIndex>,
   current_phase: CurrentPhase,
   tex_ids_map: TextureIdxMap,
   viewport: DeviceRect,
   mask_layers: Vec<MaskLayer>,
   clip_layer_stack: Vec<ClipLayer>,
   skip_clip_cnt: usize,
+  /// The depth of the bundles that are being drawn to their cache texture.
+  bundle_depth: usize,
+  /// The depth of the layers that are drawing, the nested layer can't be
+  /// allocated in the texture of the outer one, it's the render target.
+  layer_depth: usize,
   surface_color: Option<Color>,
 }
 
+/// The statistics of the GPU memory used by the texture atlases.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextureMemoryStats {
+  /// The bytes of all textures held by the atlases.
+  pub texture_bytes: usize,
+  /// The bytes of the allocations alive in the textures.
+  pub allocated_bytes: usize,
+  /// How many cached allocations have been evicted.
+  pub evictions: usize,
+  /// How many times the atlases have been compacted.
+  pub compactions: usize,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum CurrentPhase {
   None,
@@ -48,6 +72,7 @@ enum CurrentPhase {
   Img,
   RadialGradient,
   LinearGradient,
+  SweepGradient,
 }
 
 struct ClipLayer {
@@ -55,6 +80,14 @@ struct ClipLayer {
   mask_head: i32,
 }
 
+/// How to draw an image slice.
+#[derive(Clone, Copy)]
+struct ImgAttrs {
+  mask_head: i32,
+  opacity: f32,
+  filter_quality: FilterQuality,
+}
+
 /// Texture use to display.
 pub trait Texture {
   type Host;
@@ -83,6 +116,19 @@ struct TextureIdxMap {
   textures: Vec<TextureID>,
 }
 
+impl std::ops::Add for TextureMemoryStats {
+  type Output = Self;
+
+  fn add(self, rhs: Self) -> Self {
+    TextureMemoryStats {
+      texture_bytes: self.texture_bytes + rhs.texture_bytes,
+      allocated_bytes: self.allocated_bytes + rhs.allocated_bytes,
+      evictions: self.evictions + rhs.evictions,
+      compactions: self.compactions + rhs.compactions,
+    }
+  }
+}
+
 impl<Impl> PainterBackend for GPUBackend<Impl>
 where
   Impl: GPUBackendImpl,
@@ -95,9 +141,12 @@ where
     self.gpu_impl.begin_frame();
   }
 
+  /// The content of the texture is not kept between frames, for example, the
+  /// swap chain texture, so the damages are ignored and the whole viewport is
+  /// redrawn.
   fn draw_commands(
-    &mut self, viewport: DeviceRect, commands: &[PaintCommand], global_matrix: &Transform,
-    output: &mut Self::Texture,
+    &mut self, viewport: DeviceRect, _damages: &[DeviceRect], commands: &[PaintCommand],
+    global_matrix: &Transform, output: &mut Self::Texture,
   ) {
     let clips = self.clip_layer_stack.len();
     self.viewport = viewport;
@@ -114,7 +163,7 @@ where
 
   fn end_frame(&mut self) {
     self.mask_layers.clear();
-    self.tex_mgr.end_frame();
+    self.tex_mgr.end_frame(&mut self.gpu_impl);
     self.gpu_impl.end_frame();
   }
 }
@@ -132,6 +181,8 @@ where
       mask_layers: vec![],
       clip_layer_stack: vec![],
       skip_clip_cnt: 0,
+      bundle_depth: 0,
+      layer_depth: 0,
       color_vertices_buffer: VertexBuffers::with_capacity(256, 512),
       img_vertices_buffer: VertexBuffers::with_capacity(256, 512),
       radial_gradient_vertices_buffer: VertexBuffers::with_capacity(256, 512),
@@ -140,6 +191,9 @@ where
       linear_gradient_vertices_buffer: VertexBuffers::with_capacity(256, 512),
       linear_gradient_stops: vec![],
       linear_gradient_prims: vec![],
+      sweep_gradient_vertices_buffer: VertexBuffers::with_capacity(256, 512),
+      sweep_gradient_stops: vec![],
+      sweep_gradient_prims: vec![],
       img_prims: vec![],
       current_phase: CurrentPhase::None,
       viewport: DeviceRect::zero(),
@@ -156,6 +210,20 @@ where
   #[inline]
   pub fn into_impl(self) -> Impl { self.gpu_impl }
 
+  /// Set the max bytes of the cached textures that can be retained when they
+  /// are not used in a frame, the least recently used ones are evicted first.
+  /// The textures used in the current frame are always kept.
+  ///
+  /// The budget is 0 by default, the textures not used in a frame are freed
+  /// at the end of the frame.
+  pub fn set_texture_budget(&mut self, bytes: usize) { self.tex_mgr.set_budget(bytes); }
+
+  /// The max bytes of the cached textures that can be retained.
+  pub fn texture_budget(&self) -> usize { self.tex_mgr.budget() }
+
+  /// Return the statistics of the GPU memory used by the texture atlases.
+  pub fn texture_memory_stats(&self) -> TextureMemoryStats { self.tex_mgr.memory_stats() }
+
   fn draw_command(
     &mut self, cmd: &PaintCommand, global_matrix: &Transform, output_tex_size: DeviceSize,
     output: &mut Impl::Texture,
@@ -179,6 +247,19 @@ where
           return;
         };
 
+        if let PaintPathAction::Paint { brush, painting_style, blend_mode } = action {
+          if *blend_mode != BlendMode::Normal {
+            let action = PaintPathAction::Paint {
+              brush: brush.clone(),
+              painting_style: painting_style.clone(),
+              blend_mode: BlendMode::Normal,
+            };
+            let normal = PaintCommand::Path(PathCommand { action, ..cmd.clone() });
+            self.draw_blend_layer(&normal, &viewport, *blend_mode, global_matrix, output);
+            return;
+          }
+        }
+
         if !self.can_batch_path_command(cmd) {
           self.new_draw_phase(output);
         }
@@ -204,10 +285,12 @@ where
               add_rect_vertices(rect, output_tex_size, color_attr, buffer);
               self.current_phase = CurrentPhase::Color;
             }
-            CommandBrush::Image { img, opacity } => {
+            CommandBrush::Image { img, opacity, filter_quality } => {
               let slice = self.tex_mgr.store_image(img, &mut self.gpu_impl);
               let ts = matrix.inverse().unwrap();
-              self.draw_img_slice(slice, &ts, mask_head, *opacity, output_tex_size, rect);
+              let attrs =
+                ImgAttrs { mask_head, opacity: *opacity, filter_quality: *filter_quality };
+              self.draw_img_slice(slice, &ts, attrs, output_tex_size, rect);
             }
             CommandBrush::Radial(radial) => {
               let prim: RadialGradientPrimitive = RadialGradientPrimitive {
@@ -254,9 +337,75 @@ where
               add_rect_vertices(rect, output_tex_size, LinearGradientPrimIndex(prim_idx), buffer);
               self.current_phase = CurrentPhase::LinearGradient;
             }
+            CommandBrush::Sweep(sweep) => {
+              let stop = (self.sweep_gradient_stops.len() << 16 | sweep.stops.len()) as u32;
+              let mask_head_and_spread = mask_head << 16 | sweep.spread_method as i32;
+              let prim = SweepGradientPrimitive {
+                transform: matrix.inverse().unwrap().to_array(),
+                center: sweep.center.to_array(),
+                start_angle: sweep.start_angle,
+                end_angle: sweep.end_angle,
+                stop,
+                mask_head_and_spread,
+              };
+              let stops = sweep.stops.iter().map(GradientStopPrimitive::new);
+              self.sweep_gradient_stops.extend(stops);
+              let prim_idx = self.sweep_gradient_prims.len() as u32;
+              self.sweep_gradient_prims.push(prim);
+              let buffer = &mut self.sweep_gradient_vertices_buffer;
+              add_rect_vertices(rect, output_tex_size, SweepGradientPrimIndex(prim_idx), buffer);
+              self.current_phase = CurrentPhase::SweepGradient;
+            }
           },
         }
       }
+      PaintCommand::Shadow(shadow) => {
+        if self.skip_clip_cnt > 0 {
+          return;
+        }
+        let ShadowCommand { path, paint_bounds, transform, color, .. } = shadow;
+        let bounds = transform_to_device_rect(paint_bounds, global_matrix);
+        let Some(viewport) = self.viewport().intersection(&bounds) else {
+          return;
+        };
+
+        if !self.can_batch_color_command() {
+          self.new_draw_phase(output);
+        }
+
+        let matrix = transform.then(global_matrix);
+        let sigma = shadow.blur_sigma(&matrix);
+        let (mask, mask_to_view) =
+          self
+            .tex_mgr
+            .store_blur_path(path, &matrix, sigma, &viewport, &mut self.gpu_impl);
+        let (rect, mask_head) = self.push_mask_layer(mask, &mask_to_view);
+        let color_attr = ColorAttr { color: color.into_components(), mask_head };
+        add_rect_vertices(rect, output_tex_size, color_attr, &mut self.color_vertices_buffer);
+        self.current_phase = CurrentPhase::Color;
+      }
+      PaintCommand::Layer(layer) => {
+        if self.skip_clip_cnt > 0 {
+          return;
+        }
+        let sigma = layer.blur_sigma(&layer.transform.then(global_matrix));
+        match layer.target {
+          BlurTarget::Content => self.draw_blur_content(layer, sigma, global_matrix, output),
+          BlurTarget::Backdrop => {
+            if sigma > 0. {
+              self.draw_blur_backdrop(layer, sigma, global_matrix, output);
+            }
+            for cmd in layer.cmds.iter() {
+              self.draw_command(cmd, global_matrix, output_tex_size, output);
+            }
+          }
+        }
+      }
+      PaintCommand::Mask(mask) => {
+        if self.skip_clip_cnt == 0 {
+          self.draw_mask_layer(mask, global_matrix, output);
+        }
+      }
       PaintCommand::PopClip => {
         if self.skip_clip_cnt > 0 {
           self.skip_clip_cnt -= 1;
@@ -264,8 +413,32 @@ where
           self.clip_layer_stack.pop();
         }
       }
-      PaintCommand::Bundle { transform, opacity, bounds, cmds } => {
+      PaintCommand::Bundle { transform, opacity, blend_mode, bounds, cmds } => {
         let matrix = transform.then(global_matrix);
+        if *blend_mode != BlendMode::Normal {
+          if self.skip_clip_cnt == 0 {
+            let normal = PaintCommand::Bundle {
+              transform: *transform,
+              opacity: *opacity,
+              blend_mode: BlendMode::Normal,
+              bounds: *bounds,
+              cmds: cmds.clone(),
+            };
+            let bounds = transform_to_device_rect(bounds, &matrix);
+            self.draw_blend_layer(&normal, &bounds, *blend_mode, global_matrix, output);
+          }
+          return;
+        }
+        if self.bundle_depth > 0 {
+          // The cache texture of the outer bundle is the render target now and can't
+          // be sampled, so the nested bundle is drawn through a layer.
+          if self.skip_clip_cnt == 0 {
+            let bounds = transform_to_device_rect(bounds, &matrix);
+            self.draw_nested_bundle(cmds, &bounds, *opacity, &matrix, output);
+          }
+          return;
+        }
+
         let scale = self.tex_mgr.cache_scale(&bounds.size, &matrix);
         let cache_size = bounds.size * scale;
 
@@ -291,12 +464,14 @@ where
             // Overwrite the viewport to the slice bounds.
             self
               .clip_layer_stack
-              .push(ClipLayer { viewport, mask_head: -1 });
+              .push(ClipLayer { viewport: *slice, mask_head: -1 });
 
-            let matrix = Transform::translation(bounds.origin.x, bounds.origin.y)
+            let matrix = Transform::translation(-bounds.origin.x, -bounds.origin.y)
               .then_scale(scale, scale)
               .then_translate(slice.origin.to_f32().cast_unit().to_vector());
-            this.draw_commands(*slice, cmds, &matrix, tex);
+            this.bundle_depth += 1;
+            this.draw_commands(*slice, &[*slice], cmds, &matrix, tex);
+            this.bundle_depth -= 1;
 
             // restore the clip layer and viewport
             self.clip_layer_stack.pop();
@@ -326,11 +501,200 @@ where
           .clip_layer_stack
           .last()
           .map_or(-1, |l| l.mask_head);
-        self.draw_img_slice(slice, &view_to_slice, mask_head, *opacity, output_tex_size, points);
+        let attrs =
+          ImgAttrs { mask_head, opacity: *opacity, filter_quality: FilterQuality::Linear };
+        self.draw_img_slice(slice, &view_to_slice, attrs, output_tex_size, points);
       }
     }
   }
 
+  /// Draw the content of the layer to a texture, blur it and then draw the
+  /// texture to the output.
+  fn draw_blur_content(
+    &mut self, layer: &LayerCommand, sigma: f32, global_matrix: &Transform,
+    output: &mut Impl::Texture,
+  ) {
+    // The pixels around the visible area affect it by the blur, so they are
+    // drawn too.
+    let margin = (sigma * 3.).ceil() as i32;
+    let bounds = transform_to_device_rect(&layer.paint_bounds, global_matrix);
+    let Some(bounds) = bounds.intersection(&self.viewport().inflate(margin, margin)) else {
+      return;
+    };
+    let Some(visible) = bounds.intersection(self.viewport()) else { return };
+
+    let alone = self.layer_depth > 0;
+    let this = self as *mut Self;
+    let slice =
+      self
+        .tex_mgr
+        .store_layer(bounds.size, alone, &mut self.gpu_impl, |slice, tex, _| {
+          // SAFETY: The same as the bundle, the layer texture always exists within the
+          // frame, and the slice that has been allocated will not be modified.
+          let this = unsafe { &mut *this };
+          this.new_draw_phase(output);
+          tex.clear_areas(&[*slice], &mut this.gpu_impl);
+
+          let viewport = this.viewport;
+          this
+            .clip_layer_stack
+            .push(ClipLayer { viewport: *slice, mask_head: -1 });
+          let offset = (slice.origin - bounds.origin)
+            .to_f32()
+            .cast_unit();
+          let matrix = global_matrix.then_translate(offset);
+          this.layer_depth += 1;
+          this.draw_commands(*slice, &[*slice], &layer.cmds, &matrix, tex);
+          this.layer_depth -= 1;
+          this.clip_layer_stack.pop();
+          this.viewport = viewport;
+          this.begin_draw_phase();
+
+          if sigma > 0. {
+            this.gpu_impl.blur_texture(tex, slice, sigma);
+          }
+        });
+
+    if !self.can_batch_img_path() {
+      self.new_draw_phase(output);
+    }
+    let points = rect_corners(&visible.to_f32().cast_unit());
+    let view_to_slice = Transform::translation(-bounds.min_x() as f32, -bounds.min_y() as f32);
+    let mask_head = self.current_clip_mask_index();
+    let attrs = ImgAttrs { mask_head, opacity: 1., filter_quality: FilterQuality::Linear };
+    self.draw_img_slice(slice, &view_to_slice, attrs, output.size(), points);
+  }
+
+  /// Copy the backdrop under the path of the layer to a texture, blur it and
+  /// then draw it back to the output inside the path.
+  fn draw_blur_backdrop(
+    &mut self, layer: &LayerCommand, sigma: f32, global_matrix: &Transform,
+    output: &mut Impl::Texture,
+  ) {
+    let matrix = layer.transform.then(global_matrix);
+    let area = transform_to_device_rect(&layer.path.bounds(None), &matrix);
+    let Some(area) = area.intersection(self.viewport()) else { return };
+    let margin = (sigma * 3.).ceil() as i32;
+    let Some(src) = area
+      .inflate(margin, margin)
+      .intersection(&DeviceRect::from_size(output.size()))
+    else {
+      return;
+    };
+
+    // The backdrop must be drawn before it's copied.
+    self.new_draw_phase(output);
+    let alone = self.layer_depth > 0;
+    let slice = self
+      .tex_mgr
+      .store_layer(src.size, alone, &mut self.gpu_impl, |slice, tex, gpu| {
+        gpu.copy_texture_from_texture(tex, slice.origin, output, &src);
+        gpu.blur_texture(tex, slice, sigma);
+      });
+
+    let (points, mask_head) =
+      self.new_mask_layer(&area, &matrix, &layer.path, &PaintingStyle::Fill);
+    let view_to_slice = Transform::translation(-src.min_x() as f32, -src.min_y() as f32);
+    let attrs = ImgAttrs { mask_head, opacity: 1., filter_quality: FilterQuality::Linear };
+    self.draw_img_slice(slice, &view_to_slice, attrs, output.size(), points);
+  }
+
+  /// Draw the command to a texture, blend it with the backdrop in the `mode`
+  /// and then draw it back to the output. The command should be in the normal
+  /// blend mode.
+  fn draw_blend_layer(
+    &mut self, cmd: &PaintCommand, bounds: &DeviceRect, mode: BlendMode, global_matrix: &Transform,
+    output: &mut Impl::Texture,
+  ) {
+    let Some(bounds) = bounds.intersection(self.viewport()) else { return };
+
+    // The backdrop must be drawn before it's blended.
+    self.new_draw_phase(output);
+    let cmds = std::slice::from_ref(cmd);
+    let slice = self.draw_layer(&bounds, cmds, global_matrix, |slice, tex, this| {
+      this
+        .gpu_impl
+        .blend_texture(tex, slice, output, bounds.origin, mode);
+    });
+    self.draw_layer_slice(slice, &bounds, 1., output);
+  }
+
+  /// Draw the mask and the content of the layer to two textures, mask the
+  /// content and then draw it to the output.
+  fn draw_mask_layer(
+    &mut self, mask: &MaskCommand, global_matrix: &Transform, output: &mut Impl::Texture,
+  ) {
+    let bounds = transform_to_device_rect(&mask.paint_bounds, global_matrix);
+    let Some(bounds) = bounds.intersection(self.viewport()) else { return };
+
+    self.new_draw_phase(output);
+    let mask_slice = self.draw_layer(&bounds, &mask.mask, global_matrix, |_, _, _| {});
+    let slice = self.draw_layer(&bounds, &mask.cmds, global_matrix, |slice, tex, this| {
+      let mask_tex = this.tex_mgr.texture(mask_slice.tex_id);
+      this
+        .gpu_impl
+        .mask_texture(tex, slice, mask_tex, mask_slice.rect.origin, mask.mode);
+    });
+    self.draw_layer_slice(slice, &bounds, 1., output);
+  }
+
+  fn draw_nested_bundle(
+    &mut self, cmds: &[PaintCommand], bounds: &DeviceRect, opacity: f32, matrix: &Transform,
+    output: &mut Impl::Texture,
+  ) {
+    let Some(bounds) = bounds.intersection(self.viewport()) else { return };
+
+    self.new_draw_phase(output);
+    let slice = self.draw_layer(&bounds, cmds, matrix, |_, _, _| {});
+    self.draw_layer_slice(slice, &bounds, opacity, output);
+  }
+
+  /// Draw the commands to a layer texture as large as the device `bounds`, and
+  /// call `then` with the drawn slice.
+  fn draw_layer(
+    &mut self, bounds: &DeviceRect, cmds: &[PaintCommand], global_matrix: &Transform,
+    then: impl FnOnce(&DeviceRect, &mut Impl::Texture, &mut Self),
+  ) -> TextureSlice {
+    let alone = self.layer_depth > 0;
+    let this = self as *mut Self;
+    self
+      .tex_mgr
+      .store_layer(bounds.size, alone, &mut self.gpu_impl, |slice, tex, _| {
+        // SAFETY: The same as the bundle, the layer texture always exists within the
+        // frame, and the slice that has been allocated will not be modified.
+        let this = unsafe { &mut *this };
+        tex.clear_areas(&[*slice], &mut this.gpu_impl);
+
+        let viewport = this.viewport;
+        this
+          .clip_layer_stack
+          .push(ClipLayer { viewport: *slice, mask_head: -1 });
+        let offset = (slice.origin - bounds.origin)
+          .to_f32()
+          .cast_unit();
+        let matrix = global_matrix.then_translate(offset);
+        this.layer_depth += 1;
+        this.draw_commands(*slice, &[*slice], cmds, &matrix, tex);
+        this.layer_depth -= 1;
+        this.clip_layer_stack.pop();
+        this.viewport = viewport;
+        this.begin_draw_phase();
+
+        then(slice, tex, this);
+      })
+  }
+
+  /// Draw the layer slice to the device `bounds` of the output.
+  fn draw_layer_slice(
+    &mut self, slice: TextureSlice, bounds: &DeviceRect, opacity: f32, output: &mut Impl::Texture,
+  ) {
+    let points = rect_corners(&bounds.to_f32().cast_unit());
+    let view_to_slice = Transform::translation(-bounds.min_x() as f32, -bounds.min_y() as f32);
+    let mask_head = self.current_clip_mask_index();
+    let attrs = ImgAttrs { mask_head, opacity, filter_quality: FilterQuality::Linear };
+    self.draw_img_slice(slice, &view_to_slice, attrs, output.size(), points);
+  }
+
   fn can_batch_img_path(&self) -> bool {
     let limits = self.gpu_impl.limits();
     self.current_phase == CurrentPhase::None
@@ -405,15 +769,27 @@ where
       .indices
       .clear();
     self.linear_gradient_stops.clear();
+    self.sweep_gradient_prims.clear();
+    self.sweep_gradient_stops.clear();
+    self
+      .sweep_gradient_vertices_buffer
+      .indices
+      .clear();
+    self
+      .sweep_gradient_vertices_buffer
+      .vertices
+      .clear();
   }
 
   fn draw_img_slice(
-    &mut self, img_slice: TextureSlice, transform: &Transform, mask_head: i32, opacity: f32,
+    &mut self, img_slice: TextureSlice, transform: &Transform, attrs: ImgAttrs,
     output_tex_size: DeviceSize, rect: [Point; 4],
   ) {
+    let ImgAttrs { mask_head, opacity, filter_quality } = attrs;
     let img_start = img_slice.rect.origin.to_f32().to_array();
     let img_size = img_slice.rect.size.to_f32().to_array();
-    let mask_head_and_tex_idx = mask_head << 16 | self.tex_ids_map.tex_idx(img_slice.tex_id) as i32;
+    let tex_idx = self.tex_ids_map.tex_idx(img_slice.tex_id) as i32;
+    let mask_head_and_tex_idx = mask_head << 16 | (filter_quality as i32) << 8 | tex_idx;
     let prim_idx = self.img_prims.len() as u32;
     let prim = ImgPrimitive {
       transform: transform.to_array(),
@@ -452,6 +828,19 @@ where
           && self.linear_gradient_prims.len() < limits.max_linear_gradient_primitives
           && self.linear_gradient_stops.len() < limits.max_gradient_stop_primitives
       }
+      (CurrentPhase::SweepGradient, CommandBrush::Sweep(_)) => {
+        tex_used < limits.max_tex_load
+          && self.sweep_gradient_prims.len() < limits.max_sweep_gradient_primitives
+          && self.sweep_gradient_stops.len() < limits.max_gradient_stop_primitives
+      }
+      _ => false,
+    }
+  }
+
+  fn can_batch_color_command(&self) -> bool {
+    match self.current_phase {
+      CurrentPhase::None => true,
+      CurrentPhase::Color => self.tex_ids_map.len() < self.gpu_impl.limits().max_tex_load,
       _ => false,
     }
   }
@@ -477,7 +866,10 @@ where
       self
         .tex_mgr
         .store_alpha_path(path, style, matrix, view, &mut self.gpu_impl);
+    self.push_mask_layer(mask, &mask_to_view)
+  }
 
+  fn push_mask_layer(&mut self, mask: TextureSlice, mask_to_view: &Transform) -> ([Point; 4], i32) {
     let mut points = rect_corners(&mask.rect.to_f32().cast_unit());
     for p in points.iter_mut() {
       *p = mask_to_view.transform_point(*p);
@@ -558,6 +950,18 @@ where
         let rg = 0..self.linear_gradient_vertices_buffer.indices.len() as u32;
         gpu_impl.draw_linear_gradient_triangles(output, rg, color.take())
       }
+      CurrentPhase::SweepGradient
+        if !self
+          .sweep_gradient_vertices_buffer
+          .indices
+          .is_empty() =>
+      {
+        gpu_impl.load_sweep_gradient_primitives(&self.sweep_gradient_prims);
+        gpu_impl.load_sweep_gradient_stops(&self.sweep_gradient_stops);
+        gpu_impl.load_sweep_gradient_vertices(&self.sweep_gradient_vertices_buffer);
+        let rg = 0..self.sweep_gradient_vertices_buffer.indices.len() as u32;
+        gpu_impl.draw_sweep_gradient_triangles(output, rg, color.take())
+      }
       _ => {}
     }
   }
@@ -611,9 +1015,12 @@ mod tests {
   use ribir_algo::Resource;
   use ribir_dev_helper::*;
   use ribir_geom::*;
-  use ribir_painter::{Brush, Painter, Path, Svg};
+  use ribir_painter::{
+    Brush, GradientStop, LineCap, Lottie, NinePatch, Painter, Path, Radius, SpreadMethod,
+    StrokeOptions, Svg, SvgAnimValue, color::SweepGradient,
+  };
 
-  use super::*;
+  use super::{mock_gpu::MockGPUImpl, *};
 
   fn painter(bounds: Size) -> Painter { Painter::new(Rect::from_size(bounds)) }
 
@@ -633,7 +1040,7 @@ mod tests {
 
     let mut painter = painter(Size::new(512., 512.));
 
-    let img = PixelImage::from_png(include_bytes!("../imgs/leaves.png"));
+    let img = PixelImage::from_png(include_bytes!("../imgs/leaves.png")).unwrap();
     let share_img = Resource::new(img);
 
     let img_brush = Brush::Image(share_img);
@@ -674,7 +1081,8 @@ mod tests {
       .rect(&rect)
       .fill();
 
-    let leaves_brush = Resource::new(PixelImage::from_png(include_bytes!("../imgs/leaves.png")));
+    let leaves_brush =
+      Resource::new(PixelImage::from_png(include_bytes!("../imgs/leaves.png")).unwrap());
 
     painter
       .set_fill_brush(leaves_brush)
@@ -685,6 +1093,64 @@ mod tests {
     painter
   }
 
+  painter_backend_eq_image_test!(filter_quality_img, comparison = 0.001);
+  fn filter_quality_img() -> Painter {
+    let mut painter = painter(Size::new(200., 100.));
+    let (w, b) = ([255; 4], [0, 0, 0, 255]);
+    let checker = [w, b, b, w].concat();
+    let img = Resource::new(PixelImage::new(checker.into(), 2, 2, ColorFormat::Rgba8));
+    let dst = Rect::new(Point::new(10., 10.), Size::new(80., 80.));
+
+    painter
+      .set_filter_quality(FilterQuality::Nearest)
+      .draw_img(img.clone(), &dst, &None);
+    painter
+      .set_filter_quality(FilterQuality::Linear)
+      .translate(100., 0.)
+      .draw_img(img, &dst, &None);
+
+    painter
+  }
+
+  painter_backend_eq_image_test!(nine_patch, comparison = 0.001);
+  fn nine_patch() -> Painter {
+    let mut painter = painter(Size::new(220., 120.));
+    // A 6x6 image, the red corners and green edges are 2 pixels wide.
+    let (r, g, b) = ([255, 0, 0, 255], [0, 160, 0, 255], [0, 0, 255, 255]);
+    let mut data = vec![];
+    for y in 0..6 {
+      for x in 0..6 {
+        let edge_x = !(2..4).contains(&x);
+        let edge_y = !(2..4).contains(&y);
+        let c = if edge_x && edge_y {
+          r
+        } else if edge_x || edge_y {
+          g
+        } else {
+          b
+        };
+        data.extend(c);
+      }
+    }
+    let img = Resource::new(PixelImage::new(data.into(), 6, 6, ColorFormat::Rgba8));
+    let patch = NinePatch::new(img, 2, 2, 2, 2);
+
+    painter
+      .set_filter_quality(FilterQuality::Nearest)
+      .scale(2., 2.)
+      .draw_nine_patch(&patch, &Rect::new(Point::new(5., 5.), Size::new(40., 20.)));
+    painter
+      .set_filter_quality(FilterQuality::Linear)
+      .set_fill_brush(patch)
+      .apply_transform(
+        &Transform::rotation(Angle::degrees(15.)).then_translate(Vector::new(30., 0.)),
+      )
+      .rect_round(&Rect::new(Point::new(20., 5.), Size::new(30., 40.)), &Radius::all(6.))
+      .fill();
+
+    painter
+  }
+
   painter_backend_eq_image_test!(clip_layers, comparison = 0.0065);
   fn clip_layers() -> Painter {
     let mut painter = painter(Size::new(120., 340.));
@@ -721,8 +1187,9 @@ mod tests {
   fn two_img_brush() -> Painter {
     let mut painter = painter(Size::new(200., 100.));
 
-    let brush1 = PixelImage::from_png(include_bytes!("../imgs/leaves.png"));
-    let brush2 = PixelImage::from_png(include_bytes!("../../examples/attachments/3DDD-1.png"));
+    let brush1 = PixelImage::from_png(include_bytes!("../imgs/leaves.png")).unwrap();
+    let brush2 =
+      PixelImage::from_png(include_bytes!("../../examples/attachments/3DDD-1.png")).unwrap();
     let rect = rect(0., 0., 100., 100.);
     painter
       .set_fill_brush(brush1)
@@ -739,7 +1206,7 @@ mod tests {
 
   painter_backend_eq_image_test!(draw_partial_img, comparison = 0.0015);
   fn draw_partial_img() -> Painter {
-    let img = Resource::new(PixelImage::from_png(include_bytes!("../imgs/leaves.png")));
+    let img = Resource::new(PixelImage::from_png(include_bytes!("../imgs/leaves.png")).unwrap());
     let m_width = img.width() as f32;
     let m_height = img.height() as f32;
     let mut painter = painter(Size::new(m_width * 2., m_height * 2.));
@@ -770,6 +1237,90 @@ mod tests {
     painter
   }
 
+  painter_backend_eq_image_test!(draw_svg_text, comparison = 0.005);
+  fn draw_svg_text() -> Painter {
+    let mut painter = painter(Size::new(128., 64.));
+    let svg = Svg::parse_from_bytes(include_bytes!("../../tests/assets/svg_text.svg"), true, false)
+      .unwrap();
+
+    painter.draw_svg(&svg);
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_svg_pattern);
+  fn draw_svg_pattern() -> Painter {
+    let mut painter = painter(Size::new(64., 64.));
+    let svg =
+      Svg::parse_from_bytes(include_bytes!("../../tests/assets/svg_pattern.svg"), true, false)
+        .unwrap();
+
+    painter.draw_svg(&svg);
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_svg_mask);
+  fn draw_svg_mask() -> Painter {
+    let mut painter = painter(Size::new(64., 64.));
+    let svg = Svg::parse_from_bytes(include_bytes!("../../tests/assets/svg_mask.svg"), true, false)
+      .unwrap();
+
+    painter.draw_svg(&svg);
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_svg_filters);
+  fn draw_svg_filters() -> Painter {
+    let mut painter = painter(Size::new(64., 64.));
+    let svg =
+      Svg::parse_from_bytes(include_bytes!("../../tests/assets/svg_filters.svg"), true, false)
+        .unwrap();
+
+    painter.draw_svg(&svg);
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_svg_image);
+  fn draw_svg_image() -> Painter {
+    let mut painter = painter(Size::new(64., 64.));
+    let svg =
+      Svg::parse_from_bytes(include_bytes!("../../tests/assets/svg_image.svg"), true, false)
+        .unwrap();
+
+    painter.draw_svg(&svg);
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_svg_smil_frame);
+  fn draw_svg_smil_frame() -> Painter {
+    let mut painter = painter(Size::new(64., 32.));
+    let svg = Svg::parse_from_bytes(include_bytes!("../../tests/assets/svg_smil.svg"), true, false)
+      .unwrap();
+    let values = [
+      Some(SvgAnimValue::Transform([45., 16., 16.])),
+      Some(SvgAnimValue::Color(Color::from_u32(0x1D3557FF))),
+      Some(SvgAnimValue::Number(0.5)),
+    ];
+
+    painter
+      .draw_svg(&svg)
+      .translate(32., 0.)
+      .draw_svg_frame(&svg, &[], &values);
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_lottie_frames);
+  fn draw_lottie_frames() -> Painter {
+    let mut painter = painter(Size::new(128., 64.));
+    let lottie =
+      Lottie::parse_from_bytes(include_bytes!("../../tests/assets/lottie_shapes.json")).unwrap();
+
+    painter
+      .draw_lottie(&lottie, 15.)
+      .translate(64., 0.)
+      .draw_lottie(&lottie, 45.);
+    painter
+  }
+
   // This test is disabled on Windows as it fails in the CI environment (exit code
   // 2173), although it passes on a physical Windows machine.
   #[cfg(not(target_os = "windows"))]
@@ -804,6 +1355,7 @@ mod tests {
           action: PaintPathAction::Paint {
             brush: CommandBrush::Color(color),
             painting_style: PaintingStyle::Fill,
+            blend_mode: BlendMode::Normal,
           },
         })
       })
@@ -813,4 +1365,286 @@ mod tests {
     painter
   }
   painter_backend_eq_image_test!(draw_bundle_svg, comparison = 0.001);
+
+  fn draw_nested_bundle() -> Painter {
+    let mut tile = painter(Size::new(8., 8.));
+    tile
+      .set_fill_brush(Color::RED)
+      .rect(&Rect::from_size(Size::new(4., 4.)))
+      .fill();
+    let tile = Resource::new(tile.finish().to_vec().into_boxed_slice());
+
+    let mut inner = painter(Size::new(64., 64.));
+    inner.clip(Path::circle(Point::new(24., 24.), 22.).into());
+    for i in 0..6 {
+      for j in 0..6 {
+        inner.set_transform(Transform::translation(i as f32 * 8., j as f32 * 8.));
+        inner.draw_bundle_commands(Rect::from_size(Size::new(8., 8.)), tile.clone());
+      }
+    }
+    let inner = Resource::new(inner.finish().to_vec().into_boxed_slice());
+
+    let mut painter = painter(Size::new(64., 64.));
+    painter.draw_bundle_commands(Rect::from_size(Size::new(64., 64.)), inner);
+    painter
+  }
+  painter_backend_eq_image_test!(draw_nested_bundle);
+
+  fn draw_bundle_offset_bounds() -> Painter {
+    let bounds = Rect::new(Point::new(16., 16.), Size::new(32., 32.));
+    let mut bundle = painter(Size::new(64., 64.));
+    bundle
+      .set_fill_brush(Color::RED)
+      .rect(&bounds)
+      .fill()
+      .set_fill_brush(Color::BLUE)
+      .rect(&Rect::new(Point::new(24., 24.), Size::new(16., 16.)))
+      .fill();
+    let bundle = Resource::new(bundle.finish().to_vec().into_boxed_slice());
+
+    let mut painter = painter(Size::new(64., 64.));
+    painter.draw_bundle_commands(bounds, bundle);
+    painter
+  }
+  painter_backend_eq_image_test!(draw_bundle_offset_bounds);
+
+  fn draw_shadows() -> Painter {
+    let mut painter = painter(Size::new(320., 120.));
+    let rect = Rect::new(Point::new(20., 20.), Size::new(80., 80.));
+    let shadow = ribir_painter::Shadow::new(Vector::new(4., 6.), 12., 0., Color::BLACK);
+    painter
+      .draw_box_shadow(&rect, &ribir_painter::Radius::all(16.), &shadow)
+      .set_fill_brush(Color::WHITE)
+      .rect_round(&rect, &ribir_painter::Radius::all(16.))
+      .fill();
+
+    let spread = ribir_painter::Shadow { spread: 8., color: Color::RED, ..shadow };
+    painter
+      .translate(100., 0.)
+      .draw_box_shadow(&rect, &ribir_painter::Radius::all(0.), &spread);
+
+    let circle = Path::circle(Point::new(60., 60.), 30.);
+    painter
+      .translate(100., 0.)
+      .draw_path_shadow(circle.into(), &ribir_painter::Shadow { color: Color::BLUE, ..shadow });
+    painter
+  }
+  painter_backend_eq_image_test!(draw_shadows, comparison = 0.002);
+
+  fn draw_blur_layers() -> Painter {
+    let mut painter = painter(Size::new(320., 120.));
+    // Stripes over a white background as the backdrop.
+    painter
+      .set_fill_brush(Color::WHITE)
+      .rect(&Rect::from_size(Size::new(320., 120.)))
+      .fill();
+    for i in 0..16 {
+      let color = if i % 2 == 0 { Color::RED } else { Color::BLUE };
+      painter
+        .set_fill_brush(color)
+        .rect(&Rect::new(Point::new(i as f32 * 20., 0.), Size::new(10., 120.)))
+        .fill();
+    }
+
+    let rect = Rect::new(Point::new(20., 20.), Size::new(120., 80.));
+    painter
+      .save()
+      .blur_layer(Path::rect(&rect).into(), 8., BlurTarget::Content)
+      .set_fill_brush(Color::BLACK)
+      .rect(&rect.inflate(-20., -20.))
+      .fill()
+      .restore();
+
+    let panel = Path::rect_round(&rect, &ribir_painter::Radius::all(16.));
+    painter
+      .save()
+      .translate(160., 0.)
+      .blur_layer(panel.clone().into(), 12., BlurTarget::Backdrop)
+      .set_fill_brush(Color::WHITE.with_alpha(0.3))
+      .fill_path(panel.into())
+      .restore();
+    painter
+  }
+  painter_backend_eq_image_test!(draw_blur_layers, comparison = 0.002);
+
+  fn draw_blend_modes() -> Painter {
+    let mut painter = painter(Size::new(320., 80.));
+    // The backdrop, light on the top and dark on the bottom.
+    painter
+      .set_fill_brush(Color::from_rgb(255, 200, 64))
+      .rect(&Rect::from_size(Size::new(320., 40.)))
+      .fill()
+      .set_fill_brush(Color::from_rgb(32, 64, 128))
+      .rect(&Rect::new(Point::new(0., 40.), Size::new(320., 40.)))
+      .fill();
+
+    let modes = [
+      BlendMode::Normal,
+      BlendMode::Multiply,
+      BlendMode::Screen,
+      BlendMode::Overlay,
+      BlendMode::Darken,
+      BlendMode::Lighten,
+      BlendMode::Difference,
+      BlendMode::Plus,
+    ];
+    for (i, mode) in modes.into_iter().enumerate() {
+      painter
+        .set_blend_mode(mode)
+        .set_fill_brush(Color::from_rgb(64, 160, 224))
+        .circle(Point::new(i as f32 * 40. + 20., 40.), 16.)
+        .fill();
+    }
+
+    // A bundle is blended as a whole.
+    let cmds: Box<[PaintCommand]> = {
+      let mut p = Painter::new(Rect::from_size(Size::new(40., 40.)));
+      p.set_fill_brush(Color::RED)
+        .rect(&Rect::from_size(Size::new(20., 20.)))
+        .fill()
+        .rect(&Rect::new(Point::new(10., 10.), Size::new(20., 20.)))
+        .fill();
+      let cmds = p.finish().to_vec();
+      cmds.into()
+    };
+    painter
+      .set_blend_mode(BlendMode::Difference)
+      .translate(285., 45.)
+      .draw_bundle_commands(Rect::from_size(Size::new(30., 30.)), Resource::new(cmds));
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_blend_modes, comparison = 0.002);
+
+  fn draw_dashed_strokes() -> Painter {
+    let mut painter = painter(Size::new(200., 100.));
+    let line = |painter: &mut Painter, y: f32, options: StrokeOptions| {
+      painter
+        .set_strokes(options)
+        .begin_path(Point::new(10., y))
+        .line_to(Point::new(190., y))
+        .end_path(false)
+        .stroke();
+    };
+    painter.set_stroke_brush(Color::RED);
+    let dashed = StrokeOptions { width: 4., dash_array: vec![12., 6.], ..Default::default() };
+    line(&mut painter, 10., dashed.clone());
+    line(&mut painter, 20., StrokeOptions { dash_offset: 9., ..dashed.clone() });
+    // The zero length dashes with the round caps are dots.
+    let dotted = StrokeOptions {
+      width: 6.,
+      line_cap: LineCap::Round,
+      dash_array: vec![0., 12.],
+      ..Default::default()
+    };
+    line(&mut painter, 32., dotted);
+    // An odd number of lengths is repeated.
+    line(&mut painter, 44., StrokeOptions { dash_array: vec![12., 4., 4.], ..dashed });
+
+    painter
+      .set_strokes(StrokeOptions { width: 3., dash_array: vec![8., 4.], ..Default::default() })
+      .set_stroke_brush(Color::BLUE)
+      .circle(Point::new(50., 75.), 18.)
+      .stroke()
+      .rect_round(&Rect::new(Point::new(100., 58.), Size::new(80., 34.)), &Radius::all(10.))
+      .stroke();
+    painter
+  }
+
+  painter_backend_eq_image_test!(draw_dashed_strokes, comparison = 0.002);
+
+  fn draw_sweep_gradient() -> Painter {
+    let mut painter = painter(Size::new(200., 100.));
+    let wheel = SweepGradient {
+      center: Point::new(50., 50.),
+      start_angle: 0.,
+      end_angle: std::f32::consts::TAU,
+      stops: vec![
+        GradientStop::new(Color::RED, 0.),
+        GradientStop::new(Color::YELLOW, 1. / 3.),
+        GradientStop::new(Color::BLUE, 2. / 3.),
+        GradientStop::new(Color::RED, 1.),
+      ],
+      spread_method: SpreadMethod::Pad,
+    };
+    painter
+      .set_fill_brush(Brush::SweepGradient(wheel))
+      .circle(Point::new(50., 50.), 40.)
+      .fill();
+
+    // A quarter sweep repeated around the center.
+    let repeated = SweepGradient {
+      center: Point::new(150., 50.),
+      start_angle: -std::f32::consts::FRAC_PI_2,
+      end_angle: 0.,
+      stops: vec![GradientStop::new(Color::GREEN, 0.), GradientStop::new(Color::BLUE, 1.)],
+      spread_method: SpreadMethod::Repeat,
+    };
+    painter
+      .set_strokes(StrokeOptions { width: 12., ..Default::default() })
+      .set_stroke_brush(Brush::SweepGradient(repeated))
+      .circle(Point::new(150., 50.), 34.)
+      .stroke();
+    painter
+  }
+
+  // The wgpu output has no reference image rendered by a real adapter yet, so
+  // only the cpu output is asserted.
+  #[test]
+  fn cpu_draw_sweep_gradient() {
+    let mut painter = draw_sweep_gradient();
+    let viewport = painter.viewport().to_i32().cast_unit();
+    let img = cpu_render_commands(&painter.finish(), viewport, Color::TRANSPARENT);
+    let file_path = test_case_name!("draw_sweep_gradient_cpu", "png");
+    ImageTest::new(img, &file_path)
+      .with_comparison(0.002)
+      .test();
+  }
+
+  #[test]
+  fn texture_budget() {
+    fn draw_frame(backend: &mut GPUBackend<MockGPUImpl>, img: &Resource<PixelImage>) {
+      let mut painter = painter(Size::new(64., 64.));
+      painter.draw_img(img.clone(), &Rect::from_size(Size::new(32., 32.)), &None);
+      let rect = DeviceRect::from_size(DeviceSize::new(64, 64));
+      let mut output = backend
+        .get_impl_mut()
+        .new_texture(rect.size, ColorFormat::Rgba8);
+      backend.begin_frame(Color::WHITE);
+      backend.draw_commands(rect, &[rect], &painter.finish(), &Transform::identity(), &mut output);
+      backend.end_frame();
+    }
+
+    let mut backend = GPUBackend::new(MockGPUImpl::new(DeviceSize::new(1024, 1024)));
+    let data = vec![255; 32 * 32 * 4];
+    let img = Resource::new(PixelImage::new(data.into(), 32, 32, ColorFormat::Rgba8));
+    let img_bytes = 32 * 32 * 4;
+
+    draw_frame(&mut backend, &img);
+    let stats = backend.texture_memory_stats();
+    assert_eq!(stats.allocated_bytes, img_bytes);
+    assert_eq!(stats.evictions, 0);
+
+    // No budget by default, the image not used in this frame is freed.
+    let other =
+      Resource::new(PixelImage::new(vec![0; 16 * 16 * 4].into(), 16, 16, ColorFormat::Rgba8));
+    draw_frame(&mut backend, &other);
+    let stats = backend.texture_memory_stats();
+    assert_eq!(stats.allocated_bytes, 16 * 16 * 4);
+    assert_eq!(stats.evictions, 1);
+
+    // The image is retained in the budget, even it's not used in this frame.
+    backend.set_texture_budget(usize::MAX);
+    draw_frame(&mut backend, &img);
+    draw_frame(&mut backend, &other);
+    let stats = backend.texture_memory_stats();
+    assert_eq!(stats.allocated_bytes, img_bytes + 16 * 16 * 4);
+    assert_eq!(stats.evictions, 1);
+
+    backend.set_texture_budget(img_bytes);
+    draw_frame(&mut backend, &img);
+    let stats = backend.texture_memory_stats();
+    assert_eq!(stats.allocated_bytes, img_bytes);
+    assert_eq!(stats.evictions, 2);
+  }
 }
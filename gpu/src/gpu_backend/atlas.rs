@@ -1,4 +1,4 @@
-use std::hash::Hash;
+use std::{cmp::Reverse, hash::Hash};
 
 use guillotiere::{Allocation, AtlasAllocator};
 use ribir_algo::FrameCache;
@@ -7,11 +7,13 @@ use ribir_painter::image::ColorFormat;
 use slab::Slab;
 
 use super::Texture;
-use crate::GPUBackendImpl;
+use crate::{GPUBackendImpl, TextureMemoryStats};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub(super) enum AtlasDist {
-  Atlas(Allocation),
+  /// The index of the allocation in the atlas texture. The allocation may be
+  /// moved by the compaction, so we keep the index rather than the allocation.
+  Atlas(usize),
   Extra(usize),
 }
 
@@ -31,11 +33,21 @@ pub(crate) struct Atlas<K, T: Texture> {
   config: AtlasConfig,
   atlas_allocator: AtlasAllocator,
   texture: T,
+  allocs: Slab<Allocation>,
   cache: FrameCache<K, AtlasHandle>,
   /// Extra textures which store only single allocation.
   extras: Slab<T>,
   /// All allocations in the current frame and not cached.
   islands: ahash::HashSet<AtlasDist>,
+  /// An allocation failed in this frame even though the atlas had enough free
+  /// space for it.
+  fragmented: bool,
+  /// The area of the alive allocations in the atlas texture.
+  used_area: i64,
+  /// The max of `used_area` when allocating in the current frame.
+  peak_area: i64,
+  evictions: usize,
+  compactions: usize,
 }
 
 impl<K, T: Texture> Atlas<K, T>
@@ -50,9 +62,15 @@ where
       config,
       texture,
       atlas_allocator: AtlasAllocator::new(min_size.cast_unit()),
+      allocs: Slab::default(),
       cache: FrameCache::new(),
       extras: Slab::default(),
       islands: <_>::default(),
+      fragmented: false,
+      used_area: 0,
+      peak_area: 0,
+      evictions: 0,
+      compactions: 0,
     }
   }
 
@@ -104,6 +122,9 @@ where
     let mut alloc = self.atlas_allocator.allocate(alloc_size);
 
     if alloc.is_none() {
+      if self.size().area() as i64 - self.used_area >= size.area() as i64 {
+        self.fragmented = true;
+      }
       let expand_size = (current_size * 2)
         .max(current_size)
         .min(self.config.max_size);
@@ -132,17 +153,26 @@ where
     }
 
     let dist = if let Some(alloc) = alloc {
-      AtlasDist::Atlas(alloc)
+      self.used_area += alloc.rectangle.area() as i64;
+      self.peak_area = self.peak_area.max(self.used_area);
+      AtlasDist::Atlas(self.allocs.insert(alloc))
     } else {
-      let texture = gpu_impl.new_texture(size, self.texture.color_format());
-      let id = self.extras.insert(texture);
-      AtlasDist::Extra(id)
+      return self.allocate_alone(size, gpu_impl);
     };
     self.islands.insert(dist);
 
     dist
   }
 
+  /// Allocate a new extra texture for the `size`, the allocation never shares
+  /// its texture with others.
+  pub fn allocate_alone(&mut self, size: DeviceSize, gpu_impl: &mut T::Host) -> AtlasDist {
+    let texture = gpu_impl.new_texture(size, self.texture.color_format());
+    let dist = AtlasDist::Extra(self.extras.insert(texture));
+    self.islands.insert(dist);
+    dist
+  }
+
   /// Get a mut reference of a texture that `id` point to. The `id` get from
   /// `AtlasHandle::tex_id`
   pub fn get_texture_mut(&mut self, id: usize) -> &mut T {
@@ -165,24 +195,150 @@ where
       && size.area() <= self.config.max_size.area() / 4
   }
 
-  pub(crate) fn end_frame(&mut self) { self.end_frame_with(|_| {}) }
+  /// The bytes of the atlas texture and the extra textures.
+  pub fn texture_bytes(&self) -> usize {
+    let bytes = self.texture.size().area() as usize + self.extras_area();
+    bytes * self.pixel_bytes()
+  }
 
-  pub(crate) fn end_frame_with(&mut self, mut on_deallocate: impl FnMut(DeviceRect)) {
-    self
-      .cache
-      .end_frame(self.config.label)
-      .map(|h| h.dist)
-      .chain(self.islands.drain())
-      .for_each(|dist| match dist {
-        AtlasDist::Atlas(alloc) => {
-          on_deallocate(alloc.rectangle.to_rect().cast_unit());
-          self.atlas_allocator.deallocate(alloc.id);
-        }
-        AtlasDist::Extra(id) => {
-          self.extras.remove(id);
+  /// The bytes of the alive allocations, include the extra textures.
+  pub fn allocated_bytes(&self) -> usize {
+    (self.used_area as usize + self.extras_area()) * self.pixel_bytes()
+  }
+
+  pub fn memory_stats(&self) -> TextureMemoryStats {
+    TextureMemoryStats {
+      texture_bytes: self.texture_bytes(),
+      allocated_bytes: self.allocated_bytes(),
+      evictions: self.evictions,
+      compactions: self.compactions,
+    }
+  }
+
+  pub(crate) fn end_frame(&mut self, gpu: &mut T::Host) { self.end_frame_with(0, gpu, |_| {}); }
+
+  /// End the frame, the allocations not used in this frame are retained from
+  /// the most recently used one, until the bytes of all allocations exceed the
+  /// `budget`. Then compact the atlas if it's sparse or fragmented.
+  ///
+  /// Return if the atlas texture is replaced by the compaction.
+  pub(crate) fn end_frame_with(
+    &mut self, budget: usize, gpu: &mut T::Host, mut on_deallocate: impl FnMut(DeviceRect),
+  ) -> bool {
+    let islands = std::mem::take(&mut self.islands);
+    for dist in islands {
+      self.deallocate(dist, &mut on_deallocate);
+    }
+
+    let mut used = self.allocated_bytes();
+    let pixel_bytes = self.pixel_bytes();
+    let Self { cache, allocs, extras, config, .. } = self;
+    let evicted = cache
+      .end_frame_evict_while(config.label, |h| {
+        let over = used > budget;
+        if over {
+          let area = match h.dist {
+            AtlasDist::Atlas(id) => allocs[id].rectangle.area() as usize,
+            AtlasDist::Extra(id) => extras[id].size().area() as usize,
+          };
+          used -= area * pixel_bytes;
         }
-      });
+        over
+      })
+      .map(|h| h.dist)
+      .collect::<Vec<_>>();
+    self.evictions += evicted.len();
+    for dist in evicted {
+      self.deallocate(dist, &mut on_deallocate);
+    }
+
+    let compacted = self.compact(gpu);
+    self.peak_area = 0;
+    compacted
+  }
+
+  fn deallocate(&mut self, dist: AtlasDist, on_deallocate: &mut impl FnMut(DeviceRect)) {
+    match dist {
+      AtlasDist::Atlas(id) => {
+        let alloc = self.allocs.remove(id);
+        self.used_area -= alloc.rectangle.area() as i64;
+        on_deallocate(alloc.rectangle.to_rect().cast_unit());
+        self.atlas_allocator.deallocate(alloc.id);
+      }
+      AtlasDist::Extra(id) => {
+        self.extras.remove(id);
+      }
+    }
+  }
+
+  /// Repack the allocations to a new texture if the atlas is fragmented, or it
+  /// used less than a quarter of its texture in this frame, then it will
+  /// shrink to a smaller size.
+  fn compact(&mut self, gpu: &mut T::Host) -> bool {
+    let size = self.size();
+    let min_size = self.config.min_size;
+    let used = self.peak_area.max(self.used_area);
+    let mut new_size = size;
+    while used * 4 <= size.area() as i64
+      && new_size.width / 2 >= min_size.width
+      && new_size.height / 2 >= min_size.height
+      && used * 2 <= (new_size / 2).area() as i64
+    {
+      new_size /= 2;
+    }
+    if new_size == size && !self.fragmented {
+      return false;
+    }
+    self.fragmented = false;
+
+    let mut allocs = self
+      .allocs
+      .iter()
+      .map(|(id, a)| (id, *a))
+      .collect::<Vec<_>>();
+    // Place the bigger allocations first to reduce the fragmentation.
+    allocs.sort_by_key(|(_, a)| Reverse(a.rectangle.area()));
+
+    let packed = loop {
+      let mut allocator = AtlasAllocator::new(new_size.cast_unit());
+      let moved = allocs
+        .iter()
+        .map(|(id, old)| Some((*id, allocator.allocate(old.rectangle.size())?)))
+        .collect::<Option<Vec<_>>>();
+      if let Some(moved) = moved {
+        break Some((allocator, moved));
+      } else if new_size == size {
+        break None;
+      }
+      new_size = (new_size * 2).min(size);
+    };
+    let Some((allocator, moved)) = packed else { return false };
+
+    let mut new_tex = gpu.new_texture(new_size, self.texture.color_format());
+    for (id, alloc) in moved {
+      let old = std::mem::replace(&mut self.allocs[id], alloc);
+      gpu.copy_texture_from_texture(
+        &mut new_tex,
+        alloc.rectangle.min.cast_unit(),
+        &self.texture,
+        &old.rectangle.to_rect().cast_unit(),
+      );
+    }
+    self.texture = new_tex;
+    self.atlas_allocator = allocator;
+    self.compactions += 1;
+    true
+  }
+
+  fn extras_area(&self) -> usize {
+    self
+      .extras
+      .iter()
+      .map(|(_, t)| t.size().area() as usize)
+      .sum()
   }
+
+  fn pixel_bytes(&self) -> usize { self.texture.color_format().pixel_per_bytes() as usize }
 }
 
 impl AtlasConfig {
@@ -204,43 +360,23 @@ impl AtlasDist {
     T: Texture,
   {
     match self {
-      AtlasDist::Atlas(alloc) => alloc.rectangle.to_rect().cast_unit(),
+      AtlasDist::Atlas(id) => atlas.allocs[*id].rectangle.to_rect().cast_unit(),
       AtlasDist::Extra(id) => DeviceRect::from_size(atlas.extras[*id].size()),
     }
   }
 }
 
 impl AtlasHandle {
-  pub fn tex_id(&self) -> usize {
-    match &self.dist {
-      AtlasDist::Atlas(_) => 0,
-      AtlasDist::Extra(id) => *id + 1,
-    }
-  }
+  pub fn tex_id(&self) -> usize { self.dist.tex_id() }
 
   pub(super) fn tex_rect<K, T>(&self, atlas: &Atlas<K, T>) -> DeviceRect
   where
     T: Texture,
   {
-    match &self.dist {
-      AtlasDist::Atlas(alloc) => alloc.rectangle.to_rect().cast_unit(),
-      AtlasDist::Extra(id) => DeviceRect::from_size(atlas.extras[*id].size()),
-    }
+    self.dist.tex_rect(atlas)
   }
 }
 
-impl Hash for AtlasDist {
-  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-    match self {
-      // hash id enough, because the id is unique.
-      AtlasDist::Atlas(alloc) => alloc.id.hash(state),
-      AtlasDist::Extra(id) => id.hash(state),
-    }
-  }
-}
-
-impl Eq for AtlasDist {}
-
 #[cfg(feature = "wgpu")]
 #[cfg(test)]
 mod tests {
@@ -301,8 +437,8 @@ mod tests {
     let dist = atlas.allocate(DeviceSize::new(32, 32), &mut wgpu);
     atlas.cache(Resource::new(1).into_any(), 1., dist);
     atlas.allocate(size, &mut wgpu);
-    atlas.end_frame();
-    atlas.end_frame();
+    atlas.end_frame(&mut wgpu);
+    atlas.end_frame(&mut wgpu);
     wgpu.end_frame();
 
     assert!(atlas.extras.is_empty());
@@ -330,7 +466,7 @@ mod tests {
       .for_each_allocated_rectangle(|_, _| alloc_count += 1);
     assert_eq!(alloc_count, 2);
 
-    atlas.end_frame();
+    atlas.end_frame(&mut wgpu);
 
     // after end frame, the smaller allocation of the keep should be release.
     alloc_count = 0;
@@ -381,3 +517,104 @@ mod tests {
     )
   }
 }
+
+#[cfg(test)]
+mod mock_tests {
+  use super::*;
+  use crate::gpu_backend::mock_gpu::{MockGPUImpl, MockTexture};
+
+  fn mock_atlas(gpu: &mut MockGPUImpl) -> Atlas<i32, MockTexture> {
+    let config = AtlasConfig::new("", DeviceSize::new(1024, 1024));
+    Atlas::new(config, ColorFormat::Alpha8, gpu)
+  }
+
+  #[test]
+  fn evict_unused_over_budget() {
+    let mut gpu = MockGPUImpl::new(DeviceSize::new(1024, 1024));
+    let mut atlas = mock_atlas(&mut gpu);
+    let size = DeviceSize::new(16, 16);
+    for key in 0..3 {
+      atlas.get_or_cache(key, 1., size, &mut gpu, |_, _, _| {});
+    }
+    atlas.end_frame_with(usize::MAX, &mut gpu, |_| {});
+    assert_eq!(atlas.memory_stats().allocated_bytes, 768);
+
+    // Only the `0` is used in this frame, and the `1` is the least recently used.
+    assert!(atlas.get(&0, 1.).is_some());
+    atlas.end_frame_with(512, &mut gpu, |_| {});
+
+    let stats = atlas.memory_stats();
+    assert_eq!(stats.allocated_bytes, 512);
+    assert_eq!(stats.evictions, 1);
+    assert!(atlas.get(&1, 1.).is_none());
+    assert!(atlas.get(&2, 1.).is_some());
+  }
+
+  #[test]
+  fn evict_extra_texture() {
+    let mut gpu = MockGPUImpl::new(DeviceSize::new(1024, 1024));
+    let mut atlas = mock_atlas(&mut gpu);
+    let h = atlas.get_or_cache(0, 1., DeviceSize::new(1024, 1024), &mut gpu, |_, _, _| {});
+    assert_eq!(h.tex_id(), 1);
+    atlas.end_frame_with(usize::MAX, &mut gpu, |_| {});
+    assert_eq!(atlas.memory_stats().texture_bytes, 1024 * 1024 + 128 * 128);
+
+    atlas.end_frame_with(1024, &mut gpu, |_| {});
+    let stats = atlas.memory_stats();
+    assert_eq!(stats.texture_bytes, 128 * 128);
+    assert_eq!(stats.evictions, 1);
+  }
+
+  #[test]
+  fn shrink_sparse_atlas() {
+    let mut gpu = MockGPUImpl::new(DeviceSize::new(1024, 1024));
+    let mut atlas = mock_atlas(&mut gpu);
+    let min_size = atlas.size();
+
+    atlas.get_or_cache(0, 1., DeviceSize::new(200, 200), &mut gpu, |_, _, _| {});
+    let icon = atlas.get_or_cache(1, 1., DeviceSize::new(16, 16), &mut gpu, |_, _, _| {});
+    assert_eq!(atlas.size(), min_size * 2);
+    atlas.end_frame(&mut gpu);
+    assert_eq!(atlas.memory_stats().compactions, 0);
+
+    // Only the icon is used in this frame, the atlas is sparse.
+    assert!(atlas.get(&1, 1.).is_some());
+    let copies = gpu.copies;
+    atlas.end_frame(&mut gpu);
+
+    let stats = atlas.memory_stats();
+    assert_eq!(stats.compactions, 1);
+    assert_eq!(stats.texture_bytes, min_size.area() as usize);
+    assert_eq!(gpu.copies, copies + 1);
+    assert_eq!(atlas.size(), min_size);
+    let rect = icon.tex_rect(&atlas);
+    assert_eq!(rect.size, DeviceSize::new(16, 16));
+    assert!(DeviceRect::from_size(min_size).contains_rect(&rect));
+  }
+
+  #[test]
+  fn compact_fragmented_atlas() {
+    let mut gpu = MockGPUImpl::new(DeviceSize::new(1024, 1024));
+    let mut atlas = mock_atlas(&mut gpu);
+    let quarter = DeviceSize::new(64, 64);
+    for key in 0..4 {
+      let dist = atlas.allocate(quarter, &mut gpu);
+      // Only cache the diagonal quarters.
+      if key == 0 || key == 3 {
+        atlas.cache(key, 1., dist);
+      }
+    }
+    atlas.end_frame(&mut gpu);
+
+    atlas.get(&0, 1.);
+    atlas.get(&3, 1.);
+    // The free area is enough, but not continuous.
+    atlas.allocate(DeviceSize::new(128, 64), &mut gpu);
+    atlas.end_frame(&mut gpu);
+
+    assert_eq!(atlas.memory_stats().compactions, 1);
+    assert!(atlas.get(&0, 1.).is_some());
+    assert!(atlas.get(&3, 1.).is_some());
+    assert!(!atlas.fragmented);
+  }
+}
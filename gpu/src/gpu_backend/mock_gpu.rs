@@ -0,0 +1,145 @@
+use std::{error::Error, ops::Range};
+
+use ribir_geom::{DevicePoint, DeviceRect, DeviceSize};
+use ribir_painter::{BlendMode, Color, MaskMode, PixelImage, VertexBuffers, image::ColorFormat};
+
+use super::Texture;
+use crate::*;
+
+/// A GPU backend that draws nothing, it only records the textures it created
+/// and the copies between them.
+pub(crate) struct MockGPUImpl {
+  limits: DrawPhaseLimits,
+  pub(crate) new_textures: usize,
+  pub(crate) copies: usize,
+}
+
+pub(crate) struct MockTexture {
+  size: DeviceSize,
+  format: ColorFormat,
+}
+
+impl MockGPUImpl {
+  pub(crate) fn new(texture_size: DeviceSize) -> Self {
+    let limits = DrawPhaseLimits {
+      texture_size,
+      max_tex_load: 8,
+      max_image_primitives: 256,
+      max_radial_gradient_primitives: 256,
+      max_linear_gradient_primitives: 256,
+      max_sweep_gradient_primitives: 256,
+      max_gradient_stop_primitives: 1024,
+      max_mask_layers: 256,
+    };
+    Self { limits, new_textures: 0, copies: 0 }
+  }
+}
+
+impl GPUBackendImpl for MockGPUImpl {
+  type Texture = MockTexture;
+
+  fn begin_frame(&mut self) {}
+
+  fn limits(&self) -> &DrawPhaseLimits { &self.limits }
+
+  fn new_texture(&mut self, size: DeviceSize, format: ColorFormat) -> Self::Texture {
+    self.new_textures += 1;
+    MockTexture { size, format }
+  }
+
+  fn load_alpha_vertices(&mut self, _: &VertexBuffers<()>) {}
+
+  fn draw_alpha_triangles(&mut self, _: &Range<u32>, _: &mut Self::Texture) {}
+
+  fn draw_alpha_triangles_with_scissor(
+    &mut self, _: &Range<u32>, _: &mut Self::Texture, _: DeviceRect,
+  ) {
+  }
+
+  fn blur_texture(&mut self, _: &mut Self::Texture, _: &DeviceRect, _: f32) {}
+
+  fn blend_texture(
+    &mut self, _: &mut Self::Texture, _: &DeviceRect, _: &Self::Texture, _: DevicePoint,
+    _: BlendMode,
+  ) {
+  }
+
+  fn mask_texture(
+    &mut self, _: &mut Self::Texture, _: &DeviceRect, _: &Self::Texture, _: DevicePoint,
+    _: MaskMode,
+  ) {
+  }
+
+  fn load_textures(&mut self, _: &[&Self::Texture]) {}
+
+  fn load_mask_layers(&mut self, _: &[MaskLayer]) {}
+
+  fn load_color_vertices(&mut self, _: &VertexBuffers<ColorAttr>) {}
+
+  fn load_img_primitives(&mut self, _: &[ImgPrimitive]) {}
+
+  fn load_img_vertices(&mut self, _: &VertexBuffers<ImagePrimIndex>) {}
+
+  fn load_radial_gradient_primitives(&mut self, _: &[RadialGradientPrimitive]) {}
+
+  fn load_radial_gradient_stops(&mut self, _: &[GradientStopPrimitive]) {}
+
+  fn load_radial_gradient_vertices(&mut self, _: &VertexBuffers<RadialGradientPrimIndex>) {}
+
+  fn load_linear_gradient_primitives(&mut self, _: &[LinearGradientPrimitive]) {}
+
+  fn load_linear_gradient_stops(&mut self, _: &[GradientStopPrimitive]) {}
+
+  fn load_linear_gradient_vertices(&mut self, _: &VertexBuffers<LinearGradientPrimIndex>) {}
+
+  fn load_sweep_gradient_primitives(&mut self, _: &[SweepGradientPrimitive]) {}
+
+  fn load_sweep_gradient_stops(&mut self, _: &[GradientStopPrimitive]) {}
+
+  fn load_sweep_gradient_vertices(&mut self, _: &VertexBuffers<SweepGradientPrimIndex>) {}
+
+  fn draw_color_triangles(&mut self, _: &mut Self::Texture, _: Range<u32>, _: Option<Color>) {}
+
+  fn draw_img_triangles(&mut self, _: &mut Self::Texture, _: Range<u32>, _: Option<Color>) {}
+
+  fn draw_radial_gradient_triangles(
+    &mut self, _: &mut Self::Texture, _: Range<u32>, _: Option<Color>,
+  ) {
+  }
+
+  fn draw_linear_gradient_triangles(
+    &mut self, _: &mut Self::Texture, _: Range<u32>, _: Option<Color>,
+  ) {
+  }
+
+  fn draw_sweep_gradient_triangles(
+    &mut self, _: &mut Self::Texture, _: Range<u32>, _: Option<Color>,
+  ) {
+  }
+
+  fn copy_texture_from_texture(
+    &mut self, _: &mut Self::Texture, _: DevicePoint, _: &Self::Texture, _: &DeviceRect,
+  ) {
+    self.copies += 1;
+  }
+
+  fn end_frame(&mut self) {}
+}
+
+impl Texture for MockTexture {
+  type Host = MockGPUImpl;
+
+  fn clear_areas(&mut self, _: &[DeviceRect], _: &mut Self::Host) {}
+
+  fn write_data(&mut self, _: &DeviceRect, _: &[u8], _: &mut Self::Host) {}
+
+  fn copy_as_image(
+    &self, _: &DeviceRect, _: &mut Self::Host,
+  ) -> impl std::future::Future<Output = Result<PixelImage, Box<dyn Error>>> + 'static {
+    std::future::ready(Err("the mock texture has no content".into()))
+  }
+
+  fn color_format(&self) -> ColorFormat { self.format }
+
+  fn size(&self) -> DeviceSize { self.size }
+}
@@ -13,7 +13,7 @@ use super::{
   Texture,
   atlas::{Atlas, AtlasConfig, AtlasDist},
 };
-use crate::GPUBackendImpl;
+use crate::{GPUBackendImpl, TextureMemoryStats};
 const TOLERANCE: f32 = 0.1_f32;
 const PAR_CHUNKS_SIZE: usize = 64;
 
@@ -22,6 +22,7 @@ pub(super) enum TextureID {
   Alpha(usize),
   Rgba(usize),
   Bundle(usize),
+  Layer(usize),
 }
 
 #[derive(PartialEq, Clone)]
@@ -40,9 +41,19 @@ pub(super) struct TexturesMgr<T: Texture> {
   /// texture to be used both as a target and as a sampled resource in the same
   /// draw call.
   target_atlas: Atlas<Resource<dyn Any>, T>,
+  /// The target textures of the layers, they only live in one frame. Keep
+  /// them separate from the `target_atlas`, because the content of a layer may
+  /// sample the bundles.
+  layer_atlas: Atlas<(), T>,
   tess_task: Vec<TessTask>,
   tess_task_buffer: VertexBuffers<()>,
+  /// The alpha slices need to blur after the tessellation and the standard
+  /// deviation of their gaussian blur.
+  blur_task: Vec<(TextureSlice, f32)>,
   need_clear_areas: Vec<DeviceRect>,
+  /// The max bytes of the allocations that the atlases can retain when they
+  /// are not used in a frame.
+  budget: usize,
 }
 
 struct TessTask {
@@ -66,6 +77,7 @@ macro_rules! id_to_texture_mut {
       TextureID::Alpha(id) => $mgr.alpha_atlas.get_texture_mut(id),
       TextureID::Rgba(id) => $mgr.rgba_atlas.get_texture_mut(id),
       TextureID::Bundle(id) => $mgr.target_atlas.get_texture_mut(id),
+      TextureID::Layer(id) => $mgr.layer_atlas.get_texture_mut(id),
     }
   };
 }
@@ -76,6 +88,7 @@ macro_rules! id_to_texture {
       TextureID::Alpha(id) => $mgr.alpha_atlas.get_texture(id),
       TextureID::Rgba(id) => $mgr.rgba_atlas.get_texture(id),
       TextureID::Bundle(id) => $mgr.target_atlas.get_texture(id),
+      TextureID::Layer(id) => $mgr.layer_atlas.get_texture(id),
     }
   };
 }
@@ -104,9 +117,16 @@ where
         ColorFormat::Rgba8,
         gpu_impl,
       ),
+      layer_atlas: Atlas::new(
+        AtlasConfig::new("Layer atlas", max_size),
+        ColorFormat::Rgba8,
+        gpu_impl,
+      ),
       tess_task: <_>::default(),
       tess_task_buffer: <_>::default(),
+      blur_task: vec![],
       need_clear_areas: vec![],
+      budget: DEFAULT_TEXTURE_BUDGET,
     }
   }
 
@@ -187,6 +207,45 @@ where
     }
   }
 
+  /// Store the blurred alpha mask of the filled path in texture and return the
+  /// texture and a transform that can transform the mask to viewport. The
+  /// blurred mask is not cached, it's only valid in the current frame.
+  pub(super) fn store_blur_path(
+    &mut self, path: &PaintPath, matrix: &Transform, sigma: f32, viewport: &DeviceRect,
+    gpu: &mut T::Host,
+  ) -> (TextureSlice, Transform) {
+    // The blur spreads the path three times of the sigma.
+    let margin = (sigma * 3.).ceil() as i32;
+    let paint_bounds = transform_to_device_rect(&path.bounds(None), matrix).inflate(margin, margin);
+
+    let alloc_size = size_expand_blank(paint_bounds.size);
+    let (visual_rect, clip) = if self.alpha_atlas.is_good_size_to_alloc(alloc_size) {
+      (paint_bounds, false)
+    } else {
+      // The pixels out of the viewport also affect the visible pixels by the
+      // blur, so keep a margin around the viewport.
+      let view = viewport.inflate(margin, margin);
+      (paint_bounds.intersection(&view).unwrap(), true)
+    };
+
+    let (_, slice) = self.alpha_allocate(visual_rect.size, gpu);
+    let offset = (slice.rect.origin - visual_rect.origin)
+      .to_f32()
+      .cast_unit();
+    let transform = matrix.then_translate(offset);
+    let clip_rect = clip.then_some(slice.rect);
+    let style = PaintingStyle::Fill;
+    self
+      .tess_task
+      .push(TessTask { slice, transform, path: path.clone(), style, clip_rect });
+    if sigma > 0. {
+      self.blur_task.push((slice, sigma));
+    }
+
+    let offset = (visual_rect.origin - slice.rect.origin).to_f32();
+    (slice.expand_for_paste(), Transform::translation(offset.x, offset.y))
+  }
+
   pub(super) fn store_image(
     &mut self, img: &Resource<PixelImage>, gpu: &mut T::Host,
   ) -> TextureSlice {
@@ -217,10 +276,30 @@ where
     let dist = self
       .target_atlas
       .get_or_cache(target, scale, size, gpu, init);
-    (dist.scale, TextureSlice {
-      tex_id: TextureID::Bundle(dist.tex_id()),
-      rect: dist.tex_rect(&self.target_atlas),
-    })
+    (
+      dist.scale,
+      TextureSlice {
+        tex_id: TextureID::Bundle(dist.tex_id()),
+        rect: dist.tex_rect(&self.target_atlas),
+      },
+    )
+  }
+
+  /// Allocate a texture slice that only lives in this frame for a layer, and
+  /// call `init` to draw the layer. The `alone` layer is allocated in its own
+  /// texture, so it can be drawn while another layer is the render target.
+  pub(super) fn store_layer(
+    &mut self, size: DeviceSize, alone: bool, gpu: &mut T::Host,
+    init: impl FnOnce(&DeviceRect, &mut T, &mut T::Host),
+  ) -> TextureSlice {
+    let dist = if alone {
+      self.layer_atlas.allocate_alone(size, gpu)
+    } else {
+      self.layer_atlas.allocate(size, gpu)
+    };
+    let rect = DeviceRect::new(dist.tex_rect(&self.layer_atlas).origin, size);
+    init(&rect, self.layer_atlas.get_texture_mut(dist.tex_id()), gpu);
+    TextureSlice { tex_id: TextureID::Layer(dist.tex_id()), rect }
   }
 
   pub(super) fn texture(&self, tex_id: TextureID) -> &T { id_to_texture!(self, tex_id) }
@@ -389,17 +468,48 @@ where
       gpu_impl.draw_alpha_triangles(&indices, texture);
     }
 
+    for (slice, sigma) in self.blur_task.drain(..) {
+      let texture = id_to_texture_mut!(self, slice.tex_id);
+      gpu_impl.blur_texture(texture, &slice.rect, sigma);
+    }
+
     self.tess_task.clear();
     self.tess_task_buffer.vertices.clear();
     self.tess_task_buffer.indices.clear();
   }
 
-  pub(crate) fn end_frame(&mut self) {
-    self.alpha_atlas.end_frame_with(|rect| {
-      self.need_clear_areas.push(rect);
-    });
-    self.rgba_atlas.end_frame();
-    self.target_atlas.end_frame();
+  pub(crate) fn set_budget(&mut self, budget: usize) { self.budget = budget; }
+
+  pub(crate) fn budget(&self) -> usize { self.budget }
+
+  pub(crate) fn memory_stats(&self) -> TextureMemoryStats {
+    self.alpha_atlas.memory_stats()
+      + self.rgba_atlas.memory_stats()
+      + self.target_atlas.memory_stats()
+      + self.layer_atlas.memory_stats()
+  }
+
+  pub(crate) fn end_frame(&mut self, gpu: &mut T::Host) {
+    // The budget is shared by the atlases in order, the path masks first, then
+    // the images and the bundles.
+    let mut budget = self.budget;
+    let need_clear_areas = &mut self.need_clear_areas;
+    let compacted = self
+      .alpha_atlas
+      .end_frame_with(budget, gpu, |rect| need_clear_areas.push(rect));
+    if compacted {
+      // The compacted atlas is a new texture, nothing needs to be cleared.
+      self.need_clear_areas.clear();
+    }
+    budget = budget.saturating_sub(self.alpha_atlas.allocated_bytes());
+    self
+      .rgba_atlas
+      .end_frame_with(budget, gpu, |_| {});
+    budget = budget.saturating_sub(self.rgba_atlas.allocated_bytes());
+    self
+      .target_atlas
+      .end_frame_with(budget, gpu, |_| {});
+    self.layer_atlas.end_frame(gpu);
   }
 }
 
@@ -418,6 +528,10 @@ fn extend_buffer<V>(dist: &mut VertexBuffers<V>, from: VertexBuffers<V>) {
 
 const ALPHA_BLANK_EDGE: i32 = 2;
 
+/// The default bytes that the texture atlases can retain for the allocations
+/// not used in a frame, they are freed at the end of the frame by default.
+const DEFAULT_TEXTURE_BUDGET: usize = 0;
+
 fn size_expand_blank(mut size: DeviceSize) -> DeviceSize {
   size.width += ALPHA_BLANK_EDGE * 2;
   size.height += ALPHA_BLANK_EDGE * 2;
@@ -445,11 +559,16 @@ impl Hash for PathKey {
       PathKey::Fill(path) => path.hash(state),
       PathKey::Stroke { resource: path, options } => {
         path.hash(state);
-        let StrokeOptions { width, miter_limit, line_cap, line_join } = options;
+        let StrokeOptions { width, miter_limit, line_cap, line_join, dash_array, dash_offset } =
+          options;
         width.to_bits().hash(state);
         miter_limit.to_bits().hash(state);
         line_cap.hash(state);
         line_join.hash(state);
+        dash_array
+          .iter()
+          .for_each(|d| d.to_bits().hash(state));
+        dash_offset.to_bits().hash(state);
       }
     }
   }
@@ -568,7 +687,7 @@ pub mod tests {
     }
 
     for _ in 0..10 {
-      mgr.end_frame();
+      mgr.end_frame(&mut wgpu);
       let red_img = color_image(Color::RED, 32, 32).into_any();
       assert!(mgr.rgba_atlas.get(&red_img, 1.).is_none());
     }
@@ -4,7 +4,7 @@ use std::ops::Range;
 
 pub use gpu_backend::Texture;
 use ribir_geom::{DevicePoint, DeviceRect, DeviceSize};
-use ribir_painter::{Color, GradientStop, VertexBuffers, image::ColorFormat};
+use ribir_painter::{BlendMode, Color, GradientStop, MaskMode, VertexBuffers, image::ColorFormat};
 mod gpu_backend;
 use zerocopy::AsBytes;
 
@@ -35,6 +35,10 @@ pub use wgpu_impl::*;
 ///   |    ^                                      v      |
 ///   |    +----<-----------<---------------------+      |
 ///   |                                                  |
+///   | -> + blur_texture()-----------------------+      |
+///   |    ^                                      v      |
+///   |    +----<-----------<---------------------+      |
+///   |                                                  |
 ///   | -> load_textures()                               |
 ///   | -> load_mask_layers()                            |
 ///   |                                                  |    
@@ -59,8 +63,15 @@ pub use wgpu_impl::*;
 ///   |     |  +------------------------------------+    |
 ///   |     |  | load_linear_gradient_primitives()  |    |
 ///   |     +->| load_linear_gradient_stops()       |    |
-///   |        | load_linear_gradient_vertices()    |    |
-///   |        | draw_linear_gradient_triangles()   |    |
+///   |     |  | load_linear_gradient_vertices()    |    |
+///   |     |  | draw_linear_gradient_triangles()   |    |
+///   |     |  +------------------------------------+    |
+///   |     |                                            |
+///   |     |  +------------------------------------+    |
+///   |     |  | load_sweep_gradient_primitives()   |    |
+///   |     +->| load_sweep_gradient_stops()        |    |
+///   |        | load_sweep_gradient_vertices()     |    |
+///   |        | draw_sweep_gradient_triangles()    |    |
 ///   |        +------------------------------------+    |
 ///   +---<----------------------------------------------+
 ///
@@ -100,6 +111,25 @@ pub trait GPUBackendImpl {
   fn draw_alpha_triangles_with_scissor(
     &mut self, indices: &Range<u32>, texture: &mut Self::Texture, scissor: DeviceRect,
   );
+  /// Blur the `rect` area of the texture in place by a gaussian blur with the
+  /// standard deviation `sigma`, the pixels outside the `rect` are treated as
+  /// transparent. It's called after the triangles of the area are drawn.
+  fn blur_texture(&mut self, texture: &mut Self::Texture, rect: &DeviceRect, sigma: f32);
+  /// Blend the `rect` area of the texture in place with the backdrop in the
+  /// `mode`, the backdrop is the area of the same size at `backdrop_pos` of the
+  /// `backdrop` texture. The blended pixels keep the alpha of the texture, and
+  /// will be drawn over the backdrop.
+  fn blend_texture(
+    &mut self, texture: &mut Self::Texture, rect: &DeviceRect, backdrop: &Self::Texture,
+    backdrop_pos: DevicePoint, mode: BlendMode,
+  );
+  /// Mask the `rect` area of the texture in place by the area of the same size
+  /// at `mask_pos` of the `mask` texture in the `mode`. Like the blended
+  /// pixels, the masked pixels will be drawn over the backdrop.
+  fn mask_texture(
+    &mut self, texture: &mut Self::Texture, rect: &DeviceRect, mask: &Self::Texture,
+    mask_pos: DevicePoint, mode: MaskMode,
+  );
 
   /// load textures that will be use in this draw phase
   fn load_textures(&mut self, textures: &[&Self::Texture]);
@@ -131,6 +161,15 @@ pub trait GPUBackendImpl {
   /// Load the vertices and indices buffer that `draw_linear_gradient_triangles`
   /// will use.
   fn load_linear_gradient_vertices(&mut self, buffers: &VertexBuffers<LinearGradientPrimIndex>);
+
+  /// Load the primitives that `draw_sweep_gradient_triangles` will use.
+  fn load_sweep_gradient_primitives(&mut self, primitives: &[SweepGradientPrimitive]);
+  /// Load the gradient color stops that `draw_sweep_gradient_triangles` will
+  /// use.
+  fn load_sweep_gradient_stops(&mut self, stops: &[GradientStopPrimitive]);
+  /// Load the vertices and indices buffer that `draw_sweep_gradient_triangles`
+  /// will use.
+  fn load_sweep_gradient_vertices(&mut self, buffers: &VertexBuffers<SweepGradientPrimIndex>);
   /// Draw pure color triangles in the texture. And use the clear color clear
   /// the texture first if it's a Some-Value
   fn draw_color_triangles(
@@ -153,6 +192,12 @@ pub trait GPUBackendImpl {
     &mut self, texture: &mut Self::Texture, indices: Range<u32>, clear: Option<Color>,
   );
 
+  /// Draw triangles fill with color sweep gradient. And use the clear color
+  /// clear the texture first if it's a Some-Value
+  fn draw_sweep_gradient_triangles(
+    &mut self, texture: &mut Self::Texture, indices: Range<u32>, clear: Option<Color>,
+  );
+
   fn copy_texture_from_texture(
     &mut self, dist_tex: &mut Self::Texture, copy_to: DevicePoint, from_tex: &Self::Texture,
     from_rect: &DeviceRect,
@@ -176,6 +221,9 @@ pub struct DrawPhaseLimits {
   /// The maximum number of linear gradient primitives that the backend can load
   /// in a single draw
   pub max_linear_gradient_primitives: usize,
+  /// The maximum number of sweep gradient primitives that the backend can load
+  /// in a single draw
+  pub max_sweep_gradient_primitives: usize,
   /// The maximum number of gradient stops that the backend can load in a single
   /// draw phase
   pub max_gradient_stop_primitives: usize,
@@ -204,6 +252,10 @@ pub struct RadialGradientPrimIndex(u32);
 #[derive(AsBytes, PartialEq, Clone, Copy, Debug)]
 pub struct LinearGradientPrimIndex(u32);
 
+#[repr(packed)]
+#[derive(AsBytes, PartialEq, Clone, Copy, Debug)]
+pub struct SweepGradientPrimIndex(u32);
+
 #[repr(packed)]
 #[derive(AsBytes, PartialEq, Clone, Copy, Debug)]
 pub struct GradientStopPrimitive {
@@ -263,6 +315,29 @@ pub struct LinearGradientPrimitive {
   pub mask_head_and_spread: i32,
 }
 
+#[repr(packed)]
+#[derive(AsBytes, PartialEq, Clone, Copy, Debug)]
+pub struct SweepGradientPrimitive {
+  /// A 2x3 column-major matrix, transform a vertex position to the texture
+  /// position
+  pub transform: [f32; 6],
+  /// position of the center
+  pub center: [f32; 2],
+  /// the angle in radians where the offset of the stops is 0.
+  pub start_angle: f32,
+  /// the angle in radians where the offset of the stops is 1.
+  pub end_angle: f32,
+  /// The color stop information, there are two parts:
+  /// - The high 16-bit index represents the start index of the color stop.
+  /// - The low 16-bit index represents the size of the color stop.
+  pub stop: u32,
+  /// A mix of two 16-bit values:
+  /// - The high 16-bit index represents the head mask layer.
+  /// - The low 16-bit represents the spread method of the gradient. 0 for pad,
+  ///   1 for reflect and 2 for repeat
+  pub mask_head_and_spread: i32,
+}
+
 #[repr(packed)]
 #[derive(AsBytes, PartialEq, Clone, Copy)]
 pub struct ImgPrimitive {
@@ -273,9 +348,11 @@ pub struct ImgPrimitive {
   pub img_start: [f32; 2],
   /// The size of the image image.
   pub img_size: [f32; 2],
-  /// This represents a mix of two 16-bit indices:
+  /// This represents a mix of three values:
   /// - The high 16-bit index represents the head mask layer. It is an i16.
-  /// - The low 16-bit index represents the texture. It is a u16.
+  /// - The next 8-bit represents the filter quality, 0 for nearest and 1 for
+  ///   linear.
+  /// - The low 8-bit index represents the texture. It is a u8.
   pub mask_head_and_tex_idx: i32,
   /// extra alpha apply to current vertex
   pub opacity: f32,
@@ -6,7 +6,7 @@ use std::{
 
 use futures::channel::oneshot;
 use ribir_geom::{DevicePoint, DeviceRect, DeviceSize};
-use ribir_painter::{Color, PixelImage, VertexBuffers, image::ColorFormat};
+use ribir_painter::{BlendMode, Color, MaskMode, PixelImage, VertexBuffers, image::ColorFormat};
 
 use self::{
   draw_alpha_triangles_pass::DrawAlphaTrianglesPass,
@@ -14,13 +14,14 @@ use self::{
   draw_img_triangles_pass::DrawImgTrianglesPass,
   draw_linear_gradient_pass::DrawLinearGradientTrianglesPass,
   draw_radial_gradient_pass::DrawRadialGradientTrianglesPass,
-  texture_pass::{ClearTexturePass, CopyTexturePass},
+  draw_sweep_gradient_pass::DrawSweepGradientTrianglesPass,
+  texture_pass::{BlendTexturePass, BlurTexturePass, ClearTexturePass, CopyTexturePass},
   uniform::Uniform,
 };
 use crate::{
   ColorAttr, DrawPhaseLimits, GPUBackendImpl, GradientStopPrimitive, ImagePrimIndex, ImgPrimitive,
   LinearGradientPrimIndex, LinearGradientPrimitive, MaskLayer, RadialGradientPrimIndex,
-  RadialGradientPrimitive, gpu_backend::Texture,
+  RadialGradientPrimitive, SweepGradientPrimIndex, SweepGradientPrimitive, gpu_backend::Texture,
 };
 mod shaders;
 mod uniform;
@@ -31,6 +32,7 @@ mod draw_color_triangles_pass;
 mod draw_img_triangles_pass;
 mod draw_linear_gradient_pass;
 mod draw_radial_gradient_pass;
+mod draw_sweep_gradient_pass;
 mod texture_pass;
 
 pub const TEX_PER_DRAW: usize = 8;
@@ -46,10 +48,13 @@ pub struct WgpuImpl {
   clear_tex_pass: ClearTexturePass,
   alpha_triangles_pass: DrawAlphaTrianglesPass,
   copy_tex_pass: Option<CopyTexturePass>,
+  blur_tex_pass: Option<BlurTexturePass>,
+  blend_tex_pass: Option<BlendTexturePass>,
   color_triangles_pass: Option<DrawColorTrianglesPass>,
   img_triangles_pass: Option<DrawImgTrianglesPass>,
   radial_gradient_pass: Option<DrawRadialGradientTrianglesPass>,
   linear_gradient_pass: Option<DrawLinearGradientTrianglesPass>,
+  sweep_gradient_pass: Option<DrawSweepGradientTrianglesPass>,
   texs_layout: wgpu::BindGroupLayout,
   textures_bind: Option<wgpu::BindGroup>,
   mask_layers_uniform: Uniform<MaskLayer>,
@@ -125,6 +130,21 @@ macro_rules! linear_gradient_pass {
   };
 }
 
+macro_rules! sweep_gradient_pass {
+  ($backend:ident) => {
+    $backend
+      .sweep_gradient_pass
+      .get_or_insert_with(|| {
+        DrawSweepGradientTrianglesPass::new(
+          &$backend.device,
+          $backend.mask_layers_uniform.layout(),
+          &$backend.texs_layout,
+          &$backend.limits,
+        )
+      })
+  };
+}
+
 pub(crate) use command_encoder;
 
 pub struct Surface<'a> {
@@ -175,6 +195,9 @@ impl GPUBackendImpl for WgpuImpl {
   }
 
   fn load_alpha_vertices(&mut self, buffers: &VertexBuffers<()>) {
+    // The alpha passes recorded before still use the old vertices, submit them
+    // before the buffer is overwritten.
+    self.submit();
     self
       .alpha_triangles_pass
       .load_alpha_vertices(buffers, &self.device, &self.queue);
@@ -216,6 +239,18 @@ impl GPUBackendImpl for WgpuImpl {
     linear_gradient_pass!(self).load_triangles_vertices(buffers, &self.device, &self.queue);
   }
 
+  fn load_sweep_gradient_primitives(&mut self, primitives: &[SweepGradientPrimitive]) {
+    sweep_gradient_pass!(self).load_sweep_gradient_primitives(&self.queue, primitives);
+  }
+
+  fn load_sweep_gradient_stops(&mut self, stops: &[GradientStopPrimitive]) {
+    sweep_gradient_pass!(self).load_gradient_stops(&self.queue, stops);
+  }
+
+  fn load_sweep_gradient_vertices(&mut self, buffers: &VertexBuffers<SweepGradientPrimIndex>) {
+    sweep_gradient_pass!(self).load_triangles_vertices(buffers, &self.device, &self.queue);
+  }
+
   fn load_mask_layers(&mut self, layers: &[crate::MaskLayer]) {
     self
       .mask_layers_uniform
@@ -264,6 +299,23 @@ impl GPUBackendImpl for WgpuImpl {
 
     self.submit()
   }
+  fn draw_sweep_gradient_triangles(
+    &mut self, texture: &mut Self::Texture, indices: Range<u32>, clear: Option<Color>,
+  ) {
+    let encoder = command_encoder!(self);
+
+    sweep_gradient_pass!(self).draw_triangles(
+      texture,
+      indices,
+      clear,
+      &self.device,
+      encoder,
+      self.textures_bind.as_ref().unwrap(),
+      &self.mask_layers_uniform,
+    );
+
+    self.submit()
+  }
 
   fn draw_alpha_triangles_with_scissor(
     &mut self, indices: &Range<u32>, texture: &mut Self::Texture, scissor: DeviceRect,
@@ -278,6 +330,25 @@ impl GPUBackendImpl for WgpuImpl {
     );
   }
 
+  fn blur_texture(&mut self, texture: &mut Self::Texture, rect: &DeviceRect, sigma: f32) {
+    self.draw_blur_texture(texture, rect, sigma);
+  }
+
+  fn blend_texture(
+    &mut self, texture: &mut Self::Texture, rect: &DeviceRect, backdrop: &Self::Texture,
+    backdrop_pos: DevicePoint, mode: BlendMode,
+  ) {
+    self.draw_blend_texture(texture, rect, backdrop, backdrop_pos, mode as u32);
+  }
+
+  fn mask_texture(
+    &mut self, texture: &mut Self::Texture, rect: &DeviceRect, mask: &Self::Texture,
+    mask_pos: DevicePoint, mode: MaskMode,
+  ) {
+    let mode = texture_pass::MASK_MODE_START + mode as u32;
+    self.draw_blend_texture(texture, rect, mask, mask_pos, mode);
+  }
+
   fn draw_color_triangles(
     &mut self, texture: &mut Self::Texture, indices: Range<u32>, clear: Option<Color>,
   ) {
@@ -617,6 +688,7 @@ impl WgpuImpl {
       max_image_primitives: uniform_bytes / size_of::<ImgPrimitive>(),
       max_radial_gradient_primitives: uniform_bytes / size_of::<RadialGradientPrimitive>(),
       max_linear_gradient_primitives: uniform_bytes / size_of::<LinearGradientPrimitive>(),
+      max_sweep_gradient_primitives: uniform_bytes / size_of::<SweepGradientPrimitive>(),
       max_gradient_stop_primitives: uniform_bytes / size_of::<GradientStopPrimitive>(),
       max_mask_layers: uniform_bytes / size_of::<MaskLayer>(),
     };
@@ -634,10 +706,13 @@ impl WgpuImpl {
       alpha_triangles_pass,
       clear_tex_pass,
       copy_tex_pass: None,
+      blur_tex_pass: None,
+      blend_tex_pass: None,
       color_triangles_pass: None,
       img_triangles_pass: None,
       radial_gradient_pass: None,
       linear_gradient_pass: None,
+      sweep_gradient_pass: None,
       texs_layout,
       textures_bind: None,
       mask_layers_uniform,
@@ -646,15 +721,17 @@ impl WgpuImpl {
 
     let surface = surface.map(|surface| {
       use wgpu::TextureFormat::*;
-      let format = surface
-        .get_capabilities(&adapter)
+      let capabilities = surface.get_capabilities(&adapter);
+      let format = capabilities
         .formats
         .into_iter()
         .find(|&f| f == Rgba8Unorm || f == Bgra8Unorm)
         .expect("No suitable format found for the surface!");
+      // The backdrop blur layers copy the content of the surface.
+      let copy_usages = wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::TEXTURE_BINDING;
 
       let config = wgpu::SurfaceConfiguration {
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | (capabilities.usages & copy_usages),
         format,
         width: 0,
         height: 0,
@@ -325,6 +325,153 @@ fn fs_main(input: FragInput) -> @location(0) vec4<f32> {
 "#
 }
 
+pub fn sweep_gradient_shader(limits: &DrawPhaseLimits) -> String {
+  basic_template(limits.max_mask_layers)
+    + &format!(
+      r#"
+@group(2) @binding(0)
+var<uniform> prims: array<Primitive, {}>;
+
+@group(3) @binding(0)
+var<uniform> stops: array<StopPair, {}>;"#,
+      limits.max_sweep_gradient_primitives,
+      limits.max_gradient_stop_primitives / 2,
+    )
+    + r#"
+struct Vertex {
+  @location(0) pos: vec2<f32>,
+  @location(1) @interpolate(flat) prim_idx: u32,
+};
+
+struct FragInput {
+  @builtin(position) pos: vec4<f32>,
+  @location(0) @interpolate(flat) prim_idx: u32,
+}
+
+@vertex
+fn vs_main(v: Vertex) -> FragInput {
+    var input: FragInput;
+    // convert from gpu-backend coords(0..1) to wgpu corrds(-1..1)
+    let pos = v.pos * vec2(2., -2.) + vec2(-1., 1.);
+    input.pos = vec4<f32>(pos, 0.0, 1.0);
+    input.prim_idx = v.prim_idx;
+    return input;
+}
+
+// A pair of stops. This arrangement aligns the stops with 16 bytes, minimizing excessive padding.
+struct StopPair {
+    color1: u32,
+    offset1: f32,
+    color2: u32,
+    offset2: f32,
+}
+
+struct Stop {
+    color: vec4<f32>,
+    offset: f32,
+}
+
+// Since a the different alignment between WebGPU and WebGL, we not use 
+// mat3x2<f32> in the struct, but use vec2<f32> instead. Then, we compose it.
+struct Primitive {
+  t0: vec2<f32>,
+  t1: vec2<f32>,
+  t2: vec2<f32>,
+  center: vec2<f32>,
+  start_angle: f32,
+  end_angle: f32,
+  // A value mixed stop_start(u16) and stop_cnt(u16)
+  stop: u32,
+  // A value mixed mask_head(i16) and spread(u16)
+  mask_head_and_spread: i32
+}
+
+const TAU: f32 = 6.283185307179586;
+
+fn rem_euclid(a: f32, b: f32) -> f32 {
+    return a - b * floor(a / b);
+}
+
+// The offset of the stops at the position, the angle swept from the start
+// angle in the direction of the gradient divided by the whole sweep.
+fn calc_offset(x: f32, y: f32, center: vec2<f32>, start_angle: f32, sweep: f32) -> f32 {
+    let angle = atan2(y - center.y, x - center.x);
+    var swept: f32;
+    if sweep > 0. {
+        swept = rem_euclid(angle - start_angle, TAU);
+    } else {
+        swept = -rem_euclid(start_angle - angle, TAU);
+    }
+    return swept / sweep;
+}
+
+fn unpackUnorm4x8(packed: u32) -> vec4<f32> {
+    return vec4<f32>(
+        f32((packed & 0xff000000) >> 24) / 255.0,
+        f32((packed & 0x00ff0000) >> 16) / 255.0,
+        f32((packed & 0x0000ff00) >> 8) / 255.0,
+        f32((packed & 0x000000ff) >> 0) / 255.0
+    );
+}
+
+fn get_stop(idx: u32) -> Stop {
+    let pair = stops[idx / 2];
+    if idx % 2 == 0 {
+        return Stop(unpackUnorm4x8(pair.color1), pair.offset1);
+    } else {
+        return Stop(unpackUnorm4x8(pair.color2), pair.offset2);
+    }
+}
+
+@fragment
+fn fs_main(input: FragInput) -> @location(0) vec4<f32> {
+    let prim = prims[input.prim_idx];
+    let pos = mat3x2(prim.t0, prim.t1, prim.t2) * vec3(input.pos.xy, 1.);
+
+    var alpha = 1.;
+    var mask_idx = prim.mask_head_and_spread >> 16;
+    loop {
+        if mask_idx < 0 { break; }
+
+        let mask = mask_layers[u32(mask_idx)];
+        alpha *= mask_sample(mask, input.pos.xy);
+        mask_idx = mask.prev_mask_idx;
+    }
+
+    let sweep = prim.end_angle - prim.start_angle;
+    if sweep == 0. {
+        return vec4<f32>(0.);
+    }
+    var offset = calc_offset(pos.x, pos.y, prim.center, prim.start_angle, sweep);
+    let spread = abs(prim.mask_head_and_spread & 0x0000ffff);
+    if spread == 0 {
+        // pad
+        offset = min(1., max(0., offset));
+    } else if spread == 1 {
+        //reflect
+        offset = 1. - abs(fract(offset / 2.) - 0.5) * 2.;
+    } else {
+        //repeat
+        offset = fract(offset);
+    }
+
+    let stop_start = prim.stop >> 16;
+    let stop_cnt = prim.stop & 0x0000ffff;
+    var prev = get_stop(stop_start);
+    var next = get_stop(stop_start + 1);
+    for (var i = 2u; i < stop_cnt && next.offset < offset; i++) {
+        prev = next;
+        next = get_stop(stop_start + i);
+    }
+
+    offset = max(prev.offset, min(next.offset, offset));
+    let weight1 = (next.offset - offset) / (next.offset - prev.offset);
+    let weight2 = 1. - weight1;
+    return (prev.color * weight1 + next.color * weight2) * vec4<f32>(1., 1., 1., alpha);
+}
+"#
+}
+
 pub fn color_triangles_shader(max_mask_layers: usize) -> String {
   basic_template(max_mask_layers)
     + r#"
@@ -398,7 +545,8 @@ pub fn img_triangles_shader(limits: &DrawPhaseLimits) -> String {
     img_size: vec2<f32>,
     /// This is a mix field,
     /// - the high 16 bits is the index of head mask layer, as a i16 type.
-    /// - the low 16 bits is the index of texture, as a u16 type.
+    /// - the next 8 bits is the filter quality, 0 for nearest and 1 for linear.
+    /// - the low 8 bits is the index of texture, as a u8 type.
     mask_head_and_tex_idx: i32,
     /// extra alpha apply to current vertex
     opacity: f32,
@@ -443,7 +591,7 @@ pub fn img_triangles_shader(limits: &DrawPhaseLimits) -> String {
   }
   
   fn img_sample(prim: ImgPrimitive, pos: vec2<f32>) -> vec4<f32> {
-      switch abs(prim.mask_head_and_tex_idx & 0x0000FFFF) {
+      switch prim.mask_head_and_tex_idx & 0x000000FF {
         case 0: { return img_tex_smaple(tex_0, prim, pos); }
         case 1: { return img_tex_smaple(tex_1, prim, pos); }
         case 2: { return img_tex_smaple(tex_2, prim, pos); }
@@ -459,7 +607,15 @@ pub fn img_triangles_shader(limits: &DrawPhaseLimits) -> String {
   
   fn img_tex_smaple(tex: texture_2d<f32>, prim: ImgPrimitive, pos: vec2<f32>) -> vec4<f32> {
       let img_tex_size = textureDimensions(tex);
-      let sample_pos = pos / vec2<f32>(f32(img_tex_size.x), f32(img_tex_size.y));
+      // The linear filter keeps the samples in the image, so the nearby images
+      // in the atlas are not blended.
+      var texel_pos = clamp(pos, prim.img_start + 0.5, prim.img_start + prim.img_size - 0.5);
+      // The nearest filter samples the center of the texel, so the linear
+      // sampler doesn't blend it with the nearby texels.
+      if ((prim.mask_head_and_tex_idx >> 8) & 0x000000FF) == 0 {
+        texel_pos = floor(pos) + vec2<f32>(0.5, 0.5);
+      }
+      let sample_pos = texel_pos / vec2<f32>(f32(img_tex_size.x), f32(img_tex_size.y));
       return textureSampleLevel(tex, s_sampler, sample_pos, 0.);
   }
   "#
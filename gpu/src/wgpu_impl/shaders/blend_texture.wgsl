@@ -0,0 +1,87 @@
+struct VertexInput {
+  @location(0) pos: vec2<f32>,
+  @location(1) tex_pos: vec2<f32>,
+  @location(2) mode: u32,
+}
+
+struct VertexOutput {
+  @builtin(position) pos: vec4<f32>,
+  // The pixel position in the source and the backdrop textures.
+  @location(0) tex_pos: vec2<f32>,
+  @location(1) @interpolate(flat) mode: u32,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    let pos = input.pos * vec2(2., -2.) + vec2(-1., 1.);
+    output.pos = vec4<f32>(pos, 0.0, 1.0);
+    output.tex_pos = input.tex_pos;
+    output.mode = input.mode;
+    return output;
+}
+
+@group(0) @binding(0)
+var src_tex: texture_2d<f32>;
+@group(0) @binding(1)
+var backdrop_tex: texture_2d<f32>;
+
+// The modes are the same order as the `BlendMode` of the painter.
+fn blend(s: vec3<f32>, d: vec3<f32>, mode: u32) -> vec3<f32> {
+    switch mode {
+        // Multiply
+        case 1u: { return s * d; }
+        // Screen
+        case 2u: { return s + d - s * d; }
+        // Overlay
+        case 3u: { return select(1. - 2. * (1. - s) * (1. - d), 2. * s * d, d <= vec3(0.5)); }
+        // Darken
+        case 4u: { return min(s, d); }
+        // Lighten
+        case 5u: { return max(s, d); }
+        // Difference
+        case 6u: { return abs(s - d); }
+        default: { return s; }
+    }
+}
+
+fn unpremultiply(c: vec4<f32>) -> vec3<f32> {
+    if c.a > 0. {
+        return c.rgb / c.a;
+    }
+    return vec3(0.);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let pos = vec2<i32>(floor(input.tex_pos));
+    // Both are drawn over transparent pixels with the alpha blending, so their
+    // colors are premultiplied by alpha.
+    let src = textureLoad(src_tex, pos, 0);
+    let backdrop = textureLoad(backdrop_tex, pos, 0);
+    let s = unpremultiply(src);
+
+    // The mask modes follow the blend modes, the backdrop is the mask that only
+    // changes the alpha of the source.
+    if input.mode >= 8u {
+        var coverage = backdrop.a;
+        if input.mode == 9u {
+            // The luminance of the premultiplied mask is multiplied by its alpha.
+            coverage = dot(backdrop.rgb, vec3(0.2125, 0.7154, 0.0721));
+        }
+        return vec4<f32>(s, src.a * coverage);
+    }
+
+    let d = unpremultiply(backdrop);
+
+    // The result is drawn over the backdrop with the alpha of the source, so the
+    // blended color is mixed with the source color by the backdrop alpha.
+    var color: vec3<f32>;
+    if input.mode == 7u {
+        // Plus, the sum of the premultiplied colors.
+        color = min(s + d * backdrop.a, vec3(1.));
+    } else {
+        color = mix(s, blend(s, d, input.mode), backdrop.a);
+    }
+    return vec4<f32>(color, src.a);
+}
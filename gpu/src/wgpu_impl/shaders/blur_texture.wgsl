@@ -0,0 +1,61 @@
+struct VertexInput {
+  @location(0) pos: vec2<f32>,
+  @location(1) tex_pos: vec2<f32>,
+  @location(2) step: vec2<f32>,
+  @location(3) src_size: vec2<f32>,
+  @location(4) sigma: f32,
+}
+
+struct VertexOutput {
+  @builtin(position) pos: vec4<f32>,
+  // The pixel position in the source texture.
+  @location(0) tex_pos: vec2<f32>,
+  // The direction of the blur, one pixel horizontal or vertical.
+  @location(1) @interpolate(flat) step: vec2<f32>,
+  // Only the pixels in `[0, src_size)` of the source texture are valid.
+  @location(2) @interpolate(flat) src_size: vec2<f32>,
+  @location(3) @interpolate(flat) sigma: f32,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    let pos = input.pos * vec2(2., -2.) + vec2(-1., 1.);
+    output.pos = vec4<f32>(pos, 0.0, 1.0);
+    output.tex_pos = input.tex_pos;
+    output.step = input.step;
+    output.src_size = input.src_size;
+    output.sigma = input.sigma;
+    return output;
+}
+
+@group(0) @binding(0)
+var texture: texture_2d<f32>;
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let center = vec2<i32>(floor(input.tex_pos));
+    let step = vec2<i32>(input.step);
+    let size = vec2<i32>(input.src_size);
+    let radius = i32(ceil(input.sigma * 3.));
+    let factor = -1. / (2. * input.sigma * input.sigma);
+
+    // The colors are blurred with premultiplied alpha, but the texture stores
+    // them with straight alpha.
+    var color = vec4<f32>(0.);
+    var total = 0.;
+    for (var i = -radius; i <= radius; i++) {
+        let weight = exp(f32(i * i) * factor);
+        total += weight;
+        let pos = center + step * i;
+        if all(pos >= vec2(0)) && all(pos < size) {
+            let c = textureLoad(texture, pos, 0);
+            color += vec4<f32>(c.rgb * c.a, c.a) * weight;
+        }
+    }
+    color /= total;
+    if color.a > 0. {
+        color = vec4<f32>(color.rgb / color.a, color.a);
+    }
+    return color;
+}
@@ -1,12 +1,14 @@
 use std::mem::size_of;
 
 use ribir_geom::{DevicePoint, DeviceRect, DeviceSize, rect_corners};
-use ribir_painter::Vertex;
+use ribir_painter::{BlendMode, Vertex};
 use wgpu::{StoreOp, include_wgsl};
 use zerocopy::AsBytes;
 
 use super::vertex_buffer::new_vertices;
-use crate::{WgpuImpl, WgpuTexture, command_encoder, gpu_backend::Texture, vertices_coord};
+use crate::{
+  GPUBackendImpl, WgpuImpl, WgpuTexture, command_encoder, gpu_backend::Texture, vertices_coord,
+};
 
 pub struct CopyTexturePass {
   pipeline: Option<wgpu::RenderPipeline>,
@@ -132,7 +134,382 @@ impl ClearTexturePass {
   }
 }
 
+/// A two passes gaussian blur, blur horizontally to a scratch texture and then
+/// blur vertically back to the target texture.
+pub struct BlurTexturePass {
+  pipeline: Option<wgpu::RenderPipeline>,
+  shader: wgpu::ShaderModule,
+  layout: wgpu::PipelineLayout,
+  bind_layout: wgpu::BindGroupLayout,
+  format: Option<wgpu::TextureFormat>,
+  vertices_buffer: wgpu::Buffer,
+  /// The textures to store the source and the horizontal blurred pixels.
+  scratches: Option<[WgpuTexture; 2]>,
+}
+
+// The fields are only read by the shader.
+#[allow(dead_code)]
+#[repr(packed)]
+#[derive(AsBytes, Clone, Copy)]
+struct BlurAttr {
+  tex_pos: [f32; 2],
+  step: [f32; 2],
+  src_size: [f32; 2],
+  sigma: f32,
+}
+
+impl BlurTexturePass {
+  pub fn new(device: &wgpu::Device) -> Self {
+    let shader = device.create_shader_module(include_wgsl!("./shaders/blur_texture.wgsl"));
+
+    let bind_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &[wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+          sample_type: wgpu::TextureSampleType::Float { filterable: false },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled: false,
+        },
+        count: None,
+      }],
+      label: Some("Blur texture"),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Blur texture"),
+      bind_group_layouts: &[&bind_layout],
+      push_constant_ranges: &[],
+    });
+    let vertices_buffer = new_vertices::<BlurAttr>(device, 8);
+    Self {
+      pipeline: None,
+      shader,
+      format: None,
+      bind_layout,
+      layout,
+      vertices_buffer,
+      scratches: None,
+    }
+  }
+
+  pub fn update(&mut self, format: wgpu::TextureFormat, device: &wgpu::Device) {
+    if Some(format) != self.format {
+      self.format = Some(format);
+      self.pipeline.take();
+      self.scratches.take();
+    }
+
+    if self.pipeline.is_none() {
+      let attr = |offset: usize, shader_location, format| wgpu::VertexAttribute {
+        offset: offset as wgpu::BufferAddress,
+        shader_location,
+        format,
+      };
+      let pipeline = tex_render_pipeline::<BlurAttr>(
+        "Blur texture",
+        device,
+        &self.layout,
+        &self.shader,
+        &[
+          attr(0, 0, wgpu::VertexFormat::Float32x2),
+          attr(size_of::<[f32; 2]>(), 1, wgpu::VertexFormat::Float32x2),
+          attr(size_of::<[f32; 4]>(), 2, wgpu::VertexFormat::Float32x2),
+          attr(size_of::<[f32; 6]>(), 3, wgpu::VertexFormat::Float32x2),
+          attr(size_of::<[f32; 8]>(), 4, wgpu::VertexFormat::Float32),
+        ],
+        format,
+        wgpu::PrimitiveTopology::TriangleStrip,
+      );
+      self.pipeline = Some(pipeline);
+    }
+  }
+
+  fn bind_group(&self, texture: &WgpuTexture, device: &wgpu::Device) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+      layout: &self.bind_layout,
+      entries: &[wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::TextureView(texture.view()),
+      }],
+      label: Some("Blur texture bind group"),
+    })
+  }
+}
+
+/// The shader mode of the first mask mode, the mask modes follow the blend
+/// modes.
+pub(crate) const MASK_MODE_START: u32 = BlendMode::Plus as u32 + 1;
+
+/// Blend the pixels of a texture with the pixels of the backdrop, the both are
+/// copied to the scratch textures first, and then the blended pixels are drawn
+/// back to the texture. A mask is blended as a backdrop in the mask modes.
+pub struct BlendTexturePass {
+  pipeline: Option<wgpu::RenderPipeline>,
+  shader: wgpu::ShaderModule,
+  layout: wgpu::PipelineLayout,
+  bind_layout: wgpu::BindGroupLayout,
+  format: Option<wgpu::TextureFormat>,
+  vertices_buffer: wgpu::Buffer,
+  /// The textures to store the source and the backdrop pixels.
+  scratches: Option<[WgpuTexture; 2]>,
+}
+
+// The fields are only read by the shader.
+#[allow(dead_code)]
+#[repr(packed)]
+#[derive(AsBytes, Clone, Copy)]
+struct BlendAttr {
+  tex_pos: [f32; 2],
+  mode: u32,
+}
+
+impl BlendTexturePass {
+  pub fn new(device: &wgpu::Device) -> Self {
+    let shader = device.create_shader_module(include_wgsl!("./shaders/blend_texture.wgsl"));
+
+    let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility: wgpu::ShaderStages::FRAGMENT,
+      ty: wgpu::BindingType::Texture {
+        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        view_dimension: wgpu::TextureViewDimension::D2,
+        multisampled: false,
+      },
+      count: None,
+    };
+    let bind_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      entries: &[texture_entry(0), texture_entry(1)],
+      label: Some("Blend texture"),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("Blend texture"),
+      bind_group_layouts: &[&bind_layout],
+      push_constant_ranges: &[],
+    });
+    let vertices_buffer = new_vertices::<BlendAttr>(device, 4);
+    Self {
+      pipeline: None,
+      shader,
+      format: None,
+      bind_layout,
+      layout,
+      vertices_buffer,
+      scratches: None,
+    }
+  }
+
+  pub fn update(&mut self, format: wgpu::TextureFormat, device: &wgpu::Device) {
+    if Some(format) != self.format {
+      self.format = Some(format);
+      self.pipeline.take();
+      self.scratches.take();
+    }
+
+    if self.pipeline.is_none() {
+      let attr = |offset: usize, shader_location, format| wgpu::VertexAttribute {
+        offset: offset as wgpu::BufferAddress,
+        shader_location,
+        format,
+      };
+      let pipeline = tex_render_pipeline::<BlendAttr>(
+        "Blend texture",
+        device,
+        &self.layout,
+        &self.shader,
+        &[
+          attr(0, 0, wgpu::VertexFormat::Float32x2),
+          attr(size_of::<[f32; 2]>(), 1, wgpu::VertexFormat::Float32x2),
+          attr(size_of::<[f32; 4]>(), 2, wgpu::VertexFormat::Uint32),
+        ],
+        format,
+        wgpu::PrimitiveTopology::TriangleStrip,
+      );
+      self.pipeline = Some(pipeline);
+    }
+  }
+}
+
 impl WgpuImpl {
+  /// Blend the `rect` area of the texture with the backdrop in the shader
+  /// `mode`, a `BlendMode` or a mask mode after `MASK_MODE_START`.
+  pub(crate) fn draw_blend_texture(
+    &mut self, texture: &WgpuTexture, rect: &DeviceRect, backdrop: &WgpuTexture,
+    backdrop_pos: DevicePoint, mode: u32,
+  ) {
+    let mut pass = self
+      .blend_tex_pass
+      .take()
+      .unwrap_or_else(|| BlendTexturePass::new(&self.device));
+    pass.update(texture.format(), &self.device);
+
+    let enough = |t: &WgpuTexture| !rect.size.greater_than(Texture::size(t)).any();
+    let mut scratches = match pass.scratches.take() {
+      Some(scratches) if enough(&scratches[0]) => scratches,
+      scratches => {
+        let size = scratches.map_or(rect.size, |s| Texture::size(&s[0]).max(rect.size));
+        let format = Texture::color_format(texture);
+        [self.new_texture(size, format), self.new_texture(size, format)]
+      }
+    };
+    let [src, backdrop_copy] = &mut scratches;
+    self.copy_same_format_texture(
+      src.inner_tex.texture(),
+      DevicePoint::zero(),
+      texture.inner_tex.texture(),
+      rect,
+    );
+    self.copy_texture_from_texture(
+      backdrop_copy,
+      DevicePoint::zero(),
+      backdrop,
+      &DeviceRect::new(backdrop_pos, rect.size),
+    );
+
+    let [w, h] = rect.size.to_f32().to_array();
+    let [d_lt, d_rt, d_rb, d_lb] = vertices_corners(rect, Texture::size(texture));
+    let vertices = [
+      Vertex::new(d_lt, BlendAttr { tex_pos: [0., 0.], mode }),
+      Vertex::new(d_lb, BlendAttr { tex_pos: [0., h], mode }),
+      Vertex::new(d_rt, BlendAttr { tex_pos: [w, 0.], mode }),
+      Vertex::new(d_rb, BlendAttr { tex_pos: [w, h], mode }),
+    ];
+    self
+      .queue
+      .write_buffer(&pass.vertices_buffer, 0, vertices.as_bytes());
+
+    let bind = self
+      .device
+      .create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &pass.bind_layout,
+        entries: &[
+          wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(src.view()),
+          },
+          wgpu::BindGroupEntry {
+            binding: 1,
+            resource: wgpu::BindingResource::TextureView(backdrop_copy.view()),
+          },
+        ],
+        label: Some("Blend texture bind group"),
+      });
+
+    let color_attachments = wgpu::RenderPassColorAttachment {
+      view: texture.view(),
+      resolve_target: None,
+      ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: StoreOp::Store },
+    };
+    let encoder = command_encoder!(self);
+    let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Blend texture"),
+      color_attachments: &[Some(color_attachments)],
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+    rpass.set_vertex_buffer(0, pass.vertices_buffer.slice(..));
+    rpass.set_bind_group(0, &bind, &[]);
+    rpass.set_scissor_rect(
+      rect.min_x() as u32,
+      rect.min_y() as u32,
+      rect.width() as u32,
+      rect.height() as u32,
+    );
+    rpass.set_pipeline(pass.pipeline.as_ref().unwrap());
+    rpass.draw(0..4, 0..1);
+    drop(rpass);
+
+    pass.scratches = Some(scratches);
+    self.blend_tex_pass = Some(pass);
+    // The vertices buffers are rewritten by the next blend or copy, so submit the
+    // commands that use them.
+    self.submit();
+  }
+
+  pub(crate) fn draw_blur_texture(&mut self, texture: &WgpuTexture, rect: &DeviceRect, sigma: f32) {
+    let mut pass = self
+      .blur_tex_pass
+      .take()
+      .unwrap_or_else(|| BlurTexturePass::new(&self.device));
+    pass.update(texture.format(), &self.device);
+
+    let enough = |t: &WgpuTexture| !rect.size.greater_than(Texture::size(t)).any();
+    let scratches = match pass.scratches.take() {
+      Some(scratches) if enough(&scratches[0]) => scratches,
+      scratches => {
+        let size = scratches.map_or(rect.size, |s| Texture::size(&s[0]).max(rect.size));
+        let format = Texture::color_format(texture);
+        [self.new_texture(size, format), self.new_texture(size, format)]
+      }
+    };
+    let [src, horizontal] = &scratches;
+    self.copy_same_format_texture(
+      src.inner_tex.texture(),
+      DevicePoint::zero(),
+      texture.inner_tex.texture(),
+      rect,
+    );
+
+    let src_rect = DeviceRect::from_size(rect.size);
+    let src_size @ [w, h] = rect.size.to_f32().to_array();
+    let tex_pos = [[0., 0.], [w, 0.], [w, h], [0., h]];
+    let mut vertices = Vec::with_capacity(8);
+    for (dist_rect, dist_size, step) in
+      [(&src_rect, Texture::size(horizontal), [1., 0.]), (rect, Texture::size(texture), [0., 1.])]
+    {
+      let [d_lt, d_rt, d_rb, d_lb] = vertices_corners(dist_rect, dist_size);
+      let [t_lt, t_rt, t_rb, t_lb] = tex_pos;
+      let attr = |tex_pos| BlurAttr { tex_pos, step, src_size, sigma };
+      vertices.extend([
+        Vertex::new(d_lt, attr(t_lt)),
+        Vertex::new(d_lb, attr(t_lb)),
+        Vertex::new(d_rt, attr(t_rt)),
+        Vertex::new(d_rb, attr(t_rb)),
+      ]);
+    }
+    self
+      .queue
+      .write_buffer(&pass.vertices_buffer, 0, vertices.as_bytes());
+
+    let src_bind = pass.bind_group(src, &self.device);
+    let horizontal_bind = pass.bind_group(horizontal, &self.device);
+    let encoder = command_encoder!(self);
+    for (dist, dist_rect, bind, vertices) in
+      [(horizontal, &src_rect, &src_bind, 0..4), (texture, rect, &horizontal_bind, 4..8)]
+    {
+      let color_attachments = wgpu::RenderPassColorAttachment {
+        view: dist.view(),
+        resolve_target: None,
+        ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: StoreOp::Store },
+      };
+      let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Blur texture"),
+        color_attachments: &[Some(color_attachments)],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+      });
+      rpass.set_vertex_buffer(0, pass.vertices_buffer.slice(..));
+      rpass.set_bind_group(0, bind, &[]);
+      rpass.set_scissor_rect(
+        dist_rect.min_x() as u32,
+        dist_rect.min_y() as u32,
+        dist_rect.width() as u32,
+        dist_rect.height() as u32,
+      );
+      rpass.set_pipeline(pass.pipeline.as_ref().unwrap());
+      rpass.draw(vertices, 0..1);
+    }
+
+    pass.scratches = Some(scratches);
+    self.blur_tex_pass = Some(pass);
+    // The vertices buffer is rewritten by the next blur, so submit the commands
+    // that use it.
+    self.submit();
+  }
+
   pub(crate) fn draw_texture_to_texture(
     &mut self, dist_tex: &WgpuTexture, dist_at: DevicePoint, from_tex: &WgpuTexture,
     src_rect: &DeviceRect,
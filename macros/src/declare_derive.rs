@@ -436,6 +436,14 @@ pub(crate) fn declare_derive(input: &mut syn::DeriveInput) -> syn::Result<TokenS
           self
         }
 
+        #[doc="Initializes the shadow of the widget box."]
+        #vis fn box_shadow<const _M: usize>(
+          mut self, v: impl DeclareInto<Option<Shadow>, _M>
+        ) -> Self {
+          self.fat_obj = self.fat_obj.box_shadow(v);
+          self
+        }
+
         #[doc="Initializes the foreground for this widget."]
         #vis fn foreground<const _M: usize>(mut self, v: impl DeclareInto<Brush, _M>) -> Self {
           self.fat_obj = self.fat_obj.foreground(v);
@@ -488,6 +496,28 @@ pub(crate) fn declare_derive(input: &mut syn::DeriveInput) -> syn::Result<TokenS
           self
         }
 
+        #[doc="Initializes the text decoration of this widget."]
+        #vis fn text_decoration<const _M: usize>(
+          mut self, v: impl DeclareInto<TextDecorationStyle, _M>
+        ) -> Self {
+          self.fat_obj = self.fat_obj.text_decoration(v);
+          self
+        }
+
+        #[doc="Initializes the max lines of the text of this widget."]
+        #vis fn text_max_lines<const _M: usize>(
+          mut self, v: impl DeclareInto<Option<usize>, _M>
+        ) -> Self {
+          self.fat_obj = self.fat_obj.text_max_lines(v);
+          self
+        }
+
+        #[doc="Initializes how to hyphenate the words of the text of this widget."]
+        #vis fn text_hyphens<const _M: usize>(mut self, v: impl DeclareInto<Hyphens, _M>) -> Self {
+          self.fat_obj = self.fat_obj.text_hyphens(v);
+          self
+        }
+
         #[doc="Initializes the extra space within the widget."]
         #vis fn padding<const _M: usize>(mut self, v: impl DeclareInto<EdgeInsets, _M>) -> Self {
           self.fat_obj = self.fat_obj.padding(v);
@@ -575,6 +605,27 @@ pub(crate) fn declare_derive(input: &mut syn::DeriveInput) -> syn::Result<TokenS
           self
         }
 
+        #[doc="Initializes the gaussian blur radius of the widget content."]
+        #vis fn blur<const _M: usize>(mut self, v: impl DeclareInto<f32, _M>) -> Self
+        {
+          self.fat_obj = self.fat_obj.blur(v);
+          self
+        }
+
+        #[doc="Initializes the gaussian blur radius of the backdrop behind the widget."]
+        #vis fn backdrop_blur<const _M: usize>(mut self, v: impl DeclareInto<f32, _M>) -> Self
+        {
+          self.fat_obj = self.fat_obj.backdrop_blur(v);
+          self
+        }
+
+        #[doc="Initializes how the widget is blended with the backdrop."]
+        #vis fn blend_mode<const _M: usize>(mut self, v: impl DeclareInto<BlendMode, _M>) -> Self
+        {
+          self.fat_obj = self.fat_obj.blend_mode(v);
+          self
+        }
+
         #[doc="Initializes the `keep_alive` value of the `KeepAlive` widget."]
         #vis fn keep_alive<const _M: usize>(mut self, v: impl DeclareInto<bool, _M>) -> Self
         {
@@ -116,6 +116,7 @@ pub static BUILTIN_INFOS: phf::Map<&'static str, BuiltinMember> = phf_map! {
   "background" => builtin_member!{"BoxDecoration", Field, "box_decoration"},
   "border" => builtin_member!{"BoxDecoration", Field, "box_decoration"},
   "border_radius" => builtin_member!{"BoxDecoration", Field, "box_decoration"},
+  "box_shadow" => builtin_member!{"BoxDecoration", Field, "box_decoration"},
   // Foreground
   "foreground" => builtin_member! { "Foreground", Field, "foreground"},
   // PaintingStyleWidget
@@ -127,6 +128,9 @@ pub static BUILTIN_INFOS: phf::Map<&'static str, BuiltinMember> = phf_map! {
   "letter_space" => builtin_member! { "TextStyleWidget", Method, "text_style" },
   "text_line_height" => builtin_member! { "TextStyleWidget", Method, "text_style" },
   "text_overflow" => builtin_member! { "TextStyleWidget", Method, "text_style" },
+  "text_decoration" => builtin_member! { "TextStyleWidget", Method, "text_style" },
+  "text_max_lines" => builtin_member! { "TextStyleWidget", Method, "text_style" },
+  "text_hyphens" => builtin_member! { "TextStyleWidget", Method, "text_style" },
   // Padding
   "padding" => builtin_member!{"Padding", Field, "padding"},
   // LayoutBox
@@ -164,6 +168,11 @@ pub static BUILTIN_INFOS: phf::Map<&'static str, BuiltinMember> = phf_map! {
   "visible" => builtin_member!{"Visibility", Field, "visibility"},
   // Opacity
   "opacity" => builtin_member!{"Opacity", Field, "opacity"},
+  // Blur
+  "blur" => builtin_member!{"Blur", Field, "blur"},
+  "backdrop_blur" => builtin_member!{"Blur", Field, "blur"},
+  // BlendModeWidget
+  "blend_mode" => builtin_member!{"BlendModeWidget", Field, "blend_mode"},
   // KeepAlive
   "keep_alive" => builtin_member!{"KeepAlive", Field, "keep_alive"},
   // Tooltips
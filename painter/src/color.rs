@@ -1,8 +1,10 @@
+use std::f32::consts::TAU;
+
 use material_color_utilities_rs::htc;
-use ribir_geom::Point;
+use ribir_geom::{Point, Rect, Vector};
 use serde::{Deserialize, Serialize};
 
-use crate::SpreadMethod;
+use crate::{Path, SpreadMethod};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct Color {
@@ -41,6 +43,107 @@ pub struct LinearGradient {
   pub spread_method: SpreadMethod,
 }
 
+/// A gradient sweeps the colors around the `center`, like a color wheel.
+///
+/// The angles are in radians and go clockwise from the positive x axis. The
+/// offset `0.` of the stops is at the `start_angle` and `1.` at the
+/// `end_angle`, the directions out of them are painted by the `spread_method`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SweepGradient {
+  pub center: Point,
+  pub start_angle: f32,
+  pub end_angle: f32,
+  pub stops: Vec<GradientStop>,
+  pub spread_method: SpreadMethod,
+}
+
+impl SweepGradient {
+  /// The number of wedges a full turn is split into by
+  /// [`SweepGradient::wedges`].
+  const WEDGES_PER_TURN: usize = 180;
+
+  /// Returns the offset of the stops at `pos` before the spread method is
+  /// applied, or `None` if the gradient sweeps no angle.
+  pub fn offset_at(&self, pos: Point) -> Option<f32> {
+    let sweep = self.end_angle - self.start_angle;
+    if sweep == 0. || !sweep.is_finite() {
+      return None;
+    }
+    let v = pos - self.center;
+    let angle = v.y.atan2(v.x);
+    // The angle swept from the start in the direction of the gradient.
+    let swept = if sweep > 0. {
+      (angle - self.start_angle).rem_euclid(TAU)
+    } else {
+      -(self.start_angle - angle).rem_euclid(TAU)
+    };
+    Some(swept / sweep)
+  }
+
+  /// Returns the color of the gradient at `pos`, `None` if nothing is painted
+  /// there.
+  pub fn color_at(&self, pos: Point) -> Option<Color> {
+    let offset = self.offset_at(pos)?;
+    gradient_color(&self.stops, self.spread_method.apply(offset))
+  }
+
+  /// Approximate the gradient by the wedges around the center that cover the
+  /// `bounds`, every wedge is painted with a solid color. It's for the backends
+  /// can't paint the sweep gradient.
+  pub fn wedges(&self, bounds: &Rect) -> Vec<(Path, Color)> {
+    let (min, max) = (bounds.min(), bounds.max());
+    let radius = [min, max, Point::new(min.x, max.y), Point::new(max.x, min.y)]
+      .iter()
+      .map(|p| (*p - self.center).length())
+      .fold(0., f32::max)
+      + 1.;
+    let step = TAU / Self::WEDGES_PER_TURN as f32;
+    let at = |angle: f32| self.center + Vector::new(angle.cos(), angle.sin()) * radius;
+    (0..Self::WEDGES_PER_TURN)
+      .filter_map(|i| {
+        let start = self.start_angle + step * i as f32;
+        let color = self.color_at(at(start + step / 2.))?;
+        let mut builder = Path::builder();
+        // Overlap the next wedge a little to avoid the seams between them.
+        builder
+          .begin_path(self.center)
+          .line_to(at(start))
+          .line_to(at(start + step * 1.1))
+          .end_path(true);
+        Some((builder.build(), color))
+      })
+      .collect()
+  }
+}
+
+impl SpreadMethod {
+  /// Map the `offset` of a gradient into `[0, 1]`.
+  pub fn apply(self, offset: f32) -> f32 {
+    match self {
+      SpreadMethod::Pad => offset.clamp(0., 1.),
+      SpreadMethod::Reflect => 1. - ((offset / 2.).rem_euclid(1.) - 0.5).abs() * 2.,
+      SpreadMethod::Repeat => offset.rem_euclid(1.),
+    }
+  }
+}
+
+/// Interpolate the color of the `stops` at the `offset`.
+fn gradient_color(stops: &[GradientStop], offset: f32) -> Option<Color> {
+  let next_idx = stops
+    .iter()
+    .position(|s| s.offset >= offset)
+    .unwrap_or(stops.len().checked_sub(1)?);
+  let prev_idx = next_idx.saturating_sub(1);
+  let (prev, next) = (&stops[prev_idx], &stops[next_idx]);
+
+  let range = next.offset - prev.offset;
+  let weight = if range > 0. { ((offset - prev.offset) / range).clamp(0., 1.) } else { 1. };
+  let prev = prev.color.into_f32_components();
+  let next = next.color.into_f32_components();
+  let c = |i: usize| prev[i] * (1. - weight) + next[i] * weight;
+  Some(Color::from_f32_rgba(c(0), c(1), c(2), c(3)))
+}
+
 /// Describe the light tone of a color, should between [0, 1.0], 0.0 gives
 /// absolute black and 1.0 give the brightest white.
 #[derive(Clone, Debug, Copy)]
@@ -349,3 +452,25 @@ impl Color {
   pub const YELLOWGREEN: Color = Self::from_rgb(154, 205, 50);
   pub const TRANSPARENT: Color = Self::new(0, 0, 0, 0);
 }
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::{FRAC_PI_2, PI};
+
+  use super::*;
+
+  #[test]
+  fn sweep_offset() {
+    let sweep = SweepGradient { end_angle: PI, ..Default::default() };
+    assert_eq!(sweep.offset_at(Point::new(1., 0.)), Some(0.));
+    assert_eq!(sweep.offset_at(Point::new(0., 1.)), Some(0.5));
+    assert_eq!(sweep.offset_at(Point::new(0., -1.)), Some(1.5));
+
+    // A counterclockwise sweep.
+    let sweep = SweepGradient { start_angle: FRAC_PI_2, end_angle: -FRAC_PI_2, ..sweep };
+    assert_eq!(sweep.offset_at(Point::new(1., 0.)), Some(0.5));
+
+    let sweep = SweepGradient { end_angle: FRAC_PI_2, ..sweep };
+    assert_eq!(sweep.offset_at(Point::new(1., 0.)), None);
+  }
+}
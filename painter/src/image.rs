@@ -1,6 +1,7 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, time::Duration};
 
-use ribir_geom::DeviceSize;
+use ribir_algo::Resource;
+use ribir_geom::{DeviceSize, Point, Rect, Size};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -19,6 +20,31 @@ impl ColorFormat {
   }
 }
 
+/// The encoded image formats that [`PixelImage`] can decode, every format is
+/// only decoded if its cargo feature is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ImageFormat {
+  Png,
+  Jpeg,
+  WebP,
+  /// Only the first frame of a GIF is decoded, use [`AnimatedImage`] to
+  /// decode all its frames.
+  Gif,
+  Bmp,
+  Ico,
+}
+
+/// The error of decoding an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+  /// The format can't be recognized from the magic bytes.
+  UnknownFormat,
+  /// The cargo feature to decode the format is not enabled.
+  Unsupported(ImageFormat),
+  /// The bytes are broken or not in the format.
+  Decode { format: ImageFormat, reason: String },
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq)]
 pub struct PixelImage {
   data: Cow<'static, [u8]>,
@@ -27,20 +53,142 @@ pub struct PixelImage {
   format: ColorFormat,
 }
 
+/// A frame of an [`AnimatedImage`].
+#[derive(Debug, Clone)]
+pub struct ImageFrame {
+  pub image: Resource<PixelImage>,
+  /// How long the frame is shown before the next one.
+  pub delay: Duration,
+}
+
+/// An image with a sequence of frames, decoded from an animated GIF, APNG or
+/// animated WebP. The other images are decoded as a single frame.
+#[derive(Debug, Clone)]
+pub struct AnimatedImage {
+  frames: Vec<ImageFrame>,
+}
+
+/// The delay that the too short frame delays are played as, the same as the
+/// browsers do.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(10);
+
+impl AnimatedImage {
+  /// Create an animated image from its frames, the delays shorter than 10ms
+  /// are played as 100ms.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `frames` is empty.
+  pub fn new(mut frames: Vec<ImageFrame>) -> Self {
+    assert!(!frames.is_empty(), "An animated image needs at least one frame.");
+    for f in frames.iter_mut() {
+      if f.delay < MIN_FRAME_DELAY {
+        f.delay = DEFAULT_FRAME_DELAY;
+      }
+    }
+    Self { frames }
+  }
+
+  /// Decode all the frames of an image whose format is sniffed from its magic
+  /// bytes.
+  pub fn decode(bytes: &[u8]) -> Result<Self, ImageError> {
+    let format = ImageFormat::sniff(bytes).ok_or(ImageError::UnknownFormat)?;
+    if !format.is_enabled() {
+      return Err(ImageError::Unsupported(format));
+    }
+    decode_frames(bytes, format).map(Self::new)
+  }
+
+  #[inline]
+  pub fn frames(&self) -> &[ImageFrame] { &self.frames }
+
+  #[inline]
+  pub fn frame_count(&self) -> usize { self.frames.len() }
+
+  #[inline]
+  pub fn is_animated(&self) -> bool { self.frames.len() > 1 }
+
+  /// The time to play all the frames once.
+  pub fn duration(&self) -> Duration { self.frames.iter().map(|f| f.delay).sum() }
+}
+
+/// An image divided into nine parts by the insets from its edges. When it's
+/// painted in a rect, the corners keep their size, the edges are stretched
+/// along their sides and the center is stretched in both directions.
+///
+/// The corners are scaled down together if the rect is smaller than them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NinePatch {
+  pub image: Resource<PixelImage>,
+  /// The insets in pixels from the left, top, right and bottom edges of the
+  /// image.
+  pub insets: [u32; 4],
+}
+
+impl NinePatch {
+  pub fn new(image: Resource<PixelImage>, left: u32, top: u32, right: u32, bottom: u32) -> Self {
+    Self { image, insets: [left, top, right, bottom] }
+  }
+
+  /// The pairs of the source rect in the image and the destination rect of the
+  /// parts to paint in `dst`, the empty parts are skipped.
+  pub fn slices(&self, dst: &Rect) -> Vec<(Rect, Rect)> {
+    let [left, top, right, bottom] = self.insets;
+    let (w, h) = (self.image.width(), self.image.height());
+    let (left, top) = (left.min(w), top.min(h));
+    let (right, bottom) = (right.min(w - left), bottom.min(h - top));
+
+    let split = |start: u32, end: u32, size: u32, dst_start: f32, dst_size: f32| {
+      let (start, end, size) = (start as f32, end as f32, size as f32);
+      let scale = (dst_size / (start + end)).min(1.);
+      let (dst_head, dst_tail) = (start * scale, end * scale);
+      [
+        (0., start, dst_start, dst_head),
+        (start, size - start - end, dst_start + dst_head, dst_size - dst_head - dst_tail),
+        (size - end, end, dst_start + dst_size - dst_tail, dst_tail),
+      ]
+    };
+    let cols = split(left, right, w, dst.min_x(), dst.width());
+    let rows = split(top, bottom, h, dst.min_y(), dst.height());
+
+    let mut slices = Vec::with_capacity(9);
+    for (sy, sh, dy, dh) in rows {
+      for (sx, sw, dx, dw) in cols {
+        if sw > 0. && sh > 0. && dw > 0. && dh > 0. {
+          let src = Rect::new(Point::new(sx, sy), Size::new(sw, sh));
+          let dst = Rect::new(Point::new(dx, dy), Size::new(dw, dh));
+          slices.push((src, dst));
+        }
+      }
+    }
+    slices
+  }
+}
+
 impl PixelImage {
   #[inline]
   pub fn new(data: Cow<'static, [u8]>, width: u32, height: u32, format: ColorFormat) -> Self {
     PixelImage { data, width, height, format }
   }
 
+  /// Decode an image whose format is sniffed from its magic bytes.
+  pub fn decode(bytes: &[u8]) -> Result<Self, ImageError> {
+    let format = ImageFormat::sniff(bytes).ok_or(ImageError::UnknownFormat)?;
+    Self::decode_with_format(bytes, format)
+  }
+
+  /// Decode an image in the `format`.
+  pub fn decode_with_format(bytes: &[u8], format: ImageFormat) -> Result<Self, ImageError> {
+    if !format.is_enabled() {
+      return Err(ImageError::Unsupported(format));
+    }
+    decode_image(bytes, format)
+  }
+
   #[cfg(feature = "png")]
-  pub fn from_png(bytes: &[u8]) -> Self {
-    let img = ::image::load(std::io::Cursor::new(bytes), image::ImageFormat::Png)
-      .unwrap()
-      .to_rgba8();
-    let width = img.width();
-    let height = img.height();
-    PixelImage::new(img.into_raw().into(), width, height, ColorFormat::Rgba8)
+  pub fn from_png(bytes: &[u8]) -> Result<Self, ImageError> {
+    Self::decode_with_format(bytes, ImageFormat::Png)
   }
 
   #[cfg(feature = "png")]
@@ -69,6 +217,143 @@ impl PixelImage {
   pub fn pixel_bytes(&self) -> &[u8] { &self.data }
 }
 
+impl ImageFormat {
+  /// Recognize the format from the magic bytes at the beginning of the
+  /// encoded image.
+  pub fn sniff(bytes: &[u8]) -> Option<Self> {
+    match bytes {
+      [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(ImageFormat::Png),
+      [0xFF, 0xD8, 0xFF, ..] => Some(ImageFormat::Jpeg),
+      [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageFormat::WebP),
+      [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(ImageFormat::Gif),
+      [b'B', b'M', ..] => Some(ImageFormat::Bmp),
+      [0x00, 0x00, 0x01, 0x00, ..] => Some(ImageFormat::Ico),
+      _ => None,
+    }
+  }
+
+  /// Return if the cargo feature to decode the format is enabled.
+  pub const fn is_enabled(self) -> bool {
+    match self {
+      ImageFormat::Png => cfg!(feature = "png"),
+      ImageFormat::Jpeg => cfg!(feature = "jpeg"),
+      ImageFormat::WebP => cfg!(feature = "webp"),
+      ImageFormat::Gif => cfg!(feature = "gif"),
+      ImageFormat::Bmp => cfg!(feature = "bmp"),
+      ImageFormat::Ico => cfg!(feature = "ico"),
+    }
+  }
+}
+
+#[cfg(any(
+  feature = "png",
+  feature = "jpeg",
+  feature = "webp",
+  feature = "gif",
+  feature = "bmp",
+  feature = "ico"
+))]
+fn decode_image(bytes: &[u8], format: ImageFormat) -> Result<PixelImage, ImageError> {
+  let codec = match format {
+    ImageFormat::Png => ::image::ImageFormat::Png,
+    ImageFormat::Jpeg => ::image::ImageFormat::Jpeg,
+    ImageFormat::WebP => ::image::ImageFormat::WebP,
+    ImageFormat::Gif => ::image::ImageFormat::Gif,
+    ImageFormat::Bmp => ::image::ImageFormat::Bmp,
+    ImageFormat::Ico => ::image::ImageFormat::Ico,
+  };
+  let img = ::image::load_from_memory_with_format(bytes, codec)
+    .map_err(|err| ImageError::Decode { format, reason: err.to_string() })?
+    .to_rgba8();
+  let (width, height) = img.dimensions();
+  Ok(PixelImage::new(img.into_raw().into(), width, height, ColorFormat::Rgba8))
+}
+
+#[cfg(not(any(
+  feature = "png",
+  feature = "jpeg",
+  feature = "webp",
+  feature = "gif",
+  feature = "bmp",
+  feature = "ico"
+)))]
+fn decode_image(_: &[u8], format: ImageFormat) -> Result<PixelImage, ImageError> {
+  Err(ImageError::Unsupported(format))
+}
+
+fn decode_frames(bytes: &[u8], format: ImageFormat) -> Result<Vec<ImageFrame>, ImageError> {
+  #[cfg(any(feature = "png", feature = "gif", feature = "webp"))]
+  {
+    use std::io::Cursor;
+
+    use ::image::AnimationDecoder;
+
+    let err = |err: ::image::ImageError| ImageError::Decode { format, reason: err.to_string() };
+    match format {
+      #[cfg(feature = "gif")]
+      ImageFormat::Gif => {
+        let decoder = ::image::codecs::gif::GifDecoder::new(Cursor::new(bytes)).map_err(err)?;
+        return collect_frames(decoder.into_frames(), format);
+      }
+      #[cfg(feature = "png")]
+      ImageFormat::Png => {
+        let decoder = ::image::codecs::png::PngDecoder::new(Cursor::new(bytes)).map_err(err)?;
+        if decoder.is_apng() {
+          return collect_frames(decoder.apng().into_frames(), format);
+        }
+      }
+      #[cfg(feature = "webp")]
+      ImageFormat::WebP => {
+        let decoder = ::image::codecs::webp::WebPDecoder::new(Cursor::new(bytes)).map_err(err)?;
+        if decoder.has_animation() {
+          return collect_frames(decoder.into_frames(), format);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  let image = PixelImage::decode_with_format(bytes, format)?;
+  Ok(vec![ImageFrame { image: Resource::new(image), delay: Duration::ZERO }])
+}
+
+#[cfg(any(feature = "png", feature = "gif", feature = "webp"))]
+fn collect_frames(
+  frames: ::image::Frames, format: ImageFormat,
+) -> Result<Vec<ImageFrame>, ImageError> {
+  let frames = frames
+    .map(|frame| {
+      let frame = frame.map_err(|err| ImageError::Decode { format, reason: err.to_string() })?;
+      let delay = Duration::from(frame.delay());
+      let buffer = frame.into_buffer();
+      let (width, height) = buffer.dimensions();
+      let image = PixelImage::new(buffer.into_raw().into(), width, height, ColorFormat::Rgba8);
+      Ok(ImageFrame { image: Resource::new(image), delay })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  if frames.is_empty() {
+    Err(ImageError::Decode { format, reason: "the image has no frame".into() })
+  } else {
+    Ok(frames)
+  }
+}
+
+impl std::fmt::Display for ImageError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ImageError::UnknownFormat => write!(f, "unknown image format"),
+      ImageError::Unsupported(format) => {
+        write!(f, "the feature to decode the {format:?} image is not enabled")
+      }
+      ImageError::Decode { format, reason } => {
+        write!(f, "failed to decode the {format:?} image, {reason}")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ImageError {}
+
 impl std::fmt::Debug for PixelImage {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     f.debug_tuple("PixelImage")
@@ -76,3 +361,156 @@ impl std::fmt::Debug for PixelImage {
       .finish()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sniff_formats() {
+    let sniff = |bytes: &[u8]| ImageFormat::sniff(bytes);
+    assert_eq!(sniff(b"\x89PNG\r\n\x1a\n...."), Some(ImageFormat::Png));
+    assert_eq!(sniff(b"\xFF\xD8\xFF\xE0"), Some(ImageFormat::Jpeg));
+    assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some(ImageFormat::WebP));
+    assert_eq!(sniff(b"GIF89a"), Some(ImageFormat::Gif));
+    assert_eq!(sniff(b"GIF87a"), Some(ImageFormat::Gif));
+    assert_eq!(sniff(b"BM...."), Some(ImageFormat::Bmp));
+    assert_eq!(sniff(b"\0\0\x01\0\x01\0"), Some(ImageFormat::Ico));
+    assert_eq!(sniff(b"RIFF\0\0\0\0WAVE"), None);
+    assert_eq!(sniff(b""), None);
+  }
+
+  #[test]
+  fn unknown_format() {
+    assert_eq!(PixelImage::decode(b"not an image"), Err(ImageError::UnknownFormat));
+  }
+
+  #[test]
+  #[cfg(feature = "png")]
+  fn png_round_trip() {
+    let img =
+      PixelImage::new(vec![255, 0, 0, 255, 0, 0, 255, 128].into(), 2, 1, ColorFormat::Rgba8);
+    let mut png = vec![];
+    img.write_as_png(&mut png).unwrap();
+    assert_eq!(PixelImage::decode(&png), Ok(img));
+
+    // A broken image is an error instead of a panic.
+    let err = PixelImage::decode(&png[..png.len() / 2]).unwrap_err();
+    assert!(matches!(err, ImageError::Decode { format: ImageFormat::Png, .. }));
+  }
+
+  #[test]
+  #[cfg(feature = "bmp")]
+  fn decode_bmp() {
+    use ::image::ImageEncoder;
+    let mut bmp = vec![];
+    ::image::codecs::bmp::BmpEncoder::new(&mut bmp)
+      .write_image(&[0, 255, 0, 255], 1, 1, ::image::ColorType::Rgba8)
+      .unwrap();
+    let img = PixelImage::decode(&bmp).unwrap();
+    assert_eq!(img.pixel_bytes(), [0, 255, 0, 255]);
+  }
+
+  #[test]
+  #[cfg(feature = "gif")]
+  fn decode_gif_first_frame() {
+    use ::image::{Delay, Frame, RgbaImage, codecs::gif::GifEncoder};
+    let mut gif = vec![];
+    {
+      let mut encoder = GifEncoder::new(&mut gif);
+      let frame = |c: [u8; 4]| {
+        Frame::from_parts(
+          RgbaImage::from_pixel(1, 1, c.into()),
+          0,
+          0,
+          Delay::from_numer_denom_ms(10, 1),
+        )
+      };
+      encoder
+        .encode_frames([frame([255, 0, 0, 255]), frame([0, 0, 255, 255])])
+        .unwrap();
+    }
+    let img = PixelImage::decode(&gif).unwrap();
+    assert_eq!(img.pixel_bytes(), [255, 0, 0, 255]);
+  }
+
+  #[test]
+  #[cfg(feature = "gif")]
+  fn decode_gif_frames() {
+    use ::image::{Delay, Frame, RgbaImage, codecs::gif::GifEncoder};
+    let mut gif = vec![];
+    {
+      let mut encoder = GifEncoder::new(&mut gif);
+      let frame = |c: [u8; 4], ms: u32| {
+        Frame::from_parts(
+          RgbaImage::from_pixel(1, 1, c.into()),
+          0,
+          0,
+          Delay::from_numer_denom_ms(ms, 1),
+        )
+      };
+      encoder
+        .encode_frames([frame([255, 0, 0, 255], 50), frame([0, 0, 255, 255], 0)])
+        .unwrap();
+    }
+    let img = AnimatedImage::decode(&gif).unwrap();
+    assert_eq!(img.frame_count(), 2);
+    let frames = img.frames();
+    assert_eq!(frames[0].image.pixel_bytes(), [255, 0, 0, 255]);
+    assert_eq!(frames[0].delay, Duration::from_millis(50));
+    assert_eq!(frames[1].image.pixel_bytes(), [0, 0, 255, 255]);
+    // A zero delay is played as the default delay.
+    assert_eq!(frames[1].delay, DEFAULT_FRAME_DELAY);
+  }
+
+  #[test]
+  #[cfg(feature = "png")]
+  fn static_image_as_single_frame() {
+    let img = PixelImage::new(vec![255; 4].into(), 1, 1, ColorFormat::Rgba8);
+    let mut png = vec![];
+    img.write_as_png(&mut png).unwrap();
+    let animated = AnimatedImage::decode(&png).unwrap();
+    assert!(!animated.is_animated());
+    assert_eq!(*animated.frames()[0].image, img);
+  }
+
+  #[test]
+  #[cfg(feature = "jpeg")]
+  fn decode_jpeg() {
+    use ::image::ImageEncoder;
+    let mut jpeg = vec![];
+    ::image::codecs::jpeg::JpegEncoder::new(&mut jpeg)
+      .write_image(&[255; 12], 2, 2, ::image::ColorType::Rgb8)
+      .unwrap();
+    let img = PixelImage::decode(&jpeg).unwrap();
+    assert_eq!(img.size(), DeviceSize::new(2, 2));
+  }
+
+  #[test]
+  #[cfg(not(feature = "webp"))]
+  fn disabled_format() {
+    let webp = b"RIFF\0\0\0\0WEBPVP8 ";
+    assert_eq!(PixelImage::decode(webp), Err(ImageError::Unsupported(ImageFormat::WebP)));
+  }
+
+  #[test]
+  fn nine_patch_slices() {
+    let img =
+      Resource::new(PixelImage::new(vec![0; 10 * 10 * 4].into(), 10, 10, ColorFormat::Rgba8));
+    let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+
+    let slices = NinePatch::new(img.clone(), 2, 3, 4, 0).slices(&rect(10., 10., 20., 30.));
+    // The bottom row is empty.
+    assert_eq!(slices.len(), 6);
+    assert_eq!(slices[0], (rect(0., 0., 2., 3.), rect(10., 10., 2., 3.)));
+    assert_eq!(slices[1], (rect(2., 0., 4., 3.), rect(12., 10., 14., 3.)));
+    assert_eq!(slices[4], (rect(2., 3., 4., 7.), rect(12., 13., 14., 27.)));
+    assert_eq!(slices[5], (rect(6., 3., 4., 7.), rect(26., 13., 4., 27.)));
+
+    // The corners are scaled down to fit the rect, and the center is empty.
+    let slices = NinePatch::new(img, 4, 4, 4, 4).slices(&rect(0., 0., 4., 16.));
+    assert_eq!(slices.len(), 6);
+    assert_eq!(slices[0], (rect(0., 0., 4., 4.), rect(0., 0., 2., 4.)));
+    assert_eq!(slices[1], (rect(6., 0., 4., 4.), rect(2., 0., 2., 4.)));
+  }
+}
@@ -17,6 +17,12 @@ pub mod image;
 mod style;
 pub use style::*;
 
-pub use crate::image::PixelImage;
+pub use crate::image::{AnimatedImage, ImageFrame, NinePatch, PixelImage};
 mod svg;
-pub use svg::Svg;
+pub use svg::{
+  Svg, SvgAnimAttr, SvgAnimValue, SvgAnimation, SvgCalcMode, SvgColorSlot, SvgTransformType,
+};
+mod svg_export;
+pub use svg_export::{SvgDocument, SvgExporter};
+mod lottie;
+pub use lottie::Lottie;
@@ -0,0 +1,366 @@
+use std::{error::Error, ops::Range, time::Duration};
+
+use lyon_algorithms::path::Path as LyonPath;
+use ribir_geom::{Point, Rect, Size, Transform};
+
+use crate::{Color, LineCap, LineJoin, Painter, Path, Radius, StrokeOptions};
+
+mod model;
+use model::{Layer, NULL_LAYER, Root, SHAPE_LAYER, SOLID_LAYER, Shape};
+
+/// A motion exported in the Lottie JSON, it's painted frame by frame by
+/// [`Painter::draw_lottie`].
+///
+/// The shape layers, the solid layers, the parenting of the layers, the
+/// transforms, the solid fills, the strokes and the trim paths are supported.
+/// The other features, like the masks, the mattes, the gradients, the images
+/// and the texts, are ignored with a warning.
+pub struct Lottie {
+  root: Root,
+}
+
+/// The paths to paint with a style.
+struct Draw {
+  paths: Vec<Path>,
+  style: DrawStyle,
+  transform: Transform,
+  alpha: f32,
+}
+
+enum DrawStyle {
+  Fill(Color),
+  Stroke(Color, StrokeOptions),
+}
+
+/// The max depth of the parents of a layer, to avoid the cyclic parenting.
+const MAX_PARENT_DEPTH: usize = 32;
+
+impl Lottie {
+  pub fn parse_from_bytes(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+    let root: Root = serde_json::from_slice(data)?;
+    if root.fr <= 0. {
+      return Err(format!("invalid frame rate of the lottie: {}", root.fr).into());
+    }
+    root.layers.iter().for_each(warn_unsupported);
+    Ok(Self { root })
+  }
+
+  /// The size of the composition.
+  pub fn size(&self) -> Size { Size::new(self.root.w, self.root.h) }
+
+  /// The number of frames per second.
+  pub fn frame_rate(&self) -> f32 { self.root.fr }
+
+  /// The range of the frames to play, the end frame is excluded.
+  pub fn frames(&self) -> Range<f32> { self.root.ip..self.root.op }
+
+  /// The time to play all the frames once.
+  pub fn duration(&self) -> Duration {
+    let frames = (self.root.op - self.root.ip).max(0.);
+    Duration::from_secs_f32(frames / self.root.fr)
+  }
+
+  pub(crate) fn paint(&self, painter: &mut Painter, frame: f32) {
+    for layer in self.root.layers.iter().rev() {
+      if layer.hd || layer.ty == NULL_LAYER || frame < layer.ip || frame >= layer.op {
+        continue;
+      }
+      let local = layer_frame(layer, frame);
+      let mut painter = painter.save_guard();
+      painter
+        .apply_transform(&self.layer_matrix(layer, frame, 0))
+        .apply_alpha(layer.ks.opacity(local));
+
+      match layer.ty {
+        SOLID_LAYER => {
+          if let Some(color) = layer.sc.as_deref().and_then(hex_color) {
+            let rect = Rect::from_size(Size::new(layer.sw, layer.sh));
+            painter
+              .set_fill_brush(color)
+              .fill_path(Path::rect(&rect).into());
+          }
+        }
+        SHAPE_LAYER => {
+          let (_, draws) = collect_shapes(&layer.shapes, local);
+          draws
+            .iter()
+            .rev()
+            .for_each(|draw| draw.paint(&mut painter));
+        }
+        _ => {}
+      }
+    }
+  }
+
+  /// The matrix of the layer with the matrices of its parents.
+  fn layer_matrix(&self, layer: &Layer, frame: f32, depth: usize) -> Transform {
+    let ts = layer.ks.matrix(layer_frame(layer, frame));
+    let parent = layer.parent.filter(|_| depth < MAX_PARENT_DEPTH);
+    match parent.and_then(|p| self.root.layers.iter().find(|l| l.ind == Some(p))) {
+      Some(parent) => ts.then(&self.layer_matrix(parent, frame, depth + 1)),
+      None => ts,
+    }
+  }
+}
+
+fn layer_frame(layer: &Layer, frame: f32) -> f32 {
+  if layer.sr == 0. { layer.st } else { (frame - layer.st) / layer.sr }
+}
+
+fn warn_unsupported(layer: &Layer) {
+  if !matches!(layer.ty, SOLID_LAYER | NULL_LAYER | SHAPE_LAYER) {
+    log::warn!("[painter]: not support the layer type `{}` in lottie, ignored!", layer.ty);
+  }
+  if layer.tt.is_some() {
+    log::warn!("[painter]: not support the track matte in lottie, ignored!");
+  }
+  if !layer.masks.is_empty() {
+    log::warn!("[painter]: not support the masks in lottie, ignored!");
+  }
+  warn_unsupported_shapes(&layer.shapes);
+}
+
+fn warn_unsupported_shapes(shapes: &[Shape]) {
+  for shape in shapes {
+    match shape {
+      Shape::Group { it, .. } => warn_unsupported_shapes(it),
+      Shape::Fill { r: Some(2), .. } => {
+        log::warn!("[painter]: not support the even-odd fill rule in lottie, use non-zero!")
+      }
+      Shape::Unsupported => log::warn!("[painter]: not support the shape in lottie, ignored!"),
+      _ => {}
+    }
+  }
+}
+
+/// Collect the paths and the draws of the shapes, the paths are returned to be
+/// painted by the styles of the outer groups.
+fn collect_shapes(shapes: &[Shape], frame: f32) -> (Vec<Path>, Vec<Draw>) {
+  let mut paths = vec![];
+  let mut draws = vec![];
+  for shape in shapes {
+    match shape {
+      Shape::Group { it, hd: false } => {
+        let (ts, alpha) = it
+          .iter()
+          .find_map(|s| match s {
+            Shape::Transform(ts) => Some((ts.matrix(frame), ts.opacity(frame))),
+            _ => None,
+          })
+          .unwrap_or((Transform::identity(), 1.));
+        let (sub_paths, sub_draws) = collect_shapes(it, frame);
+        paths.extend(sub_paths.into_iter().map(|p| p.transform(&ts)));
+        draws.extend(sub_draws.into_iter().map(|mut draw| {
+          draw.transform = draw.transform.then(&ts);
+          draw.alpha *= alpha;
+          draw
+        }));
+      }
+      Shape::Path { ks, hd: false } => {
+        if let Some(bezier) = ks.value(frame) {
+          let mut builder = Path::builder();
+          if let Some(&[x, y]) = bezier.v.first() {
+            builder.begin_path(Point::new(x, y));
+            for [ctrl1, ctrl2, to] in bezier.segments() {
+              builder.bezier_curve_to(ctrl1, ctrl2, to);
+            }
+            builder.end_path(bezier.c);
+            paths.push(builder.build());
+          }
+        }
+      }
+      Shape::Rect { p, s, r, hd: false } => {
+        let size = s.vector(frame);
+        let rect = Rect::new((p.vector(frame) - size / 2.).to_point(), size.to_size());
+        let r = r.as_ref().map_or(0., |r| r.scalar(frame));
+        let radius = r.min(size.x / 2.).min(size.y / 2.).max(0.);
+        paths.push(Path::rect_round(&rect, &Radius::all(radius)));
+      }
+      Shape::Ellipse { p, s, hd: false } => {
+        let mut builder = Path::builder();
+        builder.ellipse(p.vector(frame).to_point(), s.vector(frame) / 2., 0.);
+        paths.push(builder.build());
+      }
+      Shape::Fill { c, o, hd: false, .. } => draws.push(Draw {
+        paths: paths.clone(),
+        style: DrawStyle::Fill(c.color(frame)),
+        transform: Transform::identity(),
+        alpha: model::opacity(o.as_ref(), frame),
+      }),
+      Shape::Stroke { c, o, w, lc, lj, ml, d, hd: false } => {
+        let mut dash_array = vec![];
+        let mut dash_offset = 0.;
+        for dash in d {
+          match dash.n.as_str() {
+            "o" => dash_offset = dash.v.scalar(frame),
+            _ => dash_array.push(dash.v.scalar(frame)),
+          }
+        }
+        let line_cap = match lc {
+          Some(2) => LineCap::Round,
+          Some(3) => LineCap::Square,
+          _ => LineCap::Butt,
+        };
+        let line_join = match lj {
+          Some(2) => LineJoin::Round,
+          Some(3) => LineJoin::Bevel,
+          _ => LineJoin::Miter,
+        };
+        let options = StrokeOptions {
+          width: w.scalar(frame),
+          miter_limit: ml.unwrap_or(4.),
+          line_cap,
+          line_join,
+          dash_array,
+          dash_offset,
+        };
+        draws.push(Draw {
+          paths: paths.clone(),
+          style: DrawStyle::Stroke(c.color(frame), options),
+          transform: Transform::identity(),
+          alpha: model::opacity(o.as_ref(), frame),
+        });
+      }
+      Shape::Trim { s, e, o, m, hd: false } => {
+        let start = s.scalar(frame) / 100.;
+        let end = e.scalar(frame) / 100.;
+        let offset = o.as_ref().map_or(0., |o| o.scalar(frame)) / 360.;
+        let ranges = trim_ranges(start + offset, end + offset);
+        paths = if *m == Some(2) {
+          trim_path(&merge_paths(&paths), &ranges)
+            .into_iter()
+            .collect()
+        } else {
+          paths
+            .iter()
+            .filter_map(|p| trim_path(p, &ranges))
+            .collect()
+        };
+      }
+      _ => {}
+    }
+  }
+  (paths, draws)
+}
+
+/// The normalized ranges to keep of a path trimmed from `start` to `end`, the
+/// range across the end of the path is split into two.
+fn trim_ranges(start: f32, end: f32) -> Vec<Range<f32>> {
+  let (start, end) = if start <= end { (start, end) } else { (end, start) };
+  let len = end - start;
+  if len >= 1. {
+    return vec![0.0..1.];
+  } else if len <= 0. {
+    return vec![];
+  }
+  let start = start.rem_euclid(1.);
+  let end = start + len;
+  if end <= 1. { vec![start..end] } else { vec![start..1., 0.0..end - 1.] }
+}
+
+fn trim_path(path: &Path, ranges: &[Range<f32>]) -> Option<Path> {
+  match ranges {
+    [] => None,
+    [range] if *range == (0.0..1.) => Some(path.clone()),
+    _ => {
+      let sampler = path.sampler();
+      let parts: Vec<_> = ranges
+        .iter()
+        .map(|r| sampler.normalized_sub_path(r.clone()))
+        .collect();
+      Some(merge_paths(&parts))
+    }
+  }
+}
+
+fn merge_paths(paths: &[Path]) -> Path {
+  let slices: Vec<_> = paths
+    .iter()
+    .map(|p| p.lyon_path.as_slice())
+    .collect();
+  let mut builder = LyonPath::builder();
+  builder.extend_from_paths(&slices);
+  builder.build().into()
+}
+
+fn hex_color(hex: &str) -> Option<Color> {
+  let hex = hex.strip_prefix('#').unwrap_or(hex);
+  let value = u32::from_str_radix(hex, 16).ok()?;
+  match hex.len() {
+    6 => Some(Color::from_u32(value << 8 | 0xFF)),
+    8 => Some(Color::from_u32(value)),
+    _ => None,
+  }
+}
+
+impl Draw {
+  fn paint(&self, painter: &mut Painter) {
+    if self.paths.is_empty() {
+      return;
+    }
+    let path = if self.paths.len() == 1 { self.paths[0].clone() } else { merge_paths(&self.paths) };
+    let mut painter = painter.save_guard();
+    painter
+      .apply_transform(&self.transform)
+      .apply_alpha(self.alpha);
+    match &self.style {
+      DrawStyle::Fill(color) => {
+        painter
+          .set_fill_brush(*color)
+          .fill_path(path.into());
+      }
+      DrawStyle::Stroke(color, options) => {
+        painter
+          .set_stroke_brush(*color)
+          .set_strokes(options.clone())
+          .stroke_path(path.into());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn trim() {
+    assert_eq!(trim_ranges(0., 1.), vec![0.0..1.]);
+    assert_eq!(trim_ranges(0.5, 0.5), vec![]);
+    assert_eq!(trim_ranges(0.75, 0.25), vec![0.25..0.75]);
+    assert_eq!(trim_ranges(0.5, 1.25), vec![0.5..1., 0.0..0.25]);
+    assert_eq!(trim_ranges(-0.25, 0.25), vec![0.75..1., 0.0..0.25]);
+  }
+
+  #[test]
+  fn parse_info() {
+    let lottie = Lottie::parse_from_bytes(
+      br#"{ "v": "5.7.0", "fr": 30, "ip": 0, "op": 60, "w": 100, "h": 80, "layers": [] }"#,
+    )
+    .unwrap();
+
+    assert_eq!(lottie.size(), Size::new(100., 80.));
+    assert_eq!(lottie.frames(), 0.0..60.);
+    assert_eq!(lottie.duration(), Duration::from_secs(2));
+  }
+
+  #[test]
+  fn group_draws() {
+    let shapes: Vec<Shape> = serde_json::from_str(
+      r#"[{ "ty": "gr", "it": [
+        { "ty": "rc", "p": { "a": 0, "k": [0, 0] }, "s": { "a": 0, "k": [10, 10] } },
+        { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 50 } },
+        { "ty": "tr", "p": { "a": 0, "k": [20, 20] }, "o": { "a": 0, "k": 50 } }
+      ] }, { "ty": "st", "c": { "a": 0, "k": [0, 0, 1, 1] }, "w": { "a": 0, "k": 2 } }]"#,
+    )
+    .unwrap();
+
+    let (paths, draws) = collect_shapes(&shapes, 0.);
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].bounds(None), Rect::new(Point::new(15., 15.), Size::new(10., 10.)));
+    assert_eq!(draws.len(), 2);
+    assert_eq!(draws[0].alpha, 0.25);
+    assert_eq!(draws[0].transform, Transform::translation(20., 20.));
+    assert!(matches!(draws[1].style, DrawStyle::Stroke(..)));
+  }
+}
@@ -0,0 +1,492 @@
+//! The data model of the Lottie JSON, only the parts that can be played are
+//! kept, and the animated properties are evaluated at a frame.
+
+use ribir_geom::{Angle, Point, Transform, Vector};
+use serde::Deserialize;
+
+use crate::Color;
+
+#[derive(Deserialize)]
+pub(super) struct Root {
+  pub fr: f32,
+  pub ip: f32,
+  pub op: f32,
+  pub w: f32,
+  pub h: f32,
+  #[serde(default)]
+  pub layers: Vec<Layer>,
+}
+
+pub(super) const SOLID_LAYER: u8 = 1;
+pub(super) const NULL_LAYER: u8 = 3;
+pub(super) const SHAPE_LAYER: u8 = 4;
+
+#[derive(Deserialize)]
+pub(super) struct Layer {
+  pub ty: u8,
+  #[serde(default)]
+  pub ind: Option<i64>,
+  #[serde(default)]
+  pub parent: Option<i64>,
+  pub ip: f32,
+  pub op: f32,
+  #[serde(default)]
+  pub st: f32,
+  #[serde(default = "one")]
+  pub sr: f32,
+  #[serde(default)]
+  pub hd: bool,
+  #[serde(default)]
+  pub ks: TransformProps,
+  #[serde(default)]
+  pub shapes: Vec<Shape>,
+  /// The color of the solid layer, like `#ff0000`.
+  #[serde(default)]
+  pub sc: Option<String>,
+  #[serde(default)]
+  pub sw: f32,
+  #[serde(default)]
+  pub sh: f32,
+  /// The track matte type.
+  #[serde(default)]
+  pub tt: Option<u8>,
+  #[serde(default, rename = "masksProperties")]
+  pub masks: Vec<serde::de::IgnoredAny>,
+}
+
+fn one() -> f32 { 1. }
+
+#[derive(Deserialize)]
+#[serde(tag = "ty")]
+pub(super) enum Shape {
+  #[serde(rename = "gr")]
+  Group {
+    #[serde(default)]
+    it: Vec<Shape>,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "sh")]
+  Path {
+    ks: ShapeProp,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "rc")]
+  Rect {
+    p: Prop,
+    s: Prop,
+    r: Option<Prop>,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "el")]
+  Ellipse {
+    p: Prop,
+    s: Prop,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "fl")]
+  Fill {
+    c: Prop,
+    o: Option<Prop>,
+    /// The fill rule, `1` for non-zero and `2` for even-odd.
+    r: Option<u8>,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "st")]
+  Stroke {
+    c: Prop,
+    o: Option<Prop>,
+    w: Prop,
+    lc: Option<u8>,
+    lj: Option<u8>,
+    ml: Option<f32>,
+    #[serde(default)]
+    d: Vec<Dash>,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "tm")]
+  Trim {
+    s: Prop,
+    e: Prop,
+    o: Option<Prop>,
+    /// `1` trims the paths simultaneously, `2` trims them one after another.
+    m: Option<u8>,
+    #[serde(default)]
+    hd: bool,
+  },
+  #[serde(rename = "tr")]
+  Transform(TransformProps),
+  #[serde(other)]
+  Unsupported,
+}
+
+/// A dash, a gap or the offset of the dashes of a stroke.
+#[derive(Deserialize)]
+pub(super) struct Dash {
+  pub n: String,
+  pub v: Prop,
+}
+
+#[derive(Deserialize, Default)]
+pub(super) struct TransformProps {
+  a: Option<Prop>,
+  p: Option<Position>,
+  s: Option<Prop>,
+  r: Option<Prop>,
+  o: Option<Prop>,
+  sk: Option<Prop>,
+  sa: Option<Prop>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Position {
+  Split { x: Prop, y: Prop },
+  Whole(Prop),
+}
+
+/// An animatable property of numbers.
+#[derive(Deserialize)]
+pub(super) struct Prop {
+  k: Animatable<Numbers>,
+}
+
+/// An animatable property of a bézier shape.
+#[derive(Deserialize)]
+pub(super) struct ShapeProp {
+  k: Animatable<Bezier>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Animatable<T> {
+  Static(T),
+  Keyframes(Vec<Keyframe<T>>),
+}
+
+#[derive(Deserialize)]
+struct Keyframe<T> {
+  t: f32,
+  s: Option<T>,
+  /// The end value of the legacy keyframes, the start value of the next
+  /// keyframe is used if absent.
+  e: Option<T>,
+  /// The in tangent of the easing to this keyframe.
+  i: Option<Tangent>,
+  /// The out tangent of the easing from this keyframe.
+  o: Option<Tangent>,
+  /// Whether to hold the value until the next keyframe.
+  #[serde(default)]
+  h: u8,
+}
+
+#[derive(Deserialize)]
+struct Tangent {
+  x: Numbers,
+  y: Numbers,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "NumbersRepr")]
+pub(super) struct Numbers(pub Vec<f32>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumbersRepr {
+  One(f32),
+  Many(Vec<f32>),
+}
+
+impl From<NumbersRepr> for Numbers {
+  fn from(repr: NumbersRepr) -> Self {
+    match repr {
+      NumbersRepr::One(v) => Numbers(vec![v]),
+      NumbersRepr::Many(v) => Numbers(v),
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "BezierRepr")]
+pub(super) struct Bezier {
+  pub c: bool,
+  pub v: Vec<[f32; 2]>,
+  pub i: Vec<[f32; 2]>,
+  pub o: Vec<[f32; 2]>,
+}
+
+/// The keyframes hold the bézier in an array of one element.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BezierRepr {
+  One(BezierData),
+  Many(Vec<BezierData>),
+}
+
+#[derive(Deserialize, Default)]
+struct BezierData {
+  #[serde(default)]
+  c: bool,
+  #[serde(default)]
+  v: Vec<[f32; 2]>,
+  #[serde(default)]
+  i: Vec<[f32; 2]>,
+  #[serde(default)]
+  o: Vec<[f32; 2]>,
+}
+
+impl From<BezierRepr> for Bezier {
+  fn from(repr: BezierRepr) -> Self {
+    let BezierData { c, v, i, o } = match repr {
+      BezierRepr::One(data) => data,
+      BezierRepr::Many(list) => list.into_iter().next().unwrap_or_default(),
+    };
+    Bezier { c, v, i, o }
+  }
+}
+
+trait Interpolate: Clone {
+  /// Interpolate to `to`, the `rate` returns the eased rate of every
+  /// dimension.
+  fn interpolate(&self, to: &Self, rate: impl Fn(usize) -> f32) -> Self;
+}
+
+impl Interpolate for Numbers {
+  fn interpolate(&self, to: &Self, rate: impl Fn(usize) -> f32) -> Self {
+    let values = self
+      .0
+      .iter()
+      .zip(&to.0)
+      .enumerate()
+      .map(|(i, (from, to))| from + (to - from) * rate(i))
+      .collect();
+    Numbers(values)
+  }
+}
+
+impl Interpolate for Bezier {
+  fn interpolate(&self, to: &Self, rate: impl Fn(usize) -> f32) -> Self {
+    if self.v.len() != to.v.len() {
+      return if rate(0) < 1. { self.clone() } else { to.clone() };
+    }
+    let rate = rate(0);
+    let lerp = |from: &[[f32; 2]], to: &[[f32; 2]]| {
+      from
+        .iter()
+        .zip(to)
+        .map(|(a, b)| [a[0] + (b[0] - a[0]) * rate, a[1] + (b[1] - a[1]) * rate])
+        .collect()
+    };
+    Bezier { c: self.c, v: lerp(&self.v, &to.v), i: lerp(&self.i, &to.i), o: lerp(&self.o, &to.o) }
+  }
+}
+
+impl<T: Interpolate> Animatable<T> {
+  fn value(&self, frame: f32) -> Option<T> {
+    let keyframes = match self {
+      Animatable::Static(v) => return Some(v.clone()),
+      Animatable::Keyframes(keyframes) => keyframes,
+    };
+    // The start value of a legacy keyframe may be omitted for the last one.
+    let start = |idx: usize| {
+      let k: &Keyframe<T> = &keyframes[idx];
+      k.s.as_ref().or_else(|| {
+        idx
+          .checked_sub(1)
+          .and_then(|i| keyframes[i].e.as_ref())
+      })
+    };
+
+    let next = keyframes.partition_point(|k| k.t <= frame);
+    if next == 0 {
+      return keyframes.first().and_then(|_| start(0)).cloned();
+    }
+    let from = start(next - 1)?;
+    let k = &keyframes[next - 1];
+    let Some(to_frame) = keyframes.get(next) else { return Some(from.clone()) };
+    let Some(to) = k.e.as_ref().or(to_frame.s.as_ref()) else { return Some(from.clone()) };
+    if k.h == 1 || to_frame.t <= k.t {
+      return Some(from.clone());
+    }
+
+    let t = (frame - k.t) / (to_frame.t - k.t);
+    let value = match (&k.o, &k.i) {
+      (Some(o), Some(i)) => from.interpolate(to, |dim| {
+        let pick = |n: &Numbers, default| {
+          n.0
+            .get(dim)
+            .or(n.0.last())
+            .copied()
+            .unwrap_or(default)
+        };
+        cubic_ease(pick(&o.x, 0.), pick(&o.y, 0.), pick(&i.x, 1.), pick(&i.y, 1.), t)
+      }),
+      _ => from.interpolate(to, |_| t),
+    };
+    Some(value)
+  }
+}
+
+/// The progress of the cubic bézier easing from `(0, 0)` to `(1, 1)` with the
+/// control points `(x1, y1)` and `(x2, y2)` at the time `x`.
+fn cubic_ease(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+  let bezier = |p1: f32, p2: f32, t: f32| {
+    let u = 1. - t;
+    3. * u * u * t * p1 + 3. * u * t * t * p2 + t * t * t
+  };
+  let (x1, x2) = (x1.clamp(0., 1.), x2.clamp(0., 1.));
+  // The x of the curve is monotonic, so search the `t` of `x` by bisection.
+  let (mut lo, mut hi) = (0., 1.);
+  for _ in 0..24 {
+    let mid = (lo + hi) / 2.;
+    if bezier(x1, x2, mid) < x { lo = mid } else { hi = mid }
+  }
+  bezier(y1, y2, (lo + hi) / 2.)
+}
+
+impl Prop {
+  pub fn value(&self, frame: f32) -> Vec<f32> { self.k.value(frame).map_or_else(Vec::new, |n| n.0) }
+
+  pub fn scalar(&self, frame: f32) -> f32 { self.value(frame).first().copied().unwrap_or(0.) }
+
+  pub fn vector(&self, frame: f32) -> Vector {
+    let v = self.value(frame);
+    let x = v.first().copied().unwrap_or(0.);
+    Vector::new(x, v.get(1).copied().unwrap_or(x))
+  }
+
+  pub fn color(&self, frame: f32) -> Color {
+    let v = self.value(frame);
+    let c = |i: usize, default: f32| v.get(i).copied().unwrap_or(default);
+    Color::from_f32_rgba(c(0, 0.), c(1, 0.), c(2, 0.), c(3, 1.))
+  }
+}
+
+impl ShapeProp {
+  pub fn value(&self, frame: f32) -> Option<Bezier> { self.k.value(frame) }
+}
+
+/// The opacity of a property in the percentage, `1.` if it's absent.
+pub(super) fn opacity(prop: Option<&Prop>, frame: f32) -> f32 {
+  prop.map_or(1., |o| (o.scalar(frame) / 100.).clamp(0., 1.))
+}
+
+impl TransformProps {
+  pub fn matrix(&self, frame: f32) -> Transform {
+    let vector =
+      |p: &Option<Prop>, default: Vector| p.as_ref().map_or(default, |p| p.vector(frame));
+    let scalar = |p: &Option<Prop>| p.as_ref().map_or(0., |p| p.scalar(frame));
+
+    let anchor = vector(&self.a, Vector::zero());
+    let position = match &self.p {
+      Some(Position::Split { x, y }) => Vector::new(x.scalar(frame), y.scalar(frame)),
+      Some(Position::Whole(p)) => p.vector(frame),
+      None => Vector::zero(),
+    };
+    let scale = vector(&self.s, Vector::new(100., 100.)) / 100.;
+    let skew = scalar(&self.sk);
+    let mut ts = Transform::translation(-anchor.x, -anchor.y).then_scale(scale.x, scale.y);
+    if skew != 0. {
+      // Skew along the axis of the skew angle.
+      let axis = Angle::degrees(scalar(&self.sa));
+      let skew_x = Transform::new(1., 0., Angle::degrees(-skew).radians.tan(), 1., 0., 0.);
+      ts = ts
+        .then_rotate(-axis)
+        .then(&skew_x)
+        .then_rotate(axis);
+    }
+    ts.then_rotate(Angle::degrees(scalar(&self.r)))
+      .then_translate(position)
+  }
+
+  pub fn opacity(&self, frame: f32) -> f32 { opacity(self.o.as_ref(), frame) }
+}
+
+impl Bezier {
+  /// The points of the path, the start point and the control points and the
+  /// end point of every segment.
+  pub fn segments(&self) -> impl Iterator<Item = [Point; 3]> + '_ {
+    let n = self.v.len();
+    let segments = if self.c { n } else { n.saturating_sub(1) };
+    let point = |p: &[f32; 2]| Point::new(p[0], p[1]);
+    let tangent = move |list: &[[f32; 2]], i: usize| list.get(i).map_or(Point::zero(), point);
+    (0..segments).map(move |i| {
+      let j = (i + 1) % n;
+      let from = point(&self.v[i]);
+      let to = point(&self.v[j]);
+      [from + tangent(&self.o, i).to_vector(), to + tangent(&self.i, j).to_vector(), to]
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keyframe_values() {
+    let prop: Prop = serde_json::from_str(
+      r#"{ "a": 1, "k": [
+        { "t": 0, "s": [0, 10] },
+        { "t": 10, "s": [100, 20], "h": 1 },
+        { "t": 20, "s": [50, 50] }
+      ] }"#,
+    )
+    .unwrap();
+
+    assert_eq!(prop.value(-5.), [0., 10.]);
+    assert_eq!(prop.value(5.), [50., 15.]);
+    // Held until the next keyframe.
+    assert_eq!(prop.value(15.), [100., 20.]);
+    assert_eq!(prop.value(30.), [50., 50.]);
+
+    let prop: Prop = serde_json::from_str(r#"{ "a": 0, "k": 30 }"#).unwrap();
+    assert_eq!(prop.scalar(100.), 30.);
+  }
+
+  #[test]
+  fn eased_keyframes() {
+    let prop: Prop = serde_json::from_str(
+      r#"{ "a": 1, "k": [
+        { "t": 0, "s": [0], "o": { "x": [0.42], "y": [0] }, "i": { "x": [0.58], "y": [1] } },
+        { "t": 10, "s": [100] }
+      ] }"#,
+    )
+    .unwrap();
+
+    assert!(prop.scalar(2.) < 20.);
+    assert!((prop.scalar(5.) - 50.).abs() < 0.1);
+    assert!(prop.scalar(8.) > 80.);
+  }
+
+  #[test]
+  fn legacy_keyframes() {
+    let prop: Prop =
+      serde_json::from_str(r#"{ "a": 1, "k": [{ "t": 0, "s": [0], "e": [10] }, { "t": 10 }] }"#)
+        .unwrap();
+
+    assert_eq!(prop.scalar(5.), 5.);
+    assert_eq!(prop.scalar(20.), 10.);
+  }
+
+  #[test]
+  fn split_position() {
+    let ts: TransformProps = serde_json::from_str(
+      r#"{
+        "p": { "s": true, "x": { "a": 0, "k": 10 }, "y": { "a": 0, "k": 20 } },
+        "a": { "a": 0, "k": [5, 5] },
+        "s": { "a": 0, "k": [200, 200] }
+      }"#,
+    )
+    .unwrap();
+
+    let ts = ts.matrix(0.);
+    assert_eq!(ts.transform_point(Point::new(5., 5.)), Point::new(10., 20.));
+    assert_eq!(ts.transform_point(Point::new(6., 5.)), Point::new(12., 20.));
+  }
+}
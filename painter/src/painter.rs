@@ -1,12 +1,13 @@
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 
 use ribir_algo::Resource;
 use ribir_geom::{Angle, DeviceRect, Point, Rect, Size, Transform, Vector};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-  Brush, Color, Glyph, PixelImage, Svg, VisualGlyphs,
-  color::{LinearGradient, RadialGradient},
+  Brush, Color, DecorationLineStyle, DecorationPiece, Glyph, Lottie, NinePatch, PixelImage, Shadow,
+  Svg, SvgAnimValue, TextDecoration, VisualGlyphs,
+  color::{LinearGradient, RadialGradient, SweepGradient},
   font_db::FontDB,
   path::*,
   path_builder::PathBuilder,
@@ -22,6 +23,24 @@ pub struct Painter {
   state_stack: Vec<PainterState>,
   commands: Vec<PaintCommand>,
   path_builder: PathBuilder,
+  /// The layers that are waiting for their content, they are closed when the
+  /// state they are pushed in restored.
+  layers: Vec<OpenLayer>,
+}
+
+struct OpenLayer {
+  layer: OpenLayerKind,
+  /// The depth of the state stack when the layer is pushed.
+  depth: usize,
+  /// The index of the first command of the layer content.
+  cmd_start: usize,
+  /// The count of the clips when the layer is pushed.
+  clip_cnt: usize,
+}
+
+enum OpenLayerKind {
+  Blur(LayerCommand),
+  Mask(MaskCommand),
 }
 
 pub struct PainterResult<'a>(&'a mut Vec<PaintCommand>);
@@ -48,13 +67,18 @@ pub trait PainterBackend {
   /// Paint `commands` to the `output` Texture.  This may be called more than
   /// once during a frame.
   ///
+  /// The `damages` are the areas of the `output` changed since the last frame,
+  /// the backend can only redraw these areas and keep the other pixels of the
+  /// `output`. A backend that can't keep the content of the `output` between
+  /// frames should ignore them and redraw the whole `viewport`.
+  ///
   /// ## Undefined Behavior
   ///
   /// You should guarantee the output be same one in the same frame, otherwise
   /// it may cause undefined behavior.
   fn draw_commands(
-    &mut self, viewport: DeviceRect, commands: &[PaintCommand], global_matrix: &Transform,
-    output: &mut Self::Texture,
+    &mut self, viewport: DeviceRect, damages: &[DeviceRect], commands: &[PaintCommand],
+    global_matrix: &Transform, output: &mut Self::Texture,
   );
   /// A frame end.
   fn end_frame(&mut self);
@@ -76,6 +100,8 @@ pub enum PaintPathAction {
     brush: CommandBrush,
     /// The style to paint the path.
     painting_style: PaintingStyle,
+    /// How to blend the painted pixels with the backdrop.
+    blend_mode: BlendMode,
   },
 
   Clip,
@@ -84,9 +110,10 @@ pub enum PaintPathAction {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CommandBrush {
   Color(Color),
-  Image { img: Resource<PixelImage>, opacity: f32 },
+  Image { img: Resource<PixelImage>, opacity: f32, filter_quality: FilterQuality },
   Radial(RadialGradient),
   Linear(LinearGradient),
+  Sweep(SweepGradient),
 }
 
 #[repr(u32)]
@@ -111,6 +138,114 @@ pub struct PathCommand {
   pub action: PaintPathAction,
 }
 
+/// The shadow cast by a path, the offset and spread of the shadow are already
+/// applied to the path and the transform.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowCommand {
+  /// The shape that casts the shadow, it is always filled.
+  pub path: PaintPath,
+  /// The bounds of the blurred shadow after the transform applied.
+  pub paint_bounds: Rect,
+  /// The transform need to apply to the path.
+  pub transform: Transform,
+  pub color: Color,
+  /// The blur radius of the shadow, the standard deviation of the gaussian
+  /// blur is half of it.
+  pub blur_radius: f32,
+}
+
+/// Which pixels the gaussian blur of a [`LayerCommand`] applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlurTarget {
+  /// Blur the content painted in the layer.
+  Content,
+  /// Blur the backdrop under the path of the layer, then paint the content of
+  /// the layer over it.
+  Backdrop,
+}
+
+/// A layer of paint commands that applies a gaussian blur to its content or
+/// to the backdrop under its path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerCommand {
+  /// The area of the layer, the backdrop is only blurred inside it. The
+  /// content of the layer is not clipped by it.
+  pub path: PaintPath,
+  /// The bounds of the layer after the transform applied, include the
+  /// blurred content and backdrop.
+  pub paint_bounds: Rect,
+  /// The transform need to apply to the path.
+  pub transform: Transform,
+  /// The blur radius of the layer, the standard deviation of the gaussian
+  /// blur is half of it.
+  pub blur_radius: f32,
+  pub target: BlurTarget,
+  /// The commands painted in the layer.
+  pub cmds: Box<[PaintCommand]>,
+}
+
+/// Which channel of the mask pixels a [`MaskCommand`] uses as the coverage of
+/// its content.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MaskMode {
+  /// Use the alpha of the mask.
+  #[default]
+  Alpha,
+  /// Use the luminance of the mask multiplied by its alpha, the opaque white
+  /// pixels keep the content and the black ones hide it.
+  Luminance,
+}
+
+/// A layer of paint commands that are masked by the pixels painted by other
+/// commands, the content outside the mask is hidden.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaskCommand {
+  pub mode: MaskMode,
+  /// The bounds of the masked content after the transform applied, the
+  /// intersection of the content bounds and the mask bounds.
+  pub paint_bounds: Rect,
+  /// The commands that paint the mask.
+  pub mask: Box<[PaintCommand]>,
+  /// The commands painted in the layer.
+  pub cmds: Box<[PaintCommand]>,
+}
+
+/// How the painted colors are blended with the colors of the backdrop, the
+/// blended color is then composited over the backdrop with its alpha.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlendMode {
+  /// Paint over the backdrop, the source-over compositing.
+  #[default]
+  Normal,
+  /// Multiply the colors, the result is always darker.
+  Multiply,
+  /// Multiply the complements of the colors, the result is always lighter.
+  Screen,
+  /// `Multiply` or `Screen` the colors, depending on the backdrop color.
+  Overlay,
+  /// Keep the darker of the colors.
+  Darken,
+  /// Keep the lighter of the colors.
+  Lighten,
+  /// Subtract the darker of the colors from the lighter.
+  Difference,
+  /// Add the colors, the result is clamped to white.
+  Plus,
+}
+
+/// How the pixels of an image are sampled when it's scaled.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FilterQuality {
+  /// Use the nearest pixel, keep the hard edges of the pixel art.
+  Nearest,
+  /// Interpolate the nearby pixels, make the scaled photos smooth.
+  #[default]
+  Linear,
+}
+
 /// Explain the method for rendering shapes and paths, including filling or
 /// stroking them.
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -134,12 +269,17 @@ pub enum PathStyle {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PaintCommand {
   Path(PathCommand),
+  Shadow(ShadowCommand),
+  Layer(LayerCommand),
+  Mask(MaskCommand),
   PopClip,
   /// A Bundle of paint commands that can be assumed as a single command, that
   /// means the backend can cache it.
   Bundle {
     transform: Transform,
     opacity: f32,
+    /// How to blend the bundle, as a whole, with the backdrop.
+    blend_mode: BlendMode,
     /// the bounds of the bundle commands. This is the union of all paint
     /// command
     bounds: Rect,
@@ -156,6 +296,8 @@ struct PainterState {
   style: PathStyle,
   transform: Transform,
   opacity: f32,
+  blend_mode: BlendMode,
+  filter_quality: FilterQuality,
   clip_cnt: usize,
   /// The visible boundary of the painter in visual axis, not care about the
   /// transform.
@@ -172,6 +314,8 @@ impl PainterState {
       transform: Transform::identity(),
       clip_cnt: 0,
       opacity: 1.,
+      blend_mode: BlendMode::Normal,
+      filter_quality: FilterQuality::Linear,
       style: PathStyle::Fill,
     }
   }
@@ -186,6 +330,7 @@ impl Painter {
       init_state,
       commands: vec![],
       path_builder: Path::builder(),
+      layers: vec![],
     }
   }
 
@@ -217,6 +362,30 @@ impl Painter {
       .map_or_else(Rect::zero, |t| t.outer_transformed_rect(&s.bounds))
   }
 
+  /// The count of the commands painted, use it as the `start` of
+  /// [`Painter::commands_bounds_from`].
+  #[inline]
+  pub fn commands_count(&self) -> usize { self.commands.len() }
+
+  /// The union bounds of the pixels painted by the commands from the `start`
+  /// one, `None` if they paint nothing.
+  pub fn commands_bounds_from(&self, start: usize) -> Option<Rect> {
+    self
+      .commands
+      .get(start..)
+      .and_then(union_paint_bounds)
+  }
+
+  /// Return if there is an open layer whose content starts from the `start`
+  /// command or after it. The commands from its start are replaced by the
+  /// layer when it is closed.
+  pub fn has_open_layer_from(&self, start: usize) -> bool {
+    self
+      .layers
+      .last()
+      .is_some_and(|l| l.cmd_start >= start)
+  }
+
   #[inline]
   pub fn finish(&mut self) -> PainterResult {
     self.fill_all_pop_clips();
@@ -244,8 +413,16 @@ impl Painter {
   /// nothing.
   #[inline]
   pub fn restore(&mut self) {
-    let clip_cnt = self.current_state().clip_cnt;
+    let mut clip_cnt = self.current_state().clip_cnt;
+    let depth = self.state_stack.len();
     self.state_stack.pop();
+    while self
+      .layers
+      .last()
+      .is_some_and(|l| l.depth >= depth)
+    {
+      clip_cnt = self.close_layer(clip_cnt);
+    }
     self.push_n_pop_cmd(clip_cnt - self.current_state().clip_cnt);
   }
 
@@ -299,6 +476,26 @@ impl Painter {
     self
   }
 
+  /// Return the blend mode used to paint.
+  pub fn blend_mode(&self) -> BlendMode { self.current_state().blend_mode }
+
+  /// Set the blend mode used to paint, the paths and bundles painted after it
+  /// are blended with the backdrop in this mode.
+  pub fn set_blend_mode(&mut self, mode: BlendMode) -> &mut Self {
+    self.current_state_mut().blend_mode = mode;
+    self
+  }
+
+  /// Return how the image brushes are sampled.
+  pub fn filter_quality(&self) -> FilterQuality { self.current_state().filter_quality }
+
+  /// Set how the image brushes painted after it are sampled when they're
+  /// scaled.
+  pub fn set_filter_quality(&mut self, quality: FilterQuality) -> &mut Self {
+    self.current_state_mut().filter_quality = quality;
+    self
+  }
+
   #[inline]
   pub fn set_strokes(&mut self, strokes: StrokeOptions) -> &mut Self {
     self.current_state_mut().stroke_options = strokes;
@@ -529,6 +726,93 @@ impl Painter {
     self
   }
 
+  /// Paint the shadow of the rounded rectangle, the `spread` of the shadow
+  /// expands both the rectangle and its radius.
+  pub fn draw_box_shadow(&mut self, rect: &Rect, radius: &Radius, shadow: &Shadow) -> &mut Self {
+    let spread = shadow.spread;
+    let rect = rect.inflate(spread, spread);
+    if rect.is_empty() {
+      return self;
+    }
+    // Only the round corners are expanded, so a sharp corner keeps sharp.
+    let expand = |r: f32| if r > 0. { (r + spread).max(0.) } else { 0. };
+    let radius = Radius::new(
+      expand(radius.top_left),
+      expand(radius.top_right),
+      expand(radius.bottom_left),
+      expand(radius.bottom_right),
+    );
+    let path =
+      if radius == Radius::all(0.) { Path::rect(&rect) } else { Path::rect_round(&rect, &radius) };
+    self.inner_draw_shadow(path.into(), Transform::identity(), shadow)
+  }
+
+  /// Paint the shadow of the `path`. The `spread` of the shadow is
+  /// approximated by scaling the path around the center of its bounds.
+  pub fn draw_path_shadow(&mut self, path: PaintPath, shadow: &Shadow) -> &mut Self {
+    let bounds = path.bounds(None);
+    let spread = shadow.spread;
+    let scale_x = (bounds.width() + 2. * spread) / bounds.width();
+    let scale_y = (bounds.height() + 2. * spread) / bounds.height();
+    if !(scale_x > 0. && scale_y > 0.) {
+      return self;
+    }
+    let center = bounds.center().to_vector();
+    let ts = Transform::translation(-center.x, -center.y)
+      .then_scale(scale_x, scale_y)
+      .then_translate(center);
+    self.inner_draw_shadow(path, ts, shadow)
+  }
+
+  /// Push a layer that applies a gaussian blur with the `blur_radius` to the
+  /// `target`. The commands painted after it are the content of the layer,
+  /// until the current state is restored.
+  ///
+  /// - **path** - The area of the layer, the backdrop is only blurred inside
+  ///   it. The content of the layer is not clipped by it.
+  pub fn blur_layer(&mut self, path: PaintPath, blur_radius: f32, target: BlurTarget) -> &mut Self {
+    invisible_return!(self);
+    let layer = LayerCommand {
+      path,
+      paint_bounds: Rect::zero(),
+      transform: *self.transform(),
+      blur_radius,
+      target,
+      cmds: Box::new([]),
+    };
+    self.layers.push(OpenLayer {
+      layer: OpenLayerKind::Blur(layer),
+      depth: self.state_stack.len(),
+      cmd_start: self.commands.len(),
+      clip_cnt: self.current_state().clip_cnt,
+    });
+    self
+  }
+
+  /// Push a layer that masks its content by the pixels the `mask` paints in the
+  /// `mode`. The commands painted after it are the content of the layer, until
+  /// the current state is restored.
+  ///
+  /// - **mask** - The commands that paint the mask, usually the result of
+  ///   another painter. The current transform is applied to them.
+  pub fn mask_layer(&mut self, mask: Box<[PaintCommand]>, mode: MaskMode) -> &mut Self {
+    invisible_return!(self);
+    let transform = *self.transform();
+    let mask = mask
+      .into_vec()
+      .into_iter()
+      .map(|cmd| cmd.transform_and_fade(&transform, 1.))
+      .collect();
+    let layer = MaskCommand { mode, paint_bounds: Rect::zero(), mask, cmds: Box::new([]) };
+    self.layers.push(OpenLayer {
+      layer: OpenLayerKind::Mask(layer),
+      depth: self.state_stack.len(),
+      cmd_start: self.commands.len(),
+      clip_cnt: self.current_state().clip_cnt,
+    });
+    self
+  }
+
   /// Draws a bundle of paint commands that can be treated as a single command.
   /// This allows the backend to cache it.
   ///
@@ -543,14 +827,38 @@ impl Painter {
     invisible_return!(self);
     let transform = *self.transform();
     let opacity = self.alpha();
-    let cmd = PaintCommand::Bundle { transform, opacity, bounds, cmds };
+    let blend_mode = self.blend_mode();
+    let cmd = PaintCommand::Bundle { transform, opacity, blend_mode, bounds, cmds };
+    self.commands.push(cmd);
+    self
+  }
+
+  /// Push a bundle even if it's invisible now, its opacity may be changed
+  /// later.
+  pub(crate) fn push_bundle(
+    &mut self, transform: Transform, opacity: f32, bounds: Rect,
+    cmds: Resource<Box<[PaintCommand]>>,
+  ) -> &mut Self {
+    let blend_mode = self.blend_mode();
+    let cmd = PaintCommand::Bundle { transform, opacity, blend_mode, bounds, cmds };
     self.commands.push(cmd);
     self
   }
 
-  pub fn draw_svg(&mut self, svg: &Svg) -> &mut Self {
+  pub fn draw_svg(&mut self, svg: &Svg) -> &mut Self { self.draw_svg_with_colors(svg, &[]) }
+
+  /// Draw the SVG with the colors of its color slots, see
+  /// [`Svg::commands_with_colors`].
+  pub fn draw_svg_with_colors(&mut self, svg: &Svg, slot_colors: &[Color]) -> &mut Self {
+    self.draw_svg_frame(svg, slot_colors, &[])
+  }
+
+  /// Draw a frame of the animations of the SVG, see [`Svg::frame_commands`].
+  pub fn draw_svg_frame(
+    &mut self, svg: &Svg, slot_colors: &[Color], values: &[Option<SvgAnimValue>],
+  ) -> &mut Self {
     invisible_return!(self);
-    let commands = svg.commands(self.fill_brush(), self.stroke_brush());
+    let commands = svg.frame_commands(self.fill_brush(), self.stroke_brush(), slot_colors, values);
 
     // For a large number of path commands (more than 16), bundle them
     // together as a single resource. This allows the backend to cache
@@ -558,27 +866,15 @@ impl Painter {
     // For a small number of path commands (less than 16), store them
     // individually as multiple resources. This means the backend doesn't
     // need to perform a single draw operation for an SVG.
-    if commands.len() <= 16 {
+    //
+    // An SVG painted with a blend mode is always bundled, so it's blended with
+    // the backdrop as a whole.
+    if commands.len() <= 16 && self.blend_mode() == BlendMode::Normal {
       let transform = *self.transform();
       let alpha = self.alpha();
 
       for cmd in commands.iter() {
-        let cmd = match cmd.clone() {
-          PaintCommand::Path(mut path) => {
-            path.transform(&transform);
-            if let PaintPathAction::Paint { ref mut brush, .. } = path.action {
-              brush.apply_alpha(alpha);
-            }
-            PaintCommand::Path(path)
-          }
-          PaintCommand::PopClip => PaintCommand::PopClip,
-          PaintCommand::Bundle { transform: b_ts, opacity, bounds, cmds } => PaintCommand::Bundle {
-            transform: transform.then(&b_ts),
-            opacity: alpha * opacity,
-            bounds,
-            cmds,
-          },
-        };
+        let cmd = cmd.clone().transform_and_fade(&transform, alpha);
         self.commands.push(cmd);
       }
     } else {
@@ -589,6 +885,14 @@ impl Painter {
     self
   }
 
+  /// Draw the `frame` of the Lottie animation, the frames out of
+  /// [`Lottie::frames`] paint nothing.
+  pub fn draw_lottie(&mut self, lottie: &Lottie, frame: f32) -> &mut Self {
+    invisible_return!(self);
+    lottie.paint(self, frame);
+    self
+  }
+
   /// Draw the image
   ///
   /// if src_rect is None then will draw the whole image fitted into dst_rect,
@@ -623,6 +927,22 @@ impl Painter {
     self
   }
 
+  /// Draw the nine patch image stretched to `dst_rect`, only its center and
+  /// edges are stretched.
+  pub fn draw_nine_patch(&mut self, patch: &NinePatch, dst_rect: &Rect) -> &mut Self {
+    for (src, dst) in patch.slices(dst_rect) {
+      let mut painter = self.save_guard();
+      painter
+        .translate(dst.min_x(), dst.min_y())
+        .scale(dst.width() / src.width(), dst.height() / src.height())
+        .translate(-src.min_x(), -src.min_y())
+        .rect(&src)
+        .set_fill_brush(patch.image.clone())
+        .fill();
+    }
+    self
+  }
+
   pub fn draw_glyph(&mut self, g: &Glyph, font_size: f32, font_db: &FontDB) -> &mut Self {
     let Some(face) = font_db.try_get_face_data(g.face_id) else { return self };
 
@@ -680,19 +1000,92 @@ impl Painter {
     let Some(glyphs) = visual_glyphs.glyphs_in_bounds(&paint_rect) else {
       return self;
     };
+    let decorations = visual_glyphs.decorations_in_bounds(&paint_rect);
 
     if !paint_rect.contains_rect(&visual_rect) {
       self.clip(Path::rect(&paint_rect).into());
     }
     self.translate(visual_rect.origin.x, visual_rect.origin.y);
 
+    // The lines through the text are painted over the glyphs, and the other
+    // lines are painted under the glyphs.
+    let under_lines = TextDecoration::UNDERLINE | TextDecoration::OVERLINE;
+    self.draw_decorations(&decorations, under_lines, font_db);
     for g in glyphs {
-      self.draw_glyph(&g, visual_glyphs.font_size(), font_db);
+      let span = visual_glyphs.paint_span(g.cluster);
+      let font_size = span.map_or(visual_glyphs.font_size(), |s| s.font_size);
+      if let Some(brush) = span.and_then(|s| s.brush.clone()) {
+        let mut painter = self.save_guard();
+        painter
+          .set_fill_brush(brush.clone())
+          .set_stroke_brush(brush)
+          .draw_glyph(&g, font_size, font_db);
+      } else {
+        self.draw_glyph(&g, font_size, font_db);
+      }
     }
+    self.draw_decorations(&decorations, TextDecoration::THROUGHLINE, font_db);
 
     self
   }
 
+  fn draw_decorations(
+    &mut self, pieces: &[DecorationPiece], lines: TextDecoration, font_db: &FontDB,
+  ) {
+    for p in pieces {
+      let span = p.span;
+      let style = &span.decoration;
+      let decoration = style.decoration & lines;
+      if decoration.is_empty() {
+        continue;
+      }
+      let Some(face) = font_db.try_get_face_data(p.face_id) else { continue };
+
+      // The metrics are in font units and the y-axis goes up from the baseline,
+      // the position of a line is the top of it.
+      let unit = face.units_per_em() as f32;
+      let scale = span.font_size / unit;
+      let underline = face.underline_metrics();
+      let font_thickness = underline.map_or(unit / 20., |m| m.thickness as f32);
+      let thickness = style.thickness.unwrap_or(font_thickness * scale);
+      let line_top = |line: TextDecoration| {
+        let pos = if line == TextDecoration::UNDERLINE {
+          underline.map_or(-unit / 10., |m| m.position as f32)
+        } else if line == TextDecoration::OVERLINE {
+          face.ascender() as f32
+        } else {
+          let x_height = match face.x_height() {
+            0 => unit / 2.,
+            h => h as f32,
+          };
+          face
+            .strikeout_metrics()
+            .map_or(x_height / 2. + font_thickness / 2., |m| m.position as f32)
+        };
+        p.baseline - pos * scale
+      };
+
+      let brush = style
+        .decoration_color
+        .clone()
+        .or_else(|| span.brush.clone())
+        .unwrap_or_else(|| match self.style() {
+          PathStyle::Fill => self.fill_brush().clone(),
+          PathStyle::Stroke => self.stroke_brush().clone(),
+        });
+      let mut painter = self.save_guard();
+      painter
+        .set_stroke_brush(brush)
+        .set_line_width(thickness)
+        .set_line_cap(LineCap::Butt);
+      for line in decoration.iter() {
+        let y = line_top(line) + thickness / 2.;
+        let path = decoration_line(p.x.clone(), y, thickness, style.line_style);
+        painter.stroke_path(path.into());
+      }
+    }
+  }
+
   fn inner_draw_path(&mut self, path: PaintPath, path_style: PathStyle) -> &mut Self {
     invisible_return!(self);
     let line_width = matches!(path_style, PathStyle::Stroke).then(|| self.line_width());
@@ -709,21 +1102,58 @@ impl Painter {
       PathStyle::Stroke => self.stroke_brush().clone(),
     };
 
+    if let (PathStyle::Fill, Brush::NinePatch(patch)) = (path_style, &brush) {
+      let bounds = path.bounds(None);
+      self
+        .save_guard()
+        .clip(path)
+        .draw_nine_patch(patch, &bounds);
+      return self;
+    }
+
     if brush.is_visible() {
       let mut brush = CommandBrush::from(brush);
+      if let CommandBrush::Image { filter_quality, .. } = &mut brush {
+        *filter_quality = self.filter_quality();
+      }
       let painting_style = match path_style {
         PathStyle::Fill => PaintingStyle::Fill,
         PathStyle::Stroke => PaintingStyle::Stroke(self.stroke_options().clone()),
       };
       brush.apply_alpha(self.alpha());
       let ts = *self.transform();
-      let action = PaintPathAction::Paint { brush, painting_style };
+      let blend_mode = self.blend_mode();
+      let action = PaintPathAction::Paint { brush, painting_style, blend_mode };
       let cmd = PathCommand::new(path, action, ts);
       self.commands.push(PaintCommand::Path(cmd));
     }
 
     self
   }
+
+  fn inner_draw_shadow(
+    &mut self, path: PaintPath, path_ts: Transform, shadow: &Shadow,
+  ) -> &mut Self {
+    invisible_return!(self);
+    let color = shadow.color.apply_alpha(self.alpha());
+    if color.alpha == 0 || path.bounds(None).is_empty() {
+      return self;
+    }
+
+    let transform = path_ts
+      .then_translate(shadow.offset)
+      .then(self.transform());
+    let cmd = ShadowCommand::new(path, transform, color, shadow.blur_radius);
+    if locatable_bounds(&cmd.paint_bounds)
+      && self
+        .current_state()
+        .bounds
+        .intersects(&cmd.paint_bounds)
+    {
+      self.commands.push(PaintCommand::Shadow(cmd));
+    }
+    self
+  }
 }
 
 impl PaintingStyle {
@@ -766,7 +1196,10 @@ impl Painter {
   }
 
   fn fill_all_pop_clips(&mut self) {
-    let clip_cnt = self.current_state().clip_cnt;
+    let mut clip_cnt = self.current_state().clip_cnt;
+    while !self.layers.is_empty() {
+      clip_cnt = self.close_layer(clip_cnt);
+    }
     self
       .state_stack
       .iter_mut()
@@ -774,6 +1207,53 @@ impl Painter {
     self.push_n_pop_cmd(clip_cnt);
   }
 
+  /// Close the last open layer, the clips pushed after it are popped in the
+  /// layer. Return the count of the clips after the layer closed.
+  fn close_layer(&mut self, clip_cnt: usize) -> usize {
+    let OpenLayer { layer, cmd_start, clip_cnt: layer_clip_cnt, .. } = self
+      .layers
+      .pop()
+      .expect("Must have an open layer.");
+    self.push_n_pop_cmd(clip_cnt - layer_clip_cnt);
+    let cmds: Box<[PaintCommand]> = self.commands.drain(cmd_start..).collect();
+    let content = union_paint_bounds(&cmds);
+
+    let cmd = match layer {
+      OpenLayerKind::Blur(mut layer) => {
+        let bounds = match layer.target {
+          BlurTarget::Content => content.map(|bounds| {
+            let extent = layer.blur_sigma(&layer.transform) * 3.;
+            bounds.inflate(extent, extent)
+          }),
+          BlurTarget::Backdrop => {
+            let area = layer
+              .transform
+              .outer_transformed_rect(&layer.path.bounds(None));
+            Some(content.map_or(area, |c| c.union(&area)))
+          }
+        };
+        bounds.filter(locatable_bounds).map(|bounds| {
+          layer.paint_bounds = bounds;
+          layer.cmds = cmds;
+          PaintCommand::Layer(layer)
+        })
+      }
+      OpenLayerKind::Mask(mut mask) => content
+        .zip(union_paint_bounds(&mask.mask))
+        .and_then(|(content, mask)| content.intersection(&mask))
+        .filter(locatable_bounds)
+        .map(|bounds| {
+          mask.paint_bounds = bounds;
+          mask.cmds = cmds;
+          PaintCommand::Mask(mask)
+        }),
+    };
+    if let Some(cmd) = cmd {
+      self.commands.push(cmd);
+    }
+    layer_clip_cnt
+  }
+
   fn is_visible_canvas(&self) -> bool {
     let t = self.current_state().transform;
     self.alpha() > 0.
@@ -791,9 +1271,12 @@ impl From<Brush> for CommandBrush {
   fn from(brush: Brush) -> Self {
     match brush {
       Brush::Color(color) => CommandBrush::Color(color),
-      Brush::Image(img) => CommandBrush::Image { img, opacity: 1. },
+      Brush::Image(img) | Brush::NinePatch(NinePatch { image: img, .. }) => {
+        CommandBrush::Image { img, opacity: 1., filter_quality: FilterQuality::Linear }
+      }
       Brush::RadialGradient(radial_gradient) => CommandBrush::Radial(radial_gradient),
       Brush::LinearGradient(linear_gradient) => CommandBrush::Linear(linear_gradient),
+      Brush::SweepGradient(sweep_gradient) => CommandBrush::Sweep(sweep_gradient),
     }
   }
 }
@@ -879,13 +1362,130 @@ impl PathCommand {
   }
 }
 
+impl PaintCommand {
+  /// The bounds of the pixels the command paints, `None` if it paints nothing.
+  pub(crate) fn paint_bounds(&self) -> Option<Rect> {
+    match self {
+      PaintCommand::Path(PathCommand {
+        paint_bounds,
+        action: PaintPathAction::Paint { .. },
+        ..
+      })
+      | PaintCommand::Shadow(ShadowCommand { paint_bounds, .. })
+      | PaintCommand::Layer(LayerCommand { paint_bounds, .. })
+      | PaintCommand::Mask(MaskCommand { paint_bounds, .. }) => Some(*paint_bounds),
+      PaintCommand::Bundle { transform, bounds, .. } => {
+        Some(transform.outer_transformed_rect(bounds))
+      }
+      PaintCommand::Path(_) | PaintCommand::PopClip => None,
+    }
+  }
+
+  /// Apply the `transform` and the `alpha` to the command.
+  fn transform_and_fade(self, transform: &Transform, alpha: f32) -> Self {
+    match self {
+      PaintCommand::Path(mut path) => {
+        path.transform(transform);
+        if let PaintPathAction::Paint { ref mut brush, .. } = path.action {
+          brush.apply_alpha(alpha);
+        }
+        PaintCommand::Path(path)
+      }
+      PaintCommand::Shadow(mut shadow) => {
+        shadow.transform(transform);
+        shadow.color = shadow.color.apply_alpha(alpha);
+        PaintCommand::Shadow(shadow)
+      }
+      PaintCommand::Layer(mut layer) => {
+        layer.transform = layer.transform.then(transform);
+        layer.paint_bounds = transform.outer_transformed_rect(&layer.paint_bounds);
+        layer.cmds = layer
+          .cmds
+          .into_vec()
+          .into_iter()
+          .map(|cmd| cmd.transform_and_fade(transform, alpha))
+          .collect();
+        PaintCommand::Layer(layer)
+      }
+      PaintCommand::Mask(mut mask) => {
+        mask.paint_bounds = transform.outer_transformed_rect(&mask.paint_bounds);
+        mask.mask = mask
+          .mask
+          .into_vec()
+          .into_iter()
+          .map(|cmd| cmd.transform_and_fade(transform, 1.))
+          .collect();
+        mask.cmds = mask
+          .cmds
+          .into_vec()
+          .into_iter()
+          .map(|cmd| cmd.transform_and_fade(transform, alpha))
+          .collect();
+        PaintCommand::Mask(mask)
+      }
+      PaintCommand::PopClip => PaintCommand::PopClip,
+      PaintCommand::Bundle { transform: b_ts, opacity, blend_mode, bounds, cmds } => {
+        PaintCommand::Bundle {
+          transform: b_ts.then(transform),
+          opacity: alpha * opacity,
+          blend_mode,
+          bounds,
+          cmds,
+        }
+      }
+    }
+  }
+}
+
+impl ShadowCommand {
+  pub fn new(path: PaintPath, transform: Transform, color: Color, blur_radius: f32) -> Self {
+    let mut cmd = Self { path, paint_bounds: Rect::zero(), transform, color, blur_radius };
+    cmd.update_paint_bounds();
+    cmd
+  }
+
+  /// How far the blurred shadow extends outside the path, three times the
+  /// standard deviation of the gaussian blur.
+  pub fn blur_extent(&self) -> f32 { self.blur_radius.max(0.) * 1.5 }
+
+  /// The standard deviation of the gaussian blur after the `matrix` applied,
+  /// the `matrix` should be the transform from the path to the device.
+  pub fn blur_sigma(&self, matrix: &Transform) -> f32 { blur_sigma(self.blur_radius, matrix) }
+
+  pub fn transform(&mut self, transform: &Transform) {
+    self.transform = self.transform.then(transform);
+    self.update_paint_bounds();
+  }
+
+  fn update_paint_bounds(&mut self) {
+    let extent = self.blur_sigma(&self.transform) * 3.;
+    let bounds = self
+      .transform
+      .outer_transformed_rect(&self.path.bounds(None));
+    self.paint_bounds = bounds.inflate(extent, extent);
+  }
+}
+
+impl LayerCommand {
+  /// The standard deviation of the gaussian blur after the `matrix` applied,
+  /// the `matrix` should be the transform from the path to the device.
+  pub fn blur_sigma(&self, matrix: &Transform) -> f32 { blur_sigma(self.blur_radius, matrix) }
+}
+
+fn blur_sigma(blur_radius: f32, matrix: &Transform) -> f32 {
+  let Transform { m11, m12, m21, m22, .. } = matrix;
+  let scale = (m11.abs() + m12.abs()).max(m21.abs() + m22.abs());
+  blur_radius.max(0.) / 2. * scale
+}
+
 impl CommandBrush {
   pub fn apply_alpha(&mut self, alpha: f32) -> &mut Self {
     match self {
       CommandBrush::Color(color) => *color = color.apply_alpha(alpha),
       CommandBrush::Image { opacity, .. } => *opacity *= alpha,
       CommandBrush::Radial(RadialGradient { stops, .. })
-      | CommandBrush::Linear(LinearGradient { stops, .. }) => stops
+      | CommandBrush::Linear(LinearGradient { stops, .. })
+      | CommandBrush::Sweep(SweepGradient { stops, .. }) => stops
         .iter_mut()
         .for_each(|s| s.color = s.color.apply_alpha(alpha)),
     }
@@ -903,6 +1503,46 @@ impl From<usvg::SpreadMethod> for SpreadMethod {
   }
 }
 
+/// The union of the bounds of the pixels the commands paint.
+fn union_paint_bounds(cmds: &[PaintCommand]) -> Option<Rect> {
+  cmds
+    .iter()
+    .filter_map(PaintCommand::paint_bounds)
+    .reduce(|a, b| a.union(&b))
+}
+
+/// The path of a decoration line at `y` across the `x` range.
+fn decoration_line(x: Range<f32>, y: f32, thickness: f32, style: DecorationLineStyle) -> Path {
+  let mut builder = Path::builder();
+  builder.begin_path(Point::new(x.start, y));
+  match style {
+    DecorationLineStyle::Solid | DecorationLineStyle::Dashed => {
+      builder.line_to(Point::new(x.end, y));
+    }
+    DecorationLineStyle::Wavy => {
+      // Every half wave is a quadratic curve, its peak is one thickness away
+      // from the line.
+      let half_wave = thickness.max(1.) * 2.;
+      let mut x0 = x.start;
+      let mut dir = -1.;
+      while x0 < x.end {
+        let x1 = (x0 + half_wave).min(x.end);
+        let ctrl = Point::new((x0 + x1) / 2., y + dir * half_wave);
+        builder.quadratic_curve_to(ctrl, Point::new(x1, y));
+        x0 = x1;
+        dir = -dir;
+      }
+    }
+  }
+  builder.end_path(false);
+  let path = builder.build();
+  if style == DecorationLineStyle::Dashed {
+    path.dash(&[thickness * 3., thickness * 2.], 0.)
+  } else {
+    path
+  }
+}
+
 // bounds that has a limited location and size
 fn locatable_bounds(bounds: &Rect) -> bool {
   bounds.origin.is_finite() && !bounds.width().is_nan() && !bounds.height().is_nan()
@@ -922,6 +1562,7 @@ mod test {
   use ribir_geom::rect;
 
   use super::*;
+  use crate::SvgColorSlot;
 
   fn painter() -> Painter { Painter::new(Rect::from_size(Size::new(512., 512.))) }
 
@@ -966,6 +1607,70 @@ mod test {
     assert_eq!(painter.current_state().clip_cnt, 0);
   }
 
+  #[test]
+  fn layer_collect_content_until_restore() {
+    let mut painter = painter();
+    painter
+      .save()
+      .clip(Path::rect(&rect(0., 0., 100., 100.)).into())
+      .blur_layer(Path::rect(&rect(0., 0., 50., 50.)).into(), 4., BlurTarget::Content)
+      .rect(&rect(10., 10., 10., 10.))
+      .fill()
+      .clip(Path::rect(&rect(0., 0., 15., 15.)).into())
+      .rect(&rect(0., 0., 10., 10.))
+      .fill()
+      .restore();
+    painter.rect(&rect(0., 0., 10., 10.)).fill();
+    let commands = painter.finish();
+
+    assert_eq!(commands.len(), 4);
+    assert!(matches!(
+      commands[0],
+      PaintCommand::Path(PathCommand { action: PaintPathAction::Clip, .. })
+    ));
+    let PaintCommand::Layer(layer) = &commands[1] else { panic!("should be a layer") };
+    assert!(matches!(commands[2], PaintCommand::PopClip));
+    assert!(matches!(commands[3], PaintCommand::Path(_)));
+
+    assert_eq!(layer.cmds.len(), 4);
+    assert!(matches!(layer.cmds[3], PaintCommand::PopClip));
+    // The content bounds inflated by three times of the sigma.
+    assert_eq!(layer.paint_bounds, rect(-6., -6., 32., 32.));
+  }
+
+  #[test]
+  fn mask_layer_bounds() {
+    let mut mask = painter();
+    mask.rect(&rect(0., 0., 20., 20.)).fill();
+    let mask = mask.finish().to_vec().into_boxed_slice();
+
+    let mut painter = painter();
+    painter
+      .save()
+      .translate(10., 10.)
+      .mask_layer(mask.clone(), MaskMode::Luminance)
+      .rect(&rect(5., 5., 30., 30.))
+      .fill()
+      .restore();
+    let commands = painter.finish();
+    assert_eq!(commands.len(), 1);
+    let PaintCommand::Mask(layer) = &commands[0] else { panic!("should be a mask") };
+    assert_eq!(layer.mode, MaskMode::Luminance);
+    assert_eq!(layer.cmds.len(), 1);
+    // The mask is translated with the content.
+    assert_eq!(layer.paint_bounds, rect(15., 15., 15., 15.));
+
+    // Nothing is visible outside the mask.
+    let mut painter = self::painter();
+    painter
+      .save()
+      .mask_layer(mask, MaskMode::Alpha)
+      .rect(&rect(30., 30., 10., 10.))
+      .fill()
+      .restore();
+    assert_eq!(painter.finish().len(), 0);
+  }
+
   #[test]
   fn filter_invalid_clip() {
     let mut painter = painter();
@@ -1003,6 +1708,117 @@ mod test {
     painter.draw_svg(&svg);
   }
 
+  #[test]
+  fn svg_group_opacity_only_apply_to_children() {
+    let svg = Svg::parse_from_bytes(
+      br#"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="8">
+        <g opacity="0.5"><rect width="4" height="4" fill="red"/></g>
+        <g><rect x="4" width="4" height="4" fill="blue"/></g>
+      </svg>"#,
+      false,
+      false,
+    )
+    .unwrap();
+
+    let cmds = svg.commands(&Color::BLACK.into(), &Color::BLACK.into());
+    let alphas: Vec<_> = cmds
+      .iter()
+      .filter_map(|cmd| match cmd {
+        PaintCommand::Path(PathCommand {
+          action: PaintPathAction::Paint { brush: CommandBrush::Color(c), .. },
+          ..
+        }) => Some(c.alpha),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(alphas, [128, 255]);
+  }
+
+  #[test]
+  fn svg_color_slots() {
+    let svg = Svg::parse_from_bytes(
+      br#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+        <rect width="10" height="10" fill="var(--primary)" />
+        <rect x="10" width="10" height="10" fill-opacity="0.5"
+          style="fill: var(--accent, rgb(255, 0, 0))" />
+      </svg>"#,
+      false,
+      false,
+    )
+    .unwrap();
+    assert_eq!(
+      svg.color_slots(),
+      [
+        SvgColorSlot { name: "primary".into(), fallback: None },
+        SvgColorSlot { name: "accent".into(), fallback: Some(Color::RED) }
+      ]
+    );
+
+    let colors = |cmds: &[PaintCommand]| -> Vec<Color> {
+      cmds
+        .iter()
+        .filter_map(|c| match c {
+          PaintCommand::Path(PathCommand {
+            action: PaintPathAction::Paint { brush: CommandBrush::Color(c), .. },
+            ..
+          }) => Some(*c),
+          _ => None,
+        })
+        .collect()
+    };
+    let brush: Brush = Color::BLACK.into();
+    let cmds = svg.commands(&brush, &brush);
+    assert_eq!(colors(&cmds), [Color::BLACK, Color::RED.with_alpha(0.5)]);
+
+    let cmds = svg.commands_with_colors(&brush, &brush, &[Color::GREEN, Color::BLUE]);
+    assert_eq!(colors(&cmds), [Color::GREEN, Color::BLUE.with_alpha(0.5)]);
+  }
+
+  #[test]
+  fn svg_color_slots_not_collide() {
+    let svg = Svg::parse_from_bytes(
+      br##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+        <desc>The description mentions var(--desc).</desc>
+        <rect width="10" height="10" fill="#191C00" />
+        <rect x="10" width="10" height="10" fill="var(--primary)" />
+      </svg>"##,
+      false,
+      false,
+    )
+    .unwrap();
+    assert_eq!(svg.color_slots(), [SvgColorSlot { name: "primary".into(), fallback: None }]);
+
+    let brush: Brush = Color::BLACK.into();
+    let cmds = svg.commands_with_colors(&brush, &brush, &[Color::GREEN]);
+    let colors: Vec<_> = cmds
+      .iter()
+      .filter_map(|c| match c {
+        PaintCommand::Path(PathCommand {
+          action: PaintPathAction::Paint { brush: CommandBrush::Color(c), .. },
+          ..
+        }) => Some(*c),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(colors, [Color::from_u32(0x191C00FF), Color::GREEN]);
+  }
+
+  #[test]
+  fn transform_inner_bundle() {
+    let bundle = PaintCommand::Bundle {
+      transform: Transform::scale(2., 2.),
+      opacity: 1.,
+      blend_mode: BlendMode::default(),
+      bounds: rect(0., 0., 10., 10.),
+      cmds: Resource::new(Box::new([])),
+    };
+    let ts = Transform::translation(10., 0.);
+    let PaintCommand::Bundle { transform, .. } = bundle.transform_and_fade(&ts, 1.) else {
+      unreachable!();
+    };
+    assert_eq!(transform, Transform::scale(2., 2.).then(&ts));
+  }
+
   #[test]
   fn fix_incorrect_bounds_axis() {
     let mut painter = painter();
@@ -2,14 +2,23 @@ use std::ops::Range;
 
 use lyon_algorithms::{
   geom::euclid::SideOffsets2D,
+  hit_test::hit_test_path,
   measure::{PathMeasurements, SampleType},
-  path::{Event, Path as LyonPath},
+  path::{Event, Path as LyonPath, iterator::PathIterator},
 };
 use ribir_geom::{Point, Rect, Transform};
 use serde::{Deserialize, Serialize};
 
 use crate::path_builder::PathBuilder;
 
+mod boolean;
+pub use boolean::PathOp;
+
+/// The maximum distance between the curves and the line segments that
+/// approximate them, when the path is flattened to test the points or to
+/// combine with another path.
+const FLATTEN_TOLERANCE: f32 = 0.01;
+
 /// Path widget describe a shape, build the shape from [`Builder`]!
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Path {
@@ -44,6 +53,19 @@ pub struct StrokeOptions {
   ///
   /// Default: Miter
   pub line_join: LineJoin,
+
+  /// The lengths of the alternating dashes and gaps, an odd number of lengths
+  /// is repeated to yield an even number. An empty array draws a solid line.
+  ///
+  /// Default: empty
+  #[serde(default)]
+  pub dash_array: Vec<f32>,
+
+  /// The distance into the dash pattern at which the dashing starts.
+  ///
+  /// Default: 0.0
+  #[serde(default)]
+  pub dash_offset: f32,
 }
 
 /// Draws at the beginning and end of an open path contour.
@@ -71,6 +93,18 @@ pub enum LineJoin {
   Bevel,
 }
 
+/// The rule to determine if a point is inside a path.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, Hash)]
+pub enum FillRule {
+  /// A point is inside if the path winds around it a nonzero number of times,
+  /// it's the rule the painter fills the paths with.
+  #[default]
+  NonZero,
+  /// A point is inside if a ray from it crosses the path an odd number of
+  /// times.
+  EvenOdd,
+}
+
 /// A path segment.
 #[derive(Copy, Clone, PartialEq, Deserialize, Serialize, Debug)]
 pub enum PathSegment {
@@ -160,6 +194,138 @@ impl Path {
     PathSampler { path: self.lyon_path.clone(), measurements }
   }
 
+  /// Returns a path that only keeps the dashes of this path. The
+  /// `dash_array` is the lengths of the alternating dashes and gaps, and
+  /// every sub-path starts the pattern anew from `dash_offset`.
+  ///
+  /// The path is returned as it is if the pattern draws a solid line, that is
+  /// it's empty, has a negative length or its total length is zero.
+  pub fn dash(&self, dash_array: &[f32], dash_offset: f32) -> Path {
+    let total: f32 = dash_array.iter().sum();
+    if dash_array
+      .iter()
+      .any(|d| *d < 0. || !d.is_finite())
+      || total <= 0.
+    {
+      return self.clone();
+    }
+    // An odd number of lengths is repeated, so the dashes and gaps alternate.
+    let pattern =
+      if dash_array.len() % 2 == 1 { dash_array.repeat(2) } else { dash_array.to_vec() };
+
+    let mut builder = LyonPath::builder();
+    let mut sub_path = LyonPath::builder();
+    for e in self.lyon_path.iter() {
+      sub_path.path_event(e);
+      if let Event::End { .. } = e {
+        let sub_path = std::mem::replace(&mut sub_path, LyonPath::builder()).build();
+        let sampler = Path::from(sub_path).sampler();
+        sampler.dash_into(&pattern, dash_offset, &mut builder);
+      }
+    }
+    builder.build().into()
+  }
+
+  /// Return if the `point` is inside the area the path filled with the
+  /// `fill_rule`, the open sub-paths are treated as closed.
+  pub fn contains(&self, point: Point, fill_rule: FillRule) -> bool {
+    if !self.bounds.contains(point) {
+      return false;
+    }
+    let fill_rule = match fill_rule {
+      FillRule::NonZero => lyon_algorithms::path::FillRule::NonZero,
+      FillRule::EvenOdd => lyon_algorithms::path::FillRule::EvenOdd,
+    };
+    hit_test_path(&point.cast_unit(), self.lyon_path.iter(), fill_rule, FLATTEN_TOLERANCE)
+  }
+
+  /// Return if the `point` is on the stroke of the path painted with the
+  /// `options`.
+  ///
+  /// The joins are tested as round joins, so the tips of the miter joins are
+  /// missed and the points outside the bevel joins may be hit.
+  pub fn stroke_contains(&self, point: Point, options: &StrokeOptions) -> bool {
+    // A hairline is drawn as one pixel wide.
+    let half = if options.width > 0. { options.width / 2. } else { 0.5 };
+    let cap_extent = if options.line_cap == LineCap::Square { half * 2f32.sqrt() } else { half };
+    if !self
+      .bounds
+      .outer_rect(SideOffsets2D::new_all_same(cap_extent))
+      .contains(point)
+    {
+      return false;
+    }
+
+    let dashed =
+      (!options.dash_array.is_empty()).then(|| self.dash(&options.dash_array, options.dash_offset));
+    let path = dashed.as_ref().unwrap_or(self);
+    let p = point.cast_unit();
+    // Test a line of the stroke, `cap_from` and `cap_to` mean the ends are the
+    // open ends of a sub-path that have the caps.
+    type LPoint = lyon_algorithms::geom::Point<f32>;
+    let on_line = |from: LPoint, to: LPoint, cap_from: bool, cap_to: bool| {
+      let v = to - from;
+      let len = v.length();
+      if len == 0. {
+        let d = p - from;
+        return match options.line_cap {
+          LineCap::Butt => false,
+          LineCap::Round => d.length() <= half,
+          LineCap::Square => d.x.abs() <= half && d.y.abs() <= half,
+        };
+      }
+      let along = (p - from).dot(v) / len;
+      let cross = v.cross(p - from).abs() / len;
+      let over = if along < 0. && cap_from {
+        -along
+      } else if along > len && cap_to {
+        along - len
+      } else {
+        // The joins are round.
+        return (from + v * (along / len).clamp(0., 1.) - p).length() <= half;
+      };
+      match options.line_cap {
+        LineCap::Butt => false,
+        LineCap::Round => over.hypot(cross) <= half,
+        LineCap::Square => over <= half && cross <= half,
+      }
+    };
+
+    let mut first_line = None;
+    let mut last_line = None;
+    for e in path.lyon_path.iter().flattened(FLATTEN_TOLERANCE) {
+      match e {
+        Event::Begin { .. } => (first_line, last_line) = (None, None),
+        Event::Line { from, to } => {
+          // The first and the last lines are tested at the end of the sub-path,
+          // when we know if their ends have the caps.
+          if first_line.is_none() {
+            first_line = Some((from, to));
+          } else if let Some((f, t)) = last_line.replace((from, to)) {
+            if on_line(f, t, false, false) {
+              return true;
+            }
+          }
+        }
+        Event::End { last, first, close } => {
+          let open = !close;
+          let hit = match (first_line, last_line) {
+            (None, _) => open && on_line(first, first, true, true),
+            (Some((f, t)), None) => on_line(f, t, open, open),
+            (Some((f1, t1)), Some((f2, t2))) => {
+              on_line(f1, t1, open, false) || on_line(f2, t2, false, open)
+            }
+          };
+          if hit || (close && on_line(last, first, false, false)) {
+            return true;
+          }
+        }
+        _ => {}
+      }
+    }
+    false
+  }
+
   pub fn segments(&self) -> impl Iterator<Item = PathSegment> + '_ {
     self.lyon_path.iter().map(|e| match e {
       Event::Begin { at } => PathSegment::MoveTo(at.cast_unit()),
@@ -205,7 +371,8 @@ impl Path {
     };
 
     let mut stroke_tess = StrokeTessellator::default();
-    let StrokeOptions { width, miter_limit, line_cap, line_join } = options;
+    let StrokeOptions { width, miter_limit, line_cap, line_join, dash_array, dash_offset } =
+      options;
     let cap = match line_cap {
       LineCap::Butt => lyon_tessellation::LineCap::Butt,
       LineCap::Round => lyon_tessellation::LineCap::Round,
@@ -224,9 +391,11 @@ impl Path {
       .with_miter_limit(miter_limit)
       .with_line_width(width);
 
+    let dashed = (!dash_array.is_empty()).then(|| self.dash(&dash_array, dash_offset));
+    let path = dashed.as_ref().unwrap_or(self);
     stroke_tess
       .tessellate_path(
-        &self.lyon_path,
+        &path.lyon_path,
         &options,
         &mut BuffersBuilder::new(buffer, move |v: StrokeVertex| {
           vertex_ctor(v.position().cast_unit())
@@ -272,6 +441,41 @@ impl PathSampler {
     sampler.split_range(range, &mut builder);
     builder.build().into()
   }
+
+  /// Append the dashes of the measured path to the `builder`, the `pattern`
+  /// must have an even number of non-negative lengths with a positive sum.
+  fn dash_into(
+    &self, pattern: &[f32], offset: f32, builder: &mut lyon_algorithms::path::path::Builder,
+  ) {
+    let length = self.length();
+    if length <= 0. {
+      return;
+    }
+    let total: f32 = pattern.iter().sum();
+    let mut sampler = self
+      .measurements
+      .create_sampler(&self.path, SampleType::Distance);
+    // Start before the path, at the beginning of the pattern that covers the
+    // offset.
+    let mut pos = -offset.rem_euclid(total);
+    for (idx, len) in pattern.iter().cycle().enumerate() {
+      if pos >= length {
+        break;
+      }
+      let end = pos + len;
+      if idx % 2 == 0 && pos >= 0. && *len == 0. {
+        // A zero length dash still has its caps, so a dotted line is drawn by
+        // the round caps of zero length dashes.
+        let at = sampler.sample(pos).position();
+        builder.begin(at);
+        builder.line_to(at);
+        builder.end(false);
+      } else if idx % 2 == 0 && end > 0. {
+        sampler.split_range(pos.max(0.)..end.min(length), builder);
+      }
+      pos = end;
+    }
+  }
 }
 
 impl Radius {
@@ -343,6 +547,8 @@ impl Default for StrokeOptions {
       miter_limit: 4.0,
       line_cap: LineCap::default(),
       line_join: LineJoin::default(),
+      dash_array: vec![],
+      dash_offset: 0.,
     }
   }
 }
@@ -352,3 +558,92 @@ impl<Attr> Vertex<Attr> {
   #[inline]
   pub fn new(pos: [f32; 2], attr: Attr) -> Self { Self { attr, pos } }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn line(len: f32) -> Path {
+    let mut builder = Path::builder();
+    builder
+      .begin_path(Point::zero())
+      .line_to(Point::new(len, 0.))
+      .end_path(false);
+    builder.build()
+  }
+
+  fn dashes(path: &Path) -> Vec<(f32, f32)> {
+    let mut dashes = vec![];
+    let mut start = 0.;
+    for seg in path.segments() {
+      match seg {
+        PathSegment::MoveTo(p) => start = p.x.round(),
+        PathSegment::LineTo(p) => dashes.push((start, p.x.round())),
+        _ => {}
+      }
+    }
+    dashes
+  }
+
+  #[test]
+  fn dash_line() {
+    let path = line(50.).dash(&[10., 5.], 0.);
+    assert_eq!(dashes(&path), [(0., 10.), (15., 25.), (30., 40.), (45., 50.)]);
+
+    let path = line(50.).dash(&[10., 5.], 5.);
+    assert_eq!(dashes(&path), [(0., 5.), (10., 20.), (25., 35.), (40., 50.)]);
+
+    // An odd number of lengths is repeated.
+    let path = line(30.).dash(&[10.], 0.);
+    assert_eq!(dashes(&path), [(0., 10.), (20., 30.)]);
+  }
+
+  #[test]
+  fn solid_dash_pattern() {
+    let path = line(30.);
+    assert_eq!(path.dash(&[], 0.).segments().count(), 3);
+    assert_eq!(path.dash(&[0., 0.], 0.).segments().count(), 3);
+    assert_eq!(path.dash(&[-1., 2.], 0.).segments().count(), 3);
+  }
+
+  #[test]
+  fn contains_with_fill_rule() {
+    let mut builder = Path::builder();
+    builder
+      .rect(&Rect::new(Point::zero(), ribir_geom::Size::new(30., 30.)))
+      .rect(&Rect::new(Point::new(10., 10.), ribir_geom::Size::new(10., 10.)));
+    let path = builder.build();
+
+    let center = Point::new(15., 15.);
+    assert!(path.contains(center, FillRule::NonZero));
+    assert!(!path.contains(center, FillRule::EvenOdd));
+    assert!(path.contains(Point::new(5., 5.), FillRule::EvenOdd));
+    assert!(!path.contains(Point::new(35., 5.), FillRule::NonZero));
+  }
+
+  #[test]
+  fn stroke_hit_test() {
+    let path = line(30.);
+    let butt = StrokeOptions { width: 4., ..Default::default() };
+    assert!(path.stroke_contains(Point::new(15., 1.5), &butt));
+    assert!(!path.stroke_contains(Point::new(15., 2.5), &butt));
+    assert!(!path.stroke_contains(Point::new(31., 0.), &butt));
+
+    let square = StrokeOptions { line_cap: LineCap::Square, ..butt.clone() };
+    assert!(path.stroke_contains(Point::new(31.5, 1.5), &square));
+    let round = StrokeOptions { line_cap: LineCap::Round, ..butt.clone() };
+    assert!(path.stroke_contains(Point::new(31.5, 0.), &round));
+    assert!(!path.stroke_contains(Point::new(31.5, 1.5), &round));
+
+    // The gaps of the dashes are not hit.
+    let dashed = StrokeOptions { dash_array: vec![10., 10.], ..butt };
+    assert!(path.stroke_contains(Point::new(5., 0.), &dashed));
+    assert!(!path.stroke_contains(Point::new(15., 0.), &dashed));
+
+    // The closed path has no caps but its closing edge.
+    let circle = Path::circle(Point::new(20., 20.), 10.);
+    let options = StrokeOptions::default();
+    assert!(circle.stroke_contains(Point::new(30., 20.), &options));
+    assert!(!circle.stroke_contains(Point::new(20., 20.), &options));
+  }
+}
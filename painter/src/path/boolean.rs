@@ -0,0 +1,362 @@
+use ahash::HashMap;
+use lyon_algorithms::path::{Event, Path as LyonPath, iterator::PathIterator};
+use ribir_geom::{Point, Vector};
+use serde::{Deserialize, Serialize};
+
+use super::{FLATTEN_TOLERANCE, Path};
+
+/// The boolean operations to combine the areas of two paths.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub enum PathOp {
+  /// The area covered by either path.
+  Union,
+  /// The area covered by both paths.
+  Intersect,
+  /// The area covered by the first path but not the second one.
+  Difference,
+  /// The area covered by exactly one of the paths.
+  Xor,
+}
+
+/// The distance the points are regarded as the same.
+const EPSILON: f32 = 1e-3;
+
+/// A line segment of a flattened path.
+#[derive(Clone, Copy)]
+struct Edge {
+  from: Point,
+  to: Point,
+}
+
+impl Edge {
+  fn min_x(&self) -> f32 { self.from.x.min(self.to.x) }
+
+  fn max_x(&self) -> f32 { self.from.x.max(self.to.x) }
+}
+
+impl PathOp {
+  fn apply(self, in_a: bool, in_b: bool) -> bool {
+    match self {
+      PathOp::Union => in_a || in_b,
+      PathOp::Intersect => in_a && in_b,
+      PathOp::Difference => in_a && !in_b,
+      PathOp::Xor => in_a != in_b,
+    }
+  }
+}
+
+impl Path {
+  /// Combine the area of this path with the area of `other` by the `op`, the
+  /// areas are filled with the nonzero rule and the open sub-paths are treated
+  /// as closed.
+  ///
+  /// The curves are flattened, so the result path only has the line segments.
+  pub fn op(&self, other: &Path, op: PathOp) -> Path {
+    let a = flatten(self);
+    let b = flatten(other);
+    let len_a = a.len();
+    let edges: Vec<Edge> = a.into_iter().chain(b).collect();
+    let (a, b) = edges.split_at(len_a);
+
+    let mut kept = vec![];
+    for (from, to) in split_edges(&edges) {
+      let v = to - from;
+      let len = v.length();
+      if len < EPSILON {
+        continue;
+      }
+      // Test the sides of the edge, it's a boundary of the result if only one
+      // side is inside.
+      let mid = from.lerp(to, 0.5);
+      let normal = Vector::new(-v.y, v.x) / len * EPSILON;
+      let inside = |p: Point| op.apply(winding(a, p) != 0, winding(b, p) != 0);
+      match (inside(mid + normal), inside(mid - normal)) {
+        (true, false) => kept.push((from, to)),
+        (false, true) => kept.push((to, from)),
+        _ => {}
+      }
+    }
+    link_contours(kept)
+  }
+
+  /// The area covered by either this path or `other`.
+  #[inline]
+  pub fn union(&self, other: &Path) -> Path { self.op(other, PathOp::Union) }
+
+  /// The area covered by both this path and `other`.
+  #[inline]
+  pub fn intersect(&self, other: &Path) -> Path { self.op(other, PathOp::Intersect) }
+
+  /// The area of this path that is not covered by `other`.
+  #[inline]
+  pub fn difference(&self, other: &Path) -> Path { self.op(other, PathOp::Difference) }
+
+  /// The area covered by exactly one of this path and `other`.
+  #[inline]
+  pub fn xor(&self, other: &Path) -> Path { self.op(other, PathOp::Xor) }
+}
+
+/// Flatten the path to the line segments, every sub-path is closed.
+fn flatten(path: &Path) -> Vec<Edge> {
+  let mut edges = vec![];
+  let mut push = |from: lyon_algorithms::geom::Point<f32>,
+                  to: lyon_algorithms::geom::Point<f32>| {
+    if from != to {
+      edges.push(Edge { from: from.cast_unit(), to: to.cast_unit() });
+    }
+  };
+  for e in path.lyon_path.iter().flattened(FLATTEN_TOLERANCE) {
+    match e {
+      Event::Line { from, to } => push(from, to),
+      Event::End { last, first, .. } => push(last, first),
+      _ => {}
+    }
+  }
+  edges
+}
+
+/// Split the edges at their intersections with each other, so any two of the
+/// result edges only meet at their ends.
+fn split_edges(edges: &[Edge]) -> Vec<(Point, Point)> {
+  // The split points of every edge, with their positions along the edge.
+  let mut splits: Vec<Vec<(f32, Point)>> = edges
+    .iter()
+    .map(|e| vec![(0., e.from), (1., e.to)])
+    .collect();
+
+  // Sweep the edges from left to right, only the edges whose x ranges overlap
+  // are tested.
+  let mut order: Vec<usize> = (0..edges.len()).collect();
+  order.sort_by(|a, b| edges[*a].min_x().total_cmp(&edges[*b].min_x()));
+  let mut active: Vec<usize> = vec![];
+  for i in order {
+    let left = edges[i].min_x();
+    active.retain(|j| edges[*j].max_x() + EPSILON >= left);
+    for &j in &active {
+      if overlap_bounds(&edges[i], &edges[j]) {
+        split_at_intersection(edges, i, j, &mut splits);
+      }
+    }
+    active.push(i);
+  }
+
+  let mut pieces = vec![];
+  for mut split in splits {
+    split.sort_by(|a, b| a.0.total_cmp(&b.0));
+    split.dedup_by(|a, b| (a.1 - b.1).length() < EPSILON);
+    pieces.extend(split.windows(2).map(|w| (w[0].1, w[1].1)));
+  }
+  pieces
+}
+
+/// Add the intersection points of the `i` and `j` edges to their splits.
+fn split_at_intersection(edges: &[Edge], i: usize, j: usize, splits: &mut [Vec<(f32, Point)>]) {
+  let (e1, e2) = (edges[i], edges[j]);
+  let (v1, v2) = (e1.to - e1.from, e2.to - e2.from);
+  let denom = v1.cross(v2);
+  let w = e2.from - e1.from;
+  if denom.abs() > f32::EPSILON * v1.length() * v2.length() {
+    let t1 = w.cross(v2) / denom;
+    let t2 = w.cross(v1) / denom;
+    if (0. ..=1.).contains(&t1) && (0. ..=1.).contains(&t2) {
+      // Both edges share the same point, so they are linked at it.
+      let p = e1.from + v1 * t1;
+      splits[i].push((t1, p));
+      splits[j].push((t2, p));
+    }
+  } else {
+    // The parallel edges only meet if they are collinear, then the ends of
+    // each edge on the other one are the split points.
+    for (idx, e, other) in [(i, e1, e2), (j, e2, e1)] {
+      for p in [other.from, other.to] {
+        if let Some(t) = point_on_edge(&e, p) {
+          splits[idx].push((t, p));
+        }
+      }
+    }
+  }
+}
+
+fn overlap_bounds(e1: &Edge, e2: &Edge) -> bool {
+  e1.from.x.min(e1.to.x) <= e2.from.x.max(e2.to.x) + EPSILON
+    && e2.from.x.min(e2.to.x) <= e1.from.x.max(e1.to.x) + EPSILON
+    && e1.from.y.min(e1.to.y) <= e2.from.y.max(e2.to.y) + EPSILON
+    && e2.from.y.min(e2.to.y) <= e1.from.y.max(e1.to.y) + EPSILON
+}
+
+/// Return the position of `p` along the edge if it lies on the edge.
+fn point_on_edge(e: &Edge, p: Point) -> Option<f32> {
+  let v = e.to - e.from;
+  let len_sq = v.square_length();
+  if len_sq == 0. {
+    return None;
+  }
+  let t = (p - e.from).dot(v) / len_sq;
+  let dist = v.cross(p - e.from).abs() / len_sq.sqrt();
+  ((0. ..=1.).contains(&t) && dist < EPSILON).then_some(t)
+}
+
+/// The winding number of the edges around `p`.
+fn winding(edges: &[Edge], p: Point) -> i32 {
+  let mut winding = 0;
+  for Edge { from, to } in edges {
+    let side = (*to - *from).cross(p - *from);
+    if from.y <= p.y {
+      if to.y > p.y && side > 0. {
+        winding += 1;
+      }
+    } else if to.y <= p.y && side < 0. {
+      winding -= 1;
+    }
+  }
+  winding
+}
+
+fn point_key(p: Point) -> (i64, i64) {
+  ((p.x / EPSILON).round() as i64, (p.y / EPSILON).round() as i64)
+}
+
+/// Link the directed edges to the closed contours.
+fn link_contours(mut edges: Vec<(Point, Point)>) -> Path {
+  // The same edge may come from both paths if they share a boundary.
+  let mut keys = ahash::HashSet::default();
+  edges.retain(|(from, to)| keys.insert((point_key(*from), point_key(*to))));
+
+  let mut starts: HashMap<(i64, i64), Vec<usize>> = HashMap::default();
+  for (idx, (from, _)) in edges.iter().enumerate() {
+    starts
+      .entry(point_key(*from))
+      .or_default()
+      .push(idx);
+  }
+
+  let mut used = vec![false; edges.len()];
+  let mut builder = LyonPath::builder();
+  for first in 0..edges.len() {
+    if used[first] {
+      continue;
+    }
+    used[first] = true;
+    let (start, mut to) = edges[first];
+    let mut points = vec![start];
+    loop {
+      let next = starts
+        .get(&point_key(to))
+        .and_then(|idxs| idxs.iter().copied().find(|idx| !used[*idx]));
+      let Some(next) = next else { break };
+      used[next] = true;
+      points.push(to);
+      to = edges[next].1;
+    }
+    points.push(to);
+    remove_collinear(&mut points);
+    if points.len() < 3 {
+      continue;
+    }
+    builder.begin(points[0].cast_unit());
+    for p in &points[1..] {
+      builder.line_to(p.cast_unit());
+    }
+    builder.end(true);
+  }
+  builder.build().into()
+}
+
+/// Remove the points that are on the line between their neighbors and the
+/// last point if it closes the contour.
+fn remove_collinear(points: &mut Vec<Point>) {
+  if points.len() > 1 && (points[0] - points[points.len() - 1]).length() < EPSILON {
+    points.pop();
+  }
+  let mut idx = 0;
+  while points.len() > 2 && idx < points.len() {
+    let len = points.len();
+    let prev = points[(idx + len - 1) % len];
+    let next = points[(idx + 1) % len];
+    let v = next - prev;
+    let dist = v.cross(points[idx] - prev).abs() / v.length().max(f32::EPSILON);
+    if dist < EPSILON && (points[idx] - prev).dot(next - points[idx]) >= 0. {
+      points.remove(idx);
+    } else {
+      idx += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_geom::{Rect, Size};
+
+  use super::*;
+  use crate::FillRule;
+
+  fn square(x: f32, y: f32, size: f32) -> Path {
+    Path::rect(&Rect::new(Point::new(x, y), Size::new(size, size)))
+  }
+
+  fn area(path: &Path) -> f32 {
+    let mut area = 0.;
+    for e in flatten(path) {
+      area += e.from.to_vector().cross(e.to.to_vector());
+    }
+    area.abs() / 2.
+  }
+
+  #[test]
+  fn overlapped_squares() {
+    let a = square(0., 0., 10.);
+    let b = square(5., 5., 10.);
+
+    let union = a.union(&b);
+    assert_eq!(area(&union), 175.);
+    assert!(union.contains(Point::new(12., 12.), FillRule::NonZero));
+    assert!(!union.contains(Point::new(12., 2.), FillRule::NonZero));
+
+    let intersect = a.intersect(&b);
+    assert_eq!(area(&intersect), 25.);
+    assert_eq!(intersect.bounds(None), Rect::new(Point::new(5., 5.), Size::new(5., 5.)));
+
+    let difference = a.difference(&b);
+    assert_eq!(area(&difference), 75.);
+    assert!(!difference.contains(Point::new(7., 7.), FillRule::NonZero));
+
+    let xor = a.xor(&b);
+    assert_eq!(area(&xor), 150.);
+    assert!(!xor.contains(Point::new(7., 7.), FillRule::NonZero));
+    assert!(xor.contains(Point::new(2., 2.), FillRule::NonZero));
+  }
+
+  #[test]
+  fn shared_edges() {
+    let a = square(0., 0., 10.);
+    let b = square(10., 0., 10.);
+    let union = a.union(&b);
+    assert_eq!(area(&union), 200.);
+    // The shared edge is removed and the collinear points are merged, only a
+    // move, three lines and the close are left.
+    assert_eq!(union.segments().count(), 5);
+
+    assert_eq!(area(&a.intersect(&b)), 0.);
+    assert_eq!(area(&a.xor(&b)), 200.);
+  }
+
+  #[test]
+  fn hole() {
+    let ring = square(0., 0., 30.).difference(&square(10., 10., 10.));
+    assert_eq!(area(&ring), 800.);
+    assert!(!ring.contains(Point::new(15., 15.), FillRule::NonZero));
+    assert!(ring.contains(Point::new(5., 15.), FillRule::NonZero));
+  }
+
+  #[test]
+  fn circles() {
+    let a = Path::circle(Point::new(10., 10.), 10.);
+    let b = Path::circle(Point::new(20., 10.), 10.);
+    let union = a.union(&b);
+    assert!(union.contains(Point::new(25., 10.), FillRule::NonZero));
+    assert!(!union.contains(Point::new(15., 1.), FillRule::NonZero));
+    let intersect = a.intersect(&b);
+    assert!(intersect.contains(Point::new(15., 10.), FillRule::NonZero));
+    assert!(!intersect.contains(Point::new(5., 10.), FillRule::NonZero));
+  }
+}
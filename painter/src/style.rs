@@ -1,9 +1,10 @@
 use ribir_algo::Resource;
+use ribir_geom::Vector;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-  Color, PixelImage,
-  color::{LinearGradient, RadialGradient},
+  Color, NinePatch, PixelImage,
+  color::{LinearGradient, RadialGradient, SweepGradient},
 };
 
 /// The brush is used to fill or stroke shapes with color, image, or gradient.
@@ -12,8 +13,35 @@ pub enum Brush {
   Color(Color),
   /// Image brush always use a repeat mode to brush the path.
   Image(Resource<PixelImage>),
+  /// Nine patch brush stretches the image to the bounds of the filled path,
+  /// see [`NinePatch`]. The stroke uses it as an image brush.
+  NinePatch(NinePatch),
   RadialGradient(RadialGradient),
   LinearGradient(LinearGradient),
+  SweepGradient(SweepGradient),
+}
+
+/// The shadow cast by a shape, it's painted below the shape, moved by the
+/// `offset`, expanded by the `spread` and then blurred.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Shadow {
+  /// The offset of the shadow relative to the shape.
+  pub offset: Vector,
+  /// The larger the blur radius, the bigger and lighter the shadow is. The
+  /// shadow is blurred by a gaussian blur with a standard deviation of half the
+  /// blur radius.
+  pub blur_radius: f32,
+  /// The distance the shadow expands before blurring, a negative value shrinks
+  /// the shadow.
+  pub spread: f32,
+  pub color: Color,
+}
+
+impl Shadow {
+  #[inline]
+  pub fn new(offset: Vector, blur_radius: f32, spread: f32, color: Color) -> Self {
+    Self { offset, blur_radius, spread, color }
+  }
 }
 
 impl Brush {
@@ -27,9 +55,10 @@ impl Brush {
   pub fn is_visible(&self) -> bool {
     match self {
       Brush::Color(c) => c.alpha > 0,
-      Brush::Image(_) => true,
+      Brush::Image(_) | Brush::NinePatch(_) => true,
       Brush::RadialGradient(RadialGradient { ref stops, .. })
-      | Brush::LinearGradient(LinearGradient { ref stops, .. }) => {
+      | Brush::LinearGradient(LinearGradient { ref stops, .. })
+      | Brush::SweepGradient(SweepGradient { ref stops, .. }) => {
         stops.iter().any(|s| s.color.alpha > 0)
       }
     }
@@ -51,6 +80,16 @@ impl From<Resource<PixelImage>> for Brush {
   fn from(img: Resource<PixelImage>) -> Self { Brush::Image(img) }
 }
 
+impl From<NinePatch> for Brush {
+  #[inline]
+  fn from(patch: NinePatch) -> Self { Brush::NinePatch(patch) }
+}
+
+impl From<NinePatch> for Option<Brush> {
+  #[inline]
+  fn from(patch: NinePatch) -> Self { Some(patch.into()) }
+}
+
 impl From<PixelImage> for Brush {
   #[inline]
   fn from(img: PixelImage) -> Self { Resource::new(img).into() }
@@ -1,19 +1,47 @@
-use std::{cell::RefCell, error::Error, io::Read, vec};
+use std::{
+  borrow::Cow,
+  cell::RefCell,
+  collections::HashSet,
+  error::Error,
+  io::Read,
+  ops::Range,
+  path::PathBuf,
+  sync::{Arc, OnceLock},
+  vec,
+};
 
 use ribir_algo::Resource;
-use ribir_geom::{Point, Rect, Size, Transform};
+use ribir_geom::{Point, Rect, Size, Transform, Vector};
+use roxmltree::{Document, ParsingOptions};
 use serde::{Deserialize, Serialize};
-use usvg::{Options, Stop, Tree};
+use usvg::{Options, Stop, Tree, filter::Kind as FilterKind, fontdb, tiny_skia_path};
 
 use crate::{
-  Brush, Color, CommandBrush, GradientStop, LineCap, LineJoin, PaintCommand, PaintPathAction, Path,
-  StrokeOptions,
-  color::{LinearGradient, RadialGradient},
+  Brush, Color, CommandBrush, FilterQuality, GradientStop, LayerCommand, LineCap, LineJoin,
+  MaskCommand, MaskMode, PaintCommand, PaintPathAction, Painter, Path, PixelImage, StrokeOptions,
+  color::{LinearGradient, RadialGradient, SweepGradient},
+  font_db::FontDB,
+  image::ImageFormat,
 };
 
+mod animation;
+use animation::{AnimTarget, FrameBundle, Smil, SvgAnimations};
+pub use animation::{SvgAnimAttr, SvgAnimValue, SvgAnimation, SvgCalcMode, SvgTransformType};
+
 /// This is a basic SVG support designed for rendering to Ribir painter. It is
-/// currently quite simple and primarily used for Ribir icons. More features
-/// will be added as needed.
+/// primarily used for Ribir icons and illustrations, the texts, patterns,
+/// masks, embedded images and the blur and drop shadow filters are supported.
+///
+/// The colors of the SVG can be named color slots by the CSS variables, like
+/// `fill="var(--primary)"` or `stop-color="var(--accent, #FF5722)"`. Their
+/// colors are supplied when the SVG is painted, see
+/// [`Svg::commands_with_colors`]. The variables are only supported in the
+/// `fill`, `stroke` and `stop-color` attributes and the `style` attributes, not
+/// in the style sheets.
+///
+/// The SMIL animations of the transform, the opacity, the fill and the stroke
+/// of the elements are parsed, see [`Svg::animations`] and
+/// [`Svg::frame_commands`].
 
 #[derive(Serialize, Deserialize)]
 pub struct Svg {
@@ -22,25 +50,54 @@ pub struct Svg {
 
   inherited_fill: bool,
   inherited_stroke: bool,
+  #[serde(default)]
+  color_slots: Box<[SvgColorSlot]>,
+  #[serde(default)]
+  animations: SvgAnimations,
+  #[serde(default)]
+  mark_colors: MarkColors,
   #[serde(skip)]
   last: RefCell<Option<StaticSvg>>,
 }
 
+/// A named color of the SVG, declared by `var(--name)` or
+/// `var(--name, fallback)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SvgColorSlot {
+  /// The name of the variable without the `--` prefix.
+  pub name: String,
+  /// The color used when no color is supplied for the slot.
+  pub fallback: Option<Color>,
+}
+
+/// The red and green of the colors that stand for the color slots and the
+/// animated colors in the commands, the blue is the index of the color. They
+/// are picked from the ones that no color of the SVG has.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct MarkColors {
+  slot: [u8; 2],
+  anim: [u8; 2],
+}
+
 #[derive(Clone)]
 struct StaticSvg {
   inherited_fill: Brush,
   inherited_stroke: Brush,
+  slot_colors: Box<[Color]>,
   commands: Resource<Box<[PaintCommand]>>,
 }
 
-// todo: share fontdb
+/// The max count of the tiles a pattern paints, a larger pattern is ignored.
+const MAX_PATTERN_TILES: usize = 4096;
+
 impl Svg {
   // FIXME: This is a temporary workaround. Utilize the magic color for the SVG,
   // and replace it with the actual color when rendering.
   const DYNAMIC_COLOR: Color = Color::from_u32(0x191B1901);
   const DYNAMIC_COLOR_STR: &'static str = "#191B1901";
+  const MAX_COLOR_SLOTS: usize = u8::MAX as usize + 1;
 
-  /// Parse SVG from bytes.
+  /// Parse SVG from bytes, the texts in it are shaped with the builtin font.
   ///
   /// - **inherit_fill**: Indicates whether this SVG will inherit the fill color
   ///   from the environment.
@@ -48,6 +105,27 @@ impl Svg {
   ///   color from the environment.
   pub fn parse_from_bytes(
     svg_data: &[u8], inherit_fill: bool, inherit_stroke: bool,
+  ) -> Result<Self, Box<dyn Error>> {
+    Self::parse(svg_data, inherit_fill, inherit_stroke, builtin_fonts(), None)
+  }
+
+  /// Parse SVG from bytes, the texts in it are shaped with the fonts of the
+  /// `font_db`, and fall back to its default fonts.
+  ///
+  /// - **inherit_fill**: Indicates whether this SVG will inherit the fill color
+  ///   from the environment.
+  /// - **inherit_stroke**: Indicates whether this SVG will inherit the stroke
+  ///   color from the environment.
+  pub fn parse_with_fonts(
+    svg_data: &[u8], inherit_fill: bool, inherit_stroke: bool, font_db: &FontDB,
+  ) -> Result<Self, Box<dyn Error>> {
+    let fonts = Arc::new(font_db.svg_database());
+    Self::parse(svg_data, inherit_fill, inherit_stroke, fonts, None)
+  }
+
+  fn parse(
+    svg_data: &[u8], inherit_fill: bool, inherit_stroke: bool, fontdb: Arc<fontdb::Database>,
+    resources_dir: Option<PathBuf>,
   ) -> Result<Self, Box<dyn Error>> {
     let magic = Self::DYNAMIC_COLOR_STR;
     let style_sheet = match (inherit_fill, inherit_stroke) {
@@ -57,23 +135,40 @@ impl Svg {
       _ => None,
     };
 
-    let opt = Options { style_sheet, ..<_>::default() };
-    let tree = Tree::from_data(svg_data, &opt).unwrap();
+    let opt = Options { style_sheet, fontdb, resources_dir, ..<_>::default() };
+    let mark_colors = if may_use_marks(svg_data) {
+      MarkColors::pick(&Tree::from_data(svg_data, &opt)?)
+    } else {
+      MarkColors::default()
+    };
+
+    let smil = Smil::parse(svg_data, mark_colors.anim);
+    let svg_data = smil
+      .as_ref()
+      .map_or(svg_data, |smil| smil.svg.as_bytes());
+    let (svg_data, color_slots) = replace_color_vars(svg_data, mark_colors.slot);
+    let tree = Tree::from_data(&svg_data, &opt)?;
 
     let size = tree.size();
 
-    let bound_rect = Rect::from_size(Size::new(f32::MAX, f32::MAX));
-    let mut painter = crate::Painter::new(bound_rect);
-    paint_group(tree.root(), &mut painter);
+    let mut painter = svg_painter();
+    let mut anim_groups = vec![];
+    paint_group(tree.root(), &Transform::identity(), &mut painter, &mut anim_groups);
 
     let paint_commands = painter.finish().to_owned().into_boxed_slice();
     let (used_fill_fallback, used_stroke_fallback) = fallback_color_check(&paint_commands);
+    let animations = smil
+      .map(|smil| svg_animations(smil, anim_groups, &paint_commands))
+      .unwrap_or_default();
 
     Ok(Svg {
       size: Size::new(size.width(), size.height()),
       commands: Resource::new(paint_commands),
       inherited_fill: used_fill_fallback,
       inherited_stroke: used_stroke_fallback,
+      color_slots: color_slots.into_boxed_slice(),
+      animations,
+      mark_colors,
       last: RefCell::new(None),
     })
   }
@@ -87,41 +182,129 @@ impl Svg {
   pub fn open<P: AsRef<std::path::Path>>(
     path: P, fill_inject: bool, stroke_inject: bool,
   ) -> Result<Self, Box<dyn Error>> {
+    let path = path.as_ref();
     let mut file = std::fs::File::open(path)?;
     let mut bytes = vec![];
     file.read_to_end(&mut bytes)?;
-    Self::parse_from_bytes(&bytes, fill_inject, stroke_inject)
+    // The images linked by relative paths are loaded from the directory of the
+    // file.
+    let resources_dir = path.parent().map(|dir| dir.to_path_buf());
+    Self::parse(&bytes, fill_inject, stroke_inject, builtin_fonts(), resources_dir)
   }
 
   pub fn size(&self) -> Size { self.size }
 
+  /// The named color slots of the SVG, in the order they first appear.
+  pub fn color_slots(&self) -> &[SvgColorSlot] { &self.color_slots }
+
+  /// The colors of the color slots, the color of a slot is the one `named`
+  /// returns for its name, or its fallback color, or black.
+  pub fn slot_colors(&self, named: impl Fn(&str) -> Option<Color>) -> Vec<Color> {
+    self
+      .color_slots
+      .iter()
+      .map(|slot| {
+        named(&slot.name)
+          .or(slot.fallback)
+          .unwrap_or(Color::BLACK)
+      })
+      .collect()
+  }
+
+  /// The commands to paint the SVG, the color slots use their fallback colors.
   pub fn commands(
     &self, fill_brush: &Brush, stroke_brush: &Brush,
   ) -> Resource<Box<[PaintCommand]>> {
-    if !self.inherited_fill && !self.inherited_stroke {
-      self.commands.clone()
+    self.commands_with_colors(fill_brush, stroke_brush, &[])
+  }
+
+  /// The commands to paint the SVG, the n-th color slot is painted with
+  /// `slot_colors[n]`, the slots without a color in `slot_colors` use their
+  /// fallback colors, or black.
+  pub fn commands_with_colors(
+    &self, fill_brush: &Brush, stroke_brush: &Brush, slot_colors: &[Color],
+  ) -> Resource<Box<[PaintCommand]>> {
+    if !self.inherited_fill
+      && !self.inherited_stroke
+      && self.color_slots.is_empty()
+      && self.animations.base_colors.is_empty()
+    {
+      return self.commands.clone();
+    }
+
+    let slot_colors = self.resolve_slot_colors(slot_colors);
+    let mut last = self.last.borrow_mut();
+    if let Some(last) = last.as_ref().filter(|last| {
+      &last.inherited_fill == fill_brush
+        && &last.inherited_stroke == stroke_brush
+        && last.slot_colors == slot_colors
+    }) {
+      last.commands.clone()
     } else {
-      let mut last = self.last.borrow_mut();
-      if let Some(last) = last
-        .as_ref()
-        .filter(|last| &last.inherited_fill == fill_brush && &last.inherited_stroke == stroke_brush)
-      {
-        last.commands.clone()
-      } else {
-        let commands = brush_replace(&self.commands, fill_brush, stroke_brush);
-        let commands = Resource::new(commands);
-        *last = Some(StaticSvg {
-          inherited_fill: fill_brush.clone(),
-          inherited_stroke: stroke_brush.clone(),
-          commands: commands.clone(),
-        });
-        commands
-      }
+      let replacer = Replacer {
+        fill: fill_brush,
+        stroke: stroke_brush,
+        marks: self.mark_colors,
+        slot_colors: &slot_colors,
+        anim_colors: &self.animations.base_colors,
+        bundles: &[],
+      };
+      let commands = Resource::new(replacer.replace(&self.commands, &mut vec![]));
+      *last = Some(StaticSvg {
+        inherited_fill: fill_brush.clone(),
+        inherited_stroke: stroke_brush.clone(),
+        slot_colors,
+        commands: commands.clone(),
+      });
+      commands
+    }
+  }
+
+  /// The SMIL animations of the SVG.
+  pub fn animations(&self) -> &[SvgAnimation] { &self.animations.list }
+
+  /// The commands to paint a frame of the animations of the SVG, the n-th
+  /// animation has the value `values[n]`, or it doesn't change the SVG if its
+  /// value is `None`. The colors are supplied like
+  /// [`Svg::commands_with_colors`].
+  pub fn frame_commands(
+    &self, fill_brush: &Brush, stroke_brush: &Brush, slot_colors: &[Color],
+    values: &[Option<SvgAnimValue>],
+  ) -> Resource<Box<[PaintCommand]>> {
+    if values.iter().all(Option::is_none) {
+      return self.commands_with_colors(fill_brush, stroke_brush, slot_colors);
     }
+
+    let slot_colors = self.resolve_slot_colors(slot_colors);
+    let (bundles, anim_colors) = self.animations.frame(values);
+    let replacer = Replacer {
+      fill: fill_brush,
+      stroke: stroke_brush,
+      marks: self.mark_colors,
+      slot_colors: &slot_colors,
+      anim_colors: &anim_colors,
+      bundles: &bundles,
+    };
+    Resource::new(replacer.replace(&self.commands, &mut vec![]))
   }
 
   pub fn command_size(&self) -> usize { self.commands.len() }
 
+  fn resolve_slot_colors(&self, slot_colors: &[Color]) -> Box<[Color]> {
+    self
+      .color_slots
+      .iter()
+      .enumerate()
+      .map(|(i, slot)| {
+        slot_colors
+          .get(i)
+          .copied()
+          .or(slot.fallback)
+          .unwrap_or(Color::BLACK)
+      })
+      .collect()
+  }
+
   pub fn serialize(&self) -> Result<String, Box<dyn Error>> {
     // use json replace bincode, because https://github.com/Ogeon/palette/issues/130
     Ok(serde_json::to_string(self)?)
@@ -130,80 +313,421 @@ impl Svg {
   pub fn deserialize(str: &str) -> Result<Self, Box<dyn Error>> { Ok(serde_json::from_str(str)?) }
 }
 
-fn paint_group(g: &usvg::Group, painter: &mut crate::Painter) {
-  let mut painter = painter.save_guard();
+fn builtin_fonts() -> Arc<fontdb::Database> {
+  static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+  FONTS
+    .get_or_init(|| Arc::new(FontDB::default().svg_database()))
+    .clone()
+}
+
+fn svg_painter() -> Painter { Painter::new(Rect::from_size(Size::new(f32::MAX, f32::MAX))) }
+
+/// Paint the children of the group. The absolute transforms of the nodes in
+/// the patterns, the masks and the nested SVGs are relative to their own roots,
+/// so the `base` transform is applied after them.
+///
+/// The groups of the animated elements are collected into `anim_groups`, the
+/// animations in the patterns, the masks and the nested SVGs are not played.
+fn paint_group(
+  g: &usvg::Group, base: &Transform, painter: &mut Painter, anim_groups: &mut Vec<AnimGroup>,
+) {
   for child in g.children() {
     match child {
-      usvg::Node::Group(g) => {
-        // todo;
-        painter.apply_alpha(g.opacity().get());
+      usvg::Node::Group(g) => paint_sub_group(g, base, painter, anim_groups),
+      usvg::Node::Path(p) => paint_path(p, base, painter),
+      usvg::Node::Image(img) => paint_image(img, base, painter),
+      usvg::Node::Text(t) => paint_group(t.flattened(), base, painter, anim_groups),
+    }
+  }
+}
 
-        if g.clip_path().is_some() {
-          log::warn!("[painter]: not support `clip path` in svg, ignored!");
-        }
-        if g.mask().is_some() {
-          log::warn!("[painter]: not support `mask` in svg, ignored!");
-        }
-        if !g.filters().is_empty() {
-          log::warn!("[painter]: not support `filters` in svg, ignored!");
-        }
-        paint_group(g, &mut painter);
-      }
-      usvg::Node::Path(p) => {
-        painter.set_transform(matrix_convert(p.abs_transform()));
-        let path = usvg_path_to_path(p);
-        if let Some(fill) = p.fill() {
-          let (brush, transform) = brush_from_usvg_paint(fill.paint(), fill.opacity());
-
-          let inverse_ts = transform.inverse().unwrap();
-          let path = Resource::new(path.clone().transform(&inverse_ts));
-          painter
-            .set_fill_brush(brush.clone())
-            .apply_transform(&transform)
-            .fill_path(path.into());
-          //&o_ts.then(&n_ts.inverse().unwrap())));
-        }
+fn paint_sub_group(
+  g: &usvg::Group, base: &Transform, painter: &mut Painter, anim_groups: &mut Vec<AnimGroup>,
+) {
+  if let Some(target) = animation::target_of_group(g.id()) {
+    paint_anim_group(target, g, base, painter, anim_groups);
+  } else {
+    let mut painter = painter.save_guard();
+    painter.apply_alpha(g.opacity().get());
+    paint_group_content(g, base, &mut painter, anim_groups);
+  }
+}
 
-        if let Some(stroke) = p.stroke() {
-          let options = StrokeOptions {
-            width: stroke.width().get(),
-            line_cap: stroke.linecap().into(),
-            line_join: stroke.linejoin().into(),
-            miter_limit: stroke.miterlimit().get(),
-          };
-
-          let (brush, transform) = brush_from_usvg_paint(stroke.paint(), stroke.opacity());
-          painter
-            .set_stroke_brush(brush.clone())
-            .set_strokes(options)
-            .apply_transform(&transform)
-            .stroke_path(path.into());
-        };
-      }
-      usvg::Node::Image(_) => {
-        // todo;
-        log::warn!("[painter]: not support draw embed image in svg, ignored!");
-      }
-      usvg::Node::Text(t) => paint_group(t.flattened(), &mut painter),
+/// Paint the group of an animated element as a bundle in its own coordinates,
+/// so its transform and opacity can be replaced in every frame.
+fn paint_anim_group(
+  target: usize, g: &usvg::Group, base: &Transform, painter: &mut Painter,
+  anim_groups: &mut Vec<AnimGroup>,
+) {
+  let transform = matrix_convert(g.transform());
+  let ts = matrix_convert(g.abs_transform()).then(base);
+  let (Some(inverse), Some(transform_inverse)) = (ts.inverse(), transform.inverse()) else {
+    return;
+  };
+
+  let mut content = svg_painter();
+  paint_group_content(g, &base.then(&inverse), &mut content, anim_groups);
+  let cmds: Box<[PaintCommand]> = content.finish().to_vec().into();
+  let bounds = cmds
+    .iter()
+    .filter_map(PaintCommand::paint_bounds)
+    .reduce(|a, b| a.union(&b))
+    .unwrap_or_default();
+  let cmds = Resource::new(cmds);
+
+  let parent_alpha = painter.alpha();
+  let opacity = g.opacity().get();
+  painter.push_bundle(ts, parent_alpha * opacity, bounds, cmds.clone());
+  anim_groups.push(AnimGroup {
+    target,
+    cmds,
+    parent: transform_inverse.then(&ts),
+    transform,
+    parent_alpha,
+    opacity,
+  });
+}
+
+fn paint_group_content(
+  g: &usvg::Group, base: &Transform, painter: &mut Painter, anim_groups: &mut Vec<AnimGroup>,
+) {
+  if g.clip_path().is_some() {
+    log::warn!("[painter]: not support `clip path` in svg, ignored!");
+  }
+
+  let ts = matrix_convert(g.abs_transform()).then(base);
+  if let Some(mask) = g.mask() {
+    push_mask(mask, &ts, painter);
+  }
+  for filter in g.filters() {
+    match filter.primitives() {
+      [primitive] => apply_filter(primitive.kind(), filter.rect(), g, base, painter),
+      _ => log::warn!("[painter]: not support the filter chain in svg, ignored!"),
     }
   }
+  paint_group(g, base, painter, anim_groups);
 }
-fn usvg_path_to_path(path: &usvg::Path) -> Path {
+
+/// The bundle painted for the group of an animated element.
+struct AnimGroup {
+  target: usize,
+  cmds: Resource<Box<[PaintCommand]>>,
+  parent: Transform,
+  transform: Transform,
+  parent_alpha: f32,
+  opacity: f32,
+}
+
+/// Collect the animations of the SVG with the paths of the bundles painted for
+/// their target elements.
+fn svg_animations(smil: Smil, groups: Vec<AnimGroup>, cmds: &[PaintCommand]) -> SvgAnimations {
+  let mut targets: Vec<AnimTarget> = smil
+    .color_marks
+    .into_iter()
+    .map(|color_marks| AnimTarget {
+      cmd_path: None,
+      parent: Transform::identity(),
+      transform: Transform::identity(),
+      parent_alpha: 1.,
+      opacity: 1.,
+      color_marks,
+    })
+    .collect();
+  for g in groups {
+    let Some(target) = targets.get_mut(g.target) else { continue };
+    let mut path = vec![];
+    if find_bundle(cmds, &g.cmds, &mut path) {
+      target.cmd_path = Some(path.into());
+      target.parent = g.parent;
+      target.transform = g.transform;
+      target.parent_alpha = g.parent_alpha;
+      target.opacity = g.opacity;
+    }
+  }
+
+  SvgAnimations {
+    list: smil.animations.into(),
+    targets: targets.into(),
+    base_colors: smil.base_colors.into(),
+  }
+}
+
+fn find_bundle(
+  cmds: &[PaintCommand], bundle: &Resource<Box<[PaintCommand]>>, path: &mut Vec<usize>,
+) -> bool {
+  for (i, cmd) in cmds.iter().enumerate() {
+    path.push(i);
+    let found = match cmd {
+      PaintCommand::Bundle { cmds, .. } => cmds == bundle || find_bundle(cmds, bundle, path),
+      PaintCommand::Layer(l) => find_bundle(&l.cmds, bundle, path),
+      PaintCommand::Mask(m) => find_bundle(&m.cmds, bundle, path),
+      _ => false,
+    };
+    if found {
+      return true;
+    }
+    path.pop();
+  }
+  false
+}
+
+/// Push a mask layer for the content painted after it, until the state
+/// restored.
+fn push_mask(mask: &usvg::Mask, ts: &Transform, painter: &mut Painter) {
+  let mut mask_painter = svg_painter();
+  mask_painter
+    .set_transform(*ts)
+    .clip(Path::rect(&nonzero_rect_convert(mask.rect())).into());
+  paint_group(mask.root(), ts, &mut mask_painter, &mut vec![]);
+  let cmds = mask_painter.finish().to_vec().into_boxed_slice();
+
+  let mode = match mask.kind() {
+    usvg::MaskType::Luminance => MaskMode::Luminance,
+    usvg::MaskType::Alpha => MaskMode::Alpha,
+  };
+  // The mask commands are already in the coordinates of the SVG.
+  painter
+    .set_transform(Transform::identity())
+    .mask_layer(cmds, mode);
+  if let Some(mask) = mask.mask() {
+    push_mask(mask, ts, painter);
+  }
+}
+
+/// Apply the filter primitive to the content of the group painted after it,
+/// only the gaussian blur and the drop shadow of the source graphic are
+/// supported.
+fn apply_filter(
+  kind: &FilterKind, region: usvg::NonZeroRect, g: &usvg::Group, base: &Transform,
+  painter: &mut Painter,
+) {
+  let ts = matrix_convert(g.abs_transform()).then(base);
+  match kind {
+    FilterKind::GaussianBlur(blur) if blur.input() == &usvg::filter::Input::SourceGraphic => {
+      let blur_radius = blur_radius(blur.std_dev_x().get(), blur.std_dev_y().get());
+      let region = Path::rect(&nonzero_rect_convert(region));
+      painter
+        .set_transform(ts)
+        .blur_layer(region.into(), blur_radius, crate::BlurTarget::Content);
+    }
+    FilterKind::DropShadow(shadow) if shadow.input() == &usvg::filter::Input::SourceGraphic => {
+      // The shadow is cast by the group as a whole, so paint the group to be the
+      // alpha mask of the shadow color.
+      let mut content = svg_painter();
+      paint_group(g, base, &mut content, &mut vec![]);
+      let cmds = content.finish().to_vec().into_boxed_slice();
+      let Some(bounds) = cmds
+        .iter()
+        .filter_map(PaintCommand::paint_bounds)
+        .reduce(|a, b| a.union(&b))
+      else {
+        return;
+      };
+
+      let usvg::Color { red, green, blue } = shadow.color();
+      let color = Color::from_rgb(red, green, blue).with_alpha(shadow.opacity().get());
+      let blur_radius = blur_radius(shadow.std_dev_x().get(), shadow.std_dev_y().get());
+      let offset = ts.transform_vector(Vector::new(shadow.dx(), shadow.dy()));
+      let region = Path::rect(&nonzero_rect_convert(region));
+
+      let mut painter = painter.save_guard();
+      painter
+        .set_transform(ts)
+        .blur_layer(region.into(), blur_radius, crate::BlurTarget::Content);
+      // The commands of the group are already in the coordinates of the SVG.
+      painter
+        .set_transform(Transform::translation(offset.x, offset.y))
+        .mask_layer(cmds, MaskMode::Alpha)
+        .set_fill_brush(color)
+        .rect(&bounds)
+        .fill();
+    }
+    kind => log::warn!("[painter]: not support the filter `{kind:?}` in svg, ignored!"),
+  }
+}
+
+/// The blur radius of the gaussian blur, it's twice the standard deviation.
+/// The blur with different standard deviations in the x and y is not supported,
+/// it's approximated by their average.
+fn blur_radius(std_dev_x: f32, std_dev_y: f32) -> f32 {
+  if std_dev_x != std_dev_y {
+    log::warn!(
+      "[painter]: not support the different standard deviations of the blur in svg, blurred by \
+       their average!"
+    );
+  }
+  std_dev_x + std_dev_y
+}
+
+fn paint_path(p: &usvg::Path, base: &Transform, painter: &mut Painter) {
+  if !p.is_visible() {
+    return;
+  }
+  let ts = matrix_convert(p.abs_transform()).then(base);
+  let path = usvg_path_to_path(p.data());
+  if let Some(fill) = p.fill() {
+    if let usvg::Paint::Pattern(pattern) = fill.paint() {
+      let mut painter = painter.save_guard();
+      painter
+        .set_transform(ts)
+        .apply_alpha(fill.opacity().get())
+        .clip(path.clone().into());
+      paint_pattern(pattern, &ts, &path.bounds(None), &mut painter);
+    } else {
+      let (brush, transform) = brush_from_usvg_paint(fill.paint(), fill.opacity());
+
+      let inverse_ts = transform.inverse().unwrap();
+      let path = Resource::new(path.clone().transform(&inverse_ts));
+      painter
+        .set_transform(ts)
+        .set_fill_brush(brush.clone())
+        .apply_transform(&transform)
+        .fill_path(path.into());
+    }
+  }
+
+  if let Some(stroke) = p.stroke() {
+    if let usvg::Paint::Pattern(pattern) = stroke.paint() {
+      let Some(outline) = stroke_outline(p, stroke) else { return };
+      let mut painter = painter.save_guard();
+      let bounds = outline.bounds(None);
+      painter
+        .set_transform(ts)
+        .apply_alpha(stroke.opacity().get())
+        .clip(outline.into());
+      paint_pattern(pattern, &ts, &bounds, &mut painter);
+    } else {
+      let options = StrokeOptions {
+        width: stroke.width().get(),
+        line_cap: stroke.linecap().into(),
+        line_join: stroke.linejoin().into(),
+        miter_limit: stroke.miterlimit().get(),
+        dash_array: stroke.dasharray().map_or(vec![], <[f32]>::to_vec),
+        dash_offset: stroke.dashoffset(),
+      };
+
+      let (brush, transform) = brush_from_usvg_paint(stroke.paint(), stroke.opacity());
+      painter
+        .set_transform(ts)
+        .set_stroke_brush(brush.clone())
+        .set_strokes(options)
+        .apply_transform(&transform)
+        .stroke_path(path.into());
+    }
+  }
+}
+
+/// Paint the tiles of the pattern that cover the `area`, the `ts` is the
+/// transform of the path the pattern paints. The tile is painted once as a
+/// bundle, so the backend can cache it.
+fn paint_pattern(pattern: &usvg::Pattern, ts: &Transform, area: &Rect, painter: &mut Painter) {
+  let pattern_ts = matrix_convert(pattern.transform());
+  let Some(area) = pattern_ts
+    .inverse()
+    .map(|inverse| inverse.outer_transformed_rect(area))
+  else {
+    return;
+  };
+
+  let tile = nonzero_rect_convert(pattern.rect());
+  let Size { width, height, .. } = tile.size;
+  let cols = ((area.min_x() - tile.min_x()) / width).floor() as i64
+    ..((area.max_x() - tile.min_x()) / width).ceil() as i64;
+  let rows = ((area.min_y() - tile.min_y()) / height).floor() as i64
+    ..((area.max_y() - tile.min_y()) / height).ceil() as i64;
+  let tiles = cols
+    .clone()
+    .count()
+    .saturating_mul(rows.clone().count());
+  if tiles > MAX_PATTERN_TILES {
+    log::warn!("[painter]: the pattern in svg needs {tiles} tiles, it's too many to paint!");
+    return;
+  }
+
+  let tile_rect = Rect::from_size(tile.size);
+  let mut tile_painter = svg_painter();
+  tile_painter.clip(Path::rect(&tile_rect).into());
+  paint_group(pattern.root(), &Transform::identity(), &mut tile_painter, &mut vec![]);
+  let cmds = Resource::new(tile_painter.finish().to_vec().into_boxed_slice());
+
+  let tile_ts = pattern_ts.then(ts);
+  for row in rows {
+    for col in cols.clone() {
+      let x = tile.min_x() + col as f32 * width;
+      let y = tile.min_y() + row as f32 * height;
+      painter
+        .set_transform(Transform::translation(x, y).then(&tile_ts))
+        .draw_bundle_commands(tile_rect, cmds.clone());
+    }
+  }
+}
+
+fn paint_image(img: &usvg::Image, base: &Transform, painter: &mut Painter) {
+  if !img.is_visible() {
+    return;
+  }
+  let ts = matrix_convert(img.abs_transform()).then(base);
+  let (data, format) = match img.kind() {
+    usvg::ImageKind::SVG(tree) => {
+      paint_group(tree.root(), &ts, painter, &mut vec![]);
+      return;
+    }
+    usvg::ImageKind::PNG(data) => (data, ImageFormat::Png),
+    usvg::ImageKind::JPEG(data) => (data, ImageFormat::Jpeg),
+    usvg::ImageKind::GIF(data) => (data, ImageFormat::Gif),
+    usvg::ImageKind::WEBP(data) => (data, ImageFormat::WebP),
+  };
+
+  match PixelImage::decode_with_format(data, format) {
+    Ok(pixels) => {
+      let quality = match img.rendering_mode() {
+        usvg::ImageRendering::OptimizeSpeed
+        | usvg::ImageRendering::CrispEdges
+        | usvg::ImageRendering::Pixelated => FilterQuality::Nearest,
+        _ => FilterQuality::Linear,
+      };
+      let usvg_size = img.size();
+      let rect = Rect::from_size(Size::new(usvg_size.width(), usvg_size.height()));
+      let mut painter = painter.save_guard();
+      painter
+        .set_transform(ts)
+        .set_filter_quality(quality)
+        .draw_img(Resource::new(pixels), &rect, &None);
+    }
+    Err(err) => log::warn!("[painter]: failed to decode the image in svg, ignored! {err}"),
+  }
+}
+
+/// The outline of the stroke of the path, as a path to fill.
+fn stroke_outline(p: &usvg::Path, stroke: &usvg::Stroke) -> Option<Path> {
+  let stroke = stroke.to_tiny_skia();
+  let dashed = match &stroke.dash {
+    Some(dash) => Some(p.data().dash(dash, 1.)?),
+    None => None,
+  };
+  let outline = dashed
+    .as_ref()
+    .unwrap_or(p.data())
+    .stroke(&stroke, 1.)?;
+  Some(usvg_path_to_path(&outline))
+}
+
+fn usvg_path_to_path(path: &tiny_skia_path::Path) -> Path {
   let mut builder = lyon_algorithms::path::Path::svg_builder();
-  path.data().segments().for_each(|seg| match seg {
-    usvg::tiny_skia_path::PathSegment::MoveTo(pt) => {
+  path.segments().for_each(|seg| match seg {
+    tiny_skia_path::PathSegment::MoveTo(pt) => {
       builder.move_to(point(pt.x, pt.y));
     }
-    usvg::tiny_skia_path::PathSegment::LineTo(pt) => {
+    tiny_skia_path::PathSegment::LineTo(pt) => {
       builder.line_to(point(pt.x, pt.y));
     }
-    usvg::tiny_skia_path::PathSegment::CubicTo(pt1, pt2, pt3) => {
+    tiny_skia_path::PathSegment::CubicTo(pt1, pt2, pt3) => {
       builder.cubic_bezier_to(point(pt1.x, pt1.y), point(pt2.x, pt2.y), point(pt3.x, pt3.y));
     }
-    usvg::tiny_skia_path::PathSegment::QuadTo(pt1, pt2) => {
+    tiny_skia_path::PathSegment::QuadTo(pt1, pt2) => {
       builder.quadratic_bezier_to(point(pt1.x, pt1.y), point(pt2.x, pt2.y));
     }
-    usvg::tiny_skia_path::PathSegment::Close => builder.close(),
+    tiny_skia_path::PathSegment::Close => builder.close(),
   });
 
   builder.build().into()
@@ -216,6 +740,10 @@ fn matrix_convert(t: usvg::Transform) -> Transform {
   Transform::new(sx, ky, kx, sy, tx, ty)
 }
 
+fn nonzero_rect_convert(rect: usvg::NonZeroRect) -> Rect {
+  Rect::new(Point::new(rect.x(), rect.y()), Size::new(rect.width(), rect.height()))
+}
+
 fn brush_from_usvg_paint(paint: &usvg::Paint, opacity: usvg::Opacity) -> (Brush, Transform) {
   match paint {
     usvg::Paint::Color(usvg::Color { red, green, blue }) => (
@@ -274,10 +802,22 @@ fn fallback_color_check(cmds: &[PaintCommand]) -> (bool, bool) {
           }
         }
       }
-      PaintCommand::PopClip => {}
+      PaintCommand::Shadow(_) | PaintCommand::PopClip => {}
+      PaintCommand::Layer(LayerCommand { cmds, .. }) => {
+        let (f, s) = fallback_color_check(cmds);
+        fill_fallback |= f;
+        stroke_fallback |= s;
+      }
+      PaintCommand::Mask(MaskCommand { mask, cmds, .. }) => {
+        for cmds in [mask, cmds] {
+          let (f, s) = fallback_color_check(cmds);
+          fill_fallback |= f;
+          stroke_fallback |= s;
+        }
+      }
       PaintCommand::Bundle { cmds, .. } => {
         let (f, s) = fallback_color_check(cmds);
-        fill_fallback = f;
+        fill_fallback |= f;
         stroke_fallback |= s;
       }
     }
@@ -285,31 +825,319 @@ fn fallback_color_check(cmds: &[PaintCommand]) -> (bool, bool) {
   (fill_fallback, stroke_fallback)
 }
 
-fn brush_replace(cmds: &[PaintCommand], fill: &Brush, stroke: &Brush) -> Box<[PaintCommand]> {
-  cmds
-    .iter()
-    .map(|c| match c {
+/// Replace the colors and the animated bundles in the commands of the SVG.
+struct Replacer<'a> {
+  fill: &'a Brush,
+  stroke: &'a Brush,
+  marks: MarkColors,
+  slot_colors: &'a [Color],
+  anim_colors: &'a [Color],
+  bundles: &'a [FrameBundle<'a>],
+}
+
+impl Replacer<'_> {
+  /// Replace the commands, the `path` is the path of the `cmds` in the
+  /// commands of the SVG.
+  fn replace(&self, cmds: &[PaintCommand], path: &mut Vec<usize>) -> Box<[PaintCommand]> {
+    cmds
+      .iter()
+      .enumerate()
+      .map(|(i, c)| {
+        path.push(i);
+        let c = self.replace_command(c, path);
+        path.pop();
+        c
+      })
+      .collect()
+  }
+
+  fn replace_command(&self, cmd: &PaintCommand, path: &mut Vec<usize>) -> PaintCommand {
+    match cmd {
       PaintCommand::Path(p) => {
         let mut p = p.clone();
         if let PaintPathAction::Paint { painting_style, brush, .. } = &mut p.action {
-          if matches!(brush, CommandBrush::Color(c) if c == &Svg::DYNAMIC_COLOR) {
-            match painting_style {
-              crate::PaintingStyle::Fill => *brush = fill.clone().into(),
-              crate::PaintingStyle::Stroke(_) => *brush = stroke.clone().into(),
-            }
+          match brush {
+            CommandBrush::Color(c) if c == &Svg::DYNAMIC_COLOR => match painting_style {
+              crate::PaintingStyle::Fill => *brush = self.fill.clone().into(),
+              crate::PaintingStyle::Stroke(_) => *brush = self.stroke.clone().into(),
+            },
+            CommandBrush::Color(c) => self.replace_color(c),
+            CommandBrush::Radial(RadialGradient { stops, .. })
+            | CommandBrush::Linear(LinearGradient { stops, .. })
+            | CommandBrush::Sweep(SweepGradient { stops, .. }) => stops
+              .iter_mut()
+              .for_each(|s| self.replace_color(&mut s.color)),
+            CommandBrush::Image { .. } => {}
           }
         }
         PaintCommand::Path(p)
       }
+      PaintCommand::Shadow(s) => {
+        let mut s = s.clone();
+        self.replace_color(&mut s.color);
+        PaintCommand::Shadow(s)
+      }
+      PaintCommand::Layer(l) => {
+        PaintCommand::Layer(LayerCommand { cmds: self.replace(&l.cmds, path), ..l.clone() })
+      }
+      PaintCommand::Mask(m) => {
+        // The bundles in the mask are not animated, so their paths never match.
+        path.push(usize::MAX);
+        let mask = self.replace(&m.mask, path);
+        path.pop();
+        PaintCommand::Mask(MaskCommand {
+          mode: m.mode,
+          paint_bounds: m.paint_bounds,
+          mask,
+          cmds: self.replace(&m.cmds, path),
+        })
+      }
       PaintCommand::PopClip => PaintCommand::PopClip,
-      PaintCommand::Bundle { transform, opacity, bounds, cmds } => {
-        let cmds = brush_replace(cmds, fill, stroke);
-        let cmds = Resource::new(cmds);
+      PaintCommand::Bundle { transform, opacity, blend_mode, bounds, cmds } => {
+        let (transform, opacity) = self
+          .bundles
+          .iter()
+          .find(|(p, ..)| *p == path.as_slice())
+          .map_or((*transform, *opacity), |(_, ts, opacity)| (*ts, *opacity));
+        PaintCommand::Bundle {
+          transform,
+          opacity,
+          blend_mode: *blend_mode,
+          bounds: *bounds,
+          cmds: Resource::new(self.replace(cmds, path)),
+        }
+      }
+    }
+  }
+
+  /// Replace the color that stands for a color slot or an animated color, the
+  /// opacity applied to it is kept.
+  fn replace_color(&self, color: &mut Color) {
+    let Color { red, green, blue, alpha } = *color;
+    let colors = match [red, green] {
+      mark if mark == self.marks.slot => self.slot_colors,
+      mark if mark == self.marks.anim => self.anim_colors,
+      _ => return,
+    };
+    if let Some(c) = colors.get(blue as usize) {
+      *color = c.apply_alpha(alpha as f32 / 255.);
+    }
+  }
+}
 
-        PaintCommand::Bundle { transform: *transform, opacity: *opacity, bounds: *bounds, cmds }
+impl Default for MarkColors {
+  fn default() -> Self { Self { slot: [0x19, 0x1C], anim: [0x19, 0x1D] } }
+}
+
+impl MarkColors {
+  /// Pick the marks that no color of the SVG `tree` has, so a color of the SVG
+  /// is never replaced as a mark.
+  fn pick(tree: &Tree) -> Self {
+    let mut used = HashSet::new();
+    used.insert([Svg::DYNAMIC_COLOR.red, Svg::DYNAMIC_COLOR.green]);
+    collect_group_colors(tree.root(), &mut used);
+
+    let default = Self::default();
+    let first = u16::from_be_bytes(default.slot);
+    let mut free = (first..=u16::MAX)
+      .chain(0..first)
+      .map(u16::to_be_bytes)
+      .filter(|mark| !used.contains(mark));
+    match (free.next(), free.next()) {
+      (Some(slot), Some(anim)) => Self { slot, anim },
+      _ => {
+        log::warn!("[painter]: no color is free to mark the color slots of the svg!");
+        default
       }
-    })
-    .collect()
+    }
+  }
+}
+
+/// Whether the SVG may have the color slots or the animated colors, that need
+/// the mark colors.
+fn may_use_marks(svg_data: &[u8]) -> bool {
+  std::str::from_utf8(svg_data).is_ok_and(|svg| {
+    ["var(--", "<animate", "<set"]
+      .iter()
+      .any(|p| svg.contains(p))
+  })
+}
+
+/// Collect the red and green of the colors the group paints.
+fn collect_group_colors(g: &usvg::Group, colors: &mut HashSet<[u8; 2]>) {
+  let mut mask = g.mask();
+  while let Some(m) = mask {
+    collect_group_colors(m.root(), colors);
+    mask = m.mask();
+  }
+  for filter in g.filters() {
+    for primitive in filter.primitives() {
+      if let FilterKind::DropShadow(shadow) = primitive.kind() {
+        let usvg::Color { red, green, .. } = shadow.color();
+        colors.insert([red, green]);
+      }
+    }
+  }
+
+  for child in g.children() {
+    match child {
+      usvg::Node::Group(g) => collect_group_colors(g, colors),
+      usvg::Node::Path(p) => {
+        let fill = p.fill().map(usvg::Fill::paint);
+        let stroke = p.stroke().map(usvg::Stroke::paint);
+        for paint in fill.into_iter().chain(stroke) {
+          collect_paint_colors(paint, colors);
+        }
+      }
+      usvg::Node::Image(img) => {
+        if let usvg::ImageKind::SVG(tree) = img.kind() {
+          collect_group_colors(tree.root(), colors);
+        }
+      }
+      usvg::Node::Text(t) => collect_group_colors(t.flattened(), colors),
+    }
+  }
+}
+
+fn collect_paint_colors(paint: &usvg::Paint, colors: &mut HashSet<[u8; 2]>) {
+  let stops = match paint {
+    usvg::Paint::Color(usvg::Color { red, green, .. }) => {
+      colors.insert([*red, *green]);
+      return;
+    }
+    usvg::Paint::LinearGradient(linear) => linear.stops(),
+    usvg::Paint::RadialGradient(radial) => radial.stops(),
+    usvg::Paint::Pattern(pattern) => return collect_group_colors(pattern.root(), colors),
+  };
+  for stop in stops {
+    let usvg::Color { red, green, .. } = stop.color();
+    colors.insert([red, green]);
+  }
+}
+
+/// Replace the CSS variables `var(--name)` and `var(--name, fallback)` in the
+/// color attributes of the SVG with the colors that stand for the color slots,
+/// because usvg doesn't support them. The colors have the red and green of the
+/// `mark`, and the blue is the index of the slot.
+fn replace_color_vars(svg_data: &[u8], mark: [u8; 2]) -> (Cow<'_, [u8]>, Vec<SvgColorSlot>) {
+  let mut slots: Vec<SvgColorSlot> = vec![];
+  let Ok(text) = std::str::from_utf8(svg_data) else {
+    return (Cow::Borrowed(svg_data), slots);
+  };
+  if !text.contains(VAR_START) {
+    return (Cow::Borrowed(svg_data), slots);
+  }
+  let opt = ParsingOptions { allow_dtd: true, ..<_>::default() };
+  let Ok(doc) = Document::parse_with_options(text, opt) else {
+    return (Cow::Borrowed(svg_data), slots);
+  };
+
+  let mut svg = String::with_capacity(svg_data.len());
+  let mut cursor = 0;
+  for attr in doc
+    .descendants()
+    .flat_map(|n| n.attributes())
+    .filter(|a| a.namespace().is_none())
+  {
+    let values = match attr.name() {
+      "style" => style_color_values(text, attr.range_value()),
+      name if COLOR_PROPERTIES.contains(&name) => vec![attr.range_value()],
+      _ => continue,
+    };
+    for value in values {
+      svg.push_str(&text[cursor..value.start]);
+      replace_vars_in_value(&text[value.clone()], mark, &mut slots, &mut svg);
+      cursor = value.end;
+    }
+  }
+  svg.push_str(&text[cursor..]);
+
+  (Cow::Owned(svg.into_bytes()), slots)
+}
+
+const VAR_START: &str = "var(--";
+
+/// The properties the color slots can be used in.
+const COLOR_PROPERTIES: [&str; 3] = ["fill", "stroke", "stop-color"];
+
+/// The ranges of the values of the color properties declared in the `style`
+/// attribute, the `range` is the range of the attribute value in the `text`.
+fn style_color_values(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+  let mut values = vec![];
+  let mut start = range.start;
+  for declaration in text[range].split(';') {
+    if let Some((name, _)) = declaration.split_once(':') {
+      if COLOR_PROPERTIES.contains(&name.trim()) {
+        values.push(start + name.len() + 1..start + declaration.len());
+      }
+    }
+    start += declaration.len() + 1;
+  }
+  values
+}
+
+/// Push the `value` to the `svg`, with its variables replaced by the mark
+/// colors of their slots.
+fn replace_vars_in_value(
+  mut value: &str, mark: [u8; 2], slots: &mut Vec<SvgColorSlot>, svg: &mut String,
+) {
+  while let Some(start) = value.find(VAR_START) {
+    svg.push_str(&value[..start]);
+    let args = &value[start + VAR_START.len()..];
+    // The fallback may be a color function, like `rgb(0, 0, 0)`.
+    let mut depth = 0;
+    let end = args.find(|c| {
+      match c {
+        '(' => depth += 1,
+        ')' if depth == 0 => return true,
+        ')' => depth -= 1,
+        _ => {}
+      }
+      false
+    });
+    let Some(end) = end else {
+      value = &value[start..];
+      break;
+    };
+
+    let (name, fallback) = match args[..end].split_once(',') {
+      Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+      None => (args[..end].trim(), None),
+    };
+    let fallback = fallback.and_then(|f| match f.parse::<svgtypes::Color>() {
+      Ok(svgtypes::Color { red, green, blue, alpha }) => Some(Color::new(red, green, blue, alpha)),
+      Err(_) => {
+        log::warn!("[painter]: the fallback `{f}` of the svg color `--{name}` is not a color.");
+        None
+      }
+    });
+    let idx = match slots.iter().position(|s| s.name == name) {
+      Some(idx) => {
+        let slot = &mut slots[idx];
+        slot.fallback = slot.fallback.or(fallback);
+        Some(idx)
+      }
+      None if slots.len() < Svg::MAX_COLOR_SLOTS => {
+        slots.push(SvgColorSlot { name: name.to_string(), fallback });
+        Some(slots.len() - 1)
+      }
+      None => {
+        log::warn!("[painter]: too many color slots in svg, `--{name}` is ignored.");
+        None
+      }
+    };
+
+    let var_end = start + VAR_START.len() + end + 1;
+    match idx {
+      Some(idx) => {
+        let [red, green] = mark;
+        svg.push_str(&format!("#{red:02X}{green:02X}{idx:02X}"));
+      }
+      None => svg.push_str(&value[start..var_end]),
+    }
+    value = &value[var_end..];
+  }
+  svg.push_str(value);
 }
 
 fn convert_to_gradient_stops(stops: &[Stop]) -> Vec<GradientStop> {
@@ -369,6 +1197,9 @@ impl Clone for Svg {
       commands: self.commands.clone(),
       inherited_fill: self.inherited_fill,
       inherited_stroke: self.inherited_stroke,
+      color_slots: self.color_slots.clone(),
+      animations: self.animations.clone(),
+      mark_colors: self.mark_colors,
       last: RefCell::new(self.last.borrow().clone()),
     }
   }
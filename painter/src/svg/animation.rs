@@ -0,0 +1,837 @@
+//! The SMIL animations of the SVG.
+//!
+//! usvg drops the animation elements, so they are parsed from the source
+//! before the SVG is handed to usvg. Every animated element is wrapped in a
+//! group, the group is painted as a bundle that its transform and opacity can
+//! be changed without painting it again. The animated fill and stroke are
+//! replaced with the mark colors, like the color slots, the marks are picked
+//! from the colors that the SVG doesn't have.
+//!
+//! The CSS `@keyframes` animations are not supported, they are ignored like
+//! the other CSS rules that usvg doesn't support.
+
+use std::{ops::Range, time::Duration};
+
+use ribir_geom::{Angle, Transform, Vector};
+use roxmltree::{Document, Node, ParsingOptions};
+use serde::{Deserialize, Serialize};
+use svgtypes::NumberListParser;
+
+use crate::Color;
+
+/// A SMIL animation of the SVG, declared by `<animate>`, `<animateTransform>`
+/// or `<set>`.
+///
+/// It only describes the timing and the values of the animation, the player
+/// computes the value of the animation at a time, and paints the SVG with it by
+/// [`Svg::frame_commands`](super::Svg::frame_commands).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SvgAnimation {
+  /// The index of the animated element, the animations of an element have the
+  /// same target.
+  pub target: usize,
+  /// The attribute the animation changes.
+  pub attribute: SvgAnimAttr,
+  /// The delay from the SVG starts to play to the animation begins.
+  pub begin: Duration,
+  /// How long the animation has already played when the SVG starts, for the
+  /// negative `begin`.
+  pub elapsed: Duration,
+  /// The simple duration, the duration of one iteration.
+  pub dur: Duration,
+  /// How many iterations the animation plays, `None` plays indefinitely.
+  pub repeat: Option<f32>,
+  /// Whether the attribute keeps the last value after the animation ends,
+  /// otherwise it goes back to its base value.
+  pub freeze: bool,
+  /// Whether the animated transform is applied after the transform of the
+  /// element, otherwise it replaces the transform of the element.
+  pub additive: bool,
+  pub calc_mode: SvgCalcMode,
+  /// The values of the animation, at least one.
+  pub values: Box<[SvgAnimValue]>,
+  /// The progress of every value in the simple duration, empty if the values
+  /// are evenly spaced.
+  pub key_times: Box<[f32]>,
+  /// The control points `[x1, y1, x2, y2]` of the cubic bézier easings between
+  /// every two values, only used by [`SvgCalcMode::Spline`].
+  pub key_splines: Box<[[f32; 4]]>,
+}
+
+/// The attribute a SVG animation changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SvgAnimAttr {
+  Transform(SvgTransformType),
+  Opacity,
+  Fill,
+  Stroke,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SvgTransformType {
+  Translate,
+  Scale,
+  Rotate,
+  SkewX,
+  SkewY,
+}
+
+/// How a SVG animation interpolates between its values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SvgCalcMode {
+  /// Jump from one value to the next without interpolation.
+  Discrete,
+  Linear,
+  /// Interpolate the values at an even pace, it's interpolated as `Linear`.
+  Paced,
+  /// Interpolate the values with the cubic bézier easings of the key splines.
+  Spline,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SvgAnimValue {
+  Number(f32),
+  Color(Color),
+  /// The parameters of the transform, `[tx, ty, _]` for translate, `[sx, sy,
+  /// _]` for scale, `[angle, cx, cy]` for rotate and `[angle, _, _]` for the
+  /// skews. The angles are in degrees.
+  Transform([f32; 3]),
+}
+
+impl SvgTransformType {
+  /// The transform of the parameters of a transform value.
+  pub fn to_transform(&self, [a, b, c]: [f32; 3]) -> Transform {
+    match self {
+      SvgTransformType::Translate => Transform::translation(a, b),
+      SvgTransformType::Scale => Transform::scale(a, b),
+      SvgTransformType::Rotate => Transform::translation(-b, -c)
+        .then_rotate(Angle::degrees(a))
+        .then_translate(Vector::new(b, c)),
+      SvgTransformType::SkewX => {
+        Transform::new(1., 0., Angle::degrees(a).radians.tan(), 1., 0., 0.)
+      }
+      SvgTransformType::SkewY => {
+        Transform::new(1., Angle::degrees(a).radians.tan(), 0., 1., 0., 0.)
+      }
+    }
+  }
+
+  /// The parameters of the transform that changes nothing.
+  fn identity(&self, other: &[f32; 3]) -> [f32; 3] {
+    match self {
+      SvgTransformType::Scale => [1., 1., 0.],
+      // Rotate around the same center.
+      SvgTransformType::Rotate => [0., other[1], other[2]],
+      _ => [0., 0., 0.],
+    }
+  }
+}
+
+impl SvgAnimValue {
+  fn add(&self, by: &SvgAnimValue) -> Option<SvgAnimValue> {
+    let value = match (self, by) {
+      (SvgAnimValue::Number(a), SvgAnimValue::Number(b)) => SvgAnimValue::Number(a + b),
+      (SvgAnimValue::Color(a), SvgAnimValue::Color(b)) => SvgAnimValue::Color(Color::new(
+        a.red.saturating_add(b.red),
+        a.green.saturating_add(b.green),
+        a.blue.saturating_add(b.blue),
+        a.alpha.max(b.alpha),
+      )),
+      (SvgAnimValue::Transform(a), SvgAnimValue::Transform(b)) => {
+        SvgAnimValue::Transform([a[0] + b[0], a[1] + b[1], a[2] + b[2]])
+      }
+      _ => return None,
+    };
+    Some(value)
+  }
+}
+
+/// The prefix of the id of the group that wraps an animated element, it's
+/// followed by the index of the target.
+const TARGET_ID_PREFIX: &str = "__ribir_smil_";
+
+/// The elements can be animated.
+const ANIMATABLE_TAGS: [&str; 12] = [
+  "g", "path", "rect", "circle", "ellipse", "line", "polyline", "polygon", "use", "image", "text",
+  "a",
+];
+
+/// The elements whose content is not painted directly, the animations in them
+/// are not supported.
+const UNPAINTED_TAGS: [&str; 6] = ["defs", "clipPath", "mask", "pattern", "marker", "symbol"];
+
+/// The index of the animation target that the group wraps.
+pub(super) fn target_of_group(id: &str) -> Option<usize> {
+  id.strip_prefix(TARGET_ID_PREFIX)?.parse().ok()
+}
+
+/// The animations parsed from the SVG source.
+pub(super) struct Smil {
+  /// The SVG without the animation elements, and its animated elements are
+  /// wrapped in the groups.
+  pub(super) svg: String,
+  pub(super) animations: Vec<SvgAnimation>,
+  /// The marks of the animated fill and stroke of every target.
+  pub(super) color_marks: Vec<[Option<u8>; 2]>,
+  /// The base colors of the marks.
+  pub(super) base_colors: Vec<Color>,
+}
+
+/// The animations of the SVG and the elements they animate.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(super) struct SvgAnimations {
+  pub(super) list: Box<[SvgAnimation]>,
+  pub(super) targets: Box<[AnimTarget]>,
+  /// The base colors of the animated fills and strokes, indexed by the marks.
+  pub(super) base_colors: Box<[Color]>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct AnimTarget {
+  /// The path of the bundle that paints the element in the commands, `None` if
+  /// the element isn't painted as a bundle.
+  pub(super) cmd_path: Option<Box<[usize]>>,
+  /// The transform from the parent of the element to the SVG.
+  pub(super) parent: Transform,
+  pub(super) transform: Transform,
+  pub(super) parent_alpha: f32,
+  pub(super) opacity: f32,
+  /// The marks of the animated fill and stroke.
+  pub(super) color_marks: [Option<u8>; 2],
+}
+
+/// An animated bundle in a frame, the path of the bundle in the commands, and
+/// its transform and opacity.
+pub(super) type FrameBundle<'a> = (&'a [usize], Transform, f32);
+
+impl SvgAnimations {
+  /// The animated bundles and the colors of the marks in the frame that the
+  /// animations have the `values`.
+  pub(super) fn frame(
+    &self, values: &[Option<SvgAnimValue>],
+  ) -> (Vec<FrameBundle<'_>>, Vec<Color>) {
+    let mut states: Vec<_> = self
+      .targets
+      .iter()
+      .map(|t| (t.transform, t.opacity))
+      .collect();
+    let mut colors = self.base_colors.to_vec();
+    for (anim, value) in self.list.iter().zip(values) {
+      let Some(value) = value else { continue };
+      let target = &self.targets[anim.target];
+      let (transform, opacity) = &mut states[anim.target];
+      match (anim.attribute, value) {
+        (SvgAnimAttr::Transform(ty), SvgAnimValue::Transform(params)) => {
+          let ts = ty.to_transform(*params);
+          *transform = if anim.additive { ts.then(transform) } else { ts };
+        }
+        (SvgAnimAttr::Opacity, SvgAnimValue::Number(value)) => *opacity = value.clamp(0., 1.),
+        (SvgAnimAttr::Fill, SvgAnimValue::Color(color)) => {
+          if let Some(mark) = target.color_marks[0] {
+            colors[mark as usize] = *color;
+          }
+        }
+        (SvgAnimAttr::Stroke, SvgAnimValue::Color(color)) => {
+          if let Some(mark) = target.color_marks[1] {
+            colors[mark as usize] = *color;
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let bundles = self
+      .targets
+      .iter()
+      .zip(states)
+      .filter_map(|(target, (transform, opacity))| {
+        let path = target.cmd_path.as_deref()?;
+        Some((path, transform.then(&target.parent), target.parent_alpha * opacity))
+      })
+      .collect();
+    (bundles, colors)
+  }
+}
+
+/// An animation parsed from an animation element.
+struct Parsed<'a, 'input> {
+  target: Node<'a, 'input>,
+  animation: SvgAnimation,
+  base_color: Option<Color>,
+}
+
+impl Smil {
+  /// Parse the animations of the SVG, return `None` if it has no animation.
+  /// The animated colors are replaced with the colors that have the red and
+  /// green of the `mark`, and the blue is the index of the color.
+  pub(super) fn parse(svg_data: &[u8], mark: [u8; 2]) -> Option<Self> {
+    let text = std::str::from_utf8(svg_data).ok()?;
+    if !text.contains("<animate") && !text.contains("<set") {
+      return None;
+    }
+    let opt = ParsingOptions { allow_dtd: true, ..<_>::default() };
+    let doc = Document::parse_with_options(text, opt).ok()?;
+
+    // The edits of the source, the closing tags of the groups are inserted
+    // before the other edits at the same position.
+    let mut edits: Vec<(Range<usize>, bool, String)> = vec![];
+    let mut targets: Vec<Node> = vec![];
+    let mut color_marks: Vec<[Option<u8>; 2]> = vec![];
+    let mut base_colors = vec![];
+    let mut animations = vec![];
+
+    for node in doc.descendants().filter(|n| is_animation_elem(n)) {
+      edits.push((with_leading_space(text, node.range()), false, String::new()));
+      let Some(Parsed { target, mut animation, base_color }) = parse_animation(&doc, node) else {
+        continue;
+      };
+
+      let idx = match targets.iter().position(|t| t == &target) {
+        Some(idx) => idx,
+        None => {
+          targets.push(target);
+          color_marks.push([None, None]);
+          targets.len() - 1
+        }
+      };
+      let color_idx = match animation.attribute {
+        SvgAnimAttr::Fill => Some(0),
+        SvgAnimAttr::Stroke => Some(1),
+        _ => None,
+      };
+      if let (Some(color_idx), Some(base)) = (color_idx, base_color) {
+        let mark = &mut color_marks[idx][color_idx];
+        if mark.is_none() {
+          if base_colors.len() > u8::MAX as usize {
+            log::warn!("[painter]: too many animated colors in svg, ignored!");
+            continue;
+          }
+          *mark = Some(base_colors.len() as u8);
+          base_colors.push(base);
+        }
+      }
+      animation.target = idx;
+      animations.push(animation);
+    }
+
+    for (idx, (target, marks)) in targets.iter().zip(&color_marks).enumerate() {
+      let range = target.range();
+      // The group takes over the transform and the opacity of the element, that
+      // the animations replace.
+      let mut attrs = String::new();
+      for attr in target
+        .attributes()
+        .filter(|a| a.namespace().is_none() && matches!(a.name(), "transform" | "opacity"))
+      {
+        attrs.push(' ');
+        attrs.push_str(&text[attr.range()]);
+        edits.push((with_leading_space(text, attr.range()), false, String::new()));
+      }
+      let group = format!("<g id=\"{TARGET_ID_PREFIX}{idx}\"{attrs}>");
+      edits.push((range.start..range.start, false, group));
+      edits.push((range.end..range.end, true, "</g>".to_string()));
+
+      let [red, green] = mark;
+      let style = ["fill", "stroke"]
+        .iter()
+        .zip(marks)
+        .filter_map(|(name, mark)| mark.map(|m| format!("{name}:#{red:02X}{green:02X}{m:02X}")))
+        .collect::<Vec<_>>()
+        .join(";");
+      if !style.is_empty() {
+        // The style has the highest priority, so the mark colors are not
+        // overridden by the style sheets.
+        match target.attribute_node("style") {
+          Some(attr) => {
+            let end = attr.range_value().end;
+            let sep = if attr.value().trim_end().ends_with(';') { "" } else { ";" };
+            edits.push((end..end, false, format!("{sep}{style}")));
+          }
+          None => {
+            let tag = &text[range.start + 1..];
+            let name_end =
+              range.start + 1 + tag.find(|c: char| c.is_whitespace() || c == '/' || c == '>')?;
+            edits.push((name_end..name_end, false, format!(" style=\"{style}\"")));
+          }
+        }
+      }
+    }
+
+    edits.sort_by_key(|(range, closing, _)| (range.start, !closing, range.end));
+    let mut svg = String::with_capacity(text.len() + edits.len() * 16);
+    let mut cursor = 0;
+    for (range, _, replace) in edits {
+      // The edits in a removed animation element.
+      if range.start < cursor {
+        continue;
+      }
+      svg.push_str(&text[cursor..range.start]);
+      svg.push_str(&replace);
+      cursor = range.end;
+    }
+    svg.push_str(&text[cursor..]);
+
+    Some(Smil { svg, animations, color_marks, base_colors })
+  }
+}
+
+fn is_animation_elem(node: &Node) -> bool {
+  node.is_element()
+    && matches!(
+      node.tag_name().name(),
+      "animate" | "animateTransform" | "animateColor" | "animateMotion" | "set"
+    )
+}
+
+fn parse_animation<'a, 'input>(
+  doc: &'a Document<'input>, node: Node<'a, 'input>,
+) -> Option<Parsed<'a, 'input>> {
+  let tag = node.tag_name().name();
+  if tag == "animateMotion" {
+    log::warn!("[painter]: not support `animateMotion` in svg, ignored!");
+    return None;
+  }
+
+  let target = match href(&node) {
+    Some(href) => {
+      let id = href.strip_prefix('#')?;
+      doc
+        .descendants()
+        .find(|n| n.attribute("id") == Some(id))
+    }
+    None => node.parent_element(),
+  };
+  let Some(target) = target.filter(is_animatable) else {
+    log::warn!("[painter]: the target of the svg animation `{tag}` is not supported, ignored!");
+    return None;
+  };
+
+  let name = node
+    .attribute("attributeName")
+    .unwrap_or_default();
+  let attribute = match (tag, name) {
+    ("animateTransform", "transform") => {
+      let ty = match node.attribute("type").unwrap_or("translate") {
+        "translate" => SvgTransformType::Translate,
+        "scale" => SvgTransformType::Scale,
+        "rotate" => SvgTransformType::Rotate,
+        "skewX" => SvgTransformType::SkewX,
+        "skewY" => SvgTransformType::SkewY,
+        ty => {
+          log::warn!("[painter]: the svg transform type `{ty}` is invalid, ignored!");
+          return None;
+        }
+      };
+      SvgAnimAttr::Transform(ty)
+    }
+    (tag, "opacity") if tag != "animateTransform" => SvgAnimAttr::Opacity,
+    (tag, "fill") if tag != "animateTransform" => SvgAnimAttr::Fill,
+    (tag, "stroke") if tag != "animateTransform" => SvgAnimAttr::Stroke,
+    (_, name) => {
+      log::warn!("[painter]: not support to animate the `{name}` in svg, ignored!");
+      return None;
+    }
+  };
+
+  let base_color = match attribute {
+    SvgAnimAttr::Fill => Some(base_color(&target, "fill")?),
+    SvgAnimAttr::Stroke => Some(base_color(&target, "stroke")?),
+    _ => None,
+  };
+  let base = match attribute {
+    SvgAnimAttr::Fill | SvgAnimAttr::Stroke => base_color.map(SvgAnimValue::Color),
+    SvgAnimAttr::Opacity => {
+      let opacity = presentation_attr(&target, "opacity").and_then(parse_number);
+      Some(SvgAnimValue::Number(opacity.unwrap_or(1.)))
+    }
+    SvgAnimAttr::Transform(_) => None,
+  };
+
+  let (values, by_additive) = anim_values(&node, attribute, base)?;
+  let is_set = tag == "set";
+
+  let begin = match node.attribute("begin") {
+    Some(begin) => {
+      let offset = begin
+        .split(';')
+        .filter_map(clock_value)
+        .reduce(f32::min);
+      let Some(offset) = offset else {
+        log::warn!("[painter]: not support the svg animation begins at `{begin}`, ignored!");
+        return None;
+      };
+      offset
+    }
+    None => 0.,
+  };
+  let dur = node
+    .attribute("dur")
+    .and_then(clock_value)
+    .filter(|dur| *dur > 0.);
+  // A `set` without duration keeps its value since it begins.
+  let (dur, freeze) = match dur {
+    Some(dur) => (dur, node.attribute("fill") == Some("freeze")),
+    None if is_set => (f32::EPSILON, true),
+    None => {
+      log::warn!("[painter]: the svg animation `{tag}` has no duration, ignored!");
+      return None;
+    }
+  };
+
+  let repeat_count = match node.attribute("repeatCount") {
+    Some("indefinite") => Some(f32::INFINITY),
+    Some(count) => parse_number(count).filter(|c| *c > 0.),
+    None => None,
+  };
+  let repeat_dur = match node.attribute("repeatDur") {
+    Some("indefinite") => Some(f32::INFINITY),
+    Some(repeat_dur) => clock_value(repeat_dur).map(|d| d / dur),
+    None => None,
+  };
+  let repeat = match (repeat_count, repeat_dur) {
+    (Some(count), Some(times)) => count.min(times),
+    (Some(times), None) | (None, Some(times)) => times,
+    (None, None) => 1.,
+  };
+
+  let mut calc_mode = match node.attribute("calcMode") {
+    _ if is_set => SvgCalcMode::Discrete,
+    Some("discrete") => SvgCalcMode::Discrete,
+    Some("paced") => SvgCalcMode::Paced,
+    Some("spline") => SvgCalcMode::Spline,
+    _ => SvgCalcMode::Linear,
+  };
+
+  let key_times = node
+    .attribute("keyTimes")
+    .and_then(|key_times| {
+      let times = key_times
+        .split(';')
+        .map(|t| parse_number(t).filter(|t| (0. ..=1.).contains(t)))
+        .collect::<Option<Vec<_>>>()
+        .filter(|times| {
+          times.len() == values.len()
+            && times.first() == Some(&0.)
+            && times.windows(2).all(|w| w[0] <= w[1])
+            && (calc_mode == SvgCalcMode::Discrete || times.last() == Some(&1.))
+        });
+      if times.is_none() {
+        log::warn!("[painter]: the svg animation keyTimes `{key_times}` is invalid, ignored!");
+      }
+      times
+    })
+    .unwrap_or_default();
+
+  let mut key_splines = vec![];
+  if calc_mode == SvgCalcMode::Spline {
+    let splines = node.attribute("keySplines").and_then(|splines| {
+      splines
+        .split(';')
+        .map(|s| {
+          let nums = NumberListParser::from(s)
+            .map(|n| {
+              n.ok()
+                .map(|n| n as f32)
+                .filter(|n| (0. ..=1.).contains(n))
+            })
+            .collect::<Option<Vec<_>>>()?;
+          <[f32; 4]>::try_from(nums).ok()
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|splines| splines.len() + 1 == values.len())
+    });
+    match splines {
+      Some(splines) => key_splines = splines,
+      None => {
+        log::warn!("[painter]: the svg animation keySplines is invalid, interpolated linearly.");
+        calc_mode = SvgCalcMode::Linear;
+      }
+    }
+  }
+
+  let animation = SvgAnimation {
+    target: 0,
+    attribute,
+    begin: Duration::from_secs_f32(begin.max(0.)),
+    elapsed: Duration::from_secs_f32((-begin).max(0.)),
+    dur: Duration::from_secs_f32(dur),
+    repeat: repeat.is_finite().then_some(repeat),
+    freeze,
+    additive: by_additive || node.attribute("additive") == Some("sum"),
+    calc_mode,
+    values: values.into_boxed_slice(),
+    key_times: key_times.into_boxed_slice(),
+    key_splines: key_splines.into_boxed_slice(),
+  };
+  Some(Parsed { target, animation, base_color })
+}
+
+/// The values of the animation, and whether the animation adds to the base
+/// value because it only has the `by` value.
+fn anim_values(
+  node: &Node, attribute: SvgAnimAttr, base: Option<SvgAnimValue>,
+) -> Option<(Vec<SvgAnimValue>, bool)> {
+  let parse = |s: &str| {
+    let value = match attribute {
+      SvgAnimAttr::Transform(ty) => transform_params(ty, s).map(SvgAnimValue::Transform),
+      SvgAnimAttr::Opacity => parse_number(s).map(|n| SvgAnimValue::Number(n.clamp(0., 1.))),
+      SvgAnimAttr::Fill | SvgAnimAttr::Stroke => paint_color(s).map(SvgAnimValue::Color),
+    };
+    if value.is_none() {
+      log::warn!("[painter]: the svg animation value `{s}` is invalid, ignored!");
+    }
+    value
+  };
+  // The base value to animate from, the transform animates from the identity.
+  let base_of = |other: &SvgAnimValue| match (attribute, other) {
+    (SvgAnimAttr::Transform(ty), SvgAnimValue::Transform(params)) => {
+      Some(SvgAnimValue::Transform(ty.identity(params)))
+    }
+    _ => base.clone(),
+  };
+
+  if let Some(values) = node.attribute("values") {
+    let values = values
+      .split(';')
+      .map(str::trim)
+      .filter(|v| !v.is_empty())
+      .map(parse)
+      .collect::<Option<Vec<_>>>()?;
+    return (!values.is_empty()).then_some((values, false));
+  }
+
+  let from = node.attribute("from").map(parse);
+  let to = node.attribute("to").map(parse);
+  let by = node.attribute("by").map(parse);
+  let values = match (from, to, by) {
+    (Some(from), Some(to), _) => (vec![from?, to?], false),
+    (Some(from), None, Some(by)) => {
+      let from = from?;
+      let to = from.add(&by?)?;
+      (vec![from, to], false)
+    }
+    (None, Some(to), _) if node.tag_name().name() == "set" => (vec![to?], false),
+    (None, Some(to), _) => {
+      let to = to?;
+      (vec![base_of(&to)?, to], false)
+    }
+    (None, None, Some(by)) => {
+      let by = by?;
+      match attribute {
+        SvgAnimAttr::Transform(_) => (vec![base_of(&by)?, by], true),
+        _ => {
+          let from = base_of(&by)?;
+          let to = from.add(&by)?;
+          (vec![from, to], false)
+        }
+      }
+    }
+    _ => {
+      log::warn!("[painter]: the svg animation has no value, ignored!");
+      return None;
+    }
+  };
+  Some(values)
+}
+
+/// Extend the range to the whitespace before it, to remove it together.
+fn with_leading_space(text: &str, range: Range<usize>) -> Range<usize> {
+  text[..range.start].trim_end().len()..range.end
+}
+
+fn href<'a>(node: &Node<'a, '_>) -> Option<&'a str> {
+  node
+    .attribute(("http://www.w3.org/1999/xlink", "href"))
+    .or_else(|| node.attribute("href"))
+}
+
+fn is_animatable(node: &Node) -> bool {
+  let name = node.tag_name().name();
+  ANIMATABLE_TAGS.contains(&name)
+    && node
+      .ancestors()
+      .skip(1)
+      .all(|n| !UNPAINTED_TAGS.contains(&n.tag_name().name()))
+}
+
+/// The value of the presentation attribute, the style of the element takes
+/// precedence over the attribute.
+fn presentation_attr<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+  let style = node.attribute("style").and_then(|style| {
+    style
+      .split(';')
+      .filter_map(|decl| decl.split_once(':'))
+      .filter(|(n, _)| n.trim() == name)
+      .map(|(_, value)| value.trim())
+      .next_back()
+  });
+  style.or_else(|| node.attribute(name))
+}
+
+/// The color the element is filled or stroked with, it's inherited from the
+/// ancestors.
+fn base_color(node: &Node, name: &str) -> Option<Color> {
+  let value = node
+    .ancestors()
+    .filter_map(|n| presentation_attr(&n, name))
+    .find(|v| *v != "inherit");
+  match value {
+    Some(value) => {
+      let color = paint_color(value);
+      if color.is_none() {
+        log::warn!("[painter]: not support to animate the svg {name} `{value}`, ignored!");
+      }
+      color
+    }
+    None if name == "fill" => Some(Color::BLACK),
+    None => Some(Color::TRANSPARENT),
+  }
+}
+
+fn paint_color(s: &str) -> Option<Color> {
+  let s = s.trim();
+  if s == "none" {
+    return Some(Color::TRANSPARENT);
+  }
+  let svgtypes::Color { red, green, blue, alpha } = s.parse().ok()?;
+  Some(Color::new(red, green, blue, alpha))
+}
+
+fn parse_number(s: &str) -> Option<f32> {
+  s.trim()
+    .parse::<svgtypes::Number>()
+    .ok()
+    .map(|n| n.0 as f32)
+}
+
+fn transform_params(ty: SvgTransformType, s: &str) -> Option<[f32; 3]> {
+  let nums = NumberListParser::from(s)
+    .map(|n| n.ok().map(|n| n as f32))
+    .collect::<Option<Vec<_>>>()?;
+  let params = match (ty, nums.as_slice()) {
+    (SvgTransformType::Translate, [x]) => [*x, 0., 0.],
+    (SvgTransformType::Scale, [s]) => [*s, *s, 0.],
+    (SvgTransformType::Translate | SvgTransformType::Scale, [x, y]) => [*x, *y, 0.],
+    (SvgTransformType::Rotate, [angle]) => [*angle, 0., 0.],
+    (SvgTransformType::Rotate, [angle, cx, cy]) => [*angle, *cx, *cy],
+    (SvgTransformType::SkewX | SvgTransformType::SkewY, [angle]) => [*angle, 0., 0.],
+    _ => return None,
+  };
+  Some(params)
+}
+
+/// Parse the clock value in seconds, the events and the other animations
+/// are not supported.
+fn clock_value(s: &str) -> Option<f32> {
+  let s = s.trim();
+  let (sign, s) = match s.strip_prefix('-') {
+    Some(s) => (-1., s),
+    None => (1., s.strip_prefix('+').unwrap_or(s)),
+  };
+  let num = |s: &str| {
+    s.parse::<f32>()
+      .ok()
+      .filter(|n| n.is_finite() && *n >= 0.)
+  };
+  let secs = if let Some(ms) = s.strip_suffix("ms") {
+    num(ms)? / 1000.
+  } else if let Some(min) = s.strip_suffix("min") {
+    num(min)? * 60.
+  } else if let Some(h) = s.strip_suffix('h') {
+    num(h)? * 3600.
+  } else if let Some(secs) = s.strip_suffix('s') {
+    num(secs)?
+  } else if s.contains(':') {
+    s.split(':')
+      .rev()
+      .enumerate()
+      .try_fold(0., |secs, (i, part)| {
+        (i < 3).then_some(())?;
+        Some(secs + num(part)? * 60f32.powi(i as i32))
+      })?
+  } else {
+    num(s)?
+  };
+  Some(sign * secs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn clock_values() {
+    assert_eq!(clock_value("1.5s"), Some(1.5));
+    assert_eq!(clock_value("200ms"), Some(0.2));
+    assert_eq!(clock_value(" 2 "), Some(2.));
+    assert_eq!(clock_value("-0.5s"), Some(-0.5));
+    assert_eq!(clock_value("1min"), Some(60.));
+    assert_eq!(clock_value("01:02:03.5"), Some(3723.5));
+    assert_eq!(clock_value("indefinite"), None);
+    assert_eq!(clock_value("spin.end"), None);
+  }
+
+  #[test]
+  fn wrap_animated_elements() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="48">
+  <g id="dots" style="fill:red">
+    <circle cx="8" cy="24" r="4" opacity="0.5" transform="translate(1 0)">
+      <animate attributeName="opacity" values="1;0.2;1" dur="1s" begin="-0.5s" repeatCount="indefinite"/>
+    </circle>
+    <circle cx="24" cy="24" r="4"/>
+  </g>
+  <animateTransform href="#dots" attributeName="transform" type="rotate" from="0 24 24" to="360 24 24" dur="2s" fill="freeze"/>
+  <set href="#dots" attributeName="fill" to="blue" begin="1s"/>
+</svg>"##;
+
+    let Smil { svg, animations, color_marks, base_colors } =
+      Smil::parse(svg.as_bytes(), [0x19, 0x1D]).unwrap();
+    assert_eq!(
+      svg,
+      [
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="48">"#,
+        r#"  <g id="__ribir_smil_1"><g id="dots" style="fill:red;fill:#191D00">"#,
+        r#"    <g id="__ribir_smil_0" opacity="0.5" transform="translate(1 0)"><circle cx="8" cy="24" r="4">"#,
+        r#"    </circle></g>"#,
+        r#"    <circle cx="24" cy="24" r="4"/>"#,
+        r#"  </g></g>"#,
+        r#"</svg>"#,
+      ]
+      .join("\n")
+    );
+    assert_eq!(color_marks, [[None, None], [Some(0), None]]);
+    assert_eq!(base_colors, [Color::from_rgb(255, 0, 0)]);
+
+    assert_eq!(animations.len(), 3);
+    let opacity = &animations[0];
+    assert_eq!(opacity.target, 0);
+    assert_eq!(opacity.attribute, SvgAnimAttr::Opacity);
+    assert_eq!(opacity.begin, Duration::ZERO);
+    assert_eq!(opacity.elapsed, Duration::from_millis(500));
+    assert_eq!(opacity.repeat, None);
+    assert_eq!(opacity.values.len(), 3);
+
+    let rotate = &animations[1];
+    assert_eq!(rotate.target, 1);
+    assert_eq!(rotate.attribute, SvgAnimAttr::Transform(SvgTransformType::Rotate));
+    assert!(rotate.freeze);
+    assert_eq!(rotate.repeat, Some(1.));
+    assert_eq!(
+      &*rotate.values,
+      &[SvgAnimValue::Transform([0., 24., 24.]), SvgAnimValue::Transform([360., 24., 24.])]
+    );
+
+    let set = &animations[2];
+    assert_eq!(set.target, 1);
+    assert_eq!(set.begin, Duration::from_secs(1));
+    assert_eq!(set.calc_mode, SvgCalcMode::Discrete);
+    assert!(set.freeze);
+    assert_eq!(&*set.values, &[SvgAnimValue::Color(Color::from_rgb(0, 0, 255))]);
+  }
+
+  #[test]
+  fn no_animation() {
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="8" height="8"/></svg>"#;
+    assert!(Smil::parse(svg.as_bytes(), [0x19, 0x1D]).is_none());
+  }
+}
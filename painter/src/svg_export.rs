@@ -0,0 +1,708 @@
+use std::fmt::{Display, Write};
+
+use ahash::HashMap;
+use ribir_algo::Resource;
+use ribir_geom::{DeviceRect, Rect, Size, Transform};
+
+use crate::{
+  BlendMode, BlurTarget, Color, CommandBrush, FilterQuality, GradientStop, LayerCommand, LineCap,
+  LineJoin, MaskCommand, MaskMode, PaintCommand, PaintPathAction, PainterBackend, PaintingStyle,
+  Path, PathCommand, PathSegment, PixelImage, ShadowCommand, SpreadMethod,
+  color::{LinearGradient, RadialGradient},
+};
+
+/// A painter backend that exports the paint commands as an SVG document.
+///
+/// The clips are kept as `<clipPath>`, the gradients as `<linearGradient>` and
+/// `<radialGradient>`, the images as embedded PNG data, the shadows as
+/// `<filter>` with a gaussian blur, and the bundles as `<g>` groups. The blur
+/// layers are `<g>` groups with a gaussian blur filter, but SVG can't blur the
+/// backdrop, so a backdrop blur layer only keeps its content.
+///
+/// # Example
+///
+/// ```
+/// use ribir_geom::{Rect, Size};
+/// use ribir_painter::{Color, Painter, SvgDocument};
+///
+/// let mut painter = Painter::new(Rect::from_size(Size::new(100., 100.)));
+/// painter
+///   .set_fill_brush(Color::RED)
+///   .rect(&Rect::from_size(Size::new(50., 50.)))
+///   .fill();
+///
+/// let doc = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish());
+/// assert!(doc.to_string().contains("<path"));
+/// ```
+#[derive(Default)]
+pub struct SvgExporter {
+  surface_color: Option<Color>,
+}
+
+/// The SVG document generated by the [`SvgExporter`].
+pub struct SvgDocument {
+  size: Size,
+  defs: String,
+  content: String,
+  id_cnt: usize,
+  images: HashMap<(Resource<PixelImage>, FilterQuality), String>,
+}
+
+impl PainterBackend for SvgExporter {
+  type Texture = SvgDocument;
+
+  fn begin_frame(&mut self, surface: Color) { self.surface_color = Some(surface); }
+
+  /// The document appends the commands of every frame, so the damages are
+  /// ignored.
+  fn draw_commands(
+    &mut self, viewport: DeviceRect, _damages: &[DeviceRect], commands: &[PaintCommand],
+    global_matrix: &Transform, output: &mut Self::Texture,
+  ) {
+    if let Some(color) = self.surface_color.take() {
+      if color.alpha > 0 {
+        let (paint, opacity) = color_paint(color);
+        let _ = write!(
+          output.content,
+          r#"<rect width="100%" height="100%"{}/>"#,
+          paint_attrs("fill", &paint, opacity)
+        );
+      }
+    }
+
+    let viewport = viewport.to_f32().cast_unit();
+    let doc_rect = Rect::from_size(output.size);
+    let clip_viewport = !viewport.contains_rect(&doc_rect);
+    if clip_viewport {
+      let id = output.new_id();
+      let _ = write!(
+        output.defs,
+        r#"<clipPath id="{id}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+        viewport.min_x(),
+        viewport.min_y(),
+        viewport.width(),
+        viewport.height()
+      );
+      let _ = write!(output.content, r#"<g clip-path="url(#{id})">"#);
+    }
+
+    let _ = write!(output.content, r#"<g{}>"#, transform(global_matrix));
+    output.write_commands(commands);
+    output.content.push_str("</g>");
+
+    if clip_viewport {
+      output.content.push_str("</g>");
+    }
+  }
+
+  fn end_frame(&mut self) { self.surface_color = None; }
+}
+
+impl SvgDocument {
+  /// Create an empty document with the `size`.
+  pub fn new(size: Size) -> Self {
+    Self { size, defs: String::new(), content: String::new(), id_cnt: 0, images: <_>::default() }
+  }
+
+  /// Export the `commands` as a document with the `size`.
+  pub fn from_commands(size: Size, commands: &[PaintCommand]) -> Self {
+    let mut doc = Self::new(size);
+    let mut exporter = SvgExporter::default();
+    let viewport = Rect::from_size(size)
+      .round_out()
+      .to_i32()
+      .cast_unit();
+    exporter.begin_frame(Color::TRANSPARENT);
+    exporter.draw_commands(viewport, &[viewport], commands, &Transform::identity(), &mut doc);
+    exporter.end_frame();
+    doc
+  }
+
+  #[inline]
+  pub fn size(&self) -> Size { self.size }
+
+  fn new_id(&mut self) -> String {
+    self.id_cnt += 1;
+    format!("ribir{}", self.id_cnt)
+  }
+
+  fn write_commands(&mut self, commands: &[PaintCommand]) {
+    let mut clips = 0;
+    for cmd in commands {
+      match cmd {
+        PaintCommand::Path(PathCommand { path, transform: ts, action, .. }) => match action {
+          PaintPathAction::Clip => {
+            let id = self.new_id();
+            let _ = write!(
+              self.defs,
+              r#"<clipPath id="{id}"><path d="{}"{}/></clipPath>"#,
+              path_data(path),
+              transform(ts)
+            );
+            let _ = write!(self.content, r#"<g clip-path="url(#{id})">"#);
+            clips += 1;
+          }
+          PaintPathAction::Paint { brush, painting_style, blend_mode } => {
+            let line_width = match painting_style {
+              PaintingStyle::Fill => None,
+              PaintingStyle::Stroke(options) => Some(options.width),
+            };
+            let (paint, opacity) = self.paint(brush, &path.bounds(line_width));
+            let style = match painting_style {
+              PaintingStyle::Fill => paint_attrs("fill", &paint, opacity),
+              PaintingStyle::Stroke(options) => {
+                let cap = match options.line_cap {
+                  LineCap::Butt => "butt",
+                  LineCap::Round => "round",
+                  LineCap::Square => "square",
+                };
+                let join = match options.line_join {
+                  LineJoin::Miter => "miter",
+                  LineJoin::MiterClip => "miter-clip",
+                  LineJoin::Round => "round",
+                  LineJoin::Bevel => "bevel",
+                };
+                let mut attrs = format!(
+                  r#" fill="none"{} stroke-width="{}" stroke-linecap="{cap}" stroke-linejoin="{join}" stroke-miterlimit="{}""#,
+                  paint_attrs("stroke", &paint, opacity),
+                  options.width,
+                  options.miter_limit
+                );
+                if !options.dash_array.is_empty() {
+                  let dashes: Vec<_> = options
+                    .dash_array
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect();
+                  let _ = write!(
+                    attrs,
+                    r#" stroke-dasharray="{}" stroke-dashoffset="{}""#,
+                    dashes.join(" "),
+                    options.dash_offset
+                  );
+                }
+                attrs
+              }
+            };
+            let _ = write!(
+              self.content,
+              r#"<path d="{}"{}{style}{}/>"#,
+              path_data(path),
+              transform(ts),
+              blend(*blend_mode)
+            );
+          }
+        },
+        PaintCommand::Shadow(cmd) => self.write_shadow(cmd),
+        PaintCommand::Layer(layer) => self.write_layer(layer),
+        PaintCommand::Mask(mask) => self.write_mask(mask),
+        PaintCommand::PopClip => {
+          if clips > 0 {
+            clips -= 1;
+            self.content.push_str("</g>");
+          }
+        }
+        PaintCommand::Bundle { transform: ts, opacity, blend_mode, cmds, .. } => {
+          let _ = write!(self.content, r#"<g{}"#, transform(ts));
+          if *opacity < 1. {
+            let _ = write!(self.content, r#" opacity="{opacity}""#);
+          }
+          self.content.push_str(&blend(*blend_mode));
+          self.content.push('>');
+          self.write_commands(cmds);
+          self.content.push_str("</g>");
+        }
+      }
+    }
+
+    // Close the clip groups that have no `PopClip` command.
+    (0..clips).for_each(|_| self.content.push_str("</g>"));
+  }
+
+  fn write_shadow(&mut self, cmd: &ShadowCommand) {
+    let ShadowCommand { path, transform: ts, color, blur_radius, .. } = cmd;
+    let (paint, opacity) = color_paint(*color);
+    let mut attrs = paint_attrs("fill", &paint, opacity);
+    if *blur_radius > 0. {
+      let id = self.new_id();
+      let extent = cmd.blur_extent();
+      let Rect { origin, size } = path.bounds(None).inflate(extent, extent);
+      let _ = write!(
+        self.defs,
+        r#"<filter id="{id}" filterUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}"><feGaussianBlur stdDeviation="{}"/></filter>"#,
+        origin.x,
+        origin.y,
+        size.width,
+        size.height,
+        blur_radius / 2.
+      );
+      let _ = write!(attrs, r#" filter="url(#{id})""#);
+    }
+    let _ = write!(self.content, r#"<path d="{}"{}{attrs}/>"#, path_data(path), transform(ts));
+  }
+
+  fn write_layer(&mut self, layer: &LayerCommand) {
+    let sigma = layer.blur_sigma(&layer.transform);
+    if layer.target == BlurTarget::Content && sigma > 0. {
+      let id = self.new_id();
+      let Rect { origin, size } = layer.paint_bounds;
+      let _ = write!(
+        self.defs,
+        r#"<filter id="{id}" filterUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}"><feGaussianBlur stdDeviation="{sigma}"/></filter>"#,
+        origin.x, origin.y, size.width, size.height,
+      );
+      let _ = write!(self.content, r#"<g filter="url(#{id})">"#);
+      self.write_commands(&layer.cmds);
+      self.content.push_str("</g>");
+    } else {
+      self.write_commands(&layer.cmds);
+    }
+  }
+
+  fn write_mask(&mut self, mask: &MaskCommand) {
+    let id = self.new_id();
+    // The mask content is written to the definitions.
+    let content = std::mem::take(&mut self.content);
+    self.write_commands(&mask.mask);
+    let mask_content = std::mem::replace(&mut self.content, content);
+    let mode = match mask.mode {
+      MaskMode::Alpha => "alpha",
+      MaskMode::Luminance => "luminance",
+    };
+    let Rect { origin, size } = mask.paint_bounds;
+    let _ = write!(
+      self.defs,
+      r#"<mask id="{id}" mask-type="{mode}" maskUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}">{mask_content}</mask>"#,
+      origin.x, origin.y, size.width, size.height,
+    );
+    let _ = write!(self.content, r#"<g mask="url(#{id})">"#);
+    self.write_commands(&mask.cmds);
+    self.content.push_str("</g>");
+  }
+
+  /// Return the paint value of the brush that paints the `bounds` and its
+  /// opacity.
+  fn paint(&mut self, brush: &CommandBrush, bounds: &Rect) -> (String, f32) {
+    match brush {
+      CommandBrush::Color(color) => color_paint(*color),
+      CommandBrush::Image { img, opacity, filter_quality } => {
+        let id = self.image_pattern(img, *filter_quality);
+        (format!("url(#{id})"), *opacity)
+      }
+      CommandBrush::Linear(LinearGradient { start, end, stops, spread_method }) => {
+        let id = self.new_id();
+        let _ = write!(
+          self.defs,
+          r#"<linearGradient id="{id}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}" spreadMethod="{}">{}</linearGradient>"#,
+          start.x,
+          start.y,
+          end.x,
+          end.y,
+          spread(*spread_method),
+          gradient_stops(stops)
+        );
+        (format!("url(#{id})"), 1.)
+      }
+      CommandBrush::Radial(RadialGradient {
+        start_center,
+        start_radius,
+        end_center,
+        end_radius,
+        stops,
+        spread_method,
+      }) => {
+        let id = self.new_id();
+        let _ = write!(
+          self.defs,
+          r#"<radialGradient id="{id}" gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{end_radius}" fx="{}" fy="{}" fr="{start_radius}" spreadMethod="{}">{}</radialGradient>"#,
+          end_center.x,
+          end_center.y,
+          start_center.x,
+          start_center.y,
+          spread(*spread_method),
+          gradient_stops(stops)
+        );
+        (format!("url(#{id})"), 1.)
+      }
+      // SVG has no sweep gradient, so it's approximated by a pattern of the
+      // wedges in solid colors.
+      CommandBrush::Sweep(sweep) => {
+        let id = self.new_id();
+        let Rect { origin, size } = bounds;
+        let _ = write!(
+          self.defs,
+          r#"<pattern id="{id}" patternUnits="userSpaceOnUse" x="{}" y="{}" width="{}" height="{}">"#,
+          origin.x, origin.y, size.width, size.height
+        );
+        // The content of the pattern is relative to its origin.
+        let to_tile = Transform::translation(-origin.x, -origin.y);
+        for (wedge, color) in sweep.wedges(bounds) {
+          let wedge = wedge.transform(&to_tile);
+          let (paint, opacity) = color_paint(color);
+          let _ = write!(
+            self.defs,
+            r#"<path d="{}"{}/>"#,
+            path_data(&wedge),
+            paint_attrs("fill", &paint, opacity)
+          );
+        }
+        self.defs.push_str("</pattern>");
+        (format!("url(#{id})"), 1.)
+      }
+    }
+  }
+
+  /// Return the id of the pattern that repeats the image, the same image only
+  /// embeds once.
+  fn image_pattern(&mut self, img: &Resource<PixelImage>, filter_quality: FilterQuality) -> String {
+    let key = (img.clone(), filter_quality);
+    if let Some(id) = self.images.get(&key) {
+      return id.clone();
+    }
+
+    let id = self.new_id();
+    let (w, h) = (img.width(), img.height());
+    let rendering = match filter_quality {
+      FilterQuality::Nearest => r#" image-rendering="pixelated""#,
+      FilterQuality::Linear => "",
+    };
+    let _ = write!(
+      self.defs,
+      r#"<pattern id="{id}" patternUnits="userSpaceOnUse" width="{w}" height="{h}"><image width="{w}" height="{h}"{rendering} href="{}"/></pattern>"#,
+      image_data_uri(img)
+    );
+    self.images.insert(key, id.clone());
+    id
+  }
+}
+
+impl Display for SvgDocument {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let Size { width, height, .. } = self.size;
+    write!(
+      f,
+      r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )?;
+    if !self.defs.is_empty() {
+      write!(f, "<defs>{}</defs>", self.defs)?;
+    }
+    write!(f, "{}</svg>", self.content)
+  }
+}
+
+fn color_paint(color: Color) -> (String, f32) {
+  let Color { red, green, blue, alpha } = color;
+  (format!("#{red:02x}{green:02x}{blue:02x}"), alpha as f32 / 255.)
+}
+
+/// Return the `fill` or `stroke` attributes with the paint and its opacity.
+fn paint_attrs(name: &str, paint: &str, opacity: f32) -> String {
+  if opacity < 1. {
+    format!(r#" {name}="{paint}" {name}-opacity="{opacity}""#)
+  } else {
+    format!(r#" {name}="{paint}""#)
+  }
+}
+
+/// Return the `mix-blend-mode` style of the blend mode.
+fn blend(mode: BlendMode) -> String {
+  let mode = match mode {
+    BlendMode::Normal => return String::new(),
+    BlendMode::Multiply => "multiply",
+    BlendMode::Screen => "screen",
+    BlendMode::Overlay => "overlay",
+    BlendMode::Darken => "darken",
+    BlendMode::Lighten => "lighten",
+    BlendMode::Difference => "difference",
+    BlendMode::Plus => "plus-lighter",
+  };
+  format!(r#" style="mix-blend-mode:{mode}""#)
+}
+
+fn transform(ts: &Transform) -> String {
+  if *ts == Transform::identity() {
+    String::new()
+  } else {
+    format!(
+      r#" transform="matrix({} {} {} {} {} {})""#,
+      ts.m11, ts.m12, ts.m21, ts.m22, ts.m31, ts.m32
+    )
+  }
+}
+
+fn spread(spread: SpreadMethod) -> &'static str {
+  match spread {
+    SpreadMethod::Pad => "pad",
+    SpreadMethod::Reflect => "reflect",
+    SpreadMethod::Repeat => "repeat",
+  }
+}
+
+fn gradient_stops(stops: &[GradientStop]) -> String {
+  stops.iter().fold(String::new(), |mut s, stop| {
+    let Color { red, green, blue, alpha } = stop.color;
+    let _ = write!(
+      s,
+      r##"<stop offset="{}" stop-color="#{red:02x}{green:02x}{blue:02x}" stop-opacity="{}"/>"##,
+      stop.offset,
+      alpha as f32 / 255.
+    );
+    s
+  })
+}
+
+fn path_data(path: &Path) -> String {
+  let mut d = String::new();
+  for seg in path.segments() {
+    let _ = match seg {
+      PathSegment::MoveTo(p) => write!(d, "M{} {}", p.x, p.y),
+      PathSegment::LineTo(p) => write!(d, "L{} {}", p.x, p.y),
+      PathSegment::QuadTo { ctrl, to } => write!(d, "Q{} {} {} {}", ctrl.x, ctrl.y, to.x, to.y),
+      PathSegment::CubicTo { to, ctrl1, ctrl2 } => {
+        write!(d, "C{} {} {} {} {} {}", ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y)
+      }
+      PathSegment::Close(true) => write!(d, "Z"),
+      PathSegment::Close(false) => Ok(()),
+    };
+  }
+  d
+}
+
+#[cfg(feature = "png")]
+fn image_data_uri(img: &PixelImage) -> String {
+  let mut png = vec![];
+  if let Err(err) = img.write_as_png(&mut png) {
+    log::warn!("[painter]: failed to encode the image as png for svg, {err}");
+  }
+  format!("data:image/png;base64,{}", base64(&png))
+}
+
+#[cfg(not(feature = "png"))]
+fn image_data_uri(_: &PixelImage) -> String {
+  log::warn!("[painter]: enable the `png` feature to embed images in svg, ignored!");
+  String::new()
+}
+
+#[cfg(feature = "png")]
+fn base64(bytes: &[u8]) -> String {
+  const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+  let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+    let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+    for i in 0..4 {
+      if i <= chunk.len() {
+        out.push(TABLE[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+      } else {
+        out.push('=');
+      }
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_geom::{Point, rect};
+
+  use super::*;
+  use crate::{Painter, StrokeOptions, color::SweepGradient};
+
+  fn painter() -> Painter { Painter::new(Rect::from_size(Size::new(100., 100.))) }
+
+  #[test]
+  fn clip_and_bundle() {
+    let cmds: Box<[PaintCommand]> = {
+      let mut p = painter();
+      p.rect(&rect(0., 0., 10., 10.)).fill();
+      let cmds = p.finish().to_vec();
+      cmds.into()
+    };
+    let mut painter = painter();
+    painter
+      .save()
+      .clip(Path::circle(Point::new(50., 50.), 50.).into())
+      .rect(&rect(0., 0., 100., 100.))
+      .fill()
+      .restore();
+    painter
+      .apply_alpha(0.5)
+      .translate(10., 10.)
+      .draw_bundle_commands(rect(0., 0., 10., 10.), Resource::new(cmds));
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(r#"<clipPath id="ribir1"><path"#));
+    assert!(svg.contains(r#"<g clip-path="url(#ribir1)"><path"#));
+    assert!(svg.contains(r#"<g transform="matrix(1 0 0 1 10 10)" opacity="0.5"><path"#));
+    assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+  }
+
+  #[test]
+  fn gradient_spread() {
+    let mut painter = painter();
+    let stops = vec![GradientStop::new(Color::RED, 0.), GradientStop::new(Color::BLUE, 1.)];
+    let linear = LinearGradient {
+      start: Point::new(0., 0.),
+      end: Point::new(10., 0.),
+      stops: stops.clone(),
+      spread_method: SpreadMethod::Reflect,
+    };
+    let radial = RadialGradient {
+      start_center: Point::new(5., 5.),
+      end_center: Point::new(5., 5.),
+      end_radius: 5.,
+      stops,
+      spread_method: SpreadMethod::Repeat,
+      ..Default::default()
+    };
+    painter
+      .set_fill_brush(crate::Brush::LinearGradient(linear))
+      .rect(&rect(0., 0., 10., 10.))
+      .fill()
+      .set_fill_brush(crate::Brush::RadialGradient(radial))
+      .rect(&rect(0., 0., 10., 10.))
+      .fill();
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(r##"spreadMethod="reflect"><stop offset="0" stop-color="#ff0000""##));
+    assert!(svg.contains(r#"<radialGradient id="ribir2""#));
+    assert!(svg.contains(r#"spreadMethod="repeat""#));
+    assert!(svg.contains(r#"fill="url(#ribir1)""#));
+  }
+
+  #[test]
+  fn sweep_gradient_pattern() {
+    let mut painter = painter();
+    let sweep = SweepGradient {
+      center: Point::new(15., 15.),
+      end_angle: std::f32::consts::TAU,
+      stops: vec![GradientStop::new(Color::RED, 0.), GradientStop::new(Color::BLUE, 1.)],
+      ..Default::default()
+    };
+    painter
+      .set_fill_brush(crate::Brush::SweepGradient(sweep))
+      .rect(&rect(10., 10., 10., 10.))
+      .fill();
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(
+      r#"<pattern id="ribir1" patternUnits="userSpaceOnUse" x="10" y="10" width="10" height="10">"#
+    ));
+    assert!(svg.contains(r#"fill="url(#ribir1)""#));
+  }
+
+  #[test]
+  fn shadow_filter() {
+    let mut painter = painter();
+    let shadow = crate::Shadow::new(ribir_geom::Vector::new(2., 2.), 4., 0., Color::BLACK);
+    painter
+      .draw_box_shadow(&rect(10., 10., 20., 20.), &crate::Radius::all(0.), &shadow)
+      .draw_box_shadow(
+        &rect(40., 10., 20., 20.),
+        &crate::Radius::all(0.),
+        &crate::Shadow { blur_radius: 0., ..shadow },
+      );
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(
+      r#"<filter id="ribir1" filterUnits="userSpaceOnUse" x="4" y="4" width="32" height="32"><feGaussianBlur stdDeviation="2"/></filter>"#
+    ));
+    assert!(svg.contains(r##"fill="#000000" filter="url(#ribir1)""##));
+    assert_eq!(svg.matches("filter=").count(), 1);
+  }
+
+  #[test]
+  fn blur_layer_filter() {
+    let mut painter = painter();
+    let area = || Path::rect(&rect(10., 10., 20., 20.)).into();
+    painter
+      .save()
+      .blur_layer(area(), 4., BlurTarget::Content)
+      .rect(&rect(10., 10., 20., 20.))
+      .fill()
+      .restore();
+    painter
+      .save()
+      .blur_layer(area(), 4., BlurTarget::Backdrop)
+      .rect(&rect(40., 10., 20., 20.))
+      .fill()
+      .restore();
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(
+      r#"<filter id="ribir1" filterUnits="userSpaceOnUse" x="4" y="4" width="32" height="32"><feGaussianBlur stdDeviation="2"/></filter>"#
+    ));
+    assert!(svg.contains(r#"<g filter="url(#ribir1)"><path"#));
+    // The backdrop blur is not supported, only its content is kept.
+    assert_eq!(svg.matches("filter=").count(), 1);
+    assert_eq!(svg.matches("<path").count(), 2);
+  }
+
+  #[test]
+  fn blend_mode_style() {
+    let mut painter = painter();
+    painter
+      .rect(&rect(0., 0., 10., 10.))
+      .fill()
+      .set_blend_mode(BlendMode::Multiply)
+      .rect(&rect(5., 5., 10., 10.))
+      .fill();
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert_eq!(svg.matches("mix-blend-mode").count(), 1);
+    assert!(svg.contains(r#" style="mix-blend-mode:multiply"/>"#));
+  }
+
+  #[test]
+  fn dashed_stroke() {
+    let mut painter = painter();
+    painter
+      .set_strokes(StrokeOptions {
+        width: 2.,
+        dash_array: vec![4., 2.],
+        dash_offset: 1.,
+        ..Default::default()
+      })
+      .rect(&rect(10., 10., 20., 20.))
+      .stroke();
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(r#" stroke-dasharray="4 2" stroke-dashoffset="1""#));
+  }
+
+  #[cfg(feature = "png")]
+  #[test]
+  fn embed_image() {
+    let mut painter = painter();
+    let img =
+      Resource::new(PixelImage::new(vec![255; 16].into(), 2, 2, crate::image::ColorFormat::Rgba8));
+    painter.draw_img(img.clone(), &rect(0., 0., 10., 10.), &None);
+    painter.draw_img(img, &rect(20., 0., 10., 10.), &None);
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert_eq!(svg.matches("data:image/png;base64,").count(), 1);
+    assert!(!svg.contains("image-rendering"));
+  }
+
+  #[cfg(feature = "png")]
+  #[test]
+  fn pixelated_image() {
+    let mut painter = painter();
+    let img =
+      Resource::new(PixelImage::new(vec![255; 16].into(), 2, 2, crate::image::ColorFormat::Rgba8));
+    painter
+      .set_filter_quality(FilterQuality::Nearest)
+      .draw_img(img, &rect(0., 0., 10., 10.), &None);
+
+    let svg = SvgDocument::from_commands(Size::new(100., 100.), &painter.finish()).to_string();
+    assert!(svg.contains(r#"image-rendering="pixelated""#));
+  }
+
+  #[cfg(feature = "png")]
+  #[test]
+  fn base64_padding() {
+    assert_eq!(base64(b"M"), "TQ==");
+    assert_eq!(base64(b"Ma"), "TWE=");
+    assert_eq!(base64(b"Man"), "TWFu");
+  }
+}
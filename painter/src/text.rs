@@ -6,16 +6,20 @@ pub mod font_db;
 pub mod shaper;
 use std::hash::Hash;
 
+use bitflags::bitflags;
 use derive_more::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use font_db::Face;
 pub use fontdb::{ID, Stretch as FontStretch, Style as FontStyle, Weight as FontWeight};
 pub use ribir_algo::Substr;
 use ribir_geom::{Rect, rect};
 use rustybuzz::{GlyphPosition, ttf_parser::GlyphId};
+
+use crate::Brush;
 pub mod text_reorder;
 pub mod typography;
 pub use text_reorder::TextReorder;
 mod typography_store;
+pub(crate) use typography_store::DecorationPiece;
 pub use typography_store::{TypographyStore, VisualGlyphs};
 mod svg_glyph_cache;
 
@@ -78,6 +82,34 @@ pub struct FontFace {
   ///
   /// [font-weight](https://www.w3.org/TR/2018/REC-css-fonts-3-20180920/#font-weight-prop) in CSS.
   pub weight: FontWeight,
+  /// The OpenType features to turn on or off when shaping the text.
+  ///
+  /// [font-feature-settings](https://www.w3.org/TR/css-fonts-4/#font-feature-settings-prop) in CSS.
+  pub features: Box<[FontFeature]>,
+  /// The values of the axes to select an instance of the variable fonts, the
+  /// faces that are not variable ignore them.
+  ///
+  /// [font-variation-settings](https://www.w3.org/TR/css-fonts-4/#font-variation-settings-def) in CSS.
+  pub variations: Box<[FontVariation]>,
+}
+
+/// An OpenType feature to turn on or off, like the tabular figures `tnum`,
+/// the ligatures `liga`, the small capitals `smcp` and the stylistic sets
+/// `ss01` to `ss20`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FontFeature {
+  pub tag: [u8; 4],
+  /// `0` turns the feature off and `1` turns it on, the greater values select
+  /// an alternate glyph for the features like `salt` and `cv01`.
+  pub value: u32,
+}
+
+/// The value of an axis of the variable fonts, like the weight `wght`, the
+/// width `wdth` and the optical size `opsz`.
+#[derive(Clone, Copy, Debug)]
+pub struct FontVariation {
+  pub tag: [u8; 4],
+  pub value: f32,
 }
 
 /// Encapsulates the text style for painting.
@@ -93,6 +125,77 @@ pub struct TextStyle {
   pub line_height: f32,
   /// How to handle the visual overflow.
   pub overflow: TextOverflow,
+  /// The decoration lines to paint near the text.
+  pub decoration: TextDecorationStyle,
+  /// The maximum number of lines to show, the lines after it are not shown.
+  pub max_lines: Option<usize>,
+  /// The string to show at the end of the elided text in the
+  /// [`TextOverflow::Ellipsis`] mode.
+  pub ellipsis: Substr,
+  /// How to hyphenate the words at the wrap points.
+  pub hyphens: Hyphens,
+}
+
+bitflags! {
+  /// A linear decoration to draw near the text.
+  #[derive(Default, PartialEq, Eq, Clone, Copy, Debug, Hash)]
+  pub struct TextDecoration: u8 {
+    const NONE = 0;
+    /// Draw a line underneath each line of text
+    const UNDERLINE = 0b0010;
+    /// Draw a line above each line of text
+    const OVERLINE = 0b0100;
+    /// Draw a line through each line of text
+    const THROUGHLINE = 0b1000;
+  }
+}
+
+/// How to draw the lines of the text decoration.
+#[derive(Default, PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum DecorationLineStyle {
+  #[default]
+  Solid,
+  Dashed,
+  Wavy,
+}
+
+/// Encapsulates the text decoration style for painting.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextDecorationStyle {
+  /// The decorations to paint near the text
+  pub decoration: TextDecoration,
+  /// The color in which to paint the text decorations, the decorations are
+  /// painted by the brush of the glyphs if it's `None`.
+  pub decoration_color: Option<Brush>,
+  /// The thickness of the lines in logical pixels, the thickness suggested by
+  /// the font is used if it's `None`.
+  pub thickness: Option<f32>,
+  pub line_style: DecorationLineStyle,
+}
+
+/// A fragment of the rich text with its own style, the spans are shaped and
+/// wrapped together as one paragraph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+  pub text: Substr,
+  pub style: SpanStyle,
+}
+
+/// The style of a [`TextSpan`], the fields that are `None` inherit from the
+/// [`TextStyle`] of the paragraph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpanStyle {
+  /// The font face of the span.
+  pub font_face: Option<FontFace>,
+  /// The size of fonts (in logical pixels) of the span.
+  pub font_size: Option<f32>,
+  /// The space between characters of the span in logical pixel units.
+  pub letter_space: Option<f32>,
+  /// The brush to paint the glyphs of the span, the glyphs are painted by the
+  /// brush of the painter if it's `None`.
+  pub brush: Option<Brush>,
+  /// The decoration lines of the span.
+  pub decoration: Option<TextDecorationStyle>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
@@ -100,10 +203,31 @@ pub enum TextOverflow {
   #[default]
   Clip,
   AutoWrap,
+  /// Wrap the text like `AutoWrap`, and elide the end of the last line with
+  /// the ellipsis if the text overflows the bounds or the max lines.
+  Ellipsis,
+}
+
+/// How to break a word that doesn't fit the line with a hyphen, only works in
+/// the wrapping modes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Hyphens {
+  /// The words are never hyphenated.
+  #[default]
+  None,
+  /// The words are only hyphenated at the soft hyphens (U+00AD) in them.
+  Manual,
+  /// The words are hyphenated by the hyphenation dictionary of the language
+  /// specified by the ISO 639-1 code, like `Hyphens::Auto(*b"en")`. The words
+  /// contain soft hyphens are only hyphenated at them.
+  ///
+  /// The dictionaries need the `hyphenation` feature, it's the same as
+  /// `Manual` without the feature or if the language is not supported.
+  Auto([u8; 2]),
 }
 
 impl TextOverflow {
-  fn is_auto_wrap(&self) -> bool { matches!(self, TextOverflow::AutoWrap) }
+  fn is_auto_wrap(&self) -> bool { matches!(self, TextOverflow::AutoWrap | TextOverflow::Ellipsis) }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -141,10 +265,39 @@ impl Default for FontFace {
       stretch: Default::default(),
       style: Default::default(),
       weight: Default::default(),
+      features: Box::new([]),
+      variations: Box::new([]),
     }
   }
 }
 
+impl FontFeature {
+  pub const fn new(tag: &[u8; 4], value: u32) -> Self { Self { tag: *tag, value } }
+
+  pub const fn on(tag: &[u8; 4]) -> Self { Self::new(tag, 1) }
+
+  pub const fn off(tag: &[u8; 4]) -> Self { Self::new(tag, 0) }
+}
+
+impl FontVariation {
+  pub const fn new(tag: &[u8; 4], value: f32) -> Self { Self { tag: *tag, value } }
+}
+
+impl PartialEq for FontVariation {
+  fn eq(&self, other: &Self) -> bool {
+    self.tag == other.tag && self.value.to_bits() == other.value.to_bits()
+  }
+}
+
+impl Eq for FontVariation {}
+
+impl Hash for FontVariation {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.tag.hash(state);
+    self.value.to_bits().hash(state);
+  }
+}
+
 /// Text-align relative to the horizontal or vertical, not caring about whether
 /// the text is left-to-right or right-to-left, In the horizontal the left is
 /// the start, and in vertical the top is the start.
@@ -153,6 +306,10 @@ pub enum TextAlign {
   Start,
   Center,
   End,
+  /// Align the lines to the start, and stretch the lines that end by wrapping
+  /// to fill the bounds. The space is distributed between the words, or
+  /// between the clusters if the line has no space between words.
+  Justify,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -255,6 +412,54 @@ impl std::ops::Div<f32> for GlyphUnit {
   fn div(self, rhs: f32) -> Self::Output { cast(self.0, 1. / rhs) }
 }
 
+impl TextSpan {
+  pub fn new(text: impl Into<Substr>) -> Self { Self { text: text.into(), style: <_>::default() } }
+
+  pub fn with_font_face(mut self, font_face: FontFace) -> Self {
+    self.style.font_face = Some(font_face);
+    self
+  }
+
+  pub fn with_font_size(mut self, font_size: f32) -> Self {
+    self.style.font_size = Some(font_size);
+    self
+  }
+
+  pub fn with_letter_space(mut self, letter_space: f32) -> Self {
+    self.style.letter_space = Some(letter_space);
+    self
+  }
+
+  pub fn with_brush(mut self, brush: impl Into<Brush>) -> Self {
+    self.style.brush = Some(brush.into());
+    self
+  }
+
+  pub fn with_decoration(mut self, decoration: TextDecorationStyle) -> Self {
+    self.style.decoration = Some(decoration);
+    self
+  }
+}
+
+impl TextDecorationStyle {
+  pub fn new(decoration: TextDecoration) -> Self { Self { decoration, ..Default::default() } }
+
+  pub fn with_color(mut self, color: impl Into<Brush>) -> Self {
+    self.decoration_color = Some(color.into());
+    self
+  }
+
+  pub fn with_thickness(mut self, thickness: f32) -> Self {
+    self.thickness = Some(thickness);
+    self
+  }
+
+  pub fn with_line_style(mut self, line_style: DecorationLineStyle) -> Self {
+    self.line_style = line_style;
+    self
+  }
+}
+
 impl Default for TextStyle {
   fn default() -> Self {
     Self {
@@ -263,6 +468,10 @@ impl Default for TextStyle {
       letter_space: 0.,
       line_height: 16.,
       overflow: <_>::default(),
+      decoration: <_>::default(),
+      max_lines: None,
+      ellipsis: "…".into(),
+      hyphens: <_>::default(),
     }
   }
 }
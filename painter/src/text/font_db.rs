@@ -3,22 +3,32 @@ use std::{cell::RefCell, num::NonZeroU16, ops::Deref, sync::Arc};
 use ahash::HashMap;
 use fontdb::{Database, Query};
 pub use fontdb::{FaceInfo, Family, ID};
-use ribir_algo::{Resource, Sc};
+use ribir_algo::{FrameCache, Resource, Sc};
 use ribir_geom::{Point, Rect, rect};
-use rustybuzz::ttf_parser::{GlyphId, OutlineBuilder};
+use rustybuzz::ttf_parser::{GlyphId, OutlineBuilder, Tag};
 
 use crate::{
   Path, PixelImage, Svg,
   path_builder::PathBuilder,
-  text::{FontFace, FontFamily, svg_glyph_cache::SvgGlyphCache},
+  text::{FontFace, FontFamily, FontVariation, svg_glyph_cache::SvgGlyphCache},
 };
 /// A wrapper of fontdb and cache font data.
 pub struct FontDB {
   default_fonts: Vec<ID>,
   data_base: fontdb::Database,
   cache: HashMap<ID, Option<Face>>,
+  /// The faces of the variable fonts with the variations applied, `None` if
+  /// the font is not variable. The instances are shared with the glyphs shaped
+  /// by them, see [`FontDB::variation_instance_handle`].
+  instances: FrameCache<InstanceKey, Option<Sc<ID>>>,
 }
 
+type InstanceKey = (ID, Box<[FontVariation]>);
+
+/// The max count of the variation instances that are retained when they are
+/// not used in a frame and no glyph is shaped by them.
+const MAX_IDLE_INSTANCES: usize = 64;
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum GlyphBaseline {
   /// The glyph baseline is the normal alphabetic baseline, which is the default
@@ -65,6 +75,86 @@ impl FontDB {
     get_or_insert_face(&mut self.cache, &self.data_base, face_id).as_ref()
   }
 
+  /// The face of the variable font `id` with the `variations` applied. The
+  /// instance is a new face that shares the data of the `id` face, and it's
+  /// never selected by the queries. Return `id` if there is no variation or
+  /// the face is not variable.
+  pub fn variation_instance(&mut self, id: ID, variations: &[FontVariation]) -> ID {
+    self
+      .variation_instance_handle(id, variations)
+      .map_or(id, |instance| *instance)
+  }
+
+  /// The handle of the face that [`FontDB::variation_instance`] returns, `None`
+  /// if there is no variation or the face is not variable. The instance is
+  /// never evicted while its handle is alive, so the glyphs shaped by the
+  /// instance should hold its handle.
+  pub fn variation_instance_handle(
+    &mut self, id: ID, variations: &[FontVariation],
+  ) -> Option<Sc<ID>> {
+    if variations.is_empty() {
+      return None;
+    }
+    let key = (id, Box::<[_]>::from(variations));
+    if let Some(instance) = self.instances.get(&key) {
+      return instance.clone();
+    }
+
+    let instance = self
+      .face_data_or_insert(id)
+      .filter(|face| face.is_variable())
+      .cloned()
+      .and_then(|face| {
+        let mut info = self.data_base.face(id)?.clone();
+        info.families.clear();
+        let instance = self.data_base.push_face_info(info);
+        let rb_variations: Vec<_> = variations
+          .iter()
+          .map(|v| rustybuzz::Variation { tag: Tag::from_bytes(&v.tag), value: v.value })
+          .collect();
+        let face = Face::new(instance, face.source_data, face.face_data_index, &rb_variations);
+        self.cache.insert(instance, face);
+        Some(Sc::new(instance))
+      });
+    self.instances.put(key, instance.clone());
+    instance
+  }
+
+  /// End the frame, the variation instances neither used in this frame nor
+  /// held by the glyphs are removed from the least recently used one, until
+  /// no more than `MAX_IDLE_INSTANCES` instances remain.
+  pub fn end_frame(&mut self) {
+    let held: Vec<_> = self
+      .instances
+      .iter()
+      .filter(|(_, instance)| {
+        instance
+          .as_ref()
+          .is_some_and(|i| Sc::ref_count(i) > 1)
+      })
+      .map(|(key, _)| key.clone())
+      .collect();
+    for key in held {
+      self.instances.promote(&key);
+    }
+
+    let mut excess = self
+      .instances
+      .len()
+      .saturating_sub(MAX_IDLE_INSTANCES);
+    let evicted = self
+      .instances
+      .end_frame_evict_while("Font variation instance", |_| {
+        let evict = excess > 0;
+        excess = excess.saturating_sub(1);
+        evict
+      });
+    for instance in evicted.flatten() {
+      self.data_base.remove_face(*instance);
+      self.cache.remove(&instance);
+    }
+  }
+
   /// Selects a `FaceInfo` by `id`.
   ///
   /// Returns `None` if a face with such ID was already removed,
@@ -86,6 +176,37 @@ impl FontDB {
     }
   }
 
+  /// Return all the outline glyphs that have been cached by the loaded faces.
+  ///
+  /// The painter shares these paths with the paint commands that draw the
+  /// glyphs, so a backend can know which glyph a path is drawn for.
+  pub fn cached_outline_glyphs(&self) -> Vec<(ID, GlyphId, Resource<Path>)> {
+    self
+      .cache
+      .iter()
+      .filter_map(|(id, face)| Some((*id, face.as_ref()?)))
+      .flat_map(|(id, face)| {
+        let glyphs = face.outline_glyphs.borrow();
+        glyphs
+          .iter()
+          .filter_map(|(glyph, path)| Some((id, *glyph, path.clone()?)))
+          .collect::<Vec<_>>()
+      })
+      .collect()
+  }
+
+  /// The number of the outline glyphs that have been cached by the loaded
+  /// faces, it's cheaper than `cached_outline_glyphs` to know if new glyphs
+  /// are cached.
+  pub fn cached_outline_glyphs_count(&self) -> usize {
+    self
+      .cache
+      .values()
+      .flatten()
+      .map(|face| face.outline_glyphs.borrow().len())
+      .sum()
+  }
+
   #[inline]
   pub fn load_from_bytes(&mut self, data: Vec<u8>) { self.data_base.load_font_data(data); }
 
@@ -116,7 +237,7 @@ impl FontDB {
 
   /// Performs a CSS-like query and returns the best matched font face id.
   pub fn select_best_match(&self, face: &FontFace) -> Option<ID> {
-    let FontFace { families, stretch, style, weight } = face;
+    let FontFace { families, stretch, style, weight, .. } = face;
     let families = families
       .iter()
       .map(to_db_family)
@@ -131,7 +252,7 @@ impl FontDB {
 
   /// Performs a CSS-like query and returns the all matched font face ids
   pub fn select_all_match(&mut self, face: &FontFace) -> Vec<ID> {
-    let FontFace { families, stretch, style, weight } = face;
+    let FontFace { families, stretch, style, weight, .. } = face;
     families
       .iter()
       .filter_map(|f| {
@@ -146,6 +267,38 @@ impl FontDB {
       .collect()
   }
 
+  /// Clone the faces to a database that shapes the texts of SVGs. The generic
+  /// families that match no face use the default font, because usvg falls back
+  /// to the `serif` family when a font family matches nothing.
+  pub(crate) fn svg_database(&self) -> Database {
+    let mut db = self.data_base.clone();
+    let default_family = self
+      .default_fonts
+      .iter()
+      .find_map(|id| db.face(*id))
+      .and_then(|f| f.families.first())
+      .map(|(name, _)| name.clone());
+    if let Some(name) = default_family {
+      type SetFamily = fn(&mut Database, String);
+      let generic_families: [(Family, SetFamily); 5] = [
+        (Family::Serif, Database::set_serif_family),
+        (Family::SansSerif, Database::set_sans_serif_family),
+        (Family::Cursive, Database::set_cursive_family),
+        (Family::Fantasy, Database::set_fantasy_family),
+        (Family::Monospace, Database::set_monospace_family),
+      ];
+      for (family, set_family) in generic_families {
+        if db
+          .query(&Query { families: &[family], ..<_>::default() })
+          .is_none()
+        {
+          set_family(&mut db, name.clone());
+        }
+      }
+    }
+    db
+  }
+
   fn static_generic_families(&mut self) {
     // We don't like to depends on some system library and not make the fallback
     // font too complicated. So here are some default fonts collect from web.
@@ -267,7 +420,12 @@ impl Default for FontDB {
     let mut data_base = fontdb::Database::new();
     data_base.load_font_data(include_bytes!("./Lato-Regular.ttf").to_vec());
     let default_font = data_base.faces().next().map(|f| f.id).unwrap();
-    let mut this = FontDB { default_fonts: vec![default_font], data_base, cache: <_>::default() };
+    let mut this = FontDB {
+      default_fonts: vec![default_font],
+      data_base,
+      cache: <_>::default(),
+      instances: <_>::default(),
+    };
     this.face_data_or_insert(default_font);
     this
   }
@@ -276,11 +434,19 @@ impl Default for FontDB {
 impl Face {
   pub fn from_data(
     face_id: ID, source_data: Arc<dyn AsRef<[u8]> + Sync + Send>, face_index: u32,
+  ) -> Option<Self> {
+    Self::new(face_id, source_data, face_index, &[])
+  }
+
+  fn new(
+    face_id: ID, source_data: Arc<dyn AsRef<[u8]> + Sync + Send>, face_index: u32,
+    variations: &[rustybuzz::Variation],
   ) -> Option<Self> {
     let ptr_data = source_data.as_ref().as_ref() as *const [u8];
     // Safety: we know the ptr_data has some valid lifetime with source data, and
     // hold them in same struct.
-    let rb_face = rustybuzz::Face::from_slice(unsafe { &*ptr_data }, face_index)?;
+    let mut rb_face = rustybuzz::Face::from_slice(unsafe { &*ptr_data }, face_index)?;
+    rb_face.set_variations(variations);
     let ascender = rb_face.ascender();
     let descender = rb_face.descender();
     let x_height = rb_face
@@ -368,9 +534,9 @@ impl Face {
           .glyph_raster_image(glyph_id, pixels_per_em)
           .and_then(|img| match img.format {
             #[cfg(feature = "png")]
-            rustybuzz::ttf_parser::RasterImageFormat::PNG => {
-              Some(Resource::new(PixelImage::from_png(img.data)))
-            }
+            rustybuzz::ttf_parser::RasterImageFormat::PNG => PixelImage::from_png(img.data)
+              .ok()
+              .map(Resource::new),
             _ => None,
           })
       })
@@ -576,4 +742,75 @@ mod tests {
     let info = fonts.face_info(id.unwrap()).unwrap();
     assert_eq!(info.weight, FontWeight::BOLD);
   }
+
+  #[test]
+  fn variation_instance() {
+    let mut db = FontDB::default();
+    let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/../fonts";
+    db.load_font_file(path.clone() + "/DejaVuSans.ttf")
+      .unwrap();
+    db.load_font_file(path + "/Nunito-VariableFont_wght.ttf")
+      .unwrap();
+    let select = |db: &FontDB, name: &'static str| {
+      db.select_best_match(&FontFace {
+        families: Box::new([FontFamily::Name(name.into())]),
+        ..<_>::default()
+      })
+      .unwrap()
+    };
+    let bold = [FontVariation::new(b"wght", 700.)];
+
+    // Not a variable font.
+    let dejavu = select(&db, "DejaVu Sans");
+    assert_eq!(db.variation_instance(dejavu, &bold), dejavu);
+
+    let nunito = select(&db, "Nunito");
+    assert_eq!(db.variation_instance(nunito, &[]), nunito);
+    let instance = db.variation_instance(nunito, &bold);
+    assert_ne!(instance, nunito);
+    assert_eq!(db.variation_instance(nunito, &bold), instance);
+    assert_eq!(select(&db, "Nunito"), nunito);
+
+    let glyph = db
+      .try_get_face_data(nunito)
+      .unwrap()
+      .glyph_index('W')
+      .unwrap();
+    let advance = |id| {
+      db.try_get_face_data(id)
+        .unwrap()
+        .glyph_hor_advance(glyph)
+    };
+    assert!(advance(nunito) < advance(instance));
+  }
+
+  #[test]
+  fn evict_idle_variation_instances() {
+    let mut db = FontDB::default();
+    let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/../fonts/Nunito-VariableFont_wght.ttf";
+    db.load_font_file(path).unwrap();
+    let nunito = db
+      .select_best_match(&FontFace {
+        families: Box::new([FontFamily::Name("Nunito".into())]),
+        ..<_>::default()
+      })
+      .unwrap();
+    let weight = |w: usize| [FontVariation::new(b"wght", 200. + w as f32)];
+
+    let first = db.variation_instance(nunito, &weight(0));
+    for w in 1..=MAX_IDLE_INSTANCES {
+      db.variation_instance(nunito, &weight(w));
+    }
+    // All the instances are used in this frame.
+    db.end_frame();
+    assert!(db.face_info(first).is_some());
+
+    let used = db.variation_instance(nunito, &weight(MAX_IDLE_INSTANCES));
+    db.end_frame();
+    // The least recently used one is evicted.
+    assert_eq!(db.instances.len(), MAX_IDLE_INSTANCES);
+    assert!(db.face_info(first).is_none());
+    assert!(db.try_get_face_data(first).is_none());
+    assert_eq!(db.variation_instance(nunito, &weight(MAX_IDLE_INSTANCES)), used);
+  }
 }
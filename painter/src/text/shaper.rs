@@ -5,11 +5,11 @@ use std::{
 
 use ribir_algo::{FrameCache, Sc, Substr};
 pub use rustybuzz::ttf_parser::GlyphId;
-use rustybuzz::{GlyphInfo, UnicodeBuffer};
+use rustybuzz::{Feature, GlyphInfo, UnicodeBuffer, ttf_parser::Tag};
 
 use super::{GlyphUnit, font_db::GlyphBaseline};
 use crate::{
-  Glyph, TextDirection,
+  FontFeature, FontVariation, Glyph, TextDirection,
   font_db::{Face, FontDB, ID},
 };
 
@@ -27,12 +27,17 @@ pub struct TextShaper {
 pub struct ShapeResult {
   pub text: Substr,
   pub glyphs: Vec<Glyph>,
+  /// The handles of the variation instances that the glyphs are shaped by, to
+  /// keep the instances alive.
+  pub(crate) instances: Box<[Sc<ID>]>,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
 struct ShapeKey {
   face_ids: Box<[ID]>,
   text: Substr,
+  features: Box<[FontFeature]>,
+  variations: Box<[FontVariation]>,
   direction: TextDirection,
   baseline: GlyphBaseline,
 }
@@ -46,18 +51,41 @@ impl TextShaper {
   #[inline]
   pub fn new(font_db: Sc<RefCell<FontDB>>) -> Self { Self { font_db, shape_cache: <_>::default() } }
 
-  pub fn end_frame(&mut self) { self.shape_cache.end_frame("Text shape"); }
+  pub fn end_frame(&mut self) {
+    self.shape_cache.end_frame("Text shape");
+    self.font_db.borrow_mut().end_frame();
+  }
 
   /// Shape text and return the glyphs, caller should do text reorder before
   /// call this method.
+  ///
+  /// The `features` are applied to the shaping, and the glyphs of the variable
+  /// fonts are from the instances of the `variations`.
   pub fn shape_text(
-    &mut self, text: &Substr, face_ids: &[ID], direction: TextDirection, baseline: GlyphBaseline,
+    &mut self, text: &Substr, face_ids: &[ID], features: &[FontFeature],
+    variations: &[FontVariation], direction: TextDirection, baseline: GlyphBaseline,
   ) -> Sc<ShapeResult> {
-    if let Some(res) = self.get_cache(text, face_ids, direction, baseline) {
+    if let Some(res) = self.get_cache(text, face_ids, features, variations, direction, baseline) {
       res.clone()
     } else {
+      let handles: Vec<_> = {
+        let mut font_db = self.font_db.borrow_mut();
+        face_ids
+          .iter()
+          .map(|id| font_db.variation_instance_handle(*id, variations))
+          .collect()
+      };
+      let instances: Vec<_> = face_ids
+        .iter()
+        .zip(&handles)
+        .map(|(id, handle)| {
+          handle
+            .as_deref()
+            .map_or(*id, |instance| *instance)
+        })
+        .collect();
       let mut glyphs = self
-        .shape_text_with_fallback(text, direction, face_ids, baseline)
+        .shape_text_with_fallback(text, direction, &instances, features, baseline)
         .unwrap_or_default();
 
       if let Some(last_char) = text.bytes().last() {
@@ -68,43 +96,55 @@ impl TextShaper {
         }
       }
 
-      let glyphs = Sc::new(ShapeResult { text: text.clone(), glyphs });
-      self.shape_cache.put(
-        ShapeKey { face_ids: face_ids.into(), text: text.clone(), direction, baseline },
-        glyphs.clone(),
-      );
+      let instances = handles.into_iter().flatten().collect();
+      let glyphs = Sc::new(ShapeResult { text: text.clone(), glyphs, instances });
+      let key = ShapeKey {
+        face_ids: face_ids.into(),
+        text: text.clone(),
+        features: features.into(),
+        variations: variations.into(),
+        direction,
+        baseline,
+      };
+      self.shape_cache.put(key, glyphs.clone());
       glyphs
     }
   }
 
   /// Directly shape text without bidi reordering.
   pub fn shape_text_with_fallback(
-    &self, text: &str, dir: TextDirection, face_ids: &[ID], baseline: GlyphBaseline,
+    &self, text: &str, dir: TextDirection, face_ids: &[ID], features: &[FontFeature],
+    baseline: GlyphBaseline,
   ) -> Option<Vec<Glyph>> {
     let mut font_fallback = FallBackFaceHelper::new(face_ids, &self.font_db);
     let face = font_fallback.next_fallback_face(text)?;
     let mut buffer = UnicodeBuffer::new();
     buffer.push_str(text);
     buffer.set_direction(dir.into());
+    let features: Vec<_> = features
+      .iter()
+      .map(|f| Feature::new(Tag::from_bytes(&f.tag), f.value, ..))
+      .collect();
 
     let GlyphsWithoutFallback { mut glyphs, mut buffer } =
-      Self::directly_shape(buffer, baseline, dir.is_horizontal(), &face);
+      Self::directly_shape(buffer, &features, baseline, dir.is_horizontal(), &face);
     let mut new_part = vec![(0, glyphs.len(), font_fallback.clone())];
     loop {
       if new_part.is_empty() {
         break;
       }
       let miss_part = collect_miss_part(&glyphs, &new_part);
-      (buffer, new_part) = regen_miss_part(text, dir, baseline, &mut glyphs, miss_part, buffer);
+      (buffer, new_part) =
+        regen_miss_part(text, dir, &features, baseline, &mut glyphs, miss_part, buffer);
     }
 
     Some(glyphs)
   }
 
   fn directly_shape(
-    text: UnicodeBuffer, baseline: GlyphBaseline, hor_text: bool, face: &Face,
+    text: UnicodeBuffer, features: &[Feature], baseline: GlyphBaseline, hor_text: bool, face: &Face,
   ) -> GlyphsWithoutFallback {
-    let output = rustybuzz::shape(face.as_rb_face(), &[], text);
+    let output = rustybuzz::shape(face.as_rb_face(), features, text);
     let mut glyphs = Vec::with_capacity(output.len());
 
     let infos = output.glyph_infos();
@@ -130,11 +170,13 @@ impl TextShaper {
   }
 
   pub fn get_cache(
-    &mut self, text: &str, face_ids: &[ID], direction: TextDirection, baseline: GlyphBaseline,
+    &mut self, text: &str, face_ids: &[ID], features: &[FontFeature], variations: &[FontVariation],
+    direction: TextDirection, baseline: GlyphBaseline,
   ) -> Option<Sc<ShapeResult>> {
+    let key = (face_ids, text, features, variations, direction, baseline);
     self
       .shape_cache
-      .get(&(face_ids, text, direction, baseline) as &(dyn ShapeKeySlice))
+      .get(&key as &(dyn ShapeKeySlice))
       .cloned()
   }
 
@@ -181,8 +223,9 @@ fn collect_miss_part<'a>(
 }
 
 fn regen_miss_part<'a>(
-  text: &str, dir: TextDirection, baseline: GlyphBaseline, glyphs: &mut Vec<Glyph>,
-  miss_part: Vec<(usize, usize, FallBackFaceHelper<'a>)>, mut buffer: UnicodeBuffer,
+  text: &str, dir: TextDirection, features: &[Feature], baseline: GlyphBaseline,
+  glyphs: &mut Vec<Glyph>, miss_part: Vec<(usize, usize, FallBackFaceHelper<'a>)>,
+  mut buffer: UnicodeBuffer,
 ) -> (UnicodeBuffer, Vec<(usize, usize, FallBackFaceHelper<'a>)>) {
   let is_rtl = matches!(dir, TextDirection::RightToLeft | TextDirection::BottomToTop);
   let hb_direction = dir.into();
@@ -211,7 +254,8 @@ fn regen_miss_part<'a>(
     if let Some(face) = helper.next_fallback_face(miss_text) {
       buffer.push_str(miss_text);
       buffer.set_direction(hb_direction);
-      let mut res = TextShaper::directly_shape(buffer, baseline, dir.is_horizontal(), &face);
+      let mut res =
+        TextShaper::directly_shape(buffer, features, baseline, dir.is_horizontal(), &face);
       buffer = res.buffer;
       for g in res.glyphs.iter_mut() {
         g.cluster += miss_range.start as u32;
@@ -228,6 +272,8 @@ fn regen_miss_part<'a>(
 trait ShapeKeySlice {
   fn face_ids(&self) -> &[ID];
   fn text(&self) -> &str;
+  fn features(&self) -> &[FontFeature];
+  fn variations(&self) -> &[FontVariation];
   fn direction(&self) -> TextDirection;
   fn baseline(&self) -> GlyphBaseline;
 }
@@ -240,6 +286,8 @@ impl Hash for dyn ShapeKeySlice + '_ {
   fn hash<H: Hasher>(&self, state: &mut H) {
     self.face_ids().hash(state);
     self.text().hash(state);
+    self.features().hash(state);
+    self.variations().hash(state);
     self.direction().hash(state);
     self.baseline().hash(state);
   }
@@ -249,6 +297,8 @@ impl PartialEq for dyn ShapeKeySlice + '_ {
   fn eq(&self, other: &Self) -> bool {
     self.face_ids() == other.face_ids()
       && self.text() == other.text()
+      && self.features() == other.features()
+      && self.variations() == other.variations()
       && self.direction() == other.direction()
       && self.baseline() == other.baseline()
   }
@@ -261,19 +311,29 @@ impl ShapeKeySlice for ShapeKey {
 
   fn text(&self) -> &str { &self.text }
 
+  fn features(&self) -> &[FontFeature] { &self.features }
+
+  fn variations(&self) -> &[FontVariation] { &self.variations }
+
   fn direction(&self) -> TextDirection { self.direction }
 
   fn baseline(&self) -> GlyphBaseline { self.baseline }
 }
 
-impl ShapeKeySlice for (&[ID], &str, TextDirection, GlyphBaseline) {
+impl ShapeKeySlice
+  for (&[ID], &str, &[FontFeature], &[FontVariation], TextDirection, GlyphBaseline)
+{
   fn face_ids(&self) -> &[ID] { self.0 }
 
   fn text(&self) -> &str { self.1 }
 
-  fn direction(&self) -> TextDirection { self.2 }
+  fn features(&self) -> &[FontFeature] { self.2 }
+
+  fn variations(&self) -> &[FontVariation] { self.3 }
 
-  fn baseline(&self) -> GlyphBaseline { self.3 }
+  fn direction(&self) -> TextDirection { self.4 }
+
+  fn baseline(&self) -> GlyphBaseline { self.5 }
 }
 
 impl From<TextDirection> for rustybuzz::Direction {
@@ -365,16 +425,16 @@ mod tests {
     // No cache exists
     assert!(
       shaper
-        .get_cache(&text, &ids, dir, baseline)
+        .get_cache(&text, &ids, &[], &[], dir, baseline)
         .is_none()
     );
 
-    let result = shaper.shape_text(&text, &ids, dir, GlyphBaseline::Alphabetic);
+    let result = shaper.shape_text(&text, &ids, &[], &[], dir, GlyphBaseline::Alphabetic);
     assert_eq!(result.glyphs.len(), 6);
 
     assert!(
       shaper
-        .get_cache(&text, &ids, dir, baseline)
+        .get_cache(&text, &ids, &[], &[], dir, baseline)
         .is_some()
     );
 
@@ -382,7 +442,7 @@ mod tests {
     shaper.end_frame();
     assert!(
       shaper
-        .get_cache(&text, &ids, dir, baseline)
+        .get_cache(&text, &ids, &[], &[], dir, baseline)
         .is_none()
     );
   }
@@ -423,6 +483,8 @@ mod tests {
     let latin1 = shaper.shape_text(
       &"hello world! 你好，世界".into(),
       &ids_latin,
+      &[],
+      &[],
       dir,
       GlyphBaseline::Alphabetic,
     );
@@ -444,6 +506,8 @@ mod tests {
     let fallback_chinese = shaper.shape_text(
       &"hello world! 你好，世界".into(),
       &ids_all,
+      &[],
+      &[],
       dir,
       GlyphBaseline::Alphabetic,
     );
@@ -466,7 +530,8 @@ mod tests {
     let mut shaper = TextShaper::new(<_>::default());
 
     let dir = TextDirection::LeftToRight;
-    let result = shaper.shape_text(&"你好世界".into(), &[], dir, GlyphBaseline::Alphabetic);
+    let result =
+      shaper.shape_text(&"你好世界".into(), &[], &[], &[], dir, GlyphBaseline::Alphabetic);
     assert_eq!(result.glyphs.len(), 4);
   }
 
@@ -497,6 +562,8 @@ mod tests {
       let res = shaper.shape_text(
         &text.substr(..),
         &ids,
+        &[],
+        &[],
         TextDirection::LeftToRight,
         GlyphBaseline::Alphabetic,
       );
@@ -523,6 +590,8 @@ mod tests {
       let res = shaper.shape_text(
         &text.substr(..),
         &ids,
+        &[],
+        &[],
         TextDirection::LeftToRight,
         GlyphBaseline::Alphabetic,
       );
@@ -557,9 +626,65 @@ mod tests {
     let res = shaper.shape_text(
       &"👨‍👩‍👦‍👦".into(),
       &ids_all,
+      &[],
+      &[],
       TextDirection::LeftToRight,
       GlyphBaseline::Alphabetic,
     );
     assert!(res.glyphs.len() == 7);
   }
+
+  #[test]
+  fn font_features() {
+    let mut shaper = TextShaper::new(<_>::default());
+    // The default font has the `fi` ligature.
+    let ids = shaper.font_db.borrow().default_fonts().to_vec();
+    let text: Substr = "fi".into();
+    let dir = TextDirection::LeftToRight;
+    let baseline = GlyphBaseline::Alphabetic;
+
+    let ligature = shaper.shape_text(&text, &ids, &[], &[], dir, baseline);
+    assert_eq!(ligature.glyphs.len(), 1);
+
+    let no_liga = [FontFeature::off(b"liga")];
+    assert!(
+      shaper
+        .get_cache(&text, &ids, &no_liga, &[], dir, baseline)
+        .is_none()
+    );
+    let res = shaper.shape_text(&text, &ids, &no_liga, &[], dir, baseline);
+    assert_eq!(res.glyphs.len(), 2);
+  }
+
+  #[test]
+  fn font_variations() {
+    let mut shaper = TextShaper::new(<_>::default());
+    let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/../fonts/Nunito-VariableFont_wght.ttf";
+    let _ = shaper.font_db.borrow_mut().load_font_file(path);
+    let ids = shaper
+      .font_db
+      .borrow_mut()
+      .select_all_match(&FontFace {
+        families: Box::new([FontFamily::Name("Nunito".into())]),
+        ..<_>::default()
+      });
+    let text: Substr = "Variable".into();
+    let dir = TextDirection::LeftToRight;
+    let baseline = GlyphBaseline::Alphabetic;
+    let width = |res: &ShapeResult| {
+      res
+        .glyphs
+        .iter()
+        .fold(GlyphUnit::ZERO, |acc, g| acc + g.x_advance)
+    };
+
+    let light = [FontVariation::new(b"wght", 200.)];
+    let black = [FontVariation::new(b"wght", 1000.)];
+    let light = shaper.shape_text(&text, &ids, &[], &light, dir, baseline);
+    let black = shaper.shape_text(&text, &ids, &[], &black, dir, baseline);
+    assert!(width(&light) < width(&black));
+    // The glyphs are from the instances of the variations.
+    assert_ne!(light.glyphs[0].face_id, ids[0]);
+    assert_ne!(light.glyphs[0].face_id, black.glyphs[0].face_id);
+  }
 }
@@ -1,4 +1,4 @@
-use std::ops::Range;
+use std::{cmp::Reverse, ops::Range};
 
 use ribir_algo::Sc;
 use ribir_geom::Size;
@@ -6,7 +6,7 @@ use smallvec::{SmallVec, smallvec};
 use unicode_script::{Script, UnicodeScript};
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{Glyph, GlyphUnit, TextAlign, TextOverflow, shaper::ShapeResult};
+use crate::{Glyph, GlyphUnit, Hyphens, TextAlign, TextOverflow, font_db::ID, shaper::ShapeResult};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PlaceLineDirection {
@@ -56,6 +56,18 @@ pub struct VisualInfos {
   pub line_dir: PlaceLineDirection,
   pub visual_width: GlyphUnit,
   pub visual_height: GlyphUnit,
+  /// If some text is not shown because of the max lines or the ellipsis.
+  pub truncated: bool,
+  /// The handles of the variation instances that the glyphs are shaped by, to
+  /// keep the instances alive with the glyphs.
+  _instances: Box<[Sc<ID>]>,
+}
+
+/// The shaped runs of a paragraph in the visual order.
+pub struct InputParagraph {
+  pub(crate) runs: SmallVec<[InputRun; 1]>,
+  /// Whether the base direction of the paragraph is right-to-left.
+  pub(crate) rtl: bool,
 }
 
 /// Typography the glyphs in a bounds.
@@ -72,11 +84,30 @@ pub struct TypographyMan<Paras> {
   inline_cursor: GlyphUnit,
   visual_lines: SmallVec<[VisualLine; 1]>,
   over_bounds: bool,
+  /// The max extents of the runs in the current line above and below their
+  /// baseline, the runs of different font sizes are placed on a same baseline.
+  line_ascent: GlyphUnit,
+  line_descent: GlyphUnit,
+  max_lines: Option<usize>,
+  ellipsis: Option<InputRun>,
+  /// The cluster of the first text that is not shown.
+  truncated_at: Option<u32>,
+  /// Whether the paragraph of the last line is right-to-left.
+  last_para_rtl: bool,
+  /// The offset of the baseline of the last line in it.
+  last_baseline: GlyphUnit,
+  hyphens: Hyphens,
+  /// Whether the glyphs of the current line are spaces.
+  line_spaces: Vec<bool>,
+  /// Whether the current line ends by wrapping.
+  wrapped: bool,
+  /// The variation instances of the consumed runs.
+  instances: Vec<Sc<ID>>,
 }
 
 impl<Paras> TypographyMan<Paras>
 where
-  Paras: DoubleEndedIterator<Item = SmallVec<[InputRun; 1]>>,
+  Paras: DoubleEndedIterator<Item = InputParagraph>,
 {
   pub fn new(
     inputs: Paras, line_dir: PlaceLineDirection, text_align: TextAlign, line_height: GlyphUnit,
@@ -92,13 +123,47 @@ where
       inline_cursor: GlyphUnit::ZERO,
       visual_lines: smallvec![],
       over_bounds: false,
+      line_ascent: GlyphUnit::ZERO,
+      line_descent: GlyphUnit::ZERO,
+      max_lines: None,
+      ellipsis: None,
+      truncated_at: None,
+      last_para_rtl: false,
+      last_baseline: GlyphUnit::ZERO,
+      hyphens: Hyphens::None,
+      line_spaces: vec![],
+      wrapped: false,
+      instances: vec![],
     }
   }
 
+  /// Limit the number of lines, the text after the last line is truncated.
+  pub fn with_max_lines(mut self, max_lines: Option<usize>) -> Self {
+    self.max_lines = max_lines;
+    self
+  }
+
+  /// The shaped ellipsis to elide the last line in the `Ellipsis` mode.
+  pub fn with_ellipsis(mut self, ellipsis: InputRun) -> Self {
+    self.hold_instances(&ellipsis);
+    self.ellipsis = Some(ellipsis);
+    self
+  }
+
+  /// How to hyphenate the words at the wrap points, only the runs that have
+  /// the shaped hyphen are hyphenated.
+  pub fn with_hyphens(mut self, hyphens: Hyphens) -> Self {
+    self.hyphens = hyphens;
+    self
+  }
+
   pub fn typography_all(mut self) -> VisualInfos {
     while let Some(p) = self.inputs.next() {
       self.consume_paragraph(p);
     }
+    if self.overflow == TextOverflow::Ellipsis {
+      self.elide_last_line();
+    }
 
     if self.line_dir.is_reverse() {
       self.visual_lines.reverse();
@@ -106,6 +171,8 @@ where
 
     let (visual_width, visual_height) = self.visual_size();
     self.adjust_lines(visual_width, visual_height);
+    self.instances.sort_by_key(|i| **i);
+    self.instances.dedup_by_key(|i| **i);
 
     VisualInfos {
       visual_width,
@@ -114,6 +181,8 @@ where
       visual_lines: self.visual_lines,
       over_bounds: self.over_bounds,
       line_dir: self.line_dir,
+      truncated: self.truncated_at.is_some(),
+      _instances: self.instances.into(),
     }
   }
 
@@ -175,7 +244,21 @@ where
   }
 
   /// consume paragraph and return if early break because over boundary.
-  fn consume_paragraph(&mut self, runs: SmallVec<[InputRun; 1]>) -> bool {
+  fn hold_instances(&mut self, run: &InputRun) {
+    let instances = &run.shape_result.instances;
+    self.instances.extend(instances.iter().cloned());
+  }
+
+  fn consume_paragraph(&mut self, para: InputParagraph) -> bool {
+    let InputParagraph { runs, rtl } = para;
+    runs.iter().for_each(|r| self.hold_instances(r));
+    if self.is_last_line() {
+      if let Some(start) = runs.iter().map(|r| r.range.start).min() {
+        self.truncate(start as u32);
+      }
+      return true;
+    }
+    self.last_para_rtl = rtl;
     self.begin_line();
 
     if self.line_dir.is_horizontal() {
@@ -206,34 +289,84 @@ where
   }
 
   fn consume_run(&mut self, run: &InputRun, cursor: &mut impl InlineCursor) {
+    if self.truncated_at.is_some() {
+      return;
+    }
     let font_size = run.font_size_factor * GlyphUnit::PIXELS_PER_EM as f32;
     let em = GlyphUnit::from_pixel(font_size);
     let text = run.text();
     let base = run.range.start as u32;
-    let line_offset = (self.line_height - em) / 2.;
+    // The glyphs are placed relative to the baseline first, and moved into the
+    // line when the line ends.
+    let line_height = self.line_height.cast_to(font_size);
+    let ascent = (line_height - em) / 2. + run.baseline;
+    let descent = line_height - ascent;
+    let line_offset = -run.baseline;
     let is_auto_wrap = self.overflow.is_auto_wrap();
+    let hyphen: SmallVec<[Glyph; 1]> = run
+      .hyphen
+      .iter()
+      .flat_map(|h| h.glyphs.iter())
+      .map(|g| g.clone().cast_to(font_size))
+      .collect();
+    let breaks =
+      if is_auto_wrap && !hyphen.is_empty() { hyphen_breaks(text, self.hyphens) } else { vec![] };
 
     let new_line = |this: &mut Self, cursor: &mut dyn InlineCursor| {
+      this.wrapped = true;
       this.end_line();
       this.begin_line();
       cursor.reset();
     };
 
     for word in run.word_glyphs() {
-      let width: GlyphUnit = word
-        .clone()
-        .fold(GlyphUnit::ZERO, |acc, g| acc + cursor.measure(&g, text));
-
-      if is_auto_wrap
-        && self.inline_cursor != GlyphUnit::ZERO
-        && self.is_over_line_bound(width + self.inline_cursor)
-      {
+      let word: SmallVec<[Glyph; 8]> = word.collect();
+      let mut word = &word[..];
+      // The spaces at the end of a justified line hang out of the bounds rather
+      // than start the next line.
+      let hang = self.text_align == TextAlign::Justify
+        && word
+          .iter()
+          .all(|g| text[g.cluster as usize..].starts_with(char::is_whitespace));
+      while is_auto_wrap && !hang && !self.is_elided_line() {
+        let width = word
+          .iter()
+          .fold(GlyphUnit::ZERO, |acc, g| acc + cursor.measure(g, text));
+        if !self.is_over_line_bound(width + self.inline_cursor) {
+          break;
+        }
+
+        if let Some(at) = self.hyphen_point(word, &hyphen, &breaks, text, cursor) {
+          let (head, rest) = word.split_at(at);
+          let cluster = head[at - 1].cluster;
+          let hyphen = hyphen
+            .iter()
+            .map(|g| Glyph { cluster, ..g.clone() });
+          for mut g in head.iter().cloned().chain(hyphen) {
+            cursor.advance_glyph(&mut g, line_offset, text);
+            g.cluster += base;
+            self.line_ascent = self.line_ascent.max(ascent);
+            self.line_descent = self.line_descent.max(descent);
+            self.push_glyph(g, false);
+            self.inline_cursor = cursor.position();
+          }
+          word = rest;
+        } else if self.inline_cursor == GlyphUnit::ZERO {
+          break;
+        }
+
+        if self.is_last_line() {
+          self.wrapped = true;
+          self.truncate(word[0].cluster + base);
+          return;
+        }
         new_line(self, cursor);
       }
 
-      let mut word = word.peekable();
+      let mut word = word.iter().peekable();
       while let Some(g) = word.peek() {
         let mut at = (*g).clone();
+        let is_space = text[at.cluster as usize..].starts_with(char::is_whitespace);
 
         cursor.advance_glyph(&mut at, line_offset, text);
 
@@ -241,11 +374,19 @@ where
 
         if self.inline_cursor == GlyphUnit::ZERO
           || !is_auto_wrap
+          || hang
+          || self.is_elided_line()
           || !self.is_over_line_bound(cursor.position())
         {
-          self.push_glyph(at);
+          self.line_ascent = self.line_ascent.max(ascent);
+          self.line_descent = self.line_descent.max(descent);
+          self.push_glyph(at, is_space);
           self.inline_cursor = cursor.position();
           word.next();
+        } else if self.is_last_line() {
+          self.wrapped = true;
+          self.truncate(at.cluster);
+          return;
         } else {
           new_line(self, cursor);
         }
@@ -253,13 +394,40 @@ where
     }
   }
 
-  fn push_glyph(&mut self, g: Glyph) {
+  /// The index of the glyph in the `word` to hyphenate before, which lets the
+  /// glyphs before it and the hyphen fit the current line. The breaks must be
+  /// in the logical order, so the right-to-left words are not hyphenated.
+  fn hyphen_point(
+    &self, word: &[Glyph], hyphen: &[Glyph], breaks: &[u32], text: &str, cursor: &impl InlineCursor,
+  ) -> Option<usize> {
+    let mut point = None;
+    let mut width = self.inline_cursor;
+    for (i, pair) in word.windows(2).enumerate() {
+      let (prev, g) = (&pair[0], &pair[1]);
+      width += cursor.measure(prev, text);
+      if prev.cluster < g.cluster && breaks.binary_search(&g.cluster).is_ok() {
+        let with_hyphen = hyphen.iter().fold(width, |acc, h| {
+          let h = Glyph { cluster: prev.cluster, ..h.clone() };
+          acc + cursor.measure(&h, text)
+        });
+        if self.is_over_line_bound(with_hyphen) {
+          break;
+        }
+        point = Some(i + 1);
+      }
+    }
+    point
+  }
+
+  fn push_glyph(&mut self, g: Glyph, is_space: bool) {
     let line = self.visual_lines.last_mut();
-    line.unwrap().glyphs.push(g)
+    line.unwrap().glyphs.push(g);
+    self.line_spaces.push(is_space);
   }
 
   fn begin_line(&mut self) {
     let mut line = VisualLine::default();
+    self.line_spaces.clear();
     if self.line_dir.is_horizontal() {
       line.width = self.line_height;
     } else {
@@ -270,17 +438,218 @@ where
 
   fn end_line(&mut self) {
     let line = self.visual_lines.last_mut().unwrap();
+    let extent = self.line_ascent + self.line_descent;
+    let line_height = self.line_height.max(extent);
+    let offset = self.line_ascent + (line_height - extent) / 2.;
     // we will reorder the line after consumed all inputs.
     if self.line_dir.is_horizontal() {
       line.height = self.inline_cursor;
+      line.width = line_height;
+      line
+        .glyphs
+        .iter_mut()
+        .for_each(|g| g.x_offset += offset);
     } else {
       line.width = self.inline_cursor;
+      line.height = line_height;
+      line
+        .glyphs
+        .iter_mut()
+        .for_each(|g| g.y_offset += offset);
+    }
+    self.last_baseline = offset;
+    self.line_ascent = GlyphUnit::ZERO;
+    self.line_descent = GlyphUnit::ZERO;
+    let justified = self.wrapped && self.text_align == TextAlign::Justify && self.justify_line();
+    if !justified {
+      self.over_bounds |= self.is_over_line_bound(self.inline_cursor);
     }
-    self.over_bounds |= self.is_over_line_bound(self.inline_cursor);
     self.over_bounds |= self.is_last_line_over();
+    self.wrapped = false;
     self.inline_cursor = GlyphUnit::ZERO;
   }
 
+  /// Stretch the last line to fill the bounds, return if the line is stretched.
+  /// The space is distributed after the spaces between the words, or between
+  /// the clusters if there is no space between the words. The spaces at the
+  /// both ends of the line keep their size.
+  fn justify_line(&mut self) -> bool {
+    let hor_text = !self.line_dir.is_horizontal();
+    let bound = if hor_text { self.bounds.width } else { self.bounds.height };
+    let spaces = &self.line_spaces;
+    let Some(line) = self.visual_lines.last_mut() else { return false };
+    let (Some(first), Some(last)) =
+      (spaces.iter().position(|s| !s), spaces.iter().rposition(|s| !s))
+    else {
+      return false;
+    };
+    let inline_pos = |g: &Glyph| if hor_text { g.x_offset } else { g.y_offset };
+    let end = line
+      .glyphs
+      .get(last + 1)
+      .map_or(self.inline_cursor, inline_pos);
+    let extra = bound - end;
+
+    // Whether to insert the space before the glyph.
+    let mut gaps: Vec<_> = (0..line.glyphs.len())
+      .map(|i| first < i && i <= last && spaces[i - 1])
+      .collect();
+    if !gaps.contains(&true) {
+      // No space before the invisible glyphs, like the soft hyphens.
+      let glyphs = &line.glyphs;
+      let inline_advance = |g: &Glyph| if hor_text { g.x_advance } else { g.y_advance };
+      gaps = (0..glyphs.len())
+        .map(|i| {
+          first < i
+            && i <= last
+            && glyphs[i - 1].cluster != glyphs[i].cluster
+            && inline_advance(&glyphs[i]) != GlyphUnit::ZERO
+        })
+        .collect();
+    }
+    let count = gaps.iter().filter(|g| **g).count();
+    if count == 0 || extra <= GlyphUnit::ZERO {
+      return false;
+    }
+
+    let mut inserted = 0;
+    for (g, gap) in line.glyphs.iter_mut().zip(gaps) {
+      inserted += gap as i32;
+      let shift = extra * inserted / count as f32;
+      if hor_text {
+        g.x_offset += shift;
+      } else {
+        g.y_offset += shift;
+      }
+    }
+    if hor_text {
+      line.width = bound;
+    } else {
+      line.height = bound;
+    }
+    true
+  }
+
+  /// Whether the current line is the last line that the max lines allows.
+  fn is_last_line(&self) -> bool {
+    self
+      .max_lines
+      .is_some_and(|max| max <= self.visual_lines.len())
+  }
+
+  /// The text that not fits the last line is elided instead of wrapped.
+  fn is_elided_line(&self) -> bool {
+    self.overflow == TextOverflow::Ellipsis && self.is_last_line()
+  }
+
+  fn truncate(&mut self, cluster: u32) {
+    let at = self.truncated_at.get_or_insert(cluster);
+    *at = (*at).min(cluster);
+  }
+
+  /// Replace the logical end of the last line with the ellipsis if the text is
+  /// truncated or overflows the line. The ellipsis is placed at the visual end
+  /// of the paragraph direction.
+  fn elide_last_line(&mut self) {
+    let hor_text = !self.line_dir.is_horizontal();
+    let inline_pos = |g: &Glyph| if hor_text { g.x_offset } else { g.y_offset };
+    let inline_advance = |g: &Glyph| if hor_text { g.x_advance } else { g.y_advance };
+    let bound = if hor_text { self.bounds.width } else { self.bounds.height };
+    let (Some(ellipsis), Some(line)) = (self.ellipsis.as_ref(), self.visual_lines.last_mut())
+    else {
+      return;
+    };
+    let extent = if hor_text { line.width } else { line.height };
+    if self.truncated_at.is_none() && extent <= bound {
+      return;
+    }
+
+    let ellipsis_size = ellipsis.font_size_factor * GlyphUnit::PIXELS_PER_EM as f32;
+    let ellipsis_glyphs: Vec<_> = ellipsis
+      .shape_result
+      .glyphs
+      .iter()
+      .map(|g| g.clone().cast_to(ellipsis_size))
+      .collect();
+    let ellipsis_extent = ellipsis_glyphs
+      .iter()
+      .fold(GlyphUnit::ZERO, |acc, g| acc + inline_advance(g));
+
+    // The space every glyph takes in the line, include the letter space.
+    let glyphs = std::mem::take(&mut line.glyphs);
+    let spaces: Vec<_> = glyphs
+      .iter()
+      .enumerate()
+      .map(|(i, g)| glyphs.get(i + 1).map_or(extent, inline_pos) - inline_pos(g))
+      .collect();
+
+    // Remove the glyphs from the logical end until the ellipsis fits, the
+    // glyphs of a cluster are removed together.
+    let mut order: Vec<_> = (0..glyphs.len()).collect();
+    order.sort_by_key(|i| Reverse(glyphs[*i].cluster));
+    let mut removed = vec![false; glyphs.len()];
+    let mut kept = extent;
+    let mut elided_at = None;
+    for i in order {
+      let cluster = glyphs[i].cluster;
+      if kept + ellipsis_extent <= bound && elided_at != Some(cluster) {
+        break;
+      }
+      removed[i] = true;
+      kept -= spaces[i];
+      elided_at = Some(cluster);
+    }
+    let cluster = elided_at
+      .into_iter()
+      .chain(self.truncated_at)
+      .min()
+      .unwrap_or(0);
+
+    let ellipsis_at = if self.last_para_rtl { GlyphUnit::ZERO } else { kept };
+    let mut shift = if self.last_para_rtl { ellipsis_extent } else { GlyphUnit::ZERO };
+    let mut line_glyphs = Vec::with_capacity(glyphs.len() + ellipsis_glyphs.len());
+    for (i, mut g) in glyphs.into_iter().enumerate() {
+      if removed[i] {
+        shift -= spaces[i];
+        continue;
+      }
+      if hor_text {
+        g.x_offset += shift;
+      } else {
+        g.y_offset += shift;
+      }
+      line_glyphs.push(g);
+    }
+
+    let cross_offset = self.last_baseline - ellipsis.baseline;
+    let mut pen = ellipsis_at;
+    let ellipsis_glyphs = ellipsis_glyphs.into_iter().map(|mut g| {
+      g.cluster = cluster;
+      if hor_text {
+        g.x_offset += pen;
+        g.y_offset += cross_offset;
+      } else {
+        g.x_offset += cross_offset;
+        g.y_offset += pen;
+      }
+      pen += inline_advance(&g);
+      g
+    });
+    if self.last_para_rtl {
+      line_glyphs.splice(0..0, ellipsis_glyphs);
+    } else {
+      line_glyphs.extend(ellipsis_glyphs);
+    }
+
+    line.glyphs = line_glyphs;
+    if hor_text {
+      line.width = kept + ellipsis_extent;
+    } else {
+      line.height = kept + ellipsis_extent;
+    }
+    self.truncate(cluster);
+  }
+
   fn is_over_line_bound(&self, position: GlyphUnit) -> bool {
     if self.text_align == TextAlign::Center {
       return false;
@@ -315,7 +684,12 @@ pub struct InputRun {
   /// The factor relative to the standard size.
   pub(crate) font_size_factor: f32,
   pub(crate) letter_space: GlyphUnit,
+  /// The distance from the top of the em box to the baseline of the glyphs.
+  pub(crate) baseline: GlyphUnit,
   pub(crate) range: Range<usize>,
+  /// The shaped hyphen to place at the end of the line that hyphenates a word
+  /// of the run.
+  pub(crate) hyphen: Option<Sc<ShapeResult>>,
   reorder_text: String,
 }
 
@@ -439,7 +813,7 @@ pub(crate) fn text_align_offset(
   content: GlyphUnit, container: GlyphUnit, text_align: TextAlign,
 ) -> GlyphUnit {
   match text_align {
-    TextAlign::Start => GlyphUnit::ZERO,
+    TextAlign::Start | TextAlign::Justify => GlyphUnit::ZERO,
     TextAlign::Center => (container - content) / 2.,
     TextAlign::End => container - content,
   }
@@ -473,7 +847,7 @@ fn letter_spacing_char(c: char) -> bool {
 impl InputRun {
   pub(crate) fn new(
     shape_result: Sc<ShapeResult>, font_size_factor: f32, letter_space: GlyphUnit,
-    range: Range<usize>,
+    baseline: GlyphUnit, range: Range<usize>,
   ) -> Self {
     let text: &str = &shape_result.text;
     // text and glyphs in run may in different order, so we recollect the chars.
@@ -484,7 +858,20 @@ impl InputRun {
       .iter()
       .filter_map(|gh| text[gh.cluster as usize..].chars().next())
       .collect();
-    Self { shape_result, font_size_factor, letter_space, range, reorder_text }
+    Self {
+      shape_result,
+      font_size_factor,
+      letter_space,
+      baseline,
+      range,
+      hyphen: None,
+      reorder_text,
+    }
+  }
+
+  pub(crate) fn with_hyphen(mut self, hyphen: Sc<ShapeResult>) -> Self {
+    self.hyphen = Some(hyphen);
+    self
   }
 
   #[inline]
@@ -507,3 +894,47 @@ impl InputRun {
       })
   }
 }
+
+/// The byte offsets in the `text` that the words can be hyphenated before.
+fn hyphen_breaks(text: &str, hyphens: Hyphens) -> Vec<u32> {
+  const SOFT_HYPHEN: char = '\u{AD}';
+  let mut breaks = vec![];
+  if hyphens == Hyphens::None {
+    return breaks;
+  }
+  for (start, word) in text.split_word_bound_indices() {
+    if word.contains(SOFT_HYPHEN) {
+      let soft = word.match_indices(SOFT_HYPHEN);
+      breaks.extend(soft.map(|(i, s)| (start + i + s.len()) as u32));
+    } else if let Hyphens::Auto(lang) = hyphens {
+      breaks.extend(
+        dictionary_breaks(word, lang)
+          .into_iter()
+          .map(|at| (start + at) as u32),
+      );
+    }
+  }
+  breaks
+}
+
+/// The byte offsets between the syllables of the `word`.
+#[cfg(feature = "hyphenation")]
+fn dictionary_breaks(word: &str, lang: [u8; 2]) -> Vec<usize> {
+  let Some(lang) = hypher::Lang::from_iso(lang) else { return vec![] };
+  if !word.chars().all(char::is_alphabetic) {
+    return vec![];
+  }
+  let mut at = 0;
+  let mut breaks: Vec<_> = hypher::hyphenate(word, lang)
+    .map(|syllable| {
+      at += syllable.len();
+      at
+    })
+    .collect();
+  // No break after the last syllable.
+  breaks.pop();
+  breaks
+}
+
+#[cfg(not(feature = "hyphenation"))]
+fn dictionary_breaks(_: &str, _: [u8; 2]) -> Vec<usize> { vec![] }
@@ -1,8 +1,9 @@
-use std::{cell::RefCell, ops::Range};
+use std::{cell::RefCell, ops::Range, rc::Rc};
 
 use font_db::GlyphBaseline;
 use ribir_algo::{FrameCache, Sc, Substr};
 use ribir_geom::{Point, Rect, Size};
+use smallvec::SmallVec;
 
 use crate::{
   font_db::FontDB,
@@ -15,6 +16,10 @@ use crate::{
 #[derive(Clone, PartialEq, Eq, Hash)]
 struct RunKey {
   pub ids: Box<[ID]>,
+  pub features: Box<[FontFeature]>,
+  pub variations: Box<[FontVariation]>,
+  /// The font size relative to the standard font size.
+  pub font_size: GlyphUnit,
   pub line_height: GlyphUnit,
   pub letter_space: GlyphUnit,
   pub text: Substr,
@@ -31,6 +36,10 @@ pub struct TypographyKey {
   baseline: GlyphBaseline,
   line_dir: PlaceLineDirection,
   overflow: TextOverflow,
+  max_lines: Option<usize>,
+  /// The ellipsis to elide the text with in the `Ellipsis` mode.
+  ellipsis: Option<RunKey>,
+  hyphens: Hyphens,
 }
 
 /// Do simple text typography and cache it.
@@ -48,6 +57,27 @@ pub struct VisualGlyphs {
   y: GlyphUnit,
   visual_info: Sc<VisualInfos>,
   order_info: Sc<ReorderResult>,
+  spans: Rc<[PaintSpan]>,
+}
+
+/// How to paint the glyphs of a span.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PaintSpan {
+  /// The byte range of the span in the text.
+  pub(crate) range: Range<usize>,
+  pub(crate) font_size: f32,
+  pub(crate) brush: Option<Brush>,
+  pub(crate) decoration: TextDecorationStyle,
+}
+
+/// A piece of the decoration lines of the glyphs of a span in a visual line.
+pub(crate) struct DecorationPiece<'a> {
+  pub(crate) span: &'a PaintSpan,
+  /// The face to provide the metrics of the lines.
+  pub(crate) face_id: ID,
+  /// The horizontal range of the glyphs.
+  pub(crate) x: Range<f32>,
+  pub(crate) baseline: f32,
 }
 
 impl VisualGlyphs {
@@ -69,10 +99,22 @@ impl VisualGlyphs {
     if line_dir == PlaceLineDirection::BottomToTop {
       y += bound_height - visual_info.visual_height
     }
-    Self { font_size, x, y, visual_info, order_info }
+    Self { font_size, x, y, visual_info, order_info, spans: Rc::new([]) }
   }
 
   pub fn font_size(&self) -> f32 { self.font_size }
+
+  /// The paint style of the span that the glyph of the `cluster` belongs to.
+  pub(crate) fn paint_span(&self, cluster: u32) -> Option<&PaintSpan> {
+    let cluster = cluster as usize;
+    let idx = self
+      .spans
+      .partition_point(|s| s.range.end <= cluster);
+    self
+      .spans
+      .get(idx)
+      .filter(|s| s.range.contains(&cluster))
+  }
 }
 
 impl TypographyStore {
@@ -93,7 +135,35 @@ impl TypographyStore {
     &mut self, text: Substr, style: &TextStyle, bounds: Size, text_align: TextAlign,
     baseline: GlyphBaseline, line_dir: PlaceLineDirection,
   ) -> VisualGlyphs {
-    let TextStyle { font_size, ref font_face, letter_space, line_height, overflow } = *style;
+    let span = TextSpan::new(text);
+    self.typography_spans(
+      std::slice::from_ref(&span),
+      style,
+      bounds,
+      text_align,
+      baseline,
+      line_dir,
+    )
+  }
+
+  /// Typography the spans as one paragraph, every span is shaped with its own
+  /// style, and the fields of the style that are not specified inherit from
+  /// the `style`.
+  pub fn typography_spans(
+    &mut self, spans: &[TextSpan], style: &TextStyle, bounds: Size, text_align: TextAlign,
+    baseline: GlyphBaseline, line_dir: PlaceLineDirection,
+  ) -> VisualGlyphs {
+    let TextStyle {
+      font_size,
+      ref font_face,
+      letter_space,
+      line_height,
+      overflow,
+      ref decoration,
+      max_lines,
+      ref ellipsis,
+      hyphens,
+    } = *style;
     // Since we cache the result of the standard font size, we must ensure that all
     // variables are cast relative to this standard font size.
     let scale = font_size / GlyphUnit::PIXELS_PER_EM as f32;
@@ -101,50 +171,207 @@ impl TypographyStore {
       GlyphUnit::from_pixel(bounds.width / scale),
       GlyphUnit::from_pixel(bounds.height / scale),
     );
-    let letter_space =
-      GlyphUnit::from_pixel(letter_space / font_size * GlyphUnit::PIXELS_PER_EM as f32);
-    let line_height =
-      GlyphUnit::from_pixel(line_height / font_size * GlyphUnit::PIXELS_PER_EM as f32);
+    let to_standard =
+      |v: f32| GlyphUnit::from_pixel(v / font_size * GlyphUnit::PIXELS_PER_EM as f32);
+    let line_height = to_standard(line_height);
+    // The ellipsis elides the last line that fits the bounds.
+    let max_lines = if overflow == TextOverflow::Ellipsis {
+      let cross = if line_dir.is_horizontal() { bounds.width } else { bounds.height };
+      let fit_lines = (cross.into_pixel() / line_height.into_pixel()).max(1.) as usize;
+      Some(max_lines.map_or(fit_lines, |max| max.min(fit_lines)))
+    } else {
+      max_lines
+    };
 
+    let text: Substr = match spans {
+      [span] => span.text.clone(),
+      _ => spans
+        .iter()
+        .map(|s| &*s.text)
+        .collect::<String>()
+        .into(),
+    };
     let info = self.reorder.reorder_text(&text).clone();
-    let ids = self
-      .font_db
-      .borrow_mut()
-      .select_all_match(font_face)
-      .into_boxed_slice();
-    let runs = [RunKey { ids, line_height, letter_space, text }].into();
-    let key = TypographyKey::new(runs, bounds, text_align, line_dir, overflow, baseline);
+
+    let mut start = 0;
+    let mut paint_spans = Vec::with_capacity(spans.len());
+    let runs = spans
+      .iter()
+      .map(|span| {
+        let SpanStyle {
+          font_face: span_face,
+          font_size: span_size,
+          letter_space: span_space,
+          brush,
+          decoration: span_decoration,
+        } = &span.style;
+        let span_size = span_size.unwrap_or(font_size);
+        let range = start..start + span.text.len();
+        start = range.end;
+        paint_spans.push(PaintSpan {
+          range,
+          font_size: span_size,
+          brush: brush.clone(),
+          decoration: span_decoration
+            .as_ref()
+            .unwrap_or(decoration)
+            .clone(),
+        });
+
+        let face = span_face.as_ref().unwrap_or(font_face);
+        let ids = self
+          .font_db
+          .borrow_mut()
+          .select_all_match(face)
+          .into_boxed_slice();
+        RunKey {
+          ids,
+          features: face.features.clone(),
+          variations: face.variations.clone(),
+          font_size: to_standard(span_size),
+          line_height,
+          letter_space: to_standard(span_space.unwrap_or(letter_space)),
+          text: span.text.clone(),
+        }
+      })
+      .collect();
+
+    let ellipsis = (overflow == TextOverflow::Ellipsis).then(|| RunKey {
+      ids: self
+        .font_db
+        .borrow_mut()
+        .select_all_match(font_face)
+        .into_boxed_slice(),
+      features: font_face.features.clone(),
+      variations: font_face.variations.clone(),
+      font_size: to_standard(font_size),
+      line_height,
+      letter_space: GlyphUnit::ZERO,
+      text: ellipsis.clone(),
+    });
+    let key = TypographyKey {
+      max_lines,
+      ellipsis,
+      hyphens,
+      ..TypographyKey::new(runs, bounds, text_align, line_dir, overflow, baseline)
+    };
     let infos = if let Some(infos) = self.cache.get(&key).cloned() {
       infos
     } else {
-      let ids = &key.runs[0].ids;
-      let text = &key.runs[0].text;
+      let ellipsis = key.ellipsis.as_ref().map(|run| {
+        let dir = TextDirection::LeftToRight;
+        let shape_result = self.shaper.shape_text(
+          &run.text,
+          &run.ids,
+          &run.features,
+          &run.variations,
+          dir,
+          baseline,
+        );
+        let run_baseline = self.run_baseline(&run.ids, run.font_size, baseline);
+        let range = 0..run.text.len();
+        InputRun::new(shape_result, 1., run.letter_space, run_baseline, range)
+      });
+      let mut offset = 0;
+      let span_runs: Vec<_> = key
+        .runs
+        .iter()
+        .map(|run| {
+          let range = offset..offset + run.text.len();
+          offset = range.end;
+          (range, run)
+        })
+        .collect();
       let inputs = info.paras.iter().map(|p| {
-        p.runs
-          .iter()
-          .map(|r| {
-            let dir = if r.is_empty() || p.levels[r.start].is_ltr() {
-              TextDirection::LeftToRight
-            } else {
-              TextDirection::RightToLeft
-            };
-
-            let shape_result = self
-              .shaper
-              .shape_text(&text.substr(r.clone()), ids, dir, baseline);
-            InputRun::new(shape_result, 1., letter_space, r.clone())
-          })
-          .collect()
+        let mut inputs = SmallVec::new();
+        for r in p.runs.iter() {
+          let dir = if r.is_empty() || p.levels[r.start].is_ltr() {
+            TextDirection::LeftToRight
+          } else {
+            TextDirection::RightToLeft
+          };
+
+          // Split the bidi run by the spans, the pieces are placed in the reverse
+          // order in a right-to-left run.
+          let mut pieces: SmallVec<[_; 1]> = span_runs
+            .iter()
+            .filter(|(rg, _)| rg.start < r.end && r.start < rg.end)
+            .map(|(rg, run)| (rg.start.max(r.start)..rg.end.min(r.end), *run))
+            .collect();
+          if pieces.is_empty() {
+            // An empty run takes the style of the span it's at.
+            let span = span_runs
+              .iter()
+              .find(|(rg, _)| r.end <= rg.end)
+              .or(span_runs.last());
+            pieces.extend(span.map(|(_, run)| (r.clone(), *run)));
+          }
+          if dir == TextDirection::RightToLeft {
+            pieces.reverse();
+          }
+
+          for (rg, run) in pieces {
+            let shape_result = self.shaper.shape_text(
+              &text.substr(rg.clone()),
+              &run.ids,
+              &run.features,
+              &run.variations,
+              dir,
+              baseline,
+            );
+            let factor = run.font_size.into_pixel() / GlyphUnit::PIXELS_PER_EM as f32;
+            let run_baseline = self.run_baseline(&run.ids, run.font_size, baseline);
+            let mut input = InputRun::new(shape_result, factor, run.letter_space, run_baseline, rg);
+            if key.hyphens != Hyphens::None {
+              let hyphen = self.shaper.shape_text(
+                &Substr::from("-"),
+                &run.ids,
+                &run.features,
+                &run.variations,
+                TextDirection::LeftToRight,
+                baseline,
+              );
+              input = input.with_hyphen(hyphen);
+            }
+            inputs.push(input);
+          }
+        }
+        let rtl = p.levels.iter().min().is_some_and(|l| l.is_rtl());
+        InputParagraph { runs: inputs, rtl }
       });
 
-      let t_man = TypographyMan::new(inputs, line_dir, text_align, line_height, bounds, overflow);
+      let mut t_man =
+        TypographyMan::new(inputs, line_dir, text_align, line_height, bounds, overflow)
+          .with_max_lines(key.max_lines)
+          .with_hyphens(key.hyphens);
+      if let Some(ellipsis) = ellipsis {
+        t_man = t_man.with_ellipsis(ellipsis);
+      }
       let visual_info = t_man.typography_all();
       let infos = Sc::new(visual_info);
       self.cache.put(key, infos.clone());
       infos
     };
 
-    VisualGlyphs::new(font_size, line_dir, info, bounds.width, bounds.height, infos.clone())
+    let mut glyphs =
+      VisualGlyphs::new(font_size, line_dir, info, bounds.width, bounds.height, infos.clone());
+    glyphs.spans = paint_spans.into();
+    glyphs
+  }
+
+  /// The distance from the top of the em box of the `font_size` to the
+  /// baseline of the glyphs shaped by the first face of the `ids`.
+  fn run_baseline(&self, ids: &[ID], font_size: GlyphUnit, baseline: GlyphBaseline) -> GlyphUnit {
+    let font_db = self.font_db.borrow();
+    let shift = ids
+      .first()
+      .and_then(|id| font_db.try_get_face_data(*id))
+      .map_or(GlyphUnit::ZERO, |face| {
+        let em = face.units_per_em() as f32;
+        let offset = face.baseline_offset(baseline) as f32 / em;
+        GlyphUnit::from_pixel(offset * font_size.into_pixel())
+      });
+    font_size - shift
   }
 
   pub fn font_db(&self) -> &Sc<RefCell<FontDB>> { &self.font_db }
@@ -432,6 +659,52 @@ impl VisualGlyphs {
     Some(iter)
   }
 
+  /// The decoration pieces of the lines in the bounds, the glyphs of a span in
+  /// a line share one piece.
+  pub(crate) fn decorations_in_bounds(&self, rc: &Rect) -> Vec<DecorationPiece<'_>> {
+    let mut pieces = vec![];
+    let visual_rect = self.visual_rect();
+    let Some(mut rc) = visual_rect.intersection(rc) else { return pieces };
+    // Only the horizontal lines are decorated.
+    if !self.is_horizontal_line() {
+      return pieces;
+    }
+    rc.origin -= visual_rect.origin.to_vector();
+
+    for line in &self.visual_info.visual_lines {
+      let y = self.to_pixel_value(line.y);
+      if y + self.to_pixel_value(line.height) <= rc.min_y() || rc.max_y() <= y {
+        continue;
+      }
+
+      let mut piece: Option<DecorationPiece> = None;
+      for g in line.glyphs_iter(true) {
+        let span = self
+          .paint_span(g.cluster)
+          .filter(|s| !s.decoration.decoration.is_empty());
+        let g = g.cast_to(self.font_size);
+        let x = g.x_offset.into_pixel()..(g.x_offset + g.x_advance).into_pixel();
+        match (piece.as_mut(), span) {
+          (Some(p), Some(span)) if std::ptr::eq(p.span, span) => {
+            p.x.start = p.x.start.min(x.start);
+            p.x.end = p.x.end.max(x.end);
+          }
+          (_, span) => {
+            pieces.extend(piece.take());
+            piece = span.map(|span| DecorationPiece {
+              span,
+              face_id: g.face_id,
+              x,
+              baseline: g.y_offset.into_pixel() + span.font_size,
+            });
+          }
+        }
+      }
+      pieces.extend(piece);
+    }
+    pieces
+  }
+
   pub fn glyph_count(&self, row: usize, ignore_new_line: bool) -> usize {
     self
       .visual_info
@@ -456,6 +729,9 @@ impl VisualGlyphs {
 
   pub fn glyph_row_count(&self) -> usize { self.visual_info.visual_lines.len() }
 
+  /// Whether some text is not shown because of the max lines or the ellipsis.
+  pub fn is_truncated(&self) -> bool { self.visual_info.truncated }
+
   fn is_horizontal_line(&self) -> bool { !self.visual_info.line_dir.is_horizontal() }
 }
 
@@ -469,7 +745,7 @@ impl TypographyKey {
       // width. The wider one can use for the narrower one. S
       TextOverflow::Clip => GlyphUnit::MAX,
 
-      TextOverflow::AutoWrap => {
+      TextOverflow::AutoWrap | TextOverflow::Ellipsis => {
         if line_dir.is_horizontal() {
           bounds.height
         } else {
@@ -478,7 +754,17 @@ impl TypographyKey {
       }
     };
 
-    Self { runs, line_width, text_align, line_dir, overflow, baseline }
+    Self {
+      runs,
+      line_width,
+      text_align,
+      line_dir,
+      overflow,
+      baseline,
+      max_lines: None,
+      ellipsis: None,
+      hyphens: Hyphens::None,
+    }
   }
 }
 
@@ -487,7 +773,7 @@ mod tests {
   use core::f32;
 
   use super::*;
-  use crate::FontFamily;
+  use crate::{Color, FontFamily};
 
   fn test_store() -> TypographyStore {
     let font_db = Sc::new(RefCell::new(FontDB::default()));
@@ -500,7 +786,14 @@ mod tests {
     FontFace { families: Box::new([FontFamily::Name("DejaVu Sans".into())]), ..<_>::default() }
   }
   fn text_style(font_size: f32, overflow: TextOverflow, letter_space: f32) -> TextStyle {
-    TextStyle { font_size, font_face: test_face(), letter_space, line_height: font_size, overflow }
+    TextStyle {
+      font_size,
+      font_face: test_face(),
+      letter_space,
+      line_height: font_size,
+      overflow,
+      ..<_>::default()
+    }
   }
   fn zero_letter_space_style(font_size: f32, overflow: TextOverflow) -> TextStyle {
     text_style(font_size, overflow, 0.)
@@ -514,6 +807,39 @@ mod tests {
     store.typography(text, style, bounds, text_align, GlyphBaseline::Alphabetic, line_dir)
   }
 
+  #[test]
+  fn paint_glyphs_of_evicted_variation() {
+    let mut store = test_store();
+    let path = env!("CARGO_MANIFEST_DIR").to_owned() + "/../fonts/Nunito-VariableFont_wght.ttf";
+    store
+      .font_db()
+      .borrow_mut()
+      .load_font_file(path)
+      .unwrap();
+    let bounds = Size::new(100., 20.);
+    let typography = |store: &mut TypographyStore, wght: f32| {
+      let font_face = FontFace {
+        families: Box::new([FontFamily::Name("Nunito".into())]),
+        variations: Box::new([FontVariation::new(b"wght", wght)]),
+        ..<_>::default()
+      };
+      let style = TextStyle { font_face, ..zero_letter_space_style(14., TextOverflow::Clip) };
+      let (baseline, dir) = (GlyphBaseline::Alphabetic, PlaceLineDirection::TopToBottom);
+      store.typography("Ribir".into(), &style, bounds, TextAlign::Start, baseline, dir)
+    };
+
+    let glyphs = typography(&mut store, 700.);
+    // Another text animates its weight, it's shaped by a new instance every frame.
+    for wght in 200..300 {
+      typography(&mut store, wght as f32);
+      store.end_frame();
+    }
+
+    let mut painter = Painter::new(Rect::from_size(bounds));
+    painter.draw_glyphs_in_rect(&glyphs, Rect::from_size(bounds), &store.font_db().borrow());
+    assert_eq!(painter.finish().len(), 5);
+  }
+
   #[test]
   fn simple_text_bounds() {
     let text = "Hello
@@ -798,6 +1124,308 @@ mod tests {
     );
   }
 
+  fn typography_spans(spans: &[TextSpan], style: &TextStyle, bounds: Size) -> VisualGlyphs {
+    let mut store = test_store();
+    store.typography_spans(
+      spans,
+      style,
+      bounds,
+      TextAlign::Start,
+      GlyphBaseline::Alphabetic,
+      PlaceLineDirection::TopToBottom,
+    )
+  }
+
+  #[test]
+  fn spans_as_plain_text() {
+    let style = zero_letter_space_style(14., TextOverflow::Clip);
+    let bounds = Size::new(f32::MAX, f32::MAX);
+    let plain = typography_text(
+      "Hello world!".into(),
+      &style,
+      bounds,
+      TextAlign::Start,
+      PlaceLineDirection::TopToBottom,
+    );
+    let spans = typography_spans(
+      &[TextSpan::new("Hello "), TextSpan::new("world"), TextSpan::new("!")],
+      &style,
+      bounds,
+    );
+
+    assert_eq!(plain.glyphs().collect::<Vec<_>>(), spans.glyphs().collect::<Vec<_>>());
+    assert_eq!(plain.visual_rect(), spans.visual_rect());
+  }
+
+  #[test]
+  fn spans_share_baseline() {
+    let style = zero_letter_space_style(10., TextOverflow::Clip);
+    let spans = [
+      TextSpan::new("ab"),
+      TextSpan::new("cd")
+        .with_font_size(20.)
+        .with_brush(Color::RED),
+      TextSpan::new("ef").with_letter_space(5.),
+    ];
+    let visual = typography_spans(&spans, &style, Size::new(f32::MAX, f32::MAX));
+
+    // The line grows with the large span.
+    assert_eq!(visual.visual_rect().height(), 20.);
+    let glyphs: Vec<_> = visual.glyphs().collect();
+    assert_eq!(glyphs.len(), 6);
+    // The baseline is at the bottom of the em box of the alphabetic glyphs.
+    let small_baseline = glyphs[0].y_offset.into_pixel() + 10.;
+    let large_baseline = glyphs[2].y_offset.into_pixel() + 20.;
+    assert!((small_baseline - large_baseline).abs() < 0.01);
+    // The letter space only applies to its span.
+    let advance = |i: usize| (glyphs[i + 1].x_offset - glyphs[i].x_offset).into_pixel();
+    assert!((advance(4) - glyphs[4].x_advance.into_pixel() - 5.).abs() < 0.01);
+    assert!((advance(0) - glyphs[0].x_advance.into_pixel()).abs() < 0.01);
+
+    let span = visual.paint_span(2).unwrap();
+    assert_eq!(span.font_size, 20.);
+    assert_eq!(span.brush, Some(Color::RED.into()));
+    assert_eq!(visual.paint_span(5).unwrap().font_size, 10.);
+  }
+
+  #[test]
+  fn wrap_and_select_across_spans() {
+    let style = zero_letter_space_style(16., TextOverflow::AutoWrap);
+    let spans = [
+      TextSpan::new("WITHIN "),
+      TextSpan::new("BOUND ").with_font_size(20.),
+      TextSpan::new("WRAP"),
+    ];
+    let visual = typography_spans(&spans, &style, Size::new(16. * 8., f32::MAX));
+
+    assert_eq!(visual.glyph_row_count(), 2);
+    assert_eq!(visual.position_by_cluster(7), (1, 0));
+    assert_eq!(visual.position_to_cluster(1, 0), 7);
+    // The selection across the spans is one rect in a line.
+    assert_eq!(visual.select_range(&(3..7)).len(), 1);
+    assert_eq!(visual.select_range(&(3..15)).len(), 2);
+  }
+
+  #[test]
+  fn decoration_pieces() {
+    let mut style = zero_letter_space_style(16., TextOverflow::AutoWrap);
+    style.decoration = TextDecorationStyle::new(TextDecoration::UNDERLINE);
+    let spans = [
+      TextSpan::new("WITHIN "),
+      TextSpan::new("BOUND ").with_decoration(TextDecorationStyle::default()),
+      TextSpan::new("WRAP WRAP WRAP"),
+    ];
+    let visual = typography_spans(&spans, &style, Size::new(16. * 8., f32::MAX));
+    assert_eq!(visual.glyph_row_count(), 3);
+
+    let pieces = visual.decorations_in_bounds(&Rect::from_size(Size::new(1000., 1000.)));
+    let ranges: Vec<_> = pieces
+      .iter()
+      .map(|p| p.span.range.clone())
+      .collect();
+    // The span without decoration has no piece, and the wrapped span has a piece
+    // in every line.
+    assert_eq!(ranges, [0..7, 13..27, 13..27]);
+    assert_eq!(pieces[0].x.start, 0.);
+    assert!(pieces[1].baseline < pieces[2].baseline);
+
+    let first_line = visual.decorations_in_bounds(&Rect::from_size(Size::new(1000., 10.)));
+    assert_eq!(first_line.len(), 1);
+  }
+
+  fn ellipsis_style(max_lines: Option<usize>) -> TextStyle {
+    let mut style = zero_letter_space_style(16., TextOverflow::Ellipsis);
+    style.max_lines = max_lines;
+    style
+  }
+
+  #[test]
+  fn max_lines() {
+    let mut style = zero_letter_space_style(16., TextOverflow::AutoWrap);
+    style.max_lines = Some(2);
+    let visual = typography_spans(
+      &[TextSpan::new("WITHIN BOUND WRAP WRAP")],
+      &style,
+      Size::new(16. * 5., f32::MAX),
+    );
+
+    assert_eq!(visual.glyph_row_count(), 2);
+    assert!(visual.is_truncated());
+    assert!(visual.glyphs().all(|g| g.cluster < 13));
+  }
+
+  #[test]
+  fn ellipsis_the_end() {
+    let bounds = Size::new(100., f32::MAX);
+    let text = [TextSpan::new("Hello world, it's a long text!")];
+    let visual = typography_spans(&text, &ellipsis_style(Some(1)), bounds);
+
+    assert_eq!(visual.glyph_row_count(), 1);
+    assert!(visual.is_truncated());
+    assert!(visual.visual_rect().width() <= 100.);
+    let glyphs: Vec<_> = visual.glyphs().collect();
+    let ellipsis = glyphs.last().unwrap();
+    // The ellipsis takes the cluster of the first elided character.
+    assert_eq!(ellipsis.cluster, glyphs[glyphs.len() - 2].cluster + 1);
+    assert!(
+      glyphs
+        .windows(2)
+        .all(|w| w[0].x_offset < w[1].x_offset)
+    );
+
+    let short = typography_spans(&[TextSpan::new("Hello")], &ellipsis_style(Some(1)), bounds);
+    assert!(!short.is_truncated());
+    assert_eq!(short.glyphs().count(), 5);
+  }
+
+  #[test]
+  fn ellipsis_lines_in_bounds() {
+    let text = [TextSpan::new("WITHIN BOUND WRAP WRAP")];
+    // Two lines fit the height.
+    let bounds = Size::new(16. * 5., 16. * 2.5);
+    let visual = typography_spans(&text, &ellipsis_style(None), bounds);
+
+    assert_eq!(visual.glyph_row_count(), 2);
+    assert!(visual.is_truncated());
+    assert!(visual.visual_rect().width() <= 16. * 5.);
+  }
+
+  #[test]
+  fn ellipsis_rtl() {
+    let text = [TextSpan::new("שלום עולם, זה טקסט ארוך")];
+    let visual = typography_spans(&text, &ellipsis_style(Some(1)), Size::new(80., f32::MAX));
+
+    assert!(visual.is_truncated());
+    let glyphs: Vec<_> = visual.glyphs().collect();
+    // The ellipsis is at the left of the right-to-left text, and the start of
+    // the text is kept at the right.
+    assert_eq!(glyphs.last().unwrap().cluster, 0);
+    assert!(glyphs[0].cluster > glyphs[1].cluster);
+    assert_eq!(glyphs[0].x_offset, GlyphUnit::ZERO);
+  }
+
+  fn typography_in_width(text: &str, style: &TextStyle, width: f32) -> VisualGlyphs {
+    test_store().typography(
+      text.to_owned().into(),
+      style,
+      Size::new(width, f32::MAX),
+      TextAlign::Justify,
+      GlyphBaseline::Alphabetic,
+      PlaceLineDirection::TopToBottom,
+    )
+  }
+
+  /// The end of the last glyph that is not a space in every line.
+  fn line_ends(visual: &VisualGlyphs, text: &str) -> Vec<f32> {
+    let lines = &visual.visual_info.visual_lines;
+    lines
+      .iter()
+      .map(|l| {
+        let g = l
+          .glyphs
+          .iter()
+          .rfind(|g| !text[g.cluster as usize..].starts_with(' '))
+          .unwrap();
+        visual.to_pixel_value(g.x_offset + g.x_advance)
+      })
+      .collect()
+  }
+
+  /// The clusters of the glyphs in every line.
+  fn line_clusters(visual: &VisualGlyphs) -> Vec<Vec<u32>> {
+    let lines = &visual.visual_info.visual_lines;
+    lines
+      .iter()
+      .map(|l| l.glyphs.iter().map(|g| g.cluster).collect())
+      .collect()
+  }
+
+  #[test]
+  fn justify_wrapped_lines() {
+    let text = "Justify the lines that end by wrapping.\nNot the end of a paragraph.";
+    let style = zero_letter_space_style(16., TextOverflow::AutoWrap);
+    let visual = typography_in_width(text, &style, 120.);
+
+    let ends = line_ends(&visual, text);
+    assert!(ends.len() > 3);
+    let para_end = visual
+      .position_by_cluster(text.find('\n').unwrap())
+      .0;
+    for (row, end) in ends.iter().enumerate() {
+      let is_para_end = row == para_end || row == ends.len() - 1;
+      assert_eq!((end - 120.).abs() < 0.1, !is_para_end, "row {row} ends at {end}");
+    }
+    assert!((visual.visual_rect().width() - 120.).abs() < 0.1);
+    // The spaces at the end of the lines hang out of the bounds.
+    for line in line_clusters(&visual) {
+      assert!(!text[line[0] as usize..].starts_with(' '));
+    }
+  }
+
+  #[test]
+  fn justify_between_clusters() {
+    // The word longer than the line has no space to stretch.
+    let text = "WWWWWWWWWW";
+    let style = zero_letter_space_style(16., TextOverflow::AutoWrap);
+    let visual = typography_in_width(text, &style, 100.);
+
+    let ends = line_ends(&visual, text);
+    assert!((ends[0] - 100.).abs() < 0.1);
+    let first = &visual.visual_info.visual_lines[0].glyphs;
+    let gaps: Vec<_> = first
+      .windows(2)
+      .map(|w| (w[1].x_offset - w[0].x_offset).into_pixel())
+      .collect();
+    assert!(gaps.iter().all(|g| (g - gaps[0]).abs() < 0.01));
+  }
+
+  fn hyphens_style(hyphens: Hyphens) -> TextStyle {
+    let mut style = zero_letter_space_style(16., TextOverflow::AutoWrap);
+    style.hyphens = hyphens;
+    style
+  }
+
+  #[test]
+  fn soft_hyphens() {
+    let text = "a co\u{AD}op\u{AD}er\u{AD}a\u{AD}tion";
+    let visual = typography_in_width(text, &hyphens_style(Hyphens::Manual), 50.);
+
+    let lines = line_clusters(&visual);
+    // The hyphen takes the cluster of the soft hyphen at the end of the line.
+    assert_eq!(lines[0], [0, 1, 2, 3, 4, 4]);
+    assert_eq!(lines[1][0], 6);
+    for line in &lines[..lines.len() - 1] {
+      let [.., shy, hyphen] = line[..] else { panic!() };
+      assert_eq!(shy, hyphen);
+      assert!(text[shy as usize..].starts_with('\u{AD}'));
+    }
+
+    let no_hyphens = typography_in_width(text, &hyphens_style(Hyphens::None), 50.);
+    assert_eq!(no_hyphens.glyphs().count(), text.chars().count());
+    assert_eq!(line_clusters(&no_hyphens)[1][0], 2);
+  }
+
+  #[cfg(feature = "hyphenation")]
+  #[test]
+  fn dictionary_hyphenation() {
+    let text = "an extensive";
+    let visual = typography_in_width(text, &hyphens_style(Hyphens::Auto(*b"en")), 60.);
+
+    let lines = line_clusters(&visual);
+    assert_eq!(lines[0], [0, 1, 2, 3, 4, 4]);
+    assert_eq!(lines[1][0], 5);
+  }
+
+  #[test]
+  fn rtl_spans_order() {
+    let style = zero_letter_space_style(16., TextOverflow::Clip);
+    let spans = [TextSpan::new("א"), TextSpan::new("ב").with_font_size(20.)];
+    let visual = typography_spans(&spans, &style, Size::new(f32::MAX, f32::MAX));
+
+    let clusters: Vec<_> = visual.glyphs().map(|g| g.cluster).collect();
+    assert_eq!(clusters, [2, 0]);
+  }
+
   #[test]
   fn text_in_different_bounds() {
     let mut store = test_store();
@@ -0,0 +1,875 @@
+use std::cell::RefCell;
+
+use ahash::{HashMap, HashMapExt};
+use pdf_writer::{
+  Chunk, Content, Filter, Finish, Name, Pdf, Rect as PdfRect, Ref, Str,
+  types::{BlendMode as PdfBlendMode, FunctionShadingType, MaskType, PaintType, TilingType},
+  writers::Resources,
+};
+use ribir_core::prelude::{
+  color::{LinearGradient, RadialGradient, SweepGradient},
+  font_db::FontDB,
+  image::ColorFormat,
+  shaper::GlyphId,
+  *,
+};
+
+use crate::font::FontUsage;
+
+/// A painter backend that writes every frame as a page of a [`PdfDocument`].
+///
+/// All `draw_commands` between `begin_frame` and `end_frame` are drawn to the
+/// same page, one logical pixel is one point of the page.
+#[derive(Default)]
+pub struct PdfBackend {
+  surface: Option<Color>,
+  new_page: bool,
+}
+
+/// A PDF document that the [`PdfBackend`] draws pages to, call
+/// [`PdfDocument::finish`] to get the bytes of the document.
+///
+/// The glyphs are kept as text with the subset fonts embedded, the other paths
+/// are written as vector paths.
+///
+/// # Limitations
+///
+/// PDF has no per stop alpha for the gradients, only the alpha shared by all
+/// the stops is applied. PDF has no blur either, the shadows are painted as
+/// their shapes without blur, and the blur layers only paint their content.
+/// PDF has no additive blending, the `BlendMode::Plus` is painted as normal.
+pub struct PdfDocument {
+  page_size: Size,
+  font_db: Sc<RefCell<FontDB>>,
+  chunk: Chunk,
+  next_ref: Ref,
+  page_tree: Ref,
+  resources: Ref,
+  pages: Vec<Ref>,
+  page: Option<Content>,
+  fonts: HashMap<ID, FontUsage>,
+  /// The glyphs that the outline paths are drawn for.
+  glyphs: HashMap<Resource<Path>, (ID, GlyphId)>,
+  /// The count of the outline glyphs cached by the `font_db` when the `glyphs`
+  /// is synced.
+  synced_glyphs: usize,
+  images: HashMap<(Resource<PixelImage>, FilterQuality), String>,
+  alphas: HashMap<u32, String>,
+  blend_modes: HashMap<BlendMode, String>,
+  x_objects: Vec<(String, Ref)>,
+  patterns: Vec<(String, Ref)>,
+  ext_g_states: Vec<(String, Ref)>,
+}
+
+impl PainterBackend for PdfBackend {
+  type Texture = PdfDocument;
+
+  fn begin_frame(&mut self, surface: Color) {
+    self.surface = Some(surface);
+    self.new_page = true;
+  }
+
+  /// Every frame is a new page, so the damages are ignored.
+  fn draw_commands(
+    &mut self, viewport: DeviceRect, _damages: &[DeviceRect], commands: &[PaintCommand],
+    global_matrix: &Transform, output: &mut Self::Texture,
+  ) {
+    if std::mem::take(&mut self.new_page) {
+      output.begin_page(self.surface.take());
+    }
+    output.draw_commands(viewport, commands, global_matrix);
+  }
+
+  fn end_frame(&mut self) {
+    self.surface = None;
+    self.new_page = false;
+  }
+}
+
+impl PdfDocument {
+  /// Create an empty document, the `font_db` must be the one that the commands
+  /// are painted with, so the glyphs can be written as text.
+  pub fn new(page_size: Size, font_db: Sc<RefCell<FontDB>>) -> Self {
+    let mut next_ref = Ref::new(1);
+    let page_tree = next_ref.bump();
+    let resources = next_ref.bump();
+    Self {
+      page_size,
+      font_db,
+      chunk: Chunk::new(),
+      next_ref,
+      page_tree,
+      resources,
+      pages: vec![],
+      page: None,
+      fonts: HashMap::new(),
+      glyphs: HashMap::new(),
+      synced_glyphs: 0,
+      images: HashMap::new(),
+      alphas: HashMap::new(),
+      blend_modes: HashMap::new(),
+      x_objects: vec![],
+      patterns: vec![],
+      ext_g_states: vec![],
+    }
+  }
+
+  #[inline]
+  pub fn page_size(&self) -> Size { self.page_size }
+
+  /// The number of pages in the document.
+  #[inline]
+  pub fn page_count(&self) -> usize { self.pages.len() + self.page.is_some() as usize }
+
+  /// Finish the current page and start a new one filled with the `surface`.
+  pub fn begin_page(&mut self, surface: Option<Color>) {
+    self.end_page();
+
+    let Size { width, height, .. } = self.page_size;
+    let mut content = Content::new();
+    content.save_state();
+    // Flip the page to let the y-axis point down, as the painter does.
+    content.transform([1., 0., 0., -1., 0., height]);
+    if let Some(color) = surface.filter(|c| c.alpha > 0) {
+      content.save_state();
+      self.set_color(&mut content, color, false);
+      content.rect(0., 0., width, height).fill_nonzero();
+      content.restore_state();
+    }
+    self.page = Some(content);
+  }
+
+  /// Draw the `commands` to the current page, a new page will be started if
+  /// there isn't one.
+  pub fn draw_commands(
+    &mut self, viewport: DeviceRect, commands: &[PaintCommand], global_matrix: &Transform,
+  ) {
+    if self.page.is_none() {
+      self.begin_page(None);
+    }
+
+    let mut content = self.page.take().unwrap();
+    let viewport = viewport.to_f32();
+    content.save_state();
+    content
+      .rect(viewport.min_x(), viewport.min_y(), viewport.width(), viewport.height())
+      .clip_nonzero()
+      .end_path();
+    content.transform(pdf_matrix(global_matrix));
+    let base = global_matrix.then(&self.page_flip());
+    self.write_commands(&mut content, commands, &base);
+    content.restore_state();
+    self.page = Some(content);
+  }
+
+  /// Finish the document and return the bytes of the PDF file.
+  pub fn finish(mut self) -> Vec<u8> {
+    self.end_page();
+    self.write_fonts();
+    self.write_resources();
+
+    let mut pdf = Pdf::new();
+    let catalog = self.next_ref.bump();
+    pdf.catalog(catalog).pages(self.page_tree);
+    pdf
+      .pages(self.page_tree)
+      .kids(self.pages.iter().copied())
+      .count(self.pages.len() as i32);
+    pdf.extend(&self.chunk);
+    pdf.finish()
+  }
+
+  fn end_page(&mut self) {
+    let Some(mut content) = self.page.take() else { return };
+    content.restore_state();
+
+    let content_ref = self.next_ref.bump();
+    let data = compress(&content.finish());
+    self
+      .chunk
+      .stream(content_ref, &data)
+      .filter(Filter::FlateDecode);
+
+    let page_ref = self.next_ref.bump();
+    let Size { width, height, .. } = self.page_size;
+    let mut page = self.chunk.page(page_ref);
+    page
+      .parent(self.page_tree)
+      .media_box(PdfRect::new(0., 0., width, height))
+      .contents(content_ref)
+      .pair(Name(b"Resources"), self.resources);
+    page.finish();
+    self.pages.push(page_ref);
+  }
+
+  /// The glyph that the path is drawn for. The glyphs are synced from the
+  /// `font_db` only if the path misses and new glyphs have been cached.
+  fn glyph_of(&mut self, path: &Resource<Path>) -> Option<(ID, GlyphId)> {
+    if let Some(glyph) = self.glyphs.get(path) {
+      return Some(*glyph);
+    }
+
+    let font_db = self.font_db.borrow();
+    let count = font_db.cached_outline_glyphs_count();
+    if count == self.synced_glyphs {
+      return None;
+    }
+    self.synced_glyphs = count;
+    self.glyphs = font_db
+      .cached_outline_glyphs()
+      .into_iter()
+      .map(|(face, glyph, path)| (path, (face, glyph)))
+      .collect();
+    self.glyphs.get(path).copied()
+  }
+
+  fn page_flip(&self) -> Transform { Transform::new(1., 0., 0., -1., 0., self.page_size.height) }
+
+  /// Write the commands to the content, the `base` is the transform from the
+  /// coordinate space of the commands to the default space of the content,
+  /// the patterns are placed by it.
+  fn write_commands(&mut self, content: &mut Content, commands: &[PaintCommand], base: &Transform) {
+    let mut clips = 0;
+    for cmd in commands {
+      match cmd {
+        PaintCommand::Path(PathCommand { path, paint_bounds, transform, action }) => match action {
+          PaintPathAction::Clip => {
+            content.save_state();
+            write_path(content, path, Some(transform));
+            content.clip_nonzero().end_path();
+            clips += 1;
+          }
+          PaintPathAction::Paint { brush, painting_style, blend_mode } => {
+            let blended = *blend_mode != BlendMode::Normal;
+            if blended {
+              content.save_state();
+              self.set_blend_mode(content, *blend_mode);
+            }
+            let glyph = match (path, brush, painting_style) {
+              (PaintPath::Share(p), CommandBrush::Color(color), PaintingStyle::Fill) => {
+                self.glyph_of(p).map(|g| (g, *color))
+              }
+              _ => None,
+            };
+            if let Some(((face, glyph), color)) = glyph {
+              self.show_glyph(content, face, glyph, color, transform);
+            } else {
+              let matrix = transform.then(base);
+              content.save_state();
+              content.transform(pdf_matrix(transform));
+              let bounds = transform
+                .inverse()
+                .map(|t| t.outer_transformed_rect(paint_bounds));
+              let stroke = matches!(painting_style, PaintingStyle::Stroke(_));
+              self.set_brush(content, brush, &matrix, bounds.as_ref(), stroke);
+              write_path(content, path, None);
+              match painting_style {
+                PaintingStyle::Fill => {
+                  content.fill_nonzero();
+                }
+                PaintingStyle::Stroke(options) => {
+                  set_stroke_options(content, options);
+                  content.stroke();
+                }
+              }
+              content.restore_state();
+            }
+            if blended {
+              content.restore_state();
+            }
+          }
+        },
+        PaintCommand::Shadow(ShadowCommand { path, transform, color, .. }) => {
+          content.save_state();
+          content.transform(pdf_matrix(transform));
+          self.set_color(content, *color, false);
+          write_path(content, path, None);
+          content.fill_nonzero();
+          content.restore_state();
+        }
+        PaintCommand::Layer(layer) => self.write_commands(content, &layer.cmds, base),
+        PaintCommand::Mask(mask) => {
+          content.save_state();
+          self.set_soft_mask(content, mask);
+          self.write_commands(content, &mask.cmds, base);
+          content.restore_state();
+        }
+        PaintCommand::PopClip => {
+          if clips > 0 {
+            clips -= 1;
+            content.restore_state();
+          }
+        }
+        PaintCommand::Bundle { transform, opacity, blend_mode, bounds, cmds } => {
+          content.save_state();
+          content.transform(pdf_matrix(transform));
+          if *opacity >= 1. && *blend_mode == BlendMode::Normal {
+            self.write_commands(content, cmds, &transform.then(base));
+          } else if *opacity > 0. {
+            // The opacity and the blend mode apply to the whole bundle, so draw it
+            // as a transparency group.
+            let mut form = Content::new();
+            self.write_commands(&mut form, cmds, &Transform::identity());
+            let data = compress(&form.finish());
+            let form_ref = self.next_ref.bump();
+            let mut x_object = self.chunk.form_xobject(form_ref, &data);
+            x_object
+              .bbox(pdf_rect(bounds))
+              .filter(Filter::FlateDecode)
+              .pair(Name(b"Resources"), self.resources);
+            x_object.group().transparency();
+            x_object.finish();
+
+            let name = add_resource(&mut self.x_objects, "X", form_ref);
+            self.set_alpha(content, *opacity);
+            self.set_blend_mode(content, *blend_mode);
+            content.x_object(Name(name.as_bytes()));
+          }
+          content.restore_state();
+        }
+      }
+    }
+
+    // Restore the clips that have no `PopClip` command.
+    (0..clips).for_each(|_| {
+      content.restore_state();
+    });
+  }
+
+  fn show_glyph(
+    &mut self, content: &mut Content, face: ID, glyph: GlyphId, color: Color, ts: &Transform,
+  ) {
+    let Some(font) = self.font_usage(face) else { return };
+    font.glyphs.insert(glyph.0);
+    let (name, units_per_em) = (font.name.clone(), font.units_per_em);
+
+    content.save_state();
+    self.set_color(content, color, false);
+    // The glyph path is in the font units, so we use the units per em as the
+    // font size to keep the same transform.
+    content
+      .begin_text()
+      .set_font(Name(name.as_bytes()), units_per_em as f32)
+      .set_text_matrix(pdf_matrix(ts))
+      .show(Str(&glyph.0.to_be_bytes()))
+      .end_text();
+    content.restore_state();
+  }
+
+  fn font_usage(&mut self, face: ID) -> Option<&mut FontUsage> {
+    if !self.fonts.contains_key(&face) {
+      let units_per_em = self
+        .font_db
+        .borrow()
+        .try_get_face_data(face)?
+        .units_per_em();
+      let name = format!("F{}", self.fonts.len() + 1);
+      let usage = FontUsage::new(name, self.next_ref.bump(), units_per_em);
+      self.fonts.insert(face, usage);
+    }
+    self.fonts.get_mut(&face)
+  }
+
+  fn set_color(&mut self, content: &mut Content, color: Color, stroke: bool) {
+    let [r, g, b, a] = color.into_f32_components();
+    if stroke {
+      content.set_stroke_rgb(r, g, b);
+    } else {
+      content.set_fill_rgb(r, g, b);
+    }
+    self.set_alpha(content, a);
+  }
+
+  fn set_alpha(&mut self, content: &mut Content, alpha: f32) {
+    if alpha >= 1. {
+      return;
+    }
+    let name = match self.alphas.get(&alpha.to_bits()) {
+      Some(name) => name.clone(),
+      None => {
+        let id = self.next_ref.bump();
+        self
+          .chunk
+          .ext_graphics(id)
+          .non_stroking_alpha(alpha)
+          .stroking_alpha(alpha);
+        let name = add_resource(&mut self.ext_g_states, "G", id);
+        self.alphas.insert(alpha.to_bits(), name.clone());
+        name
+      }
+    };
+    content.set_parameters(Name(name.as_bytes()));
+  }
+
+  /// Set a soft mask that the mask commands paint, as a transparency group in
+  /// the current coordinates.
+  fn set_soft_mask(&mut self, content: &mut Content, mask: &MaskCommand) {
+    let mut form = Content::new();
+    self.write_commands(&mut form, &mask.mask, &Transform::identity());
+    let data = compress(&form.finish());
+    let form_ref = self.next_ref.bump();
+    let mut x_object = self.chunk.form_xobject(form_ref, &data);
+    x_object
+      .bbox(pdf_rect(&mask.paint_bounds))
+      .filter(Filter::FlateDecode)
+      .pair(Name(b"Resources"), self.resources);
+    x_object
+      .group()
+      .transparency()
+      .color_space()
+      .device_rgb();
+    x_object.finish();
+
+    let subtype = match mask.mode {
+      MaskMode::Alpha => MaskType::Alpha,
+      MaskMode::Luminance => MaskType::Luminosity,
+    };
+    let id = self.next_ref.bump();
+    self
+      .chunk
+      .ext_graphics(id)
+      .soft_mask()
+      .subtype(subtype)
+      .group(form_ref);
+    let name = add_resource(&mut self.ext_g_states, "G", id);
+    content.set_parameters(Name(name.as_bytes()));
+  }
+
+  fn set_blend_mode(&mut self, content: &mut Content, mode: BlendMode) {
+    let pdf_mode = match mode {
+      BlendMode::Normal | BlendMode::Plus => return,
+      BlendMode::Multiply => PdfBlendMode::Multiply,
+      BlendMode::Screen => PdfBlendMode::Screen,
+      BlendMode::Overlay => PdfBlendMode::Overlay,
+      BlendMode::Darken => PdfBlendMode::Darken,
+      BlendMode::Lighten => PdfBlendMode::Lighten,
+      BlendMode::Difference => PdfBlendMode::Difference,
+    };
+    let name = match self.blend_modes.get(&mode) {
+      Some(name) => name.clone(),
+      None => {
+        let id = self.next_ref.bump();
+        self.chunk.ext_graphics(id).blend_mode(pdf_mode);
+        let name = add_resource(&mut self.ext_g_states, "G", id);
+        self.blend_modes.insert(mode, name.clone());
+        name
+      }
+    };
+    content.set_parameters(Name(name.as_bytes()));
+  }
+
+  fn set_brush(
+    &mut self, content: &mut Content, brush: &CommandBrush, matrix: &Transform,
+    bounds: Option<&Rect>, stroke: bool,
+  ) {
+    let (pattern, alpha) = match brush {
+      CommandBrush::Color(color) => return self.set_color(content, *color, stroke),
+      CommandBrush::Image { img, opacity, filter_quality } => {
+        (self.image_pattern(img, *filter_quality, matrix), *opacity)
+      }
+      CommandBrush::Linear(linear) => {
+        let alpha = shared_alpha(&linear.stops);
+        (self.linear_pattern(linear, matrix, bounds), alpha)
+      }
+      CommandBrush::Radial(radial) => {
+        let alpha = shared_alpha(&radial.stops);
+        (self.radial_pattern(radial, matrix, bounds), alpha)
+      }
+      CommandBrush::Sweep(sweep) => {
+        let alpha = shared_alpha(&sweep.stops);
+        let Some(bounds) = bounds else { return };
+        (self.sweep_pattern(sweep, matrix, bounds), alpha)
+      }
+    };
+
+    if stroke {
+      content.set_stroke_pattern(None, Name(pattern.as_bytes()));
+    } else {
+      content.set_fill_pattern(None, Name(pattern.as_bytes()));
+    }
+    self.set_alpha(content, alpha);
+  }
+
+  /// A tiling pattern that repeats the image, as the image brush does.
+  fn image_pattern(
+    &mut self, img: &Resource<PixelImage>, filter_quality: FilterQuality, matrix: &Transform,
+  ) -> String {
+    let img_name = self.image_x_object(img, filter_quality);
+    let (w, h) = (img.width() as f32, img.height() as f32);
+    let mut tile = Content::new();
+    tile
+      .save_state()
+      .transform([w, 0., 0., -h, 0., h])
+      .x_object(Name(img_name.as_bytes()))
+      .restore_state();
+    let tile = tile.finish();
+
+    let id = self.next_ref.bump();
+    let mut pattern = self.chunk.tiling_pattern(id, &tile);
+    pattern
+      .paint_type(PaintType::Colored)
+      .tiling_type(TilingType::ConstantSpacing)
+      .bbox(PdfRect::new(0., 0., w, h))
+      .x_step(w)
+      .y_step(h)
+      .matrix(pdf_matrix(matrix))
+      .pair(Name(b"Resources"), self.resources);
+    pattern.finish();
+    add_resource(&mut self.patterns, "P", id)
+  }
+
+  fn image_x_object(
+    &mut self, img: &Resource<PixelImage>, filter_quality: FilterQuality,
+  ) -> String {
+    let key = (img.clone(), filter_quality);
+    if let Some(name) = self.images.get(&key) {
+      return name.clone();
+    }
+    let interpolate = filter_quality == FilterQuality::Linear;
+
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    let bytes = img.pixel_bytes();
+    let (rgb, alpha) = match img.color_format() {
+      ColorFormat::Rgba8 => {
+        let rgb = bytes
+          .chunks_exact(4)
+          .flat_map(|p| [p[0], p[1], p[2]])
+          .collect::<Vec<_>>();
+        let alpha = bytes.chunks_exact(4).map(|p| p[3]).collect();
+        (rgb, alpha)
+      }
+      // An alpha image only has the alpha channel, we treat it as a black image.
+      ColorFormat::Alpha8 => (vec![0; bytes.len() * 3], bytes.to_vec()),
+    };
+
+    let mask_ref = self.next_ref.bump();
+    let alpha = compress(&alpha);
+    let mut mask = self.chunk.image_xobject(mask_ref, &alpha);
+    mask.filter(Filter::FlateDecode);
+    mask
+      .width(w)
+      .height(h)
+      .bits_per_component(8)
+      .interpolate(interpolate)
+      .color_space()
+      .device_gray();
+    mask.finish();
+
+    let img_ref = self.next_ref.bump();
+    let rgb = compress(&rgb);
+    let mut x_object = self.chunk.image_xobject(img_ref, &rgb);
+    x_object.filter(Filter::FlateDecode);
+    x_object
+      .width(w)
+      .height(h)
+      .bits_per_component(8)
+      .interpolate(interpolate)
+      .s_mask(mask_ref)
+      .color_space()
+      .device_rgb();
+    x_object.finish();
+
+    let name = add_resource(&mut self.x_objects, "Im", img_ref);
+    self.images.insert(key, name.clone());
+    name
+  }
+
+  fn linear_pattern(
+    &mut self, linear: &LinearGradient, matrix: &Transform, bounds: Option<&Rect>,
+  ) -> String {
+    let LinearGradient { start, end, stops, spread_method } = linear;
+    let v = *end - *start;
+    // The offset range the bounds covered, the PDF shading only pads, so we
+    // repeat the stops in the range to support the other spread methods.
+    let (from, to) = match bounds {
+      Some(bounds) if *spread_method != SpreadMethod::Pad && v.square_length() > 0. => {
+        let offsets = rect_corners(bounds).map(|p| (p - *start).dot(v) / v.square_length());
+        let min = offsets.iter().copied().fold(f32::MAX, f32::min);
+        let max = offsets.iter().copied().fold(f32::MIN, f32::max);
+        cycle_range(min.floor(), max.ceil())
+      }
+      _ => (0., 1.),
+    };
+    let function = self.stops_function(stops, *spread_method, from, to);
+    let (p0, p1) = (*start + v * from, *start + v * to);
+    self.shading_pattern(FunctionShadingType::Axial, [p0.x, p0.y, p1.x, p1.y], function, matrix)
+  }
+
+  fn radial_pattern(
+    &mut self, radial: &RadialGradient, matrix: &Transform, bounds: Option<&Rect>,
+  ) -> String {
+    let RadialGradient {
+      start_center: c0,
+      start_radius: r0,
+      end_center: c1,
+      end_radius: r1,
+      stops,
+      spread_method,
+    } = radial;
+    let dr = r1 - r0;
+    let to = match bounds {
+      Some(bounds) if *spread_method != SpreadMethod::Pad && dr > 0. => {
+        let max_dis = rect_corners(bounds)
+          .iter()
+          .map(|p| (*p - *c0).length())
+          .fold(0., f32::max);
+        let to = ((max_dis + (*c1 - *c0).length()) / dr)
+          .ceil()
+          .max(1.);
+        cycle_range(0., to).1
+      }
+      _ => 1.,
+    };
+    let function = self.stops_function(stops, *spread_method, 0., to);
+    let c = *c0 + (*c1 - *c0) * to;
+    let coords = [c0.x, c0.y, *r0, c.x, c.y, r0 + dr * to];
+    self.shading_pattern(FunctionShadingType::Radial, coords, function, matrix)
+  }
+
+  /// PDF has no sweep shading, so the gradient is approximated by the solid
+  /// wedges that cover the `bounds`, drawn in a tile larger than them.
+  fn sweep_pattern(&mut self, sweep: &SweepGradient, matrix: &Transform, bounds: &Rect) -> String {
+    let mut tile = Content::new();
+    for (wedge, color) in sweep.wedges(bounds) {
+      let [r, g, b, _] = color.into_f32_components();
+      tile.set_fill_rgb(r, g, b);
+      write_path(&mut tile, &wedge, None);
+      tile.fill_nonzero();
+    }
+    let tile = tile.finish();
+
+    let bbox = bounds.inflate(1., 1.);
+    let id = self.next_ref.bump();
+    let mut pattern = self.chunk.tiling_pattern(id, &tile);
+    pattern
+      .paint_type(PaintType::Colored)
+      .tiling_type(TilingType::ConstantSpacing)
+      .bbox(pdf_rect(&bbox))
+      .x_step(bbox.width().max(1.))
+      .y_step(bbox.height().max(1.))
+      .matrix(pdf_matrix(matrix))
+      .pair(Name(b"Resources"), self.resources);
+    pattern.finish();
+    add_resource(&mut self.patterns, "P", id)
+  }
+
+  fn shading_pattern(
+    &mut self, kind: FunctionShadingType, coords: impl IntoIterator<Item = f32>, function: Ref,
+    matrix: &Transform,
+  ) -> String {
+    let id = self.next_ref.bump();
+    let mut pattern = self.chunk.shading_pattern(id);
+    let mut shading = pattern.function_shading();
+    shading.shading_type(kind);
+    shading.color_space().device_rgb();
+    shading
+      .function(function)
+      .coords(coords)
+      .extend([true, true]);
+    shading.finish();
+    pattern.matrix(pdf_matrix(matrix));
+    pattern.finish();
+    add_resource(&mut self.patterns, "P", id)
+  }
+
+  /// Write a function that maps the offset in `[from, to]` of the gradient
+  /// to the color, the `from` and `to` must be integers.
+  fn stops_function(
+    &mut self, stops: &[GradientStop], spread: SpreadMethod, from: f32, to: f32,
+  ) -> Ref {
+    let color = |s: &GradientStop| {
+      let [r, g, b, _] = s.color.into_f32_components();
+      [r, g, b]
+    };
+
+    // Pad the stops to cover the range `[0, 1]`.
+    let mut stops = stops.to_vec();
+    if let Some(first) = stops.first().filter(|s| s.offset > 0.) {
+      stops.insert(0, GradientStop { offset: 0., ..first.clone() });
+    }
+    if let Some(last) = stops.last().filter(|s| s.offset < 1.) {
+      stops.push(GradientStop { offset: 1., ..last.clone() });
+    }
+
+    let cycle = self.next_ref.bump();
+    let pieces = stops
+      .windows(2)
+      .map(|w| {
+        let id = self.next_ref.bump();
+        self
+          .chunk
+          .exponential_function(id)
+          .domain([0., 1.])
+          .c0(color(&w[0]))
+          .c1(color(&w[1]))
+          .n(1.);
+        id
+      })
+      .collect::<Vec<_>>();
+    match pieces.len() {
+      0 => {
+        let c = stops.first().map_or([0.; 3], color);
+        self
+          .chunk
+          .exponential_function(cycle)
+          .domain([0., 1.])
+          .c0(c)
+          .c1(c)
+          .n(1.);
+      }
+      n => {
+        self
+          .chunk
+          .stitching_function(cycle)
+          .domain([0., 1.])
+          .functions(pieces)
+          .bounds(stops[1..n].iter().map(|s| s.offset.clamp(0., 1.)))
+          .encode((0..n).flat_map(|_| [0., 1.]));
+      }
+    }
+
+    let cycles = (to - from) as usize;
+    if cycles <= 1 {
+      return cycle;
+    }
+
+    let id = self.next_ref.bump();
+    let encode = (0..cycles).flat_map(|i| {
+      let reverse = spread == SpreadMethod::Reflect && (from as i32 + i as i32) % 2 != 0;
+      if reverse { [1., 0.] } else { [0., 1.] }
+    });
+    self
+      .chunk
+      .stitching_function(id)
+      .domain([0., 1.])
+      .functions(std::iter::repeat_n(cycle, cycles))
+      .bounds((1..cycles).map(|i| i as f32 / cycles as f32))
+      .encode(encode);
+    id
+  }
+
+  fn write_resources(&mut self) {
+    let mut resources = self
+      .chunk
+      .indirect(self.resources)
+      .start::<Resources>();
+    let mut fonts = resources.fonts();
+    for usage in self.fonts.values() {
+      fonts.pair(Name(usage.name.as_bytes()), usage.font_ref);
+    }
+    fonts.finish();
+    let mut x_objects = resources.x_objects();
+    for (name, id) in &self.x_objects {
+      x_objects.pair(Name(name.as_bytes()), *id);
+    }
+    x_objects.finish();
+    let mut patterns = resources.patterns();
+    for (name, id) in &self.patterns {
+      patterns.pair(Name(name.as_bytes()), *id);
+    }
+    patterns.finish();
+    let mut ext_g_states = resources.ext_g_states();
+    for (name, id) in &self.ext_g_states {
+      ext_g_states.pair(Name(name.as_bytes()), *id);
+    }
+  }
+
+  fn write_fonts(&mut self) {
+    let font_db = self.font_db.borrow();
+    for (face_id, usage) in &self.fonts {
+      let (Some(face), Some(info)) =
+        (font_db.try_get_face_data(*face_id), font_db.face_info(*face_id))
+      else {
+        continue;
+      };
+      usage.write(face, info, &mut self.chunk, &mut self.next_ref);
+    }
+  }
+}
+
+fn add_resource(resources: &mut Vec<(String, Ref)>, prefix: &str, id: Ref) -> String {
+  let name = format!("{prefix}{}", resources.len() + 1);
+  resources.push((name.clone(), id));
+  name
+}
+
+/// Limit the number of the repeated cycles of a gradient.
+fn cycle_range(from: f32, to: f32) -> (f32, f32) {
+  const MAX_CYCLES: f32 = 256.;
+  (from, to.min(from + MAX_CYCLES).max(from + 1.))
+}
+
+fn shared_alpha(stops: &[GradientStop]) -> f32 {
+  let alpha = stops.first().map_or(255, |s| s.color.alpha);
+  if stops.iter().all(|s| s.color.alpha == alpha) { alpha as f32 / 255. } else { 1. }
+}
+
+fn write_path(content: &mut Content, path: &Path, ts: Option<&Transform>) {
+  let map = |p: Point| ts.map_or(p, |ts| ts.transform_point(p));
+  let mut start = Point::zero();
+  let mut current = Point::zero();
+  for seg in path.segments() {
+    match seg {
+      PathSegment::MoveTo(p) => {
+        let p = map(p);
+        content.move_to(p.x, p.y);
+        (start, current) = (p, p);
+      }
+      PathSegment::LineTo(p) => {
+        let p = map(p);
+        content.line_to(p.x, p.y);
+        current = p;
+      }
+      PathSegment::QuadTo { ctrl, to } => {
+        let (ctrl, to) = (map(ctrl), map(to));
+        let ctrl1 = current + (ctrl - current) * (2. / 3.);
+        let ctrl2 = to + (ctrl - to) * (2. / 3.);
+        content.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
+        current = to;
+      }
+      PathSegment::CubicTo { to, ctrl1, ctrl2 } => {
+        let (to, ctrl1, ctrl2) = (map(to), map(ctrl1), map(ctrl2));
+        content.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y);
+        current = to;
+      }
+      PathSegment::Close(true) => {
+        content.close_path();
+        current = start;
+      }
+      PathSegment::Close(false) => {}
+    }
+  }
+}
+
+fn set_stroke_options(content: &mut Content, options: &StrokeOptions) {
+  use pdf_writer::types::{LineCapStyle, LineJoinStyle};
+
+  let cap = match options.line_cap {
+    LineCap::Butt => LineCapStyle::ButtCap,
+    LineCap::Round => LineCapStyle::RoundCap,
+    LineCap::Square => LineCapStyle::ProjectingSquareCap,
+  };
+  let join = match options.line_join {
+    LineJoin::Miter | LineJoin::MiterClip => LineJoinStyle::MiterJoin,
+    LineJoin::Round => LineJoinStyle::RoundJoin,
+    LineJoin::Bevel => LineJoinStyle::BevelJoin,
+  };
+  content
+    .set_line_width(options.width)
+    .set_line_cap(cap)
+    .set_line_join(join)
+    .set_miter_limit(options.miter_limit);
+  if !options.dash_array.is_empty() {
+    content.set_dash_pattern(options.dash_array.iter().copied(), options.dash_offset);
+  }
+}
+
+fn pdf_matrix(ts: &Transform) -> [f32; 6] { [ts.m11, ts.m12, ts.m21, ts.m22, ts.m31, ts.m32] }
+
+fn pdf_rect(rect: &Rect) -> PdfRect {
+  PdfRect::new(rect.min_x(), rect.min_y(), rect.max_x(), rect.max_y())
+}
+
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+  miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
@@ -0,0 +1,174 @@
+use std::{
+  collections::BTreeSet,
+  hash::{DefaultHasher, Hash, Hasher},
+};
+
+use pdf_writer::{
+  Chunk, Filter, Finish, Name, Rect as PdfRect, Ref, Str,
+  types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap},
+};
+use ribir_core::prelude::{
+  font_db::{Face, FaceInfo},
+  log,
+};
+
+use crate::document::compress;
+
+const IDENTITY: SystemInfo =
+  SystemInfo { registry: Str(b"Adobe"), ordering: Str(b"Identity"), supplement: 0 };
+
+/// The glyphs of a face that used by the document, only these glyphs will be
+/// embedded.
+pub(crate) struct FontUsage {
+  pub(crate) name: String,
+  pub(crate) font_ref: Ref,
+  pub(crate) units_per_em: u16,
+  pub(crate) glyphs: BTreeSet<u16>,
+}
+
+impl FontUsage {
+  pub(crate) fn new(name: String, font_ref: Ref, units_per_em: u16) -> Self {
+    // The `.notdef` glyph is required by the font file.
+    Self { name, font_ref, units_per_em, glyphs: BTreeSet::from([0]) }
+  }
+
+  /// Write the face as a Type0 font with the subset of the used glyphs. The
+  /// glyph id is used as the CID, so the text of the document is the glyph ids.
+  pub(crate) fn write(&self, face: &Face, info: &FaceInfo, chunk: &mut Chunk, next_ref: &mut Ref) {
+    let ttf = &face.rb_face;
+    let is_cff = ttf.tables().cff.is_some();
+    let glyphs = self.glyphs.iter().copied().collect::<Vec<_>>();
+    let data = face.source_data.as_ref().as_ref();
+    let subset = subsetter::subset(data, face.face_data_index, subsetter::Profile::pdf(&glyphs))
+      .unwrap_or_else(|err| {
+        log::warn!(
+          "[pdf]: failed to subset the font `{}`, embed the whole font: {err}",
+          info.post_script_name
+        );
+        data.to_vec()
+      });
+
+    let base_font = format!("{}+{}", self.subset_tag(), ps_name(&info.post_script_name));
+    let base_font = Name(base_font.as_bytes());
+    let scale = 1000. / self.units_per_em as f32;
+
+    let file_ref = next_ref.bump();
+    let subset = compress(&subset);
+    let mut file = chunk.stream(file_ref, &subset);
+    file.filter(Filter::FlateDecode);
+    if is_cff {
+      file.pair(Name(b"Subtype"), Name(b"OpenType"));
+    }
+    file.finish();
+
+    let descriptor_ref = next_ref.bump();
+    let bbox = ttf.global_bounding_box();
+    let mut flags = FontFlags::SYMBOLIC;
+    if ttf.is_italic() {
+      flags |= FontFlags::ITALIC;
+    }
+    if ttf.is_monospaced() {
+      flags |= FontFlags::FIXED_PITCH;
+    }
+    let mut descriptor = chunk.font_descriptor(descriptor_ref);
+    descriptor
+      .name(base_font)
+      .flags(flags)
+      .bbox(PdfRect::new(
+        bbox.x_min as f32 * scale,
+        bbox.y_min as f32 * scale,
+        bbox.x_max as f32 * scale,
+        bbox.y_max as f32 * scale,
+      ))
+      .italic_angle(ttf.italic_angle())
+      .ascent(face.ascender() as f32 * scale)
+      .descent(face.descender() as f32 * scale)
+      .cap_height(ttf.capital_height().unwrap_or(face.ascender()) as f32 * scale)
+      .stem_v(80.);
+    if is_cff {
+      descriptor.font_file3(file_ref);
+    } else {
+      descriptor.font_file2(file_ref);
+    }
+    descriptor.finish();
+
+    let cid_ref = next_ref.bump();
+    let mut cid = chunk.cid_font(cid_ref);
+    cid
+      .subtype(if is_cff { CidFontType::Type0 } else { CidFontType::Type2 })
+      .base_font(base_font)
+      .system_info(IDENTITY)
+      .font_descriptor(descriptor_ref)
+      .default_width(0.);
+    if !is_cff {
+      cid.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+    let mut widths = cid.widths();
+    for g in &self.glyphs {
+      let advance = ttf
+        .glyph_hor_advance(ribir_core::prelude::shaper::GlyphId(*g))
+        .unwrap_or(0);
+      widths.consecutive(*g, [advance as f32 * scale]);
+    }
+    widths.finish();
+    cid.finish();
+
+    let cmap_ref = next_ref.bump();
+    let cmap = self.to_unicode(face);
+    chunk.cmap(cmap_ref, &cmap);
+
+    chunk
+      .type0_font(self.font_ref)
+      .base_font(base_font)
+      .encoding_predefined(Name(b"Identity-H"))
+      .descendant_font(cid_ref)
+      .to_unicode(cmap_ref);
+  }
+
+  /// The map from the glyphs to the unicode, let the text of the document can
+  /// be searched and copied.
+  fn to_unicode(&self, face: &Face) -> Vec<u8> {
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), IDENTITY);
+    let mut mapped = BTreeSet::new();
+    if let Some(table) = face.rb_face.tables().cmap {
+      for subtable in table
+        .subtables
+        .into_iter()
+        .filter(|t| t.is_unicode())
+      {
+        subtable.codepoints(|c| {
+          let glyph = subtable.glyph_index(c).map(|g| g.0);
+          if let Some(g) = glyph.filter(|g| self.glyphs.contains(g) && mapped.insert(*g)) {
+            if let Some(c) = char::from_u32(c) {
+              cmap.pair(g, c);
+            }
+          }
+        });
+      }
+    }
+    cmap.finish()
+  }
+
+  /// A tag of six uppercase letters to identify the subset.
+  fn subset_tag(&self) -> String {
+    let mut hasher = DefaultHasher::new();
+    self.glyphs.hash(&mut hasher);
+    let mut hash = Hasher::finish(&hasher);
+    (0..6)
+      .map(|_| {
+        let c = (b'A' + (hash % 26) as u8) as char;
+        hash /= 26;
+        c
+      })
+      .collect()
+  }
+}
+
+/// The PostScript name can't contain spaces and the delimiters.
+fn ps_name(name: &str) -> String {
+  let name = name
+    .chars()
+    .filter(|c| c.is_ascii_graphic() && !"[](){}<>/%".contains(*c))
+    .collect::<String>();
+  if name.is_empty() { "Font".to_string() } else { name }
+}
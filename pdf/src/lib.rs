@@ -0,0 +1,257 @@
+//! Export the paint commands of Ribir as PDF documents.
+//!
+//! Use [`widget_to_pdf`] to lay out and paint a widget to a multi-page PDF, or
+//! use the [`PdfBackend`] to draw the paint commands to a [`PdfDocument`] page
+//! by page.
+//!
+//! The glyphs are kept as text, and the fonts are embedded with the subset of
+//! the used glyphs from the `FontDB`.
+use std::cell::Cell;
+
+use ribir_core::{
+  prelude::*,
+  window::{ShellWindow, WindowFlags, WindowId},
+};
+
+mod document;
+mod font;
+pub use document::{PdfBackend, PdfDocument};
+
+/// Lay out the `widget` with the width of the page and paint it as a PDF
+/// document, the content that taller than a page is split across pages.
+///
+/// One logical pixel is one point of the page, for example, the page size of
+/// A4 is `Size::new(595., 842.)`.
+pub fn widget_to_pdf(widget: impl Into<GenWidget>, page_size: Size) -> Vec<u8> {
+  let content_height = Sc::new(Cell::new(0.));
+  let widget = widget.into();
+  let c_height = content_height.clone();
+  let root = fn_widget! {
+    let height = c_height.clone();
+    let content = FatObj::new(widget.gen_widget()).on_performed_layout(move |e| {
+      height.set(e.box_size().map_or(0., |s| s.height));
+    });
+    // The content can be taller than the page, then split to pages.
+    @UnconstrainedBox {
+      dir: UnconstrainedDir::Y,
+      clamp_dim: ClampDim::MAX_SIZE,
+      @ { content }
+    }
+  };
+
+  let wnd = AppCtx::new_window(Box::new(PdfShellWindow::new(page_size)), root.into());
+  let mut flags = wnd.flags();
+  // Paint the final state of the animations.
+  flags.remove(WindowFlags::ANIMATIONS);
+  wnd.set_flags(flags);
+  wnd.run_frame_tasks();
+  wnd.draw_frame();
+
+  let pages = (content_height.get() / page_size.height)
+    .ceil()
+    .max(1.);
+  if pages > 1. {
+    wnd.request_resize(Size::new(page_size.width, page_size.height * pages));
+    wnd.draw_frame();
+  }
+
+  let frame = wnd
+    .shell_wnd()
+    .borrow_mut()
+    .as_any_mut()
+    .downcast_mut::<PdfShellWindow>()
+    .and_then(|shell| shell.frame.take());
+  AppCtx::remove_wnd(wnd.id());
+
+  let mut doc = PdfDocument::new(page_size, AppCtx::font_db().clone());
+  let mut backend = PdfBackend::default();
+  let viewport = DeviceRect::from_size(page_size.ceil().to_i32().cast_unit());
+  let (surface, commands) = frame.unwrap_or((Color::TRANSPARENT, vec![]));
+  for i in 0..pages as usize {
+    let global_matrix = Transform::translation(0., -page_size.height * i as f32);
+    backend.begin_frame(surface);
+    backend.draw_commands(viewport, &[viewport], &commands, &global_matrix, &mut doc);
+    backend.end_frame();
+  }
+  doc.finish()
+}
+
+/// A window without the platform window, it only keeps the last frame.
+struct PdfShellWindow {
+  id: WindowId,
+  size: Size,
+  surface: Color,
+  frame: Option<(Color, Vec<PaintCommand>)>,
+}
+
+impl PdfShellWindow {
+  fn new(size: Size) -> Self {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // Count down from the max id to not conflict with the platform windows.
+    static ID: AtomicU64 = AtomicU64::new(u64::MAX);
+    let id = ID.fetch_sub(1, Ordering::Relaxed).into();
+    Self { id, size, surface: Color::TRANSPARENT, frame: None }
+  }
+}
+
+impl ShellWindow for PdfShellWindow {
+  fn id(&self) -> WindowId { self.id }
+
+  fn inner_size(&self) -> Size { self.size }
+
+  fn outer_size(&self) -> Size { self.size }
+
+  fn set_ime_cursor_area(&mut self, _: &Rect) {}
+
+  fn set_ime_allowed(&mut self, _: bool) {}
+
+  fn request_resize(&mut self, size: Size) { self.on_resize(size) }
+
+  fn on_resize(&mut self, size: Size) {
+    self.size = size;
+    self.frame = None;
+  }
+
+  fn set_min_size(&mut self, _: Size) {}
+
+  fn cursor(&self) -> CursorIcon { CursorIcon::Default }
+
+  fn set_cursor(&mut self, _: CursorIcon) {}
+
+  fn set_title(&mut self, _: &str) {}
+
+  fn set_icon(&mut self, _: &PixelImage) {}
+
+  fn is_visible(&self) -> Option<bool> { Some(false) }
+
+  fn set_visible(&mut self, _: bool) {}
+
+  fn is_resizable(&self) -> bool { false }
+
+  fn set_resizable(&mut self, _: bool) {}
+
+  fn is_minimized(&self) -> bool { false }
+
+  fn set_minimized(&mut self, _: bool) {}
+
+  fn focus_window(&mut self) {}
+
+  fn set_decorations(&mut self, _: bool) {}
+
+  fn as_any(&self) -> &dyn Any { self }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any { self }
+
+  fn device_pixel_ratio(&self) -> f32 { 1. }
+
+  fn begin_frame(&mut self, surface_color: Color) { self.surface = surface_color; }
+
+  fn draw_commands(&mut self, _: Rect, _: &[Rect], commands: &[PaintCommand]) {
+    self.frame = Some((self.surface, commands.to_vec()));
+  }
+
+  fn end_frame(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_core::{reset_test_env, test_helper::*};
+
+  use super::*;
+
+  fn page_count(pdf: &[u8]) -> usize {
+    let pdf = String::from_utf8_lossy(pdf);
+    pdf.matches("/Type /Page\n").count()
+  }
+
+  #[test]
+  fn text_as_glyphs() {
+    reset_test_env!();
+
+    let pdf = widget_to_pdf(fn_widget! { @Text { text: "Hello PDF" } }, Size::new(200., 100.));
+    assert!(pdf.starts_with(b"%PDF"));
+    assert_eq!(page_count(&pdf), 1);
+
+    let pdf = String::from_utf8_lossy(&pdf);
+    assert!(pdf.contains("/Subtype /Type0"));
+    assert!(pdf.contains("/Encoding /Identity-H"));
+    assert!(pdf.contains("/FontFile2"));
+  }
+
+  #[test]
+  fn split_pages() {
+    reset_test_env!();
+
+    let pdf = widget_to_pdf(
+      fn_widget! {
+        @MockBox {
+          size: Size::new(100., 250.),
+          background: Color::RED,
+        }
+      },
+      Size::new(100., 100.),
+    );
+    assert_eq!(page_count(&pdf), 3);
+  }
+
+  #[test]
+  fn bundle_opacity_group() {
+    reset_test_env!();
+
+    let mut painter = Painter::new(Rect::from_size(Size::new(100., 100.)));
+    let cmds: Box<[PaintCommand]> = {
+      let mut p = Painter::new(Rect::from_size(Size::new(100., 100.)));
+      p.set_fill_brush(Color::RED)
+        .rect(&Rect::from_size(Size::new(10., 10.)))
+        .fill();
+      let cmds = p.finish().to_vec();
+      cmds.into()
+    };
+    painter
+      .apply_alpha(0.5)
+      .draw_bundle_commands(Rect::from_size(Size::new(10., 10.)), Resource::new(cmds));
+
+    let mut doc = PdfDocument::new(Size::new(100., 100.), AppCtx::font_db().clone());
+    let mut backend = PdfBackend::default();
+    backend.begin_frame(Color::WHITE);
+    let viewport = DeviceRect::from_size(DeviceSize::new(100, 100));
+    let cmds = painter.finish();
+    backend.draw_commands(viewport, &[viewport], &cmds, &Transform::identity(), &mut doc);
+    backend.end_frame();
+    assert_eq!(doc.page_count(), 1);
+
+    let pdf = doc.finish();
+    let pdf = String::from_utf8_lossy(&pdf);
+    assert!(pdf.contains("/Subtype /Form"));
+    assert!(pdf.contains("/S /Transparency"));
+    assert!(pdf.contains("/ca 0.5"));
+  }
+
+  #[test]
+  fn blend_mode_graphics_state() {
+    reset_test_env!();
+
+    let mut painter = Painter::new(Rect::from_size(Size::new(100., 100.)));
+    painter
+      .set_blend_mode(BlendMode::Multiply)
+      .set_fill_brush(Color::RED)
+      .rect(&Rect::from_size(Size::new(10., 10.)))
+      .fill()
+      .rect(&Rect::from_size(Size::new(20., 20.)))
+      .fill();
+
+    let mut doc = PdfDocument::new(Size::new(100., 100.), AppCtx::font_db().clone());
+    let mut backend = PdfBackend::default();
+    backend.begin_frame(Color::WHITE);
+    let viewport = DeviceRect::from_size(DeviceSize::new(100, 100));
+    let cmds = painter.finish();
+    backend.draw_commands(viewport, &[viewport], &cmds, &Transform::identity(), &mut doc);
+    backend.end_frame();
+
+    let pdf = doc.finish();
+    let pdf = String::from_utf8_lossy(&pdf);
+    // The graphics state is shared by the paths in the same blend mode.
+    assert_eq!(pdf.matches("/BM /Multiply").count(), 1);
+  }
+}
@@ -1,9 +1,21 @@
+//! The painter backend of the windows, the `wgpu` backend takes priority over
+//! the `cpu` backend if both of them are enabled. The windows paint nothing if
+//! neither of them is enabled.
+
 #[cfg(feature = "wgpu")]
 mod wgpu_backend;
 #[cfg(feature = "wgpu")]
 pub(crate) use wgpu_backend::WgpuBackend as Backend;
 
-#[cfg(not(any(feature = "wgpu")))]
+// The cpu backend is still compiled when the wgpu backend takes priority, so
+// it's checked with all the features.
+#[cfg(feature = "cpu")]
+#[cfg_attr(feature = "wgpu", allow(dead_code))]
+mod cpu_backend;
+#[cfg(all(feature = "cpu", not(feature = "wgpu")))]
+pub(crate) use cpu_backend::CpuBackend as Backend;
+
+#[cfg(not(any(feature = "wgpu", feature = "cpu")))]
 mod mock_backend;
-#[cfg(not(any(feature = "wgpu")))]
+#[cfg(not(any(feature = "wgpu", feature = "cpu")))]
 pub(crate) use mock_backend::MockBackend as Backend;
@@ -0,0 +1,102 @@
+use std::num::NonZeroU32;
+
+use ribir_core::prelude::{
+  Color, DeviceRect, DeviceSize, PaintCommand, PainterBackend, Transform, log,
+};
+use ribir_cpu::CpuTexture;
+use softbuffer::{Context, Surface};
+
+use crate::winit_shell_wnd::WinitBackend;
+
+/// The backend rasterizes the frame by the CPU, and presents it to the window
+/// by `softbuffer`.
+pub struct CpuBackend<'a> {
+  surface: Surface<&'a winit::window::Window, &'a winit::window::Window>,
+  texture: CpuTexture,
+  backend: ribir_cpu::CpuBackend,
+  /// The areas redrawn in this frame.
+  damages: Vec<softbuffer::Rect>,
+  /// The texture is recreated and has nothing, the whole viewport need to be
+  /// redrawn in the next frame.
+  full_damage: bool,
+}
+
+impl<'a> WinitBackend<'a> for CpuBackend<'a> {
+  async fn new(window: &'a winit::window::Window) -> CpuBackend<'a> {
+    let context = Context::new(window).expect("Failed to create the softbuffer context.");
+    let surface = Surface::new(&context, window).expect("Failed to create the softbuffer surface.");
+    let size = window.inner_size();
+    let size = DeviceSize::new(size.width as i32, size.height as i32);
+
+    let mut cpu = CpuBackend {
+      surface,
+      texture: CpuTexture::new(DeviceSize::zero()),
+      backend: ribir_cpu::CpuBackend::new(),
+      damages: vec![],
+      full_damage: true,
+    };
+    cpu.on_resize(size);
+
+    cpu
+  }
+
+  fn on_resize(&mut self, size: DeviceSize) {
+    if size != self.texture.size() {
+      let width = NonZeroU32::new(size.width.max(1) as u32).unwrap();
+      let height = NonZeroU32::new(size.height.max(1) as u32).unwrap();
+      self
+        .surface
+        .resize(width, height)
+        .expect("Failed to resize the softbuffer surface.");
+      self.texture = CpuTexture::new(size);
+      self.full_damage = true;
+    }
+  }
+
+  fn begin_frame(&mut self, surface_color: Color) { self.backend.begin_frame(surface_color); }
+
+  fn draw_commands(
+    &mut self, viewport: DeviceRect, damages: &[DeviceRect], global_matrix: &Transform,
+    commands: &[PaintCommand],
+  ) {
+    let full = [viewport];
+    let damages = if self.full_damage { &full[..] } else { damages };
+    self
+      .backend
+      .draw_commands(viewport, damages, commands, global_matrix, &mut self.texture);
+
+    let tex_rect = DeviceRect::from_size(self.texture.size());
+    let damages = damages
+      .iter()
+      .filter_map(|rect| rect.intersection(&tex_rect))
+      .filter_map(|rect| {
+        Some(softbuffer::Rect {
+          x: rect.min_x() as u32,
+          y: rect.min_y() as u32,
+          width: NonZeroU32::new(rect.width() as u32)?,
+          height: NonZeroU32::new(rect.height() as u32)?,
+        })
+      });
+    self.damages.extend(damages);
+  }
+
+  fn end_frame(&mut self) {
+    self.backend.end_frame();
+    self.full_damage = false;
+    let Ok(mut buffer) = self.surface.buffer_mut() else { return };
+    // The surface is opaque, so the premultiplied pixels are composited over
+    // black.
+    self
+      .texture
+      .pixel_bytes()
+      .chunks_exact(4)
+      .zip(buffer.iter_mut())
+      .for_each(|(p, b)| *b = u32::from_be_bytes([0, p[0], p[1], p[2]]));
+    // The whole buffer is updated, so the platform can only update the damaged
+    // areas of the window.
+    let damages = std::mem::take(&mut self.damages);
+    if let Err(err) = buffer.present_with_damage(&damages) {
+      log::warn!("Failed to present the frame: {err}");
+    }
+  }
+}
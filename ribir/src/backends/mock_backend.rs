@@ -1,19 +1,19 @@
+use std::marker::PhantomData;
+
+use ribir_core::prelude::{Color, DeviceRect, DeviceSize, PaintCommand, Transform};
+
 use crate::winit_shell_wnd::WinitBackend;
 
-pub struct MockBackend;
+pub struct MockBackend<'a>(PhantomData<&'a ()>);
 
-impl WinitBackend for MockBackend {
-  fn new(_: &winit::window::Window) -> Self { Self }
+impl<'a> WinitBackend<'a> for MockBackend<'a> {
+  async fn new(_: &'a winit::window::Window) -> MockBackend<'a> { MockBackend(PhantomData) }
 
-  fn on_resize(&mut self, _: ribir_core::prelude::DeviceSize) {}
+  fn on_resize(&mut self, _: DeviceSize) {}
 
-  fn begin_frame(&mut self) {}
+  fn begin_frame(&mut self, _: Color) {}
 
-  fn draw_commands(
-    &mut self, _: ribir_core::prelude::DeviceRect, _: Vec<ribir_core::prelude::PaintCommand>,
-    _: ribir_core::prelude::Color,
-  ) {
-  }
+  fn draw_commands(&mut self, _: DeviceRect, _: &[DeviceRect], _: &Transform, _: &[PaintCommand]) {}
 
   fn end_frame(&mut self) {}
 }
@@ -29,10 +29,12 @@ impl<'a> WinitBackend<'a> for WgpuBackend<'a> {
   fn begin_frame(&mut self, surface_color: Color) { self.backend.begin_frame(surface_color); }
 
   fn draw_commands(
-    &mut self, viewport: DeviceRect, global_matrix: &Transform, commands: &[PaintCommand],
+    &mut self, viewport: DeviceRect, damages: &[DeviceRect], global_matrix: &Transform,
+    commands: &[PaintCommand],
   ) {
     self.backend.draw_commands(
       viewport,
+      damages,
       commands,
       global_matrix,
       self.surface.get_current_texture(),
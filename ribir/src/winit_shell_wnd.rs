@@ -21,7 +21,8 @@ pub trait WinitBackend<'a>: Sized {
   fn begin_frame(&mut self, surface_color: Color);
 
   fn draw_commands(
-    &mut self, viewport: DeviceRect, global_matrix: &Transform, commands: &[PaintCommand],
+    &mut self, viewport: DeviceRect, damages: &[DeviceRect], global_matrix: &Transform,
+    commands: &[PaintCommand],
   );
 
   fn end_frame(&mut self);
@@ -149,18 +150,22 @@ impl ShellWindow for WinitShellWnd {
   fn begin_frame(&mut self, surface: Color) { self.backend.begin_frame(surface) }
 
   #[inline]
-  fn draw_commands(&mut self, viewport: Rect, commands: &[PaintCommand]) {
+  fn draw_commands(&mut self, viewport: Rect, damages: &[Rect], commands: &[PaintCommand]) {
     let scale = self.winit_wnd.scale_factor() as f32;
-    let viewport: DeviceRect = viewport
-      .scale(scale, scale)
-      .round_out()
-      .to_i32()
-      .cast_unit();
+    let to_device = |rect: &Rect| -> DeviceRect {
+      rect
+        .scale(scale, scale)
+        .round_out()
+        .to_i32()
+        .cast_unit()
+    };
+    let viewport = to_device(&viewport);
+    let damages = damages.iter().map(to_device).collect::<Vec<_>>();
 
     self.winit_wnd.pre_present_notify();
     self
       .backend
-      .draw_commands(viewport, &Transform::scale(scale, scale), commands);
+      .draw_commands(viewport, &damages, &Transform::scale(scale, scale), commands);
   }
 
   #[inline]
@@ -368,10 +368,7 @@ pub fn typography_theme() -> TypographyTheme {
         letter_space,
         font_face,
         overflow: TextOverflow::Clip,
-      },
-      decoration: TextDecorationStyle {
-        decoration: TextDecoration::NONE,
-        decoration_color: Color::BLACK.with_alpha(0.87).into(),
+        ..<_>::default()
       },
     }
   }
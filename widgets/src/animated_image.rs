@@ -0,0 +1,354 @@
+use std::{
+  cell::{Cell, RefCell},
+  rc::Rc,
+};
+
+use ribir_core::{
+  prelude::*,
+  ticker::FrameMsg,
+  window::{WindowFlags, WindowId},
+};
+
+/// A widget that plays the frames of an [`AnimatedImage`].
+///
+/// The frames are advanced on the frame ticks of the window, and the player
+/// stops ticking the window while it is paused, finished, not visible or
+/// disposed.
+///
+/// # Example
+///
+/// ```
+/// # use ribir_core::prelude::*;
+/// # use ribir_widgets::prelude::*;
+/// fn sticker(bytes: &[u8]) -> Widget<'static> {
+///   let image = Resource::new(AnimatedImage::decode(bytes).unwrap());
+///   fn_widget! {
+///     let player = @AnimatedImagePlayer { image: image.clone(), loop_count: Some(3) };
+///     @ $player {
+///       on_tap: move |_| {
+///         let mut player = $player.write();
+///         if player.playing { player.pause() } else { player.play() }
+///       }
+///     }
+///   }
+///   .into_widget()
+/// }
+/// ```
+#[derive(Declare)]
+pub struct AnimatedImagePlayer {
+  pub image: Resource<AnimatedImage>,
+  /// How many times to play all the frames before the player stops at the
+  /// last frame, `None` plays forever.
+  #[declare(default)]
+  pub loop_count: Option<u32>,
+  /// Whether the frames are advancing, it turns to `false` when all the loops
+  /// are played.
+  #[declare(default = true)]
+  pub playing: bool,
+  #[declare(skip)]
+  progress: Progress,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Progress {
+  frame: usize,
+  loops: u32,
+  elapsed: Duration,
+  finished: bool,
+}
+
+impl AnimatedImagePlayer {
+  /// Continue to play the frames, or replay them from the first frame if all
+  /// the loops are played.
+  pub fn play(&mut self) {
+    if self.progress.finished {
+      self.progress = Progress::default();
+    }
+    self.playing = true;
+  }
+
+  /// Stop advancing the frames and keep the current frame.
+  pub fn pause(&mut self) { self.playing = false; }
+
+  /// Whether all the loops are played.
+  pub fn is_finished(&self) -> bool { self.progress.finished }
+
+  /// The index of the frame is showing.
+  pub fn current_index(&self) -> usize {
+    self
+      .progress
+      .frame
+      .min(self.image.frame_count() - 1)
+  }
+
+  /// The frame is showing.
+  pub fn current_frame(&self) -> &ImageFrame { &self.image.frames()[self.current_index()] }
+
+  /// Advance the frames by the elapsed time, return if the showing frame or
+  /// the playing state is changed.
+  fn advance(&mut self, dt: Duration) -> bool {
+    if !self.playing {
+      return false;
+    }
+
+    let frames = self.image.frames();
+    let Progress { frame, loops, elapsed, finished } = &mut self.progress;
+    let start = *frame;
+    *elapsed += dt;
+    while *elapsed >= frames[*frame].delay {
+      if *frame + 1 < frames.len() {
+        *elapsed -= frames[*frame].delay;
+        *frame += 1;
+      } else if self.loop_count.is_none_or(|n| *loops + 1 < n) {
+        *elapsed -= frames[*frame].delay;
+        *loops += 1;
+        *frame = 0;
+      } else {
+        *elapsed = Duration::ZERO;
+        *finished = true;
+        self.playing = false;
+        return true;
+      }
+    }
+    *frame != start
+  }
+}
+
+impl Compose for AnimatedImagePlayer {
+  fn compose(this: impl StateWriter<Value = Self>) -> Widget<'static> {
+    fn_widget! {
+      let wnd = BuildCtx::get().window();
+      let painted = Rc::new(Cell::new(false));
+      let view = Stateful::new(FrameView {
+        image: $this.current_frame().image.clone(),
+        painted: painted.clone(),
+      });
+      let ticker = Rc::new(RefCell::new(Ticker::new(wnd.id())));
+
+      ticker
+        .borrow_mut()
+        .set_running($this.playing && $this.image.is_animated());
+
+      let ticker2 = ticker.clone();
+      let u1 = watch!(($this.playing, $this.image.is_animated(), $this.current_index()))
+        .subscribe(move |(playing, animated, _)| {
+          let image = $this.current_frame().image.clone();
+          if $view.image != image {
+            $view.write().image = image;
+          }
+          ticker2.borrow_mut().set_running(playing && animated);
+        });
+
+      let ticker2 = ticker.clone();
+      let u2 = wnd.frame_tick_stream().subscribe(move |msg| match msg {
+        FrameMsg::BeforeLayout(now) => {
+          let dt = ticker2.borrow_mut().tick(now);
+          if let Some(dt) = dt {
+            let mut this = $this.write();
+            if !this.advance(dt) {
+              this.forget_modifies();
+            }
+          }
+        }
+        FrameMsg::Finish(_) => {
+          let mut ticker = ticker2.borrow_mut();
+          if ticker.drawing {
+            ticker.drawing = false;
+            ticker.visible = painted.replace(false);
+            let playing = $this.playing && $this.image.is_animated();
+            ticker.set_running(playing);
+          }
+        }
+        _ => {}
+      });
+
+      FatObj::new(view.clone_writer()).on_disposed(move |_| {
+        u1.unsubscribe();
+        u2.unsubscribe();
+        ticker.borrow_mut().stop();
+      })
+    }
+    .into_widget()
+  }
+}
+
+/// The state of ticking the window to advance the frames.
+pub(crate) struct Ticker {
+  wnd_id: WindowId,
+  /// Whether the player holds a running animation of the window.
+  running: bool,
+  /// Whether the player is painted in the last drawn frame.
+  pub(crate) visible: bool,
+  /// Whether the window is drawing a frame.
+  pub(crate) drawing: bool,
+  last_tick: Option<Instant>,
+}
+
+impl Ticker {
+  pub(crate) fn new(wnd_id: WindowId) -> Self {
+    Self { wnd_id, running: false, visible: true, drawing: false, last_tick: None }
+  }
+
+  /// Return the elapsed time since the last tick if the frames are advancing.
+  pub(crate) fn tick(&mut self, now: Instant) -> Option<Duration> {
+    self.drawing = true;
+    if !self.running {
+      return None;
+    }
+    let last = self.last_tick.replace(now);
+    Some(last.map_or(Duration::ZERO, |last| now - last))
+  }
+
+  pub(crate) fn set_running(&mut self, playing: bool) {
+    let Some(wnd) = AppCtx::get_window(self.wnd_id) else { return };
+    let running = playing && self.visible && wnd.flags().contains(WindowFlags::ANIMATIONS);
+    if running != self.running {
+      self.running = running;
+      self.last_tick = None;
+      if running { wnd.inc_running_animate() } else { wnd.dec_running_animate() }
+    }
+  }
+
+  pub(crate) fn stop(&mut self) {
+    self.visible = false;
+    self.set_running(false);
+  }
+}
+
+/// The render of the current frame, it records whether it's painted in the
+/// visible area to know if the player is visible.
+struct FrameView {
+  image: Resource<PixelImage>,
+  painted: Rc<Cell<bool>>,
+}
+
+impl Render for FrameView {
+  fn perform_layout(&self, clamp: BoxClamp, ctx: &mut LayoutCtx) -> Size {
+    self.image.perform_layout(clamp, ctx)
+  }
+
+  fn paint(&self, ctx: &mut PaintingCtx) {
+    let size = ctx.box_size().unwrap();
+    if ctx
+      .painter()
+      .intersect_paint_bounds(&Rect::from_size(size))
+    {
+      self.painted.set(true);
+      self.image.paint(ctx);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_core::{reset_test_env, test_helper::*};
+
+  use super::*;
+  use crate::layout::SizedBox;
+
+  fn two_frames(delay: Duration) -> Resource<AnimatedImage> {
+    let frame = |c: u8| ImageFrame {
+      image: Resource::new(PixelImage::new(vec![c; 4].into(), 1, 1, image::ColorFormat::Rgba8)),
+      delay,
+    };
+    Resource::new(AnimatedImage::new(vec![frame(0), frame(255)]))
+  }
+
+  fn player(loop_count: Option<u32>) -> AnimatedImagePlayer {
+    AnimatedImagePlayer {
+      image: two_frames(Duration::from_millis(20)),
+      loop_count,
+      playing: true,
+      progress: <_>::default(),
+    }
+  }
+
+  #[test]
+  fn advance_frames() {
+    let mut player = player(Some(2));
+    assert!(!player.advance(Duration::from_millis(10)));
+    assert!(player.advance(Duration::from_millis(10)));
+    assert_eq!(player.current_index(), 1);
+    // Back to the first frame for the second loop.
+    assert!(player.advance(Duration::from_millis(25)));
+    assert_eq!(player.current_index(), 0);
+
+    player.pause();
+    assert!(!player.advance(Duration::from_millis(100)));
+    player.play();
+    assert!(player.advance(Duration::from_millis(100)));
+    assert!(player.is_finished());
+    assert!(!player.playing);
+    assert_eq!(player.current_index(), 1);
+
+    player.play();
+    assert_eq!(player.current_index(), 0);
+    assert!(player.playing);
+  }
+
+  #[test]
+  fn play_forever() {
+    let mut player = player(None);
+    player.advance(Duration::from_secs(1));
+    assert!(player.playing);
+    assert!(!player.is_finished());
+  }
+
+  #[test]
+  fn stop_ticking_after_loops() {
+    reset_test_env!();
+
+    let player = Stateful::new(player(Some(1)));
+    let c_player = player.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! { c_player.clone_writer() });
+    wnd.set_flags(WindowFlags::ANIMATIONS);
+    wnd.draw_frame();
+    assert!(wnd.need_draw());
+
+    wnd.draw_frame();
+    // Tick the player 50ms later than the last frame instead of waiting.
+    let later = Instant::now() + Duration::from_millis(50);
+    wnd
+      .frame_tick_stream()
+      .next(FrameMsg::BeforeLayout(later));
+    wnd.draw_frame();
+    assert!(player.read().is_finished());
+    assert_eq!(player.read().current_index(), 1);
+    assert!(!wnd.need_draw());
+
+    player.write().play();
+    wnd.draw_frame();
+    assert!(wnd.need_draw());
+  }
+
+  #[test]
+  fn stop_ticking_when_hidden() {
+    reset_test_env!();
+
+    let (visible, w_visible) = split_value(true);
+    let player = Stateful::new(player(None));
+    let c_player = player.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! {
+      @SizedBox {
+        size: Size::new(10., 10.),
+        visible: pipe!(*$visible),
+        @ { c_player.clone_writer() }
+      }
+    });
+    wnd.set_flags(WindowFlags::ANIMATIONS);
+    wnd.draw_frame();
+    assert!(wnd.need_draw());
+
+    *w_visible.write() = false;
+    wnd.draw_frame();
+    assert!(!wnd.need_draw());
+
+    *w_visible.write() = true;
+    wnd.draw_frame();
+    assert!(wnd.need_draw());
+
+    player.write().pause();
+    wnd.draw_frame();
+    assert!(!wnd.need_draw());
+  }
+}
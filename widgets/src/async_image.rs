@@ -0,0 +1,570 @@
+use std::{
+  cell::RefCell,
+  collections::HashMap,
+  future::Future,
+  path::{Path, PathBuf},
+  rc::Rc,
+  sync::Arc,
+};
+
+use futures::{
+  FutureExt,
+  future::{LocalBoxFuture, Shared},
+  task::SpawnError,
+};
+use ribir_core::prelude::*;
+
+/// Where an [`AsyncImage`] loads its image from.
+#[derive(Clone)]
+pub enum ImageSource {
+  /// An image file, it's read and decoded off the UI thread.
+  Path(PathBuf),
+  /// The bytes of an image are fetched by the future that `load` returns, and
+  /// decoded off the UI thread. The `key` identifies the image in the
+  /// [`image_cache`].
+  Bytes {
+    key: CowArc<str>,
+    load: Rc<dyn Fn() -> LocalBoxFuture<'static, std::io::Result<Vec<u8>>>>,
+  },
+}
+
+/// The error of loading an image.
+#[derive(Debug, Clone)]
+pub enum ImageLoadError {
+  /// The bytes of the image can't be read.
+  Io(Arc<std::io::Error>),
+  /// The bytes can't be decoded as an image.
+  Decode(image::ImageError),
+  /// The loading task can't be spawned to the local executor.
+  Spawn(Arc<SpawnError>),
+}
+
+/// The loading status of an [`AsyncImage`].
+#[derive(Debug, Clone, Default)]
+pub enum ImageStatus {
+  #[default]
+  Loading,
+  Loaded(Resource<PixelImage>),
+  Failed(ImageLoadError),
+}
+
+/// A widget that loads and decodes an image off the UI thread, and displays it
+/// as an [`Image`] when it's loaded.
+///
+/// It shows the `placeholder` while loading and the `failure` if the image
+/// can't be loaded, and nothing if they're not provided. The decoded
+/// images are shared by the [`image_cache`], so the same source is only loaded
+/// once until it's evicted.
+///
+/// # Example
+///
+/// ```no_run
+/// use ribir_core::prelude::*;
+/// use ribir_widgets::prelude::*;
+///
+/// let _photo = fn_widget! {
+///   @AsyncImage {
+///     src: "assets/photo.png",
+///     fit: ObjectFit::Cover,
+///     placeholder: GenWidget::new(|| @Text { text: "Loading..." }.into_widget()),
+///     failure: GenWidget::new(|| @Text { text: "Broken image" }.into_widget()),
+///   }
+/// };
+/// ```
+#[derive(Declare)]
+pub struct AsyncImage {
+  pub src: ImageSource,
+  #[declare(default)]
+  pub fit: ObjectFit,
+  #[declare(default = Align::Center)]
+  pub x_align: Align,
+  #[declare(default = Align::Center)]
+  pub y_align: Align,
+  #[declare(default)]
+  pub repeat: ImageRepeat,
+  #[declare(default)]
+  pub filter_quality: FilterQuality,
+  /// The widget to show while loading.
+  #[declare(default)]
+  pub placeholder: Option<GenWidget>,
+  /// The widget to show if the image can't be loaded.
+  #[declare(default)]
+  pub failure: Option<GenWidget>,
+  #[declare(skip)]
+  status: ImageStatus,
+}
+
+impl AsyncImage {
+  /// The loading status of the image.
+  pub fn status(&self) -> &ImageStatus { &self.status }
+}
+
+impl Compose for AsyncImage {
+  fn compose(this: impl StateWriter<Value = Self>) -> Widget<'static> {
+    load(this.clone_writer());
+    let writer = this.clone_writer();
+    fn_widget! {
+      // Reload the image when the source is changed.
+      let mut key = $this.src.cache_key();
+      let u = watch!($this.src.cache_key()).subscribe(move |k| {
+        if k != key {
+          key = k;
+          load(writer.clone_writer());
+        }
+      });
+
+      let content = pipe!($this.status.clone()).map(move |status| {
+        let this = $this;
+        let gen_or_void = |w: &Option<GenWidget>| match w {
+          Some(w) => w.gen_widget(),
+          None => Void.into_widget(),
+        };
+        match status {
+          ImageStatus::Loading => gen_or_void(&this.placeholder),
+          ImageStatus::Loaded(src) => Image {
+            src,
+            fit: this.fit,
+            x_align: this.x_align,
+            y_align: this.y_align,
+            repeat: this.repeat,
+            filter_quality: this.filter_quality,
+          }
+          .into_widget(),
+          ImageStatus::Failed(_) => gen_or_void(&this.failure),
+        }
+      });
+      FatObj::new(content).on_disposed(move |_| u.unsubscribe())
+    }
+    .into_widget()
+  }
+}
+
+type LoadResult = Result<Resource<PixelImage>, ImageLoadError>;
+type PendingLoad = Shared<LocalBoxFuture<'static, LoadResult>>;
+
+thread_local! {
+  /// The loads in flight, the images of the same key share one load.
+  static PENDING_LOADS: RefCell<HashMap<String, PendingLoad>> = RefCell::new(HashMap::new());
+}
+
+/// Load the image of the source, the result is ignored if the source is
+/// changed before it's loaded.
+fn load(this: impl StateWriter<Value = AsyncImage> + 'static) {
+  let src = this.read().src.clone();
+  let key = src.cache_key();
+  if let Some(img) = image_cache::get(&key) {
+    this.write().status = ImageStatus::Loaded(img);
+    return;
+  }
+
+  if !matches!(this.read().status, ImageStatus::Loading) {
+    this.write().status = ImageStatus::Loading;
+  }
+  let pending = pending_load(&key, src);
+  let writer = this.clone_writer();
+  let res = AppCtx::spawn_local(async move {
+    let res = pending.await;
+    if writer.read().src.cache_key() == key {
+      writer.write().status = match res {
+        Ok(img) => ImageStatus::Loaded(img),
+        Err(err) => ImageStatus::Failed(err),
+      };
+    }
+  });
+  if let Err(err) = res {
+    this.write().status = ImageStatus::Failed(ImageLoadError::Spawn(Arc::new(err)));
+  }
+}
+
+/// Return the load in flight of the `key`, or start a new one to load the
+/// `src`. The loaded image is cached when the load is finished.
+fn pending_load(key: &str, src: ImageSource) -> PendingLoad {
+  PENDING_LOADS.with_borrow_mut(|loads| {
+    loads
+      .entry(key.to_string())
+      .or_insert_with(|| {
+        let key = key.to_string();
+        async move {
+          let res = src.load().await;
+          if let Ok(img) = &res {
+            image_cache::insert(key.clone(), img.clone());
+          }
+          PENDING_LOADS.with_borrow_mut(|loads| loads.remove(&key));
+          res
+        }
+        .boxed_local()
+        .shared()
+      })
+      .clone()
+  })
+}
+
+impl ImageSource {
+  /// Create a source that fetches the bytes of the image by the future that
+  /// `load` returns, the `key` identifies the image in the [`image_cache`].
+  pub fn bytes<F>(key: impl Into<CowArc<str>>, load: impl Fn() -> F + 'static) -> Self
+  where
+    F: Future<Output = std::io::Result<Vec<u8>>> + 'static,
+  {
+    ImageSource::Bytes { key: key.into(), load: Rc::new(move || load().boxed_local()) }
+  }
+
+  /// The key of the image in the [`image_cache`].
+  pub fn cache_key(&self) -> CowArc<str> {
+    match self {
+      ImageSource::Path(path) => path.to_string_lossy().into_owned().into(),
+      ImageSource::Bytes { key, .. } => key.clone(),
+    }
+  }
+
+  async fn load(self) -> LoadResult {
+    let img = match self {
+      ImageSource::Path(path) => {
+        off_thread(move || PixelImage::decode(&std::fs::read(path)?).map_err(Into::into)).await
+      }
+      ImageSource::Bytes { load, .. } => {
+        let bytes = load().await?;
+        off_thread(move || PixelImage::decode(&bytes).map_err(Into::into)).await
+      }
+    };
+    img.map(Resource::new)
+  }
+}
+
+/// Run `f` on the blocking threads of the tokio runtime.
+#[cfg(all(not(target_arch = "wasm32"), feature = "tokio-async"))]
+async fn off_thread<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+  AppCtx::tokio_runtime()
+    .spawn_blocking(f)
+    .await
+    .expect("The thread to decode the image is panicked.")
+}
+
+/// Run `f` on the thread pool of decoding the images, the pool has a thread
+/// per CPU.
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "tokio-async")))]
+async fn off_thread<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+  use std::sync::LazyLock;
+
+  use futures::{executor::ThreadPool, task::SpawnExt};
+
+  static DECODERS: LazyLock<ThreadPool> = LazyLock::new(|| {
+    ThreadPool::builder()
+      .name_prefix("ribir-image-decoder-")
+      .create()
+      .expect("Failed to create the threads to decode the images.")
+  });
+  DECODERS
+    .spawn_with_handle(async move { f() })
+    .expect("Failed to spawn the task to decode the image.")
+    .await
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn off_thread<T>(f: impl FnOnce() -> T) -> T { f() }
+
+impl From<PathBuf> for ImageSource {
+  fn from(path: PathBuf) -> Self { ImageSource::Path(path) }
+}
+
+impl From<&Path> for ImageSource {
+  fn from(path: &Path) -> Self { ImageSource::Path(path.to_path_buf()) }
+}
+
+impl From<&str> for ImageSource {
+  fn from(path: &str) -> Self { ImageSource::Path(path.into()) }
+}
+
+impl From<std::io::Error> for ImageLoadError {
+  fn from(err: std::io::Error) -> Self { ImageLoadError::Io(Arc::new(err)) }
+}
+
+impl From<image::ImageError> for ImageLoadError {
+  fn from(err: image::ImageError) -> Self { ImageLoadError::Decode(err) }
+}
+
+impl std::fmt::Display for ImageLoadError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ImageLoadError::Io(err) => write!(f, "failed to read the image: {err}"),
+      ImageLoadError::Decode(err) => write!(f, "failed to decode the image: {err}"),
+      ImageLoadError::Spawn(err) => write!(f, "failed to spawn the loading task: {err}"),
+    }
+  }
+}
+
+impl std::error::Error for ImageLoadError {}
+
+/// A process-wide cache of the decoded images shared by the [`AsyncImage`]s.
+///
+/// The cache is bounded by the bytes of the pixels it holds, the least
+/// recently used images are evicted when it's over the capacity.
+pub mod image_cache {
+  use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+  };
+
+  use ribir_core::prelude::{PixelImage, Resource};
+
+  /// The default capacity, 64 MiB.
+  pub const DEFAULT_CAPACITY: usize = 64 * 1024 * 1024;
+
+  static CACHE: LazyLock<Mutex<Cache>> = LazyLock::new(|| Mutex::new(Cache::new(DEFAULT_CAPACITY)));
+
+  /// Get the image of the `key`, and mark it as the most recently used.
+  pub fn get(key: &str) -> Option<Resource<PixelImage>> { CACHE.lock().unwrap().get(key) }
+
+  /// Cache the image with the `key`, the image larger than the capacity is not
+  /// cached.
+  pub fn insert(key: String, img: Resource<PixelImage>) { CACHE.lock().unwrap().insert(key, img) }
+
+  /// Remove the image of the `key` from the cache.
+  pub fn remove(key: &str) -> Option<Resource<PixelImage>> { CACHE.lock().unwrap().remove(key) }
+
+  /// Set the max bytes of the pixels the cache holds, and evict the images
+  /// over it.
+  pub fn set_capacity(bytes: usize) { CACHE.lock().unwrap().set_capacity(bytes) }
+
+  pub fn capacity() -> usize { CACHE.lock().unwrap().capacity }
+
+  /// The bytes of the pixels the cache holds.
+  pub fn memory_usage() -> usize { CACHE.lock().unwrap().used }
+
+  pub fn clear() { CACHE.lock().unwrap().clear() }
+
+  pub(super) struct Cache {
+    capacity: usize,
+    used: usize,
+    tick: u64,
+    images: HashMap<String, (Resource<PixelImage>, u64)>,
+  }
+
+  impl Cache {
+    pub(super) fn new(capacity: usize) -> Self {
+      Self { capacity, used: 0, tick: 0, images: HashMap::new() }
+    }
+
+    pub(super) fn get(&mut self, key: &str) -> Option<Resource<PixelImage>> {
+      self.tick += 1;
+      let (img, last_used) = self.images.get_mut(key)?;
+      *last_used = self.tick;
+      Some(img.clone())
+    }
+
+    pub(super) fn insert(&mut self, key: String, img: Resource<PixelImage>) {
+      self.remove(&key);
+      let bytes = img.pixel_bytes().len();
+      if bytes > self.capacity {
+        return;
+      }
+      self.tick += 1;
+      self.used += bytes;
+      self.images.insert(key, (img, self.tick));
+      self.evict();
+    }
+
+    pub(super) fn remove(&mut self, key: &str) -> Option<Resource<PixelImage>> {
+      let (img, _) = self.images.remove(key)?;
+      self.used -= img.pixel_bytes().len();
+      Some(img)
+    }
+
+    pub(super) fn set_capacity(&mut self, capacity: usize) {
+      self.capacity = capacity;
+      self.evict();
+    }
+
+    pub(super) fn clear(&mut self) {
+      self.images.clear();
+      self.used = 0;
+    }
+
+    fn evict(&mut self) {
+      while self.used > self.capacity {
+        let lru = self
+          .images
+          .iter()
+          .min_by_key(|(_, (_, last_used))| *last_used)
+          .map(|(key, _)| key.clone());
+        let Some(lru) = lru else { break };
+        self.remove(&lru);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_core::{reset_test_env, test_helper::*};
+
+  use super::{image_cache::Cache, *};
+  use crate::layout::{Row, SizedBox};
+
+  fn pixels(width: u32, height: u32) -> Resource<PixelImage> {
+    let data = vec![255; (width * height * 4) as usize];
+    Resource::new(PixelImage::new(data.into(), width, height, image::ColorFormat::Rgba8))
+  }
+
+  fn png_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ribir_async_image_{name}.png"));
+    let mut file = std::fs::File::create(&path).unwrap();
+    pixels(3, 2).write_as_png(&mut file).unwrap();
+    path
+  }
+
+  /// Block until the pending load of the image is finished, then the window
+  /// applies the result in its next frame.
+  fn wait_loaded(wnd: &mut TestWindow, img: &Stateful<AsyncImage>) {
+    let key = img.read().src.cache_key();
+    let pending = PENDING_LOADS.with_borrow(|loads| loads.get(&*key).cloned());
+    if let Some(pending) = pending {
+      let _ = AppCtx::wait_future(pending);
+    }
+    wnd.draw_frame();
+    assert!(!matches!(img.read().status(), ImageStatus::Loading));
+  }
+
+  fn async_image(src: impl Into<ImageSource>) -> AsyncImage {
+    AsyncImage {
+      src: src.into(),
+      fit: ObjectFit::None,
+      x_align: Align::Start,
+      y_align: Align::Start,
+      repeat: ImageRepeat::NoRepeat,
+      filter_quality: FilterQuality::Linear,
+      placeholder: None,
+      failure: None,
+      status: ImageStatus::Loading,
+    }
+  }
+
+  #[test]
+  fn load_file() {
+    reset_test_env!();
+
+    let path = png_file("load_file");
+    let img = Stateful::new(async_image(path.clone()));
+    let c_img = img.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! { c_img.clone_writer() });
+    wait_loaded(&mut wnd, &img);
+    assert!(matches!(img.read().status(), ImageStatus::Loaded(_)));
+    wnd.draw_frame();
+    wnd.assert_root_size(Size::new(3., 2.));
+
+    // The second image is loaded from the cache.
+    let cached = Stateful::new(async_image(path));
+    let c_cached = cached.clone_writer();
+    let _wnd = TestWindow::new(fn_widget! { c_cached.clone_writer() });
+    assert!(matches!(cached.read().status(), ImageStatus::Loaded(_)));
+  }
+
+  #[test]
+  fn placeholder_while_loading() {
+    reset_test_env!();
+
+    let (tx, rx) = futures::channel::oneshot::channel::<Vec<u8>>();
+    let rx = Rc::new(std::cell::RefCell::new(Some(rx)));
+    let src = ImageSource::bytes("ribir_async_image_placeholder", move || {
+      let rx = rx.borrow_mut().take().unwrap();
+      async move { Ok(rx.await.unwrap()) }
+    });
+    let mut img = async_image(src);
+    img.placeholder = Some(GenWidget::new(|| SizedBox { size: Size::new(50., 50.) }.into_widget()));
+    let img = Stateful::new(img);
+    let c_img = img.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! { c_img.clone_writer() });
+    wnd.draw_frame();
+    wnd.assert_root_size(Size::new(50., 50.));
+
+    let mut png = vec![];
+    pixels(3, 2).write_as_png(&mut png).unwrap();
+    tx.send(png).unwrap();
+    wait_loaded(&mut wnd, &img);
+    wnd.draw_frame();
+    wnd.assert_root_size(Size::new(3., 2.));
+  }
+
+  #[test]
+  fn failed_to_load() {
+    reset_test_env!();
+
+    let path = std::env::temp_dir().join("ribir_async_image_not_exist.png");
+    let mut img = async_image(path);
+    img.failure = Some(GenWidget::new(|| SizedBox { size: Size::new(20., 10.) }.into_widget()));
+    let img = Stateful::new(img);
+    let c_img = img.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! { c_img.clone_writer() });
+    wait_loaded(&mut wnd, &img);
+    assert!(matches!(img.read().status(), ImageStatus::Failed(ImageLoadError::Io(_))));
+    wnd.draw_frame();
+    wnd.assert_root_size(Size::new(20., 10.));
+  }
+
+  #[test]
+  fn load_bytes() {
+    reset_test_env!();
+
+    let src =
+      ImageSource::bytes("ribir_async_image_load_bytes", || async { Ok(b"not an image".to_vec()) });
+    let img = Stateful::new(async_image(src));
+    let c_img = img.clone_writer();
+    let mut wnd = TestWindow::new(fn_widget! { c_img.clone_writer() });
+    wait_loaded(&mut wnd, &img);
+    assert!(matches!(
+      img.read().status(),
+      ImageStatus::Failed(ImageLoadError::Decode(image::ImageError::UnknownFormat))
+    ));
+  }
+
+  #[test]
+  fn share_pending_load() {
+    reset_test_env!();
+
+    let loads = Rc::new(std::cell::Cell::new(0));
+    let c_loads = loads.clone();
+    let src = ImageSource::bytes("ribir_async_image_share_pending_load", move || {
+      c_loads.set(c_loads.get() + 1);
+      async {
+        let mut png = vec![];
+        pixels(3, 2).write_as_png(&mut png).unwrap();
+        Ok(png)
+      }
+    });
+    let first = Stateful::new(async_image(src.clone()));
+    let second = Stateful::new(async_image(src));
+    let (c_first, c_second) = (first.clone_writer(), second.clone_writer());
+    let mut wnd = TestWindow::new(fn_widget! {
+      @Row {
+        @ { c_first.clone_writer() }
+        @ { c_second.clone_writer() }
+      }
+    });
+    wait_loaded(&mut wnd, &first);
+    assert!(matches!(first.read().status(), ImageStatus::Loaded(_)));
+    assert!(matches!(second.read().status(), ImageStatus::Loaded(_)));
+    assert_eq!(loads.get(), 1);
+  }
+
+  #[test]
+  fn evict_least_recently_used() {
+    // Every image is 400 bytes.
+    let mut cache = Cache::new(1000);
+    cache.insert("a".into(), pixels(10, 10));
+    cache.insert("b".into(), pixels(10, 10));
+    assert!(cache.get("a").is_some());
+    cache.insert("c".into(), pixels(10, 10));
+    assert!(cache.get("b").is_none());
+    assert!(cache.get("a").is_some());
+    assert!(cache.get("c").is_some());
+
+    cache.set_capacity(500);
+    assert!(cache.get("a").is_none());
+    assert!(cache.get("c").is_some());
+
+    // Too large to cache.
+    cache.insert("d".into(), pixels(20, 20));
+    assert!(cache.get("d").is_none());
+    assert!(cache.get("c").is_some());
+  }
+}
@@ -233,6 +233,7 @@ mod tests {
       letter_space: 0.,
       line_height: 16.,
       overflow: TextOverflow::AutoWrap,
+      ..<_>::default()
     };
     let glyphs = store.typography(
       "1 23 456 7890\n12345".into(),
@@ -1,3 +1,5 @@
+pub mod animated_image;
+pub mod async_image;
 pub mod avatar;
 pub mod buttons;
 pub mod checkbox;
@@ -10,6 +12,7 @@ pub mod label;
 pub mod layout;
 pub mod link;
 pub mod lists;
+pub mod lottie;
 pub mod path;
 pub mod progress;
 pub mod radio;
@@ -21,8 +24,9 @@ pub mod text_field;
 pub mod transform_box;
 pub mod prelude {
   pub use super::{
-    avatar::*, buttons::*, checkbox::*, common_widget::*, divider::*, grid_view::*, icon::*,
-    input::*, label::*, layout::*, link::*, lists::*, path::*, progress::*, radio::*, scrollbar::*,
-    slider::*, tabs::*, text_field::*, transform_box::*,
+    animated_image::*, async_image::*, avatar::*, buttons::*, checkbox::*, common_widget::*,
+    divider::*, grid_view::*, icon::*, input::*, label::*, layout::*, link::*, lists::*, lottie::*,
+    path::*, progress::*, radio::*, scrollbar::*, slider::*, tabs::*, text_field::*,
+    transform_box::*,
   };
 }
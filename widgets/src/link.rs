@@ -2,6 +2,8 @@ use log::warn;
 use ribir_core::prelude::*;
 use webbrowser::{Browser, open_browser as open};
 
+/// A link opens the `url` in the browser when its child is tapped, the text of
+/// the child is underlined.
 #[derive(Declare)]
 pub struct UrlLink {
   /// Want to open url
@@ -15,6 +17,7 @@ impl<'c> ComposeChild<'c> for UrlLink {
   type Child = Widget<'c>;
   fn compose_child(this: impl StateWriter<Value = Self>, child: Self::Child) -> Widget<'c> {
     FatObj::new(child)
+      .text_decoration(TextDecorationStyle::new(TextDecoration::UNDERLINE))
       .on_tap(move |_| {
         let this = this.read();
         if open(this.browser, &this.url).is_err() {
@@ -0,0 +1,373 @@
+use std::{
+  cell::{Cell, RefCell},
+  rc::Rc,
+};
+
+use ribir_core::{prelude::*, ticker::FrameMsg, window::WindowFlags};
+
+use crate::animated_image::Ticker;
+
+/// A widget that plays a [`Lottie`] animation.
+///
+/// The player is a handle of the playback, its clones control the same
+/// playback. It's controlled like the other animations: `run` plays the frames,
+/// `stop` pauses at the current frame, and `seek` jumps to a frame. So it can
+/// be pushed into a [`Stagger`] to play with the other animations.
+///
+/// The player doesn't play until it runs, and it stops ticking the window while
+/// it is stopped, finished, not visible or disposed.
+///
+/// # Example
+///
+/// ```
+/// # use ribir_core::prelude::*;
+/// # use ribir_widgets::prelude::*;
+/// fn motion(bytes: &[u8]) -> Widget<'static> {
+///   let lottie = Resource::new(Lottie::parse_from_bytes(bytes).unwrap());
+///   fn_widget! {
+///     let player = LottiePlayer::new(lottie.clone()).with_loop_count(None);
+///     let stagger =
+///       Stagger::new(Duration::from_millis(100), transitions::LINEAR.of(BuildCtx::get()));
+///     stagger.write().push_animation(player.clone());
+///     stagger.run();
+///     @ { player }
+///   }
+///   .into_widget()
+/// }
+/// ```
+pub struct LottiePlayer(Stateful<Playback>);
+
+struct Playback {
+  lottie: Resource<Lottie>,
+  loop_count: Option<u32>,
+  frame: f32,
+  loops: u32,
+  playing: bool,
+  finished: bool,
+}
+
+impl LottiePlayer {
+  /// Create a stopped player at the first frame, it plays the frames once when
+  /// it runs.
+  pub fn new(lottie: Resource<Lottie>) -> Self {
+    let frame = lottie.frames().start;
+    let playback =
+      Playback { lottie, loop_count: Some(1), frame, loops: 0, playing: false, finished: false };
+    Self(Stateful::new(playback))
+  }
+
+  /// Set how many times to play all the frames before the player stops at the
+  /// last frame, `None` plays forever.
+  pub fn with_loop_count(self, loop_count: Option<u32>) -> Self {
+    self.0.write().loop_count = loop_count;
+    self
+  }
+
+  pub fn lottie(&self) -> Resource<Lottie> { self.0.read().lottie.clone() }
+
+  /// The frame is showing.
+  pub fn frame(&self) -> f32 { self.0.read().frame }
+
+  /// Jump to the `frame`, it's clamped into the frames of the Lottie. The
+  /// player keeps playing from it if it's running.
+  pub fn seek(&self, frame: f32) {
+    let mut playback = self.0.write();
+    let frames = playback.lottie.frames();
+    playback.frame = frame.clamp(frames.start, last_frame(&playback.lottie));
+    playback.finished = false;
+  }
+
+  /// Whether all the loops are played.
+  pub fn is_finished(&self) -> bool { self.0.read().finished }
+}
+
+impl Clone for LottiePlayer {
+  fn clone(&self) -> Self { Self(self.0.clone_writer()) }
+}
+
+impl Animation for LottiePlayer {
+  /// Play from the current frame, or replay from the first frame if all the
+  /// loops are played.
+  fn run(&self) {
+    let mut playback = self.0.write();
+    if playback.finished {
+      playback.frame = playback.lottie.frames().start;
+      playback.loops = 0;
+      playback.finished = false;
+    }
+    playback.playing = true;
+  }
+
+  fn stop(&self) {
+    if self.0.read().playing {
+      self.0.write().playing = false;
+    }
+  }
+
+  fn is_running(&self) -> bool { self.0.read().playing }
+
+  fn box_clone(&self) -> Box<dyn Animation> { Box::new(self.clone()) }
+}
+
+fn last_frame(lottie: &Lottie) -> f32 {
+  let frames = lottie.frames();
+  (frames.end - 1.).max(frames.start)
+}
+
+impl Playback {
+  /// Advance the frames by the elapsed time, return if the showing frame or
+  /// the playing state is changed.
+  fn advance(&mut self, dt: Duration) -> bool {
+    if !self.playing {
+      return false;
+    }
+
+    let (start, loops) = (self.frame, self.loops);
+    let frames = self.lottie.frames();
+    let len = frames.end - frames.start;
+    self.frame += dt.as_secs_f32() * self.lottie.frame_rate();
+    while self.frame >= frames.end {
+      if len > 0. && self.loop_count.is_none_or(|n| self.loops + 1 < n) {
+        self.frame -= len;
+        self.loops += 1;
+      } else {
+        self.finish();
+        return true;
+      }
+    }
+    self.frame != start || self.loops != loops
+  }
+
+  fn finish(&mut self) {
+    self.frame = last_frame(&self.lottie);
+    self.finished = true;
+    self.playing = false;
+  }
+}
+
+impl Compose for LottiePlayer {
+  fn compose(this: impl StateWriter<Value = Self>) -> Widget<'static> {
+    let playback = this.read().0.clone_writer();
+    fn_widget! {
+      let wnd = BuildCtx::get().window();
+      let wnd_id = wnd.id();
+      let painted = Rc::new(Cell::new(false));
+      let view = Stateful::new(FrameView {
+        lottie: $playback.lottie.clone(),
+        frame: $playback.frame,
+        painted: painted.clone(),
+      });
+      let ticker = Rc::new(RefCell::new(Ticker::new(wnd_id)));
+      ticker.borrow_mut().set_running($playback.playing);
+
+      let ticker2 = ticker.clone();
+      let u1 = watch!(($playback.playing, $playback.frame))
+        .subscribe(move |(playing, frame)| {
+          // The frames jump to the end as the animations do if the window
+          // disables the animations.
+          let disabled = AppCtx::get_window(wnd_id)
+            .is_some_and(|wnd| !wnd.flags().contains(WindowFlags::ANIMATIONS));
+          if playing && disabled {
+            $playback.write().finish();
+            return;
+          }
+          if $view.frame != frame {
+            $view.write().frame = frame;
+          }
+          ticker2.borrow_mut().set_running(playing);
+        });
+
+      let ticker2 = ticker.clone();
+      let u2 = wnd.frame_tick_stream().subscribe(move |msg| match msg {
+        FrameMsg::BeforeLayout(now) => {
+          let dt = ticker2.borrow_mut().tick(now);
+          if let Some(dt) = dt {
+            let mut playback = $playback.write();
+            if !playback.advance(dt) {
+              playback.forget_modifies();
+            }
+          }
+        }
+        FrameMsg::Finish(_) => {
+          let mut ticker = ticker2.borrow_mut();
+          if ticker.drawing {
+            ticker.drawing = false;
+            ticker.visible = painted.replace(false);
+            ticker.set_running($playback.playing);
+          }
+        }
+        _ => {}
+      });
+
+      FatObj::new(view.clone_writer()).on_disposed(move |_| {
+        u1.unsubscribe();
+        u2.unsubscribe();
+        ticker.borrow_mut().stop();
+      })
+    }
+    .into_widget()
+  }
+}
+
+/// The render of the current frame, it records whether it's painted in the
+/// visible area to know if the player is visible.
+struct FrameView {
+  lottie: Resource<Lottie>,
+  frame: f32,
+  painted: Rc<Cell<bool>>,
+}
+
+impl Render for FrameView {
+  fn perform_layout(&self, clamp: BoxClamp, _: &mut LayoutCtx) -> Size {
+    clamp.clamp(self.lottie.size())
+  }
+
+  fn paint(&self, ctx: &mut PaintingCtx) {
+    let rect = Rect::from_size(ctx.box_size().unwrap());
+    let painter = ctx.painter();
+    if painter.intersect_paint_bounds(&rect) {
+      self.painted.set(true);
+      painter
+        .save_guard()
+        .clip(Path::rect(&rect).into())
+        .draw_lottie(&self.lottie, self.frame);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ribir_core::{reset_test_env, test_helper::*};
+
+  use super::*;
+
+  /// A square moves in 2 frames at 100 frames per second.
+  fn lottie() -> Resource<Lottie> {
+    let json = br#"{
+      "fr": 100, "ip": 0, "op": 2, "w": 20, "h": 10,
+      "layers": [{
+        "ty": 4, "ip": 0, "op": 2,
+        "ks": { "p": { "a": 1, "k": [{ "t": 0, "s": [5, 5] }, { "t": 1, "s": [15, 5] }] } },
+        "shapes": [
+          { "ty": "rc", "p": { "a": 0, "k": [0, 0] }, "s": { "a": 0, "k": [10, 10] } },
+          { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] } }
+        ]
+      }]
+    }"#;
+    Resource::new(Lottie::parse_from_bytes(json).unwrap())
+  }
+
+  #[test]
+  fn advance_frames() {
+    reset_test_env!();
+
+    let player = LottiePlayer::new(lottie()).with_loop_count(Some(2));
+    let mut playback = player.0.write();
+    assert!(!playback.advance(Duration::from_millis(5)));
+
+    playback.playing = true;
+    assert!(playback.advance(Duration::from_millis(5)));
+    assert_eq!(playback.frame, 0.5);
+    // Back to the first frame for the second loop.
+    assert!(playback.advance(Duration::from_millis(20)));
+    assert_eq!(playback.loops, 1);
+    assert!((playback.frame - 0.5).abs() < 1e-4);
+
+    assert!(playback.advance(Duration::from_millis(100)));
+    assert!(playback.finished);
+    assert!(!playback.playing);
+    assert_eq!(playback.frame, 1.);
+  }
+
+  #[test]
+  fn run_and_seek() {
+    reset_test_env!();
+
+    let player = LottiePlayer::new(lottie());
+    assert!(!player.is_running());
+    player.run();
+    player.0.write().advance(Duration::from_secs(1));
+    assert!(player.is_finished());
+    assert!(!player.is_running());
+
+    player.run();
+    assert_eq!(player.frame(), 0.);
+    assert!(player.is_running());
+
+    player.seek(10.);
+    assert_eq!(player.frame(), 1.);
+    player.stop();
+    assert!(!player.is_running());
+    assert_eq!(player.frame(), 1.);
+  }
+
+  #[test]
+  fn stop_ticking_after_finished() {
+    reset_test_env!();
+
+    let player = LottiePlayer::new(lottie());
+    let c_player = player.clone();
+    let mut wnd = TestWindow::new(fn_widget! { c_player.clone() });
+    wnd.set_flags(WindowFlags::ANIMATIONS);
+    wnd.draw_frame();
+    assert!(!wnd.need_draw());
+
+    player.run();
+    wnd.draw_frame();
+    assert!(wnd.need_draw());
+
+    // Tick the player 50ms later than the last frame instead of waiting.
+    let later = Instant::now() + Duration::from_millis(50);
+    wnd
+      .frame_tick_stream()
+      .next(FrameMsg::BeforeLayout(later));
+    wnd.draw_frame();
+    assert!(player.is_finished());
+    assert!(!wnd.need_draw());
+  }
+
+  #[test]
+  fn play_in_stagger() {
+    reset_test_env!();
+
+    let player = LottiePlayer::new(lottie());
+    let c_player = player.clone();
+    let mut wnd = TestWindow::new(fn_widget! { c_player.clone() });
+    wnd.set_flags(WindowFlags::ANIMATIONS);
+    wnd.draw_frame();
+
+    let stagger = Stagger::new(
+      Duration::ZERO,
+      EasingTransition { duration: Duration::ZERO, easing: easing::LINEAR },
+    );
+    stagger.write().push_animation(player.clone());
+    stagger.run();
+    assert!(stagger.is_running());
+    wnd.draw_frame();
+    assert!(player.is_running());
+
+    // Tick the player 50ms later than the last frame instead of waiting.
+    let later = Instant::now() + Duration::from_millis(50);
+    wnd
+      .frame_tick_stream()
+      .next(FrameMsg::BeforeLayout(later));
+    wnd.draw_frame();
+    assert!(player.is_finished());
+    assert!(!stagger.is_running());
+  }
+
+  #[test]
+  fn finish_without_animations() {
+    reset_test_env!();
+
+    let player = LottiePlayer::new(lottie());
+    let c_player = player.clone();
+    let mut wnd = TestWindow::new(fn_widget! { c_player.clone() });
+    wnd.draw_frame();
+
+    player.run();
+    wnd.draw_frame();
+    assert!(player.is_finished());
+    assert_eq!(player.frame(), 1.);
+  }
+}
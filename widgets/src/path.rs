@@ -19,22 +19,70 @@ impl Render for PathPaintKit {
     ctx.painter().draw_path(path);
   }
 
-  fn hit_test(&self, _ctx: &HitTestCtx, _: Point) -> HitTest {
-    HitTest { hit: false, can_hit_child: false }
+  fn hit_test(&self, ctx: &HitTestCtx, pos: Point) -> HitTest {
+    let pos = pos - ctx.box_pos().unwrap_or_default().to_vector();
+    let hit = match Provider::of::<PaintingStyle>(&ctx).as_deref() {
+      Some(PaintingStyle::Stroke(options)) => self.path.stroke_contains(pos, options),
+      _ => self.path.contains(pos, FillRule::NonZero),
+    };
+    HitTest { hit, can_hit_child: false }
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use ribir_core::test_helper::*;
+  use ribir_core::{reset_test_env, test_helper::*};
   use ribir_dev_helper::*;
+  use winit::event::{DeviceId, ElementState, MouseButton, WindowEvent};
 
   use super::*;
+  use crate::layout::SizedBox;
 
   fn circle40() -> Resource<Path> { Path::circle(Point::new(20., 20.), 20.).into() }
   const WND_SIZE: Size = Size::new(48., 48.);
   const SIZE_40: Size = Size::new(40., 40.);
 
+  /// Taps the path widget at `pos` and returns how many times it's tapped.
+  fn taps_at(path: impl Fn() -> FatObj<PathPaintKit> + 'static, pos: (f32, f32)) -> usize {
+    let (taps, w_taps) = split_value(0);
+    let w = fn_widget! {
+      let path = path().on_tap(move |_| *$w_taps.write() += 1);
+      @SizedBox { size: SIZE_40, @ { path } }
+    };
+    let mut wnd = TestWindow::new_with_size(w, WND_SIZE);
+    wnd.draw_frame();
+
+    let device_id = unsafe { DeviceId::dummy() };
+    #[allow(deprecated)]
+    wnd.processes_native_event(WindowEvent::CursorMoved { device_id, position: pos.into() });
+    wnd.process_mouse_input(device_id, ElementState::Pressed, MouseButton::Left);
+    wnd.process_mouse_input(device_id, ElementState::Released, MouseButton::Left);
+    wnd.draw_frame();
+    let taps = *taps.read();
+    taps
+  }
+
+  #[test]
+  fn hit_the_shape() {
+    reset_test_env!();
+    let circle = || FatObj::new(PathPaintKit { path: circle40() });
+    assert_eq!(taps_at(circle, (20., 20.)), 1);
+    // The corner is in the bounds of the circle but not in the circle.
+    assert_eq!(taps_at(circle, (2., 2.)), 0);
+  }
+
+  #[test]
+  fn hit_the_stroke() {
+    reset_test_env!();
+    let ring = || {
+      FatObj::new(PathPaintKit { path: circle40() })
+        .painting_style(PaintingStyle::Stroke(StrokeOptions { width: 4., ..Default::default() }))
+    };
+    // The center is filled by the circle but not covered by the stroke.
+    assert_eq!(taps_at(ring, (20., 20.)), 0);
+    assert_eq!(taps_at(ring, (20., 1.)), 1);
+  }
+
   widget_test_suit!(
     circle40_kit,
     WidgetTester::new(fn_widget! {